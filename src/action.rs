@@ -1,24 +1,192 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use futures::future::BoxFuture;
 use rrr::record::{HashedRecordKey, RecordReadVersionSuccess};
+use rrr::registry::Registry;
+use rrr::utils::fd_lock::ReadLock;
 use strum::Display;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 
 use crate::component::ComponentId;
+use crate::components::modal::{ModalRequest, ModalResult};
+use crate::components::record_tree::ContentPreview;
+use crate::config::CustomCommand;
+use crate::entropy::ContentStats;
+use crate::history::HistoryDirection;
+use crate::model::RecordRef;
+use crate::open_pipeline::OpenProgress;
+use crate::tasks::TaskId;
+
+/// How a notice is displayed, and for how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Severity {
+    /// Shown for a while.
+    #[default]
+    Info,
+    /// Shown until dismissed with Esc, or replaced by another notice.
+    Error,
+}
 
 /// These are applied to all components unconditionally using the `Component::update` method.
 #[derive(Debug, Clone, PartialEq, Display)]
 pub enum ComponentMessage {
     /// Sent when `Action::Tick` action is processed.
     OnTick,
+    /// Sent once the application is about to exit cleanly, so that state can be persisted.
+    OnExit,
     OnCheckboxToggle {
         id: ComponentId,
         new_value: bool,
     },
+    /// Sent by a radio array after its checked item changed.
+    OnRadioArrayChange {
+        id: ComponentId,
+    },
     ShowError {
         error: String,
     },
     RecordOpen {
+        record_ref: RecordRef,
         hashed_record_key: HashedRecordKey,
         read_result: Option<RecordReadVersionSuccess>,
+        /// The versions of the record that were listed, oldest first.
+        versions: Vec<u64>,
+    },
+    /// The result of checking the opened record for a version newer than the displayed one, which
+    /// is not displayed until requested.
+    NewVersionChecked {
+        hashed_record_key: HashedRecordKey,
+        /// The latest version, if it is newer than the displayed one.
+        read_result: Option<RecordReadVersionSuccess>,
+        /// The versions of the record that were listed, oldest first.
+        versions: Vec<u64>,
+    },
+    /// Sent while a record is being opened, before `RecordOpen`.
+    RecordOpenProgress {
+        progress: OpenProgress,
+    },
+    /// Sent when the terminal window gains or loses focus.
+    /// Never sent by terminals that do not report focus changes.
+    OnTerminalFocusChange {
+        focused: bool,
+    },
+    /// Open the record at a `/`-separated path of UTF-8 record names, relative to the root record.
+    OpenRecordPath {
+        path: String,
+    },
+    /// Show the log view with only the entries of the operation, as displayed by
+    /// `crate::tasks::OperationId`.
+    FindLogEntries {
+        operation_id: String,
+    },
+    /// Load another version of the opened record, `None` for the latest one.
+    OpenRecordVersion {
+        version: Option<u64>,
+    },
+    /// Open the record, resolving its path from the root record.
+    OpenRecord {
+        record_ref: RecordRef,
+    },
+    /// Open the record visited before or after the opened one.
+    GoThroughHistory {
+        direction: HistoryDirection,
+    },
+    /// Sent by `crate::tasks::spawn_tracked` once the task is spawned.
+    TaskStarted {
+        id: TaskId,
+        label: String,
+    },
+    /// Sent by `crate::tasks::spawn_tracked` once the task finishes or is cancelled.
+    TaskFinished {
+        id: TaskId,
+    },
+    /// Show a message in the header, for a while unless it is an error.
+    ShowNotice {
+        message: String,
+        severity: Severity,
+    },
+    /// Whether the sub-record with the name being typed exists, or `None` if it could not be
+    /// determined. Sent back to the open pane, which probes it.
+    SubRecordProbed {
+        record_ref: RecordRef,
+        exists: Option<bool>,
+    },
+    /// The preview of the content of the record in the tree. Sent back to the tree, which loads
+    /// it.
+    ContentPreviewLoaded {
+        record_ref: RecordRef,
+        preview: ContentPreview,
+    },
+    /// The registry opened in the background, or the error it failed to open with. Sent back to
+    /// the main view, which opens it.
+    RegistryOpened {
+        result: Result<SharedRegistry, String>,
     },
+    /// The statistics of the content of the opened record, or `None` if it could not be analyzed.
+    /// Sent back to the main view, which analyzes it.
+    ContentAnalyzed {
+        hashed_record_key: HashedRecordKey,
+        stats: Option<Arc<ContentStats>>,
+    },
+    /// Show content derived from the opened record in place of its content.
+    ShowDerivedView {
+        title: String,
+        data: Vec<u8>,
+    },
+    /// Focus one of the panels of the main view.
+    FocusPanel {
+        panel: Panel,
+    },
+    /// Sent by the app once the panel focused by Ctrl+^ changes, `None` if there is none.
+    AlternatePanelChanged {
+        panel: Option<Panel>,
+    },
+    /// Prompt for a path to export the content of the opened record to.
+    PromptExport,
+    /// Copy the text to the clipboard, once confirmed if it is large. The main view asks for the
+    /// confirmation, and focuses `requester` again once it is given.
+    CopyToClipboard {
+        text: String,
+        requester: ComponentId,
+    },
+    OnButtonPress {
+        id: ComponentId,
+    },
+    /// Open a modal dialog, replacing the one that is open, if any.
+    OpenModal {
+        request: ModalRequest,
+    },
+    /// The dialog requested by `requester` was closed. The requester is responsible for focusing
+    /// a component again.
+    ModalClosed {
+        /// The ID of the dialog.
+        id: ComponentId,
+        requester: ComponentId,
+        result: ModalResult,
+    },
+    /// The file the content was to be exported to already exists. Sent back to the main view,
+    /// which asks whether to overwrite it.
+    ExportTargetExists {
+        path: PathBuf,
+    },
+}
+
+/// The panels of the main view, which can be focused from outside of the component tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Panel {
+    /// The pane for opening records.
+    Open,
+    Tree,
+    Content,
+}
+
+impl Panel {
+    /// The panels in the order they are laid out in.
+    pub const ALL: [Panel; 3] = [Panel::Tree, Panel::Content, Panel::Open];
 }
 
 /// Messages generated by components, handled by the app.
@@ -32,8 +200,132 @@ pub enum Action {
     Quit,
     ClearScreen,
     FocusChange(FocusChange),
+    /// Focus the component with the given ID, if it exists.
+    FocusComponent(ComponentId),
     /// Send a message to all other components.
     BroadcastMessage(ComponentMessage),
+    /// Draw attention to the terminal, if configured to do so.
+    Feedback(Feedback),
+    /// Copy the text to the clipboard, reporting the outcome with a notice.
+    CopyToClipboard {
+        text: String,
+    },
+    /// Run an interactive custom command, handing the terminal over to it.
+    RunInTerminal {
+        command: CustomCommand,
+        input: Vec<u8>,
+    },
+    /// Await the work in the background, then send the message it resolves to back to the
+    /// component that deferred it.
+    Defer(Deferred),
+    /// Send a message to a single component, if it still exists.
+    SendMessage {
+        id: ComponentId,
+        message: ComponentMessage,
+    },
+    /// Outline the areas widgets are drawn into, or stop doing so.
+    #[cfg(feature = "layout-debug")]
+    ToggleLayoutDebug,
+    /// Show the text in the status bar, for a while unless it is an error.
+    ShowStatus {
+        severity: Severity,
+        text: String,
+    },
+}
+
+/// Asynchronous work of a component, whose output is sent back to it.
+/// Clones share the work, which is only spawned once.
+#[derive(Clone)]
+pub struct Deferred {
+    pub component_id: ComponentId,
+    cancellation: Option<CancellationToken>,
+    future: Arc<Mutex<Option<BoxFuture<'static, ComponentMessage>>>>,
+}
+
+impl Deferred {
+    /// Defers asynchronous work of the component with the ID `component_id`, which is cancelled
+    /// when the application quits.
+    pub fn new(
+        component_id: ComponentId,
+        future: impl Future<Output = ComponentMessage> + Send + 'static,
+    ) -> Self {
+        Self {
+            component_id,
+            cancellation: None,
+            future: Arc::new(Mutex::new(Some(Box::pin(future)))),
+        }
+    }
+
+    /// Also cancels the work with `cancellation`, such as when the input it depends on changes.
+    pub fn with_cancellation(mut self, cancellation: &CancellationToken) -> Self {
+        self.cancellation = Some(cancellation.clone());
+        self
+    }
+
+    pub fn get_cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    /// Takes the work to spawn it, or `None` if it was already taken.
+    pub fn take_future(&self) -> Option<BoxFuture<'static, ComponentMessage>> {
+        self.future
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Spawns the work, unless it was already spawned, as a child of `cancellation`. Its message
+    /// is sent back to the component with `Action::SendMessage`, unless the work is cancelled
+    /// first.
+    pub fn spawn(&self, cancellation: &CancellationToken, action_tx: &UnboundedSender<Action>) {
+        let Some(future) = self.take_future() else {
+            return;
+        };
+        let id = self.component_id;
+        let own_cancellation = self
+            .get_cancellation()
+            .cloned()
+            .unwrap_or_else(|| cancellation.clone());
+        let action_tx = action_tx.clone();
+
+        crate::tasks::spawn(cancellation, async move {
+            if let Some(message) = own_cancellation.run_until_cancelled(future).await {
+                let _ = action_tx.send(Action::SendMessage { id, message });
+            }
+        });
+    }
+}
+
+impl Debug for Deferred {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deferred")
+            .field("component_id", &self.component_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Deferred {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.future, &other.future)
+    }
+}
+
+/// An opened registry, compared by identity.
+#[derive(Debug, Clone)]
+pub struct SharedRegistry(pub Arc<Registry<ReadLock>>);
+
+impl PartialEq for SharedRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Occurrences the user may want to be notified about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Feedback {
+    Error,
+    /// A long-running operation finished.
+    Completion,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
@@ -43,8 +335,9 @@ pub enum FocusChangeDirection {
 }
 
 /// Describes the set of components considered for focus.
-/// The horizontal scope is all the siblings of the currently focused component.
-/// The vertical scope is all the parent or the first child
+/// The horizontal and vertical scopes are the components drawn to the right or left, and below or
+/// above the focused component, respectively, of which the nearest one is focused.
+/// The combined scope is all the focusable components, in the order of the component tree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
 pub enum FocusChangeScope {
     Horizontal,
@@ -67,3 +360,68 @@ pub struct FocusChange {
     pub direction: FocusChangeDirection,
     pub scope: FocusChangeScope,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn probed(exists: bool) -> ComponentMessage {
+        ComponentMessage::SubRecordProbed {
+            record_ref: RecordRef::from_utf8_path("a"),
+            exists: Some(exists),
+        }
+    }
+
+    #[tokio::test]
+    async fn deferred_message_is_sent_back_to_the_component() {
+        let (action_tx, mut action_rx) = tokio::sync::mpsc::unbounded_channel();
+        let id = ComponentId::new();
+        let deferred = Deferred::new(id, async { probed(true) });
+
+        deferred.spawn(&CancellationToken::new(), &action_tx);
+        // Clones share the work, which is only spawned once.
+        deferred
+            .clone()
+            .spawn(&CancellationToken::new(), &action_tx);
+
+        assert_eq!(
+            action_rx.recv().await,
+            Some(Action::SendMessage {
+                id,
+                message: probed(true),
+            })
+        );
+        tokio::task::yield_now().await;
+        assert!(action_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelled_deferred_work_sends_nothing() {
+        let (action_tx, mut action_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (complete_tx, complete_rx) = tokio::sync::oneshot::channel::<()>();
+        let edited = CancellationToken::new();
+        let deferred = Deferred::new(ComponentId::new(), async {
+            let _ = complete_rx.await;
+            probed(false)
+        })
+        .with_cancellation(&edited);
+        let application = CancellationToken::new();
+
+        deferred.spawn(&application, &action_tx);
+        tokio::task::yield_now().await;
+        // As when the probed name is edited before the probe finishes.
+        edited.cancel();
+        tokio::task::yield_now().await;
+
+        // The work was dropped, along with the receiver.
+        assert!(complete_tx.send(()).is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), action_rx.recv())
+                .await
+                .is_err()
+        );
+        assert!(!application.is_cancelled());
+    }
+}
@@ -0,0 +1,314 @@
+//! A unix socket through which external scripts follow and drive the application, enabled with
+//! `--action-socket`.
+//!
+//! Every connection receives the processed actions as JSON lines, redacted to the names of the
+//! actions and messages, so that no record names or contents leave the application. Connections
+//! may send the commands of `crate::commands` as JSON lines, each of which is answered with a
+//! response line.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc::UnboundedSender};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::action::{Action, ComponentMessage};
+
+/// Bumped whenever the lines exchanged over the action socket change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The number of actions buffered for each connection before the oldest ones are dropped.
+const EVENT_BUFFER_SIZE: usize = 256;
+
+/// The maximum number of commands accepted from a connection within `RATE_LIMIT_WINDOW`.
+const MAX_COMMANDS_PER_WINDOW: usize = 10;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// A command from `crate::commands`, with its arguments as the remaining fields.
+#[derive(Debug, Deserialize)]
+struct CommandRequest {
+    version: u32,
+    command: String,
+    #[serde(flatten)]
+    arguments: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SocketLine<'a> {
+    Action {
+        version: u32,
+        action: String,
+        /// The message of `Action::BroadcastMessage`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// Actions were dropped because the connection did not keep up.
+    Lagged { version: u32, skipped: u64 },
+    Response {
+        version: u32,
+        error: Option<&'a str>,
+    },
+}
+
+impl SocketLine<'_> {
+    fn to_json_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap();
+        line.push('\n');
+        line
+    }
+}
+
+/// Removes the socket on drop.
+#[derive(Debug)]
+pub struct ActionSocket {
+    path: PathBuf,
+    events_tx: broadcast::Sender<String>,
+}
+
+impl ActionSocket {
+    #[cfg(unix)]
+    pub fn bind(
+        path: &Path,
+        action_tx: &UnboundedSender<Action>,
+        cancellation: &CancellationToken,
+    ) -> Result<Self> {
+        use tokio::net::UnixListener;
+        use tracing::{info_span, Instrument};
+
+        if path.exists() {
+            bail!(
+                "--action-socket `{}` already exists, remove it if no other program uses it.",
+                path.display()
+            );
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let (events_tx, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        let action_tx = action_tx.clone();
+        let connection_events_tx = events_tx.clone();
+        let cancellation = cancellation.child_token();
+        let connection_cancellation = cancellation.clone();
+
+        crate::tasks::spawn(
+            &cancellation,
+            async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => {
+                            debug!("Action socket connection accepted.");
+                            crate::tasks::spawn(
+                                &connection_cancellation,
+                                handle_connection(
+                                    stream,
+                                    connection_events_tx.subscribe(),
+                                    action_tx.clone(),
+                                    connection_cancellation.child_token(),
+                                ),
+                            );
+                        }
+                        Err(error) => {
+                            warn!(%error, "Failed to accept an action socket connection.")
+                        }
+                    }
+                }
+            }
+            .instrument(info_span!("action socket task")),
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            events_tx,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(
+        _path: &Path,
+        _action_tx: &UnboundedSender<Action>,
+        _cancellation: &CancellationToken,
+    ) -> Result<Self> {
+        bail!("--action-socket is not supported on this platform.")
+    }
+
+    /// Streams the action to the connections, unless it is too frequent to be of interest.
+    pub fn publish(&self, action: &Action) {
+        if matches!(action, Action::Tick | Action::Render)
+            || matches!(action, Action::BroadcastMessage(ComponentMessage::OnTick))
+        {
+            return;
+        }
+
+        let message = match action {
+            Action::BroadcastMessage(message) => Some(message.to_string()),
+            _ => None,
+        };
+        let line = SocketLine::Action {
+            version: PROTOCOL_VERSION,
+            action: action.to_string(),
+            message,
+        };
+
+        // Fails only if there are no connections.
+        let _ = self.events_tx.send(line.to_json_line());
+    }
+}
+
+impl Drop for ActionSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+async fn handle_connection(
+    stream: tokio::net::UnixStream,
+    mut events_rx: broadcast::Receiver<String>,
+    action_tx: UnboundedSender<Action>,
+    cancellation: CancellationToken,
+) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let (responses_tx, mut responses_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    crate::tasks::spawn(&cancellation, async move {
+        loop {
+            let line = tokio::select! {
+                response = responses_rx.recv() => match response {
+                    Some(response) => response,
+                    None => break,
+                },
+                event = events_rx.recv() => match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => SocketLine::Lagged {
+                        version: PROTOCOL_VERSION,
+                        skipped,
+                    }
+                    .to_json_line(),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(reader).lines();
+    let mut window_start = Instant::now();
+    let mut commands_in_window = 0;
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if window_start.elapsed() >= RATE_LIMIT_WINDOW {
+            window_start = Instant::now();
+            commands_in_window = 0;
+        }
+
+        commands_in_window += 1;
+        let error = if commands_in_window > MAX_COMMANDS_PER_WINDOW {
+            Some(format!(
+                "Rate limited, at most {MAX_COMMANDS_PER_WINDOW} commands per second are accepted."
+            ))
+        } else {
+            handle_command(&line, &action_tx).err()
+        };
+        let response = SocketLine::Response {
+            version: PROTOCOL_VERSION,
+            error: error.as_deref(),
+        };
+
+        if responses_tx.send(response.to_json_line()).is_err() {
+            break;
+        }
+    }
+
+    debug!("Action socket connection closed.");
+    cancellation.cancel();
+}
+
+/// Validates the command and turns it into an action.
+fn handle_command(line: &str, action_tx: &UnboundedSender<Action>) -> Result<(), String> {
+    let request =
+        serde_json::from_str::<CommandRequest>(line).map_err(|error| error.to_string())?;
+
+    if request.version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Unsupported protocol version {}, expected {PROTOCOL_VERSION}.",
+            request.version
+        ));
+    }
+
+    debug!(?request, "Action socket command received.");
+    let action = crate::commands::parse(&request.command, &request.arguments)
+        .map_err(|error| error.to_string())?;
+
+    action_tx.send(action).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    #[test]
+    fn commands_are_rejected_like_by_their_schemas() {
+        let (action_tx, mut action_rx) = unbounded_channel();
+
+        for (command, arguments) in [
+            ("open_record", serde_json::json!({ "path": "a//b" })),
+            ("focus_panel", serde_json::json!({ "panel": "menu" })),
+            ("focus_panel", serde_json::json!({})),
+            ("show_whats_new", serde_json::json!({ "extra": true })),
+            ("quit", serde_json::json!({})),
+        ] {
+            let mut line = arguments.clone();
+            line["version"] = PROTOCOL_VERSION.into();
+            line["command"] = command.into();
+            let serde_json::Value::Object(arguments) = arguments else {
+                unreachable!()
+            };
+
+            assert_eq!(
+                handle_command(&line.to_string(), &action_tx),
+                Err(crate::commands::parse(command, &arguments)
+                    .unwrap_err()
+                    .to_string()),
+                "{line}"
+            );
+        }
+
+        assert!(action_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn valid_commands_are_sent_as_actions() {
+        let (action_tx, mut action_rx) = unbounded_channel();
+        let line =
+            format!(r#"{{"version":{PROTOCOL_VERSION},"command":"focus_panel","panel":"tree"}}"#);
+
+        assert_eq!(handle_command(&line, &action_tx), Ok(()));
+        assert_eq!(
+            action_rx.try_recv().ok(),
+            Some(Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                panel: crate::action::Panel::Tree,
+            }))
+        );
+    }
+
+    #[test]
+    fn other_protocol_versions_are_rejected() {
+        let (action_tx, _action_rx) = unbounded_channel();
+
+        assert_eq!(
+            handle_command(r#"{"version":0,"command":"show_whats_new"}"#, &action_tx),
+            Err(format!(
+                "Unsupported protocol version 0, expected {PROTOCOL_VERSION}."
+            ))
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Local notes about the records of a registry, which are never written into the registry itself.
+//! Several instances may share the annotations, so their changes are merged on save.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::encoding::Encoding;
+use crate::model::RecordRef;
+
+/// Name of the annotations file inside the registry state directory.
+const ANNOTATIONS_FILE_NAME: &str = "annotations.json";
+
+/// Extension of the file locked while the annotations are being saved, next to them.
+const LOCK_FILE_EXTENSION: &str = "json.lock";
+
+/// Appended to an alias combining the values of both instances that changed it.
+const CONFLICT_SUFFIX: &str = " (conflict)";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredAliasEntry")]
+struct AliasEntry {
+    alias: String,
+    /// Incremented on every change, so that a change to the same value is still detected.
+    revision: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredAliasEntry {
+    /// Written before entries had revisions.
+    Legacy(String),
+    Current {
+        alias: String,
+        revision: u64,
+    },
+}
+
+impl From<StoredAliasEntry> for AliasEntry {
+    fn from(stored: StoredAliasEntry) -> Self {
+        match stored {
+            StoredAliasEntry::Legacy(alias) => Self { alias, revision: 0 },
+            StoredAliasEntry::Current { alias, revision } => Self { alias, revision },
+        }
+    }
+}
+
+/// An exclusive lock on the annotations file, held from reading the annotations saved by other
+/// instances until the merged ones replace them, so that instances saving at the same time merge
+/// their changes in turn, instead of the last one overwriting the others.
+struct SaveLock {
+    /// The lock is released once the file is closed.
+    _file: File,
+}
+
+impl SaveLock {
+    /// Waits until no other instance is saving the annotations at `path`.
+    fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = path.with_extension(LOCK_FILE_EXTENSION);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .wrap_err_with(|| format!("Failed to open the lock file {}", lock_path.display()))?;
+        file.lock()
+            .wrap_err_with(|| format!("Failed to lock the annotations file {}", path.display()))?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Annotations {
+    /// Display aliases, keyed by `RecordRef::path_key`.
+    #[serde(default)]
+    aliases: BTreeMap<String, AliasEntry>,
+    /// The aliases as they were last loaded or saved, the common ancestor of the changes made
+    /// by this instance and by others.
+    #[serde(skip)]
+    base_aliases: BTreeMap<String, AliasEntry>,
+    /// Where the annotations are saved to, if anywhere.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Annotations {
+    /// Loads the annotations of the registry at `registry_directory`.
+    pub fn load(registry_directory: &Path) -> Result<Self> {
+        Self::load_from(
+            crate::env::get_registry_state_dir(registry_directory).join(ANNOTATIONS_FILE_NAME),
+        )
+    }
+
+    fn load_from(path: PathBuf) -> Result<Self> {
+        let mut annotations = Self::read(&path)?;
+
+        annotations.base_aliases = annotations.aliases.clone();
+        annotations.path = Some(path);
+        Ok(annotations)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).wrap_err_with(|| {
+                format!("Failed to read the annotations file {}", path.display())
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Starts over with no annotations, setting the existing annotations file aside, as it could
+    /// not be loaded.
+    pub fn reset(registry_directory: &Path) -> Result<Self> {
+        let path =
+            crate::env::get_registry_state_dir(registry_directory).join(ANNOTATIONS_FILE_NAME);
+
+        if path.exists() {
+            std::fs::rename(&path, path.with_extension("json.corrupt"))?;
+        }
+
+        Ok(Self {
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+
+    /// Saves the annotations, merged with the changes other instances saved since they were last
+    /// loaded or saved, so that neither overwrites the other.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let _lock = SaveLock::acquire(&path)?;
+
+        match Self::read(&path) {
+            Ok(saved) => self.merge(saved.aliases),
+            // The changes of other instances are lost, but not the ones of this instance.
+            Err(error) => warn!(
+                ?error,
+                "Failed to read the annotations to merge, overwriting them."
+            ),
+        }
+
+        // Written to a temporary file first, so that other instances never read a partial file.
+        let temporary_path = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        std::fs::write(&temporary_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temporary_path, &path)?;
+        self.base_aliases = self.aliases.clone();
+        Ok(())
+    }
+
+    /// Merges the aliases by entry, taking each from whichever side changed it since the base.
+    /// If both sides changed an entry to different values, both values are kept in the alias.
+    fn merge(&mut self, theirs: BTreeMap<String, AliasEntry>) {
+        let keys = self
+            .base_aliases
+            .keys()
+            .chain(self.aliases.keys())
+            .chain(theirs.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        for key in keys {
+            let base = self.base_aliases.get(&key);
+            let ours = self.aliases.get(&key);
+            let their = theirs.get(&key);
+
+            let merged = if ours == base {
+                their.cloned()
+            } else if their == base {
+                ours.cloned()
+            } else {
+                match (ours, their) {
+                    (Some(ours), Some(their)) if ours.alias == their.alias => {
+                        Some(std::cmp::max_by_key(ours, their, |entry| entry.revision).clone())
+                    }
+                    (Some(ours), Some(their)) => Some(AliasEntry {
+                        alias: format!("{} | {}{CONFLICT_SUFFIX}", ours.alias, their.alias),
+                        revision: std::cmp::max(ours.revision, their.revision) + 1,
+                    }),
+                    // A change wins over a removal.
+                    (Some(entry), None) | (None, Some(entry)) => Some(entry.clone()),
+                    (None, None) => None,
+                }
+            };
+
+            match merged {
+                Some(entry) => self.aliases.insert(key, entry),
+                None => self.aliases.remove(&key),
+            };
+        }
+    }
+
+    pub fn get_alias(&self, record_ref: &RecordRef) -> Option<&str> {
+        self.aliases
+            .get(&record_ref.path_key())
+            .map(|entry| entry.alias.as_str())
+    }
+
+    /// Sets the alias of the record, or removes it if `alias` is empty.
+    pub fn set_alias(&mut self, record_ref: &RecordRef, alias: &str) {
+        self.set_alias_by_key(record_ref.path_key(), alias);
+    }
+
+    fn set_alias_by_key(&mut self, key: String, alias: &str) {
+        let alias = alias.trim();
+
+        if alias.is_empty() {
+            self.aliases.remove(&key);
+        } else {
+            let revision = self.aliases.get(&key).map_or(0, |entry| entry.revision + 1);
+            self.aliases.insert(
+                key,
+                AliasEntry {
+                    alias: alias.to_string(),
+                    revision,
+                },
+            );
+        }
+    }
+
+    /// The records with an alias, along with it.
+    pub fn aliases(&self) -> impl Iterator<Item = (RecordRef, &str)> {
+        self.aliases.iter().filter_map(|(key, entry)| {
+            let record_ref = RecordRef::from_path_key(key, Encoding::UTF8)?;
+            Some((record_ref, entry.alias.as_str()))
+        })
+    }
+
+    /// Whether the alias of the record is also used by another record.
+    pub fn is_alias_shared(&self, record_ref: &RecordRef) -> bool {
+        let key = record_ref.path_key();
+        let Some(alias) = self.aliases.get(&key) else {
+            return false;
+        };
+
+        self.aliases
+            .iter()
+            .any(|(other_key, other_alias)| *other_key != key && other_alias.alias == alias.alias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for the annotations file of each test, as tests run in parallel.
+    fn annotations_path(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-annotations-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory.join(ANNOTATIONS_FILE_NAME)
+    }
+
+    fn load(path: &Path) -> Annotations {
+        Annotations::load_from(path.to_path_buf()).unwrap()
+    }
+
+    fn aliases(annotations: &Annotations) -> BTreeMap<&str, &str> {
+        annotations
+            .aliases
+            .iter()
+            .map(|(key, entry)| (key.as_str(), entry.alias.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn interleaved_additions_are_kept() {
+        let path = annotations_path("interleaved_additions");
+        let mut first = load(&path);
+        let mut second = load(&path);
+
+        first.set_alias_by_key("01".to_string(), "first");
+        second.set_alias_by_key("02".to_string(), "second");
+        first.save().unwrap();
+        second.save().unwrap();
+        // The entry of the second instance stays, although the first one never loaded it.
+        first.set_alias_by_key("03".to_string(), "third");
+        first.save().unwrap();
+
+        let expected = BTreeMap::from([("01", "first"), ("02", "second"), ("03", "third")]);
+        assert_eq!(aliases(&load(&path)), expected);
+        assert_eq!(aliases(&first), expected);
+        second.save().unwrap();
+        assert_eq!(aliases(&second), expected);
+    }
+
+    #[test]
+    fn removal_by_one_instance_is_kept() {
+        let path = annotations_path("removal");
+        let mut initial = load(&path);
+        initial.set_alias_by_key("01".to_string(), "removed");
+        initial.set_alias_by_key("02".to_string(), "kept");
+        initial.save().unwrap();
+
+        let mut first = load(&path);
+        let mut second = load(&path);
+        first.set_alias_by_key("01".to_string(), "");
+        first.save().unwrap();
+        second.set_alias_by_key("03".to_string(), "added");
+        second.save().unwrap();
+
+        assert_eq!(
+            aliases(&load(&path)),
+            BTreeMap::from([("02", "kept"), ("03", "added")])
+        );
+    }
+
+    #[test]
+    fn conflicting_changes_keep_both_aliases() {
+        let path = annotations_path("conflict");
+        let mut first = load(&path);
+        let mut second = load(&path);
+
+        first.set_alias_by_key("01".to_string(), "first");
+        second.set_alias_by_key("01".to_string(), "second");
+        first.save().unwrap();
+        second.save().unwrap();
+
+        assert_eq!(
+            aliases(&load(&path)),
+            BTreeMap::from([("01", "second | first (conflict)")])
+        );
+    }
+
+    #[test]
+    fn the_same_change_by_both_instances_is_not_a_conflict() {
+        let path = annotations_path("same_change");
+        let mut first = load(&path);
+        let mut second = load(&path);
+
+        first.set_alias_by_key("01".to_string(), "same");
+        second.set_alias_by_key("01".to_string(), "same");
+        first.save().unwrap();
+        second.save().unwrap();
+
+        assert_eq!(aliases(&load(&path)), BTreeMap::from([("01", "same")]));
+    }
+
+    #[test]
+    fn two_instances_saving_in_turn_lose_no_changes() {
+        let path = annotations_path("saving_in_turn");
+        let mut instances = [load(&path), load(&path)];
+        let mut expected = BTreeMap::new();
+
+        // Each step changes an alias in one of the instances and saves it, after which the file
+        // holds the changes of both instances so far. An instance only changes the aliases of the
+        // other one after having merged them, like when it displays them.
+        for (instance, key, alias) in [
+            (0, "01", "a"),
+            (1, "02", "b"),
+            (0, "03", "c"),
+            (0, "04", "d"),
+            (1, "01", "a changed"),
+            (1, "03", ""),
+            (0, "02", "b changed"),
+            (0, "05", "e"),
+            (1, "06", "f"),
+            (1, "05", ""),
+            (0, "07", "g"),
+            (0, "06", "f changed"),
+        ] {
+            instances[instance].set_alias_by_key(key.to_string(), alias);
+            instances[instance].save().unwrap();
+
+            if alias.is_empty() {
+                expected.remove(key);
+            } else {
+                expected.insert(key, alias);
+            }
+
+            assert_eq!(
+                aliases(&load(&path)),
+                expected,
+                "after instance {instance} set {key} to {alias:?}"
+            );
+            assert_eq!(aliases(&instances[instance]), expected);
+        }
+
+        for instance in &mut instances {
+            instance.save().unwrap();
+            assert_eq!(aliases(instance), expected);
+        }
+        assert_eq!(aliases(&load(&path)), expected);
+    }
+
+    #[test]
+    fn instance_saving_again_keeps_the_entries_of_the_other() {
+        let path = annotations_path("saving_again");
+        let mut first = load(&path);
+        let mut second = load(&path);
+
+        second.set_alias_by_key("02".to_string(), "second");
+        second.save().unwrap();
+        first.set_alias_by_key("01".to_string(), "first");
+        first.save().unwrap();
+        // The second instance has not seen `01`, which must not count as having removed it.
+        second.save().unwrap();
+        second.set_alias_by_key("03".to_string(), "third");
+        second.save().unwrap();
+        first.save().unwrap();
+
+        let expected = BTreeMap::from([("01", "first"), ("02", "second"), ("03", "third")]);
+        assert_eq!(aliases(&load(&path)), expected);
+        assert_eq!(aliases(&first), expected);
+        assert_eq!(aliases(&second), expected);
+    }
+
+    #[test]
+    fn concurrent_saves_lose_no_entries() {
+        const INSTANCES: usize = 8;
+        const SAVES: usize = 10;
+        let path = annotations_path("concurrent");
+
+        std::thread::scope(|scope| {
+            for instance in 0..INSTANCES {
+                let path = &path;
+                scope.spawn(move || {
+                    let mut annotations = load(path);
+
+                    for save in 0..SAVES {
+                        annotations.set_alias_by_key(
+                            format!("{instance:02x}{save:02x}"),
+                            &format!("{instance} {save}"),
+                        );
+                        annotations.save().unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(load(&path).aliases.len(), INSTANCES * SAVES);
+    }
+}
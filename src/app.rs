@@ -1,19 +1,48 @@
-use std::{ops::ControlFlow, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    ops::ControlFlow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use ratatui::prelude::Rect;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{
+    layout::Position,
+    prelude::Rect,
+    style::{Style, Stylize},
+    widgets::Block,
+};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 use crate::{
-    action::{Action, ComponentMessage, FocusChange, FocusChangeDirection, FocusChangeScope},
+    action::{
+        Action, ComponentMessage, Feedback, FocusChange, FocusChangeDirection, FocusChangeScope,
+        Panel, Severity,
+    },
+    action_socket::ActionSocket,
     args::Args,
+    capabilities::TerminalCapabilities,
+    clipboard::Clipboard,
     component::{
-        self, find_component_by_id_mut, Component, ComponentId, ComponentIdPath,
-        DefaultDrawableComponent, DrawContext, HandleEventSuccess,
+        self, find_component_by_id_mut, ComponentId, ComponentIdPath, DefaultDrawableComponent,
+        DrawContext, HandleEventSuccess, PanelFocus,
     },
-    components::main_view::MainView,
+    components::{main_view::MainView, modal::ModalRequest},
+    config::{CustomCommand, FeedbackConfig, FeedbackLevel, FeedbackMethod},
+    frame_pacer::{self, FramePacer},
+    history::HistoryDirection,
+    instance::Instance,
+    keymap::{KeyCommand, KeyMap},
+    latency::LatencyRecorder,
+    session::SessionMarker,
+    startup::Startup,
+    theme::Palette,
     tui::{Event, Tui},
 };
 
@@ -21,6 +50,8 @@ use crate::{
 pub struct App {
     tick_rate: f64,
     frame_rate: f64,
+    /// Simulates a slow terminal connection.
+    terminal_write_delay: Duration,
     should_quit: bool,
     should_suspend: bool,
     last_tick_key_events: Vec<KeyEvent>,
@@ -28,20 +59,126 @@ pub struct App {
     action_rx: mpsc::UnboundedReceiver<Action>,
     root_component: Box<dyn DefaultDrawableComponent>,
     focus_path: ComponentIdPath,
+    /// Translates the key events before they are handled.
+    keymap: KeyMap,
+    /// The panel containing the focus, and the one focused before it.
+    panel_focus: PanelFocus,
+    /// Held for the lifetime of the app to keep other instances informed.
+    _instance: Instance,
+    /// Only present if input latency is being measured.
+    latency_recorder: Option<LatencyRecorder>,
+    frame_pacer: FramePacer,
+    /// Whether the terminal window is focused, assumed to be the case unless the terminal reports
+    /// otherwise.
+    terminal_focused: bool,
+    last_tick_at: Option<Instant>,
+    /// An interactive custom command to run once the actions are handled.
+    command_in_terminal: Option<(CustomCommand, Vec<u8>)>,
+    feedback_config: FeedbackConfig,
+    palette: Palette,
+    last_feedback_at: Option<Instant>,
+    /// Until when the edges of the screen are flashed.
+    flash_until: Option<Instant>,
+    clipboard: Clipboard,
+    /// Released once the application exits cleanly.
+    session_marker: Option<SessionMarker>,
+    action_socket: Option<ActionSocket>,
+    /// The root of the cancellation tokens of the background tasks, cancelled on quit.
+    cancellation: CancellationToken,
+    /// The areas of the components in the last rendered frame, for hit-testing mouse events.
+    mouse_areas: Vec<(ComponentId, Rect)>,
+    /// The nodes of the accessibility tree as last reported, so that only changes are reported.
+    accessibility_nodes: HashMap<accesskit::NodeId, accesskit::Node>,
+    accessibility_focus: Option<accesskit::NodeId>,
+    #[cfg(feature = "layout-debug")]
+    layout_debug: crate::layout_debug::LayoutDebug,
+    /// The snapshot of the user interface as last reported, so that only changes are reported.
+    #[cfg(feature = "introspection")]
+    ui_state: Option<crate::introspection::UiState>,
 }
 
+/// The minimum interval between ticks while the terminal is not focused.
+const UNFOCUSED_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The minimum interval between feedback cues, so that a burst of errors does not ring repeatedly.
+const MIN_FEEDBACK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long the edges of the screen are flashed with `FeedbackMethod::Flash`.
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// The OSC 1337 sequence requesting the attention of the user.
+const ATTENTION_SEQUENCE: &[u8] = b"\x1b]1337;RequestAttention=yes\x07";
+
 impl App {
     #[instrument]
-    pub async fn new(args: &Arc<Args>) -> Result<Self> {
+    pub async fn new(
+        args: &Arc<Args>,
+        capabilities: &TerminalCapabilities,
+        instance: Instance,
+        mut startup: Startup,
+    ) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
+        let cancellation = CancellationToken::new();
+        instance.listen(&action_tx, &cancellation)?;
+        let feedback_config = startup.config.feedback.clone();
+        crate::tempfiles::set_never_write_plaintext(startup.config.never_write_plaintext);
+        let palette = args
+            .theme
+            .unwrap_or(startup.config.display.theme)
+            .palette(capabilities.color_count);
+        let session_marker = startup.session_marker.take();
+        let keymap = std::mem::take(&mut startup.keymap);
         let mut app = Self {
             tick_rate: args.tick_rate,
             frame_rate: args.frame_rate,
+            terminal_write_delay: Duration::from_millis(args.terminal_write_delay),
             should_quit: false,
             should_suspend: false,
             last_tick_key_events: Vec::new(),
-            root_component: Box::new(MainView::new(ComponentId::root(), &action_tx, args).await?),
+            root_component: Box::new(
+                MainView::new(
+                    ComponentId::root(),
+                    &action_tx,
+                    &cancellation,
+                    args,
+                    startup,
+                )
+                .await?,
+            ),
             focus_path: Default::default(),
+            keymap,
+            panel_focus: PanelFocus::default(),
+            _instance: instance,
+            latency_recorder: args
+                .latency_log
+                .as_deref()
+                .map(|path| LatencyRecorder::new(Some(path)))
+                .transpose()?,
+            frame_pacer: FramePacer::new(args.frame_rate),
+            terminal_focused: true,
+            last_tick_at: None,
+            command_in_terminal: None,
+            feedback_config,
+            palette,
+            last_feedback_at: None,
+            flash_until: None,
+            clipboard: Clipboard::default(),
+            session_marker,
+            // Nothing can be driven from the outside in safe mode.
+            action_socket: args
+                .action_socket
+                .as_deref()
+                .filter(|_| !args.safe_mode)
+                .map(|path| ActionSocket::bind(path, &action_tx, &cancellation))
+                .transpose()?,
+            cancellation,
+            mouse_areas: Vec::new(),
+            accessibility_nodes: HashMap::new(),
+            accessibility_focus: None,
+            #[cfg(feature = "layout-debug")]
+            layout_debug: Default::default(),
+            #[cfg(feature = "introspection")]
+            ui_state: None,
             action_tx,
             action_rx,
         };
@@ -59,67 +196,196 @@ impl App {
 
     #[instrument(skip(self))]
     pub async fn run(&mut self) -> Result<()> {
-        let mut tui = Tui::new(tracing::Span::current())?
-            // .mouse(true) // uncomment this line to enable mouse support
+        let mut tui = Tui::new(tracing::Span::current())
+            .wrap_err("Failed to initialize the terminal")?
+            .mouse(true)
+            .paste(true)
             .tick_rate(self.tick_rate)
-            .frame_rate(self.frame_rate);
-        tui.enter()?;
+            .frame_rate(self.frame_rate)
+            .write_delay(self.terminal_write_delay);
+        tui.enter().wrap_err("Failed to initialize the terminal")?;
 
         let action_tx = self.action_tx.clone();
         loop {
             self.handle_events(&mut tui).await?;
             self.handle_actions(&mut tui)?;
+            self.render_if_due(&mut tui)?;
+            self.record_written_frames(&tui)?;
+            if let Some((command, input)) = self.command_in_terminal.take() {
+                tui.exit()?;
+                let result = crate::custom_command::run_in_terminal(&command, &input);
+                tui.enter()?;
+                action_tx.send(Action::ClearScreen)?;
+
+                if let Err(error) = result {
+                    action_tx.send(Action::ShowStatus {
+                        severity: Severity::Error,
+                        text: format!("{error:#}"),
+                    })?;
+                }
+            }
             if self.should_suspend {
                 tui.suspend()?;
                 action_tx.send(Action::Resume)?;
                 action_tx.send(Action::ClearScreen)?;
-                // tui.mouse(true);
                 tui.enter()?;
             } else if self.should_quit {
+                action_tx.send(Action::BroadcastMessage(ComponentMessage::OnExit))?;
+                self.handle_actions(&mut tui)?;
                 tui.stop()?;
                 break;
             }
         }
         tui.exit()?;
+        self.cancellation.cancel();
+
+        if let Some(session_marker) = self.session_marker.take() {
+            session_marker.release();
+        }
+
+        self.record_written_frames(&tui)?;
+
+        if let Some(latency_recorder) = self.latency_recorder.as_ref() {
+            latency_recorder.log_summary();
+        }
+
+        Ok(())
+    }
+
+    /// Records the latency of the input events reflected in the frames the terminal writer
+    /// finished writing.
+    fn record_written_frames(&mut self, tui: &Tui) -> Result<()> {
+        for written_frame in tui.take_written_frames() {
+            if let Some(latency_recorder) = self.latency_recorder.as_mut() {
+                latency_recorder.frame_written(written_frame)?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Waits for the next event and handles it along with the events that are already queued up,
+    /// so that input is not held back by rendering.
     #[instrument(skip(self, tui))]
     async fn handle_events(&mut self, tui: &mut Tui) -> Result<()> {
-        let Some(event) = tui.next_event().await else {
+        let Some(first) = tui.next_event().await else {
             return Ok(());
         };
+
+        for (event, received_at) in frame_pacer::take_queued(first, &mut tui.event_rx) {
+            self.handle_event(event, received_at)?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn handle_event(&mut self, event: Event, received_at: Instant) -> Result<()> {
+        if let Some(latency_recorder) = self.latency_recorder.as_mut()
+            && matches!(event, Event::Key(_) | Event::Mouse(_) | Event::Paste(_))
+        {
+            latency_recorder.input_received(received_at);
+        }
+
+        // Bound keys are translated before anything else sees them.
+        let event = match event {
+            Event::Key(key) => match self.translate_key(key)? {
+                Some(key) => Event::Key(key),
+                None => return Ok(()),
+            },
+            event => event,
+        };
+
         let action_tx = self.action_tx.clone();
         match event {
             Event::Quit => action_tx.send(Action::Quit)?,
-            // TODO: App could get overwhelmed by tick/render events/actions.
-            Event::Tick => action_tx.send(Action::Tick)?,
-            Event::Render => action_tx.send(Action::Render)?,
+            Event::Tick => {
+                let now = Instant::now();
+
+                if self.terminal_focused
+                    || self.last_tick_at.is_none_or(|last_tick_at| {
+                        now.duration_since(last_tick_at) >= UNFOCUSED_TICK_INTERVAL
+                    })
+                {
+                    self.last_tick_at = Some(now);
+                    action_tx.send(Action::Tick)?;
+                }
+            }
+            // Only wakes up the loop, frames are rendered by `Self::render_if_due`.
+            Event::Render => {}
             Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
-            Event::Key(key) => self.handle_key_event(key)?,
+            Event::Key(key) => {
+                // Taken before the focused component sees the key, as it focuses another one.
+                if is_alternate_panel_key(&key) {
+                    if let Some(panel) = self.find_alternate_panel() {
+                        action_tx.send(Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                            panel,
+                        }))?;
+                    }
+
+                    return Ok(());
+                }
+
+                if let Some(panel) = self.find_panel_hotkey(&key) {
+                    action_tx.send(Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                        panel,
+                    }))?;
+                    return Ok(());
+                }
+
+                self.handle_key_event(key)?
+            }
+            // Sent to the component under the pointer rather than along the focus path.
+            Event::Mouse(mouse) => return self.handle_mouse_event(mouse),
+            // Focus of the terminal window, unrelated to the focus of components.
+            Event::FocusGained | Event::FocusLost => {
+                let focused = matches!(event, Event::FocusGained);
+
+                if focused != self.terminal_focused {
+                    self.terminal_focused = focused;
+                    tracing::debug!(focused, "Terminal focus changed.");
+                    action_tx.send(Action::BroadcastMessage(
+                        ComponentMessage::OnTerminalFocusChange { focused },
+                    ))?;
+
+                    if focused {
+                        // Redraw everything, the terminal might not have preserved the contents.
+                        action_tx.send(Action::ClearScreen)?;
+                    }
+                }
+
+                return Ok(());
+            }
             _ => {}
         }
 
+        let dispatch = |result: Result<HandleEventSuccess>| -> ControlFlow<Result<()>, ()> {
+            match result {
+                Ok(HandleEventSuccess { action, absorb }) => {
+                    if let Some(action) = action
+                        && let Err(error) = action_tx.send(action)
+                    {
+                        return ControlFlow::Break(Err(error.into()));
+                    }
+
+                    if absorb {
+                        ControlFlow::Break(Ok(()))
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                }
+                // The event is not propagated any further, as the component might have handled it
+                // in part.
+                Err(error) => ControlFlow::Break(report_error(&action_tx, &error)),
+            }
+        };
+
+        // Captured from the root down to the focused component, then handled in reverse.
         self.focus_path
             .for_each_component_mut::<Result<()>>(
                 &mut *self.root_component,
-                &mut |_| ControlFlow::Continue(()),
-                &mut |focused_component| -> ControlFlow<Result<()>, ()> {
-                    match focused_component.handle_event(&event) {
-                        Ok(HandleEventSuccess { action, absorb }) => {
-                            if let Some(action) = action {
-                                action_tx.send(action).unwrap();
-                            }
-
-                            if absorb {
-                                ControlFlow::Break(Ok(()))
-                            } else {
-                                ControlFlow::Continue(())
-                            }
-                        }
-                        Err(error) => ControlFlow::Break(Err(error)),
-                    }
-                },
+                &mut |focused_component| dispatch(focused_component.capture_event(&event)),
+                &mut |focused_component| dispatch(focused_component.handle_event(&event)),
             )
             .break_value()
             .transpose()?;
@@ -147,6 +413,147 @@ impl App {
         Ok(())
     }
 
+    /// Focuses the clicked component and lets the component under the pointer handle the mouse
+    /// event, whether it is focused or not, so that scrolling does not require focusing first.
+    #[instrument(skip(self))]
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        if !matches!(
+            mouse.kind,
+            MouseEventKind::Down(_) | MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) {
+            return Ok(());
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(id) = self
+            .mouse_areas
+            .iter()
+            .rev()
+            .find(|(_, area)| area.contains(position))
+            .map(|(id, _)| *id)
+        else {
+            return Ok(());
+        };
+
+        // Everything behind a modal dialog ignores the mouse.
+        if !self.is_within_focus_trap(id) {
+            return Ok(());
+        }
+
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+            && id != self.get_focused_component_id()
+            && find_component_by_id_mut(&mut *self.root_component, id)
+                .is_some_and(|(component, _)| component.is_focusable())
+        {
+            // Focused right away rather than with an action, so that the component handles the
+            // click after gaining focus.
+            self.focus_component(id)?;
+            self.frame_pacer.request();
+        }
+
+        if let Some((component, _)) = find_component_by_id_mut(&mut *self.root_component, id) {
+            let HandleEventSuccess { action, .. } = component.handle_event(&Event::Mouse(mouse))?;
+
+            if let Some(action) = action {
+                self.action_tx.send(action)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Translates the key through the key bindings into the default chord of the bound command,
+    /// which the components handle. The commands which no component handles are carried out
+    /// right away, returning `None`. Unbound keys, and characters typed as text, are returned
+    /// unchanged.
+    fn translate_key(&mut self, key: KeyEvent) -> Result<Option<KeyEvent>> {
+        let (focused_component, _) = self
+            .focus_path
+            .find_deepest_available_component(&*self.root_component);
+        let typed_text = focused_component.accepts_text_input()
+            && matches!(key.code, KeyCode::Char(_))
+            && key.modifiers.difference(KeyModifiers::SHIFT).is_empty();
+        let Some(command) = self.keymap.translate(&key).filter(|_| !typed_text) else {
+            return Ok(Some(key));
+        };
+        let action = match command {
+            KeyCommand::Quit => Some(Action::Quit),
+            KeyCommand::FocusPaneTree => {
+                Some(Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                    panel: Panel::Tree,
+                }))
+            }
+            KeyCommand::FocusPaneContent => {
+                Some(Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                    panel: Panel::Content,
+                }))
+            }
+            KeyCommand::HistoryBack | KeyCommand::HistoryForward => Some(Action::BroadcastMessage(
+                ComponentMessage::GoThroughHistory {
+                    direction: if command == KeyCommand::HistoryBack {
+                        HistoryDirection::Back
+                    } else {
+                        HistoryDirection::Forward
+                    },
+                },
+            )),
+            KeyCommand::FocusPaneAlternate => self
+                .find_alternate_panel()
+                .map(|panel| Action::BroadcastMessage(ComponentMessage::FocusPanel { panel })),
+            // A dialog that is already open is not replaced.
+            KeyCommand::Help if component::find_focus_trap(&*self.root_component).is_none() => {
+                Some(Action::BroadcastMessage(ComponentMessage::OpenModal {
+                    request: ModalRequest::new(
+                        self.root_component.get_id(),
+                        "Key bindings",
+                        self.keymap.describe(),
+                    ),
+                }))
+            }
+            _ => return Ok(Some(command.default_chord().to_event(&key))),
+        };
+
+        if key.kind == KeyEventKind::Press
+            && let Some(action) = action
+        {
+            self.action_tx.send(action)?;
+        }
+
+        Ok(None)
+    }
+
+    /// The panel whose mnemonic, advertised in its title, is pressed. While the focused component
+    /// takes typed characters as text, the mnemonics are only recognized with Alt.
+    fn find_panel_hotkey(&self, key: &KeyEvent) -> Option<Panel> {
+        let KeyEvent {
+            code: KeyCode::Char(character),
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        } = *key
+        else {
+            return None;
+        };
+        let (focused_component, _) = self
+            .focus_path
+            .find_deepest_available_component(&*self.root_component);
+        let required_modifiers = if focused_component.accepts_text_input() {
+            KeyModifiers::ALT
+        } else {
+            KeyModifiers::NONE
+        };
+
+        if modifiers.difference(KeyModifiers::SHIFT) != required_modifiers {
+            return None;
+        }
+
+        match character.to_ascii_lowercase() {
+            't' => Some(Panel::Tree),
+            'c' => Some(Panel::Content),
+            _ => None,
+        }
+    }
+
     #[instrument(skip(self))]
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         tracing::trace!(?key);
@@ -184,6 +591,12 @@ impl App {
                     FocusChangeScope::Horizontal
                 },
             })),
+            #[cfg(feature = "layout-debug")]
+            KeyEvent {
+                code: KeyCode::F(12),
+                modifiers: KeyModifiers::SHIFT,
+                ..
+            } => Some(Action::ToggleLayoutDebug),
             _ => None,
         };
         if let Some(action) = action {
@@ -212,8 +625,11 @@ impl App {
                     .copied()
                     .unwrap_or(self.root_component.get_id());
 
+                // Only the components within a focus trap can be focused while there is one.
+                let subtree_root = component::find_focus_trap(&*self.root_component)
+                    .unwrap_or(&*self.root_component);
                 let _ = component::depth_first_search(
-                    &*self.root_component,
+                    subtree_root,
                     &mut |component| -> ControlFlow<()> {
                         if component.is_focusable() {
                             if first_focusable_component.is_none() {
@@ -254,74 +670,419 @@ impl App {
                         &mut *self.root_component,
                         next_focusable_component_id,
                     )
-                    .unwrap();
+                    .ok_or_else(|| {
+                        eyre!("The component {next_focusable_component_id:?} to focus is gone.")
+                    })?;
                     self.focus_path = focus_path;
                     newly_selected_component.handle_event(&Event::FocusGained)?;
                     tracing::debug!(focus_path=?self.focus_path, "Focus changed.");
+                    self.track_focused_panel()?;
                 }
             }
-            FocusChangeScope::Horizontal => unimplemented!(),
-            FocusChangeScope::Vertical => unimplemented!(),
+            FocusChangeScope::Horizontal | FocusChangeScope::Vertical => {
+                self.change_focus_directionally(focus_change)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Focuses the nearest focusable component in the direction of the focus change, as the
+    /// components were last drawn: rightwards or downwards for `FocusChangeDirection::Forward`.
+    /// Components side by side with the focused one are preferred to those diagonal from it.
+    fn change_focus_directionally(&mut self, focus_change: FocusChange) -> Result<()> {
+        let focused_id = self.get_focused_component_id();
+        let Some(focused_area) = self.find_drawn_area(focused_id) else {
+            return Ok(());
+        };
+        let mut focusable_ids = Vec::new();
+
+        // Only the components within a focus trap can be focused while there is one.
+        let subtree_root =
+            component::find_focus_trap(&*self.root_component).unwrap_or(&*self.root_component);
+        let _ = component::depth_first_search(
+            subtree_root,
+            &mut |component| -> ControlFlow<()> {
+                if component.is_focusable() && component.get_id() != focused_id {
+                    focusable_ids.push(component.get_id());
+                }
+
+                ControlFlow::Continue(())
+            },
+            &mut |_component| -> ControlFlow<()> { ControlFlow::Continue(()) },
+        );
+
+        let nearest_id = focusable_ids
+            .into_iter()
+            .filter_map(|id| {
+                let distance =
+                    directional_distance(focused_area, self.find_drawn_area(id)?, &focus_change)?;
+                Some((distance, id))
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, id)| id);
+
+        if let Some(nearest_id) = nearest_id {
+            self.focus_component(nearest_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// The area the component registered for the mouse when last drawn, if any.
+    fn find_drawn_area(&self, id: ComponentId) -> Option<Rect> {
+        self.mouse_areas
+            .iter()
+            .find(|(area_id, _)| *area_id == id)
+            .map(|(_, area)| *area)
+    }
+
+    #[instrument(skip(self))]
+    fn focus_component(&mut self, id: ComponentId) -> Result<()> {
+        if find_component_by_id_mut(&mut *self.root_component, id).is_none()
+            || !self.is_within_focus_trap(id)
+        {
+            return Ok(());
+        }
+
+        let (originally_selected_component, _) = self
+            .focus_path
+            .find_deepest_available_component_mut(&mut *self.root_component);
+
+        originally_selected_component.handle_event(&Event::FocusLost)?;
+
+        let (newly_selected_component, focus_path) =
+            find_component_by_id_mut(&mut *self.root_component, id)
+                .ok_or_else(|| eyre!("The component {id:?} to focus is gone."))?;
+        self.focus_path = focus_path;
+        newly_selected_component.handle_event(&Event::FocusGained)?;
+        tracing::debug!(focus_path=?self.focus_path, "Focus changed.");
+        self.track_focused_panel()
+    }
+
+    /// Remembers the previous panel once the focus moves to another one, see `PanelFocus`.
+    fn track_focused_panel(&mut self) -> Result<()> {
+        if self
+            .panel_focus
+            .track(&*self.root_component, &self.focus_path)
+        {
+            self.action_tx.send(Action::BroadcastMessage(
+                ComponentMessage::AlternatePanelChanged {
+                    panel: self.find_alternate_panel(),
+                },
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// The panel Ctrl+^ focuses, see `PanelFocus::find_alternate`.
+    fn find_alternate_panel(&self) -> Option<Panel> {
+        self.panel_focus.find_alternate(&*self.root_component)
+    }
+
+    /// Spawns the deferred work of a component, which sends the resulting message back to it.
     #[instrument(skip(self, tui))]
     fn handle_actions(&mut self, tui: &mut Tui) -> Result<()> {
         while let Ok(action) = self.action_rx.try_recv() {
             let mut component_message = None;
 
+            if let Some(action_socket) = self.action_socket.as_ref() {
+                action_socket.publish(&action);
+            }
+
             match action {
                 Action::Tick => {
                     self.last_tick_key_events.drain(..);
+
+                    if self
+                        .flash_until
+                        .is_some_and(|flash_until| flash_until <= Instant::now())
+                    {
+                        self.flash_until = None;
+                        self.frame_pacer.request();
+                    }
+
                     component_message = Some(ComponentMessage::OnTick);
                 }
                 Action::BroadcastMessage(message) => component_message = Some(message),
                 Action::Quit => self.should_quit = true,
                 Action::Suspend => self.should_suspend = true,
                 Action::Resume => self.should_suspend = false,
-                Action::ClearScreen => tui.terminal.clear()?,
+                Action::ClearScreen => {
+                    tui.terminal.clear()?;
+                    self.frame_pacer.request();
+                }
                 Action::Resize(w, h) => self.handle_resize(tui, w, h)?,
-                Action::Render => self.render(tui)?,
-                Action::FocusChange(focus_change) => self.change_focus(focus_change)?,
+                Action::Render => self.frame_pacer.request(),
+                Action::FocusChange(focus_change) => {
+                    if let Err(error) = self.change_focus(focus_change) {
+                        report_error(&self.action_tx, &error)?;
+                    }
+                    self.frame_pacer.request();
+                }
+                Action::FocusComponent(id) => {
+                    if let Err(error) = self.focus_component(id) {
+                        report_error(&self.action_tx, &error)?;
+                    }
+                    self.frame_pacer.request();
+                }
+                #[cfg(feature = "layout-debug")]
+                Action::ToggleLayoutDebug => {
+                    self.layout_debug.toggle();
+                    self.frame_pacer.request();
+                }
+                Action::ShowStatus { severity, text } => {
+                    component_message = Some(ComponentMessage::ShowNotice {
+                        message: text,
+                        severity,
+                    });
+                }
+                Action::Feedback(feedback) => self.give_feedback(tui, feedback)?,
+                Action::CopyToClipboard { text } => self.copy_to_clipboard(tui, &text)?,
+                Action::RunInTerminal { command, input } => {
+                    self.command_in_terminal = Some((command, input));
+                }
+                Action::Defer(deferred) => deferred.spawn(&self.cancellation, &self.action_tx),
+                Action::SendMessage { id, message } => {
+                    if let Some((component, _)) =
+                        find_component_by_id_mut(&mut *self.root_component, id)
+                    {
+                        match component.update(message) {
+                            Ok(Some(action)) => self.action_tx.send(action)?,
+                            Ok(None) => {}
+                            Err(error) => report_error(&self.action_tx, &error)?,
+                        }
+                    }
+                }
             }
 
-            if let Some(component_message) = component_message {
-                let _ = component::depth_first_search_mut(
+            if let Some(component_message) = component_message
+                && let ControlFlow::Break(error) = component::depth_first_search_mut(
                     &mut *self.root_component,
-                    &mut |component| -> ControlFlow<()> {
-                        if let Some(action) = component.update(component_message.clone()).unwrap() {
-                            self.action_tx.send(action).unwrap()
-                        }
+                    &mut |component| -> ControlFlow<color_eyre::Report> {
+                        // A failing component does not keep the message from the others.
+                        let result = match component.update(component_message.clone()) {
+                            Ok(Some(action)) => self.action_tx.send(action).map_err(Into::into),
+                            Ok(None) => Ok(()),
+                            Err(error) => report_error(&self.action_tx, &error),
+                        };
 
-                        ControlFlow::Continue(())
+                        match result {
+                            Ok(()) => ControlFlow::Continue(()),
+                            Err(error) => ControlFlow::Break(error),
+                        }
                     },
                     &mut |_| ControlFlow::Continue(()),
-                );
+                )
+            {
+                return Err(error);
             }
         }
         Ok(())
     }
 
+    /// Draws attention to the terminal, unless disabled for this kind of feedback or another cue
+    /// was given just before.
+    fn give_feedback(&mut self, tui: &mut Tui, feedback: Feedback) -> Result<()> {
+        let required_level = match feedback {
+            Feedback::Error => FeedbackLevel::Errors,
+            Feedback::Completion => FeedbackLevel::ErrorsAndCompletions,
+        };
+
+        if self.feedback_config.level < required_level
+            || self
+                .last_feedback_at
+                .is_some_and(|last_feedback_at| last_feedback_at.elapsed() < MIN_FEEDBACK_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        self.last_feedback_at = Some(Instant::now());
+        tracing::debug!(%feedback, method = ?self.feedback_config.method, "Feedback given.");
+
+        match self.feedback_config.method {
+            FeedbackMethod::Bell => {
+                tui.terminal.backend_mut().write_all(b"\x07")?;
+                tui.terminal.backend_mut().flush()?;
+            }
+            FeedbackMethod::Attention => {
+                tui.terminal.backend_mut().write_all(ATTENTION_SEQUENCE)?;
+                tui.terminal.backend_mut().flush()?;
+            }
+            FeedbackMethod::Flash => {
+                self.flash_until = Some(Instant::now() + FLASH_DURATION);
+                self.frame_pacer.request();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the text through the terminal, and reports how much was copied.
+    fn copy_to_clipboard(&mut self, tui: &mut Tui, text: &str) -> Result<()> {
+        let (message, severity) = match self.clipboard.copy(tui.terminal.backend_mut(), text) {
+            Ok(()) => {
+                let unit = if text.len() == 1 { "byte" } else { "bytes" };
+                (format!("Copied {} {unit}.", text.len()), Severity::Info)
+            }
+            Err(error) => (format!("Failed to copy: {error:#}"), Severity::Error),
+        };
+
+        self.action_tx
+            .send(Action::BroadcastMessage(ComponentMessage::ShowNotice {
+                message,
+                severity,
+            }))?;
+        Ok(())
+    }
+
     #[instrument(skip(self, tui))]
     fn handle_resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
         tui.resize(Rect::new(0, 0, w, h))?;
-        self.render(tui)?;
+        self.frame_pacer.request();
         Ok(())
     }
 
+    /// Renders a frame if anything changed, but at most `frame_rate` times per second.
+    /// Nothing is rendered while the terminal is not focused.
+    fn render_if_due(&mut self, tui: &mut Tui) -> Result<()> {
+        if self.terminal_focused && self.frame_pacer.is_due(Instant::now()) {
+            self.render(tui)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the components into a frame, which the terminal writer of `tui` writes to the
+    /// terminal on its own thread. The components are drawn on the event loop, as they share
+    /// state through `Rc<RefCell<_>>`, so input waits for the components to be drawn, but never
+    /// for a slow terminal. Drawing is bounded by `frame_pacer` instead, and the events queued
+    /// in the meantime are handled before the next frame.
     #[instrument(skip(self, tui))]
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
+        // Rendered once the terminal writer catches up.
+        if !tui.begin_frame()? {
+            return Ok(());
+        }
+
+        self.frame_pacer.rendered(Instant::now());
         let mut result = Ok(());
+        let focused_id = self.get_focused_component_id();
+        let mut mouse_areas = Vec::new();
         tui.draw(|frame| {
             let area = frame.area();
-            result = self.root_component.default_draw(
-                &mut DrawContext::new(frame, self.get_focused_component_id()),
-                area,
-            );
+            let mut context = DrawContext::new(frame, focused_id, self.palette);
+            result = self.root_component.default_draw(&mut context, area);
+            mouse_areas = context.take_mouse_areas();
+
+            #[cfg(feature = "layout-debug")]
+            {
+                let named_areas = context.take_named_areas();
+                self.layout_debug.draw(context.frame(), named_areas);
+            }
+
+            if self.flash_until.is_some() {
+                frame.render_widget(
+                    Block::bordered()
+                        .border_style(Style::new().fg(self.palette.warning).reversed()),
+                    area,
+                );
+            }
         })?;
-        result
+        self.mouse_areas = mouse_areas;
+
+        if let Some(latency_recorder) = self.latency_recorder.as_mut() {
+            latency_recorder.frame_flushed(tui.last_flushed_frame());
+        }
+
+        result?;
+        self.report_accessibility_tree()?;
+        #[cfg(feature = "introspection")]
+        self.report_ui_state()?;
+        Ok(())
+    }
+
+    /// Logs the snapshot of the user interface as JSON at the trace level, whenever it changes,
+    /// see `crate::introspection`.
+    #[cfg(feature = "introspection")]
+    fn report_ui_state(&mut self) -> Result<()> {
+        if !tracing::enabled!(tracing::Level::TRACE) {
+            return Ok(());
+        }
+
+        let ui_state = crate::introspection::snapshot(&*self.root_component, &self.focus_path)?;
+
+        if self.ui_state.as_ref() == Some(&ui_state) {
+            return Ok(());
+        }
+
+        tracing::trace!(ui_state = %serde_json::to_string(&ui_state)?, "User interface changed.");
+        self.ui_state = Some(ui_state);
+        Ok(())
+    }
+
+    /// Reports the nodes of the accessibility tree that changed since the last report, along with
+    /// the focus and the nodes that were removed. Every frame is rendered after a change of state,
+    /// so the tree is updated along with it.
+    ///
+    /// The support is partial: AccessKit has no adapter for terminals, so nothing reaches
+    /// assistive technology yet. The updates are only logged at the trace level, for inspecting
+    /// the tree, and not built at all otherwise.
+    fn report_accessibility_tree(&mut self) -> Result<()> {
+        if !tracing::enabled!(tracing::Level::TRACE) {
+            return Ok(());
+        }
+
+        let update = component::build_accessibility_tree(
+            &*self.root_component,
+            self.get_focused_component_id(),
+        )?;
+        let initial = self.accessibility_nodes.is_empty();
+        let current_ids = update
+            .nodes
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<HashSet<_>>();
+        // AccessKit drops the nodes their parents no longer list, and the parents are among the
+        // changed nodes. They are forgotten here too, so that they are sent again if re-added.
+        let removed_ids = self
+            .accessibility_nodes
+            .keys()
+            .filter(|id| !current_ids.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        let nodes = update
+            .nodes
+            .into_iter()
+            .filter(|(id, node)| self.accessibility_nodes.get(id) != Some(node))
+            .collect::<Vec<_>>();
+
+        if nodes.is_empty()
+            && removed_ids.is_empty()
+            && self.accessibility_focus == Some(update.focus)
+        {
+            return Ok(());
+        }
+
+        self.accessibility_nodes
+            .retain(|id, _| current_ids.contains(id));
+        self.accessibility_nodes.extend(nodes.iter().cloned());
+        self.accessibility_focus = Some(update.focus);
+        let update = accesskit::TreeUpdate {
+            nodes,
+            tree: update.tree.filter(|_| initial),
+            focus: update.focus,
+        };
+        tracing::trace!(?update, ?removed_ids, "Accessibility tree updated.");
+        Ok(())
+    }
+
+    /// Returns `true` iff the component is within the component trapping the focus, or there is
+    /// no such component.
+    fn is_within_focus_trap(&self, id: ComponentId) -> bool {
+        component::find_focus_trap(&*self.root_component)
+            .is_none_or(|focus_trap| component::contains_component(focus_trap, id))
     }
 
     fn get_focused_component_id(&self) -> ComponentId {
@@ -331,3 +1092,61 @@ impl App {
             .unwrap_or_else(|| self.root_component.get_id())
     }
 }
+
+/// Logs an error a component returned and shows it in the status bar, instead of quitting.
+/// Fails only if the action could not be sent, once the application quits.
+fn report_error(
+    action_tx: &mpsc::UnboundedSender<Action>,
+    error: &color_eyre::Report,
+) -> Result<()> {
+    tracing::error!(?error, "A component failed.");
+    action_tx.send(Action::ShowStatus {
+        severity: Severity::Error,
+        text: format!("{error:#}"),
+    })?;
+    Ok(())
+}
+
+/// How far `area` is from `from` in the direction of the focus change, or `None` if it is not in
+/// that direction. Areas overlapping `from` along the other axis order first, then by the gap
+/// between the areas, then by how far apart their starts are along the other axis.
+fn directional_distance(
+    from: Rect,
+    area: Rect,
+    focus_change: &FocusChange,
+) -> Option<(bool, u16, u16)> {
+    // Transposed for vertical changes, so that only horizontal ones need handling.
+    let transpose = |rect: Rect| Rect::new(rect.y, rect.x, rect.height, rect.width);
+    let (from, area) = if focus_change.scope == FocusChangeScope::Vertical {
+        (transpose(from), transpose(area))
+    } else {
+        (from, area)
+    };
+    // Adjacent panes may share their borders, so only their starts and ends are compared.
+    let gap = match focus_change.direction {
+        FocusChangeDirection::Forward if area.x > from.x && area.right() > from.right() => {
+            area.x.saturating_sub(from.right())
+        }
+        FocusChangeDirection::Backward if area.x < from.x && area.right() < from.right() => {
+            from.x.saturating_sub(area.right())
+        }
+        _ => return None,
+    };
+    let overlaps = area.y < from.bottom() && from.y < area.bottom();
+
+    Some((!overlaps, gap, area.y.abs_diff(from.y)))
+}
+
+/// Whether the key swaps the focus between the last two focused panels, like the alternate buffer
+/// of vim. Terminals report Ctrl+^ as Ctrl+6.
+fn is_alternate_panel_key(key: &KeyEvent) -> bool {
+    matches!(
+        key,
+        KeyEvent {
+            code: KeyCode::Char('^' | '6'),
+            kind: KeyEventKind::Press,
+            modifiers,
+            ..
+        } if modifiers.contains(KeyModifiers::CONTROL)
+    )
+}
@@ -1,6 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::capabilities::TerminalCapabilities;
+
+use crate::encoding::{Encoding, EncodingError};
+use crate::model::{split_path, RecordRef};
+use crate::report::{ReportContent, ReportFormat};
+use crate::theme::Theme;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version = VERSION_MESSAGE, about)]
@@ -9,8 +16,8 @@ pub struct Args {
     #[arg(short, long, value_name = "FLOAT", default_value_t = 4.0)]
     pub tick_rate: f64,
 
-    /// Frame rate, i.e. number of frames per second
-    #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
+    /// Frame rate, i.e. the maximum number of frames per second
+    #[arg(short, long, value_name = "FLOAT", default_value_t = 30.0)]
     pub frame_rate: f64,
 
     /// The path to a registry directory containing a `registry.cbor` file.
@@ -18,6 +25,10 @@ pub struct Args {
     #[arg(short('d'), long, default_value = ".")]
     pub registry_directory: PathBuf,
 
+    /// The colors of the user interface, overriding `display.theme` of the config.
+    #[arg(long, value_name = "THEME")]
+    pub theme: Option<Theme>,
+
     /// Enforce a maximum width of the user interface.
     #[arg(short('w'), long)]
     pub force_max_width: Option<u16>,
@@ -25,8 +36,301 @@ pub struct Args {
     /// Enforce a maximum height of the user interface.
     #[arg(short('h'), long)]
     pub force_max_height: Option<u16>,
+
+    /// Load the `.rrr-tui.toml` config file inside the registry directory.
+    /// Only enable this for registries from trusted sources.
+    #[arg(long)]
+    pub trust_registry_config: bool,
+
+    /// Override a setting of the config files, e.g. `--set editing.word_separators=-:`.
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// The TOML file binding keys to commands, `keymap.toml` in the config directory by default.
+    /// The built-in bindings are used if the file does not exist.
+    #[arg(long, value_name = "PATH")]
+    pub keymap: Option<PathBuf>,
+
+    /// Print every setting of the effective configuration along with its origin, and exit.
+    #[arg(long)]
+    pub print_effective_config: bool,
+
+    /// Append the latency between each input event and the next frame written to the terminal to a
+    /// CSV file.
+    #[arg(long, value_name = "PATH")]
+    pub latency_log: Option<PathBuf>,
+
+    /// Delay every write to the terminal by the given number of milliseconds, to measure the
+    /// latency with `--latency-log` over a slow terminal connection.
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 0)]
+    pub terminal_write_delay: u64,
+
+    /// The number of the most recent log events kept for the log view, toggled with F12.
+    #[arg(long, value_name = "COUNT", default_value_t = 5000)]
+    pub log_view_capacity: usize,
+
+    /// A `/`-separated path of record names to open, relative to the root record, with `/`
+    /// within names escaped as `\/`. The records along the path are opened in turn, stopping at
+    /// the deepest one that exists.
+    #[arg(short, long, visible_alias = "open", value_name = "PATH")]
+    pub record: Option<String>,
+
+    /// The encoding of the record names in `--record`, `utf8` by default.
+    #[arg(long, value_name = "ENCODING", requires = "record")]
+    pub record_encoding: Option<Encoding>,
+
+    /// Like `--record` with `--record-encoding hex`, e.g. `dead/beef`.
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    pub open_hex: Option<String>,
+
+    /// If another instance is already running against the registry, make it open `--record` or
+    /// `--open-hex` and exit instead of starting a second user interface.
+    #[arg(long)]
+    pub reuse: bool,
+
+    /// Write a report about `--record`, or the root record, to a file, or to stdout with `-`,
+    /// and exit.
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// The format of the `--report`.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        requires = "report"
+    )]
+    pub report_format: ReportFormat,
+
+    /// How much of the content of the record is included in the `--report`.
+    #[arg(
+        long,
+        value_name = "CONTENT",
+        default_value = "none",
+        requires = "report"
+    )]
+    pub report_content: ReportContent,
+
+    /// The number of lines or bytes included with `--report-content lines` or `hex`.
+    #[arg(long, value_name = "COUNT", default_value_t = 20, requires = "report")]
+    pub report_limit: usize,
+
+    /// Open the registry and `--record`, or the root record, the given number of times without
+    /// the user interface, print how long each phase took and exit.
+    #[arg(long, value_name = "ITERATIONS")]
+    pub bench_startup: Option<usize>,
+
+    /// Print the results of `--bench-startup` as JSON.
+    #[arg(long, requires = "bench_startup")]
+    pub bench_json: bool,
+
+    /// Start the user interface even if the terminal seems to lack the features it requires.
+    #[arg(long)]
+    pub force_tui: bool,
+
+    /// Create a unix socket streaming the processed actions as JSON lines, and accepting
+    /// commands to open records, focus panels and show notices.
+    #[arg(long, value_name = "PATH")]
+    pub action_socket: Option<PathBuf>,
+
+    /// Start with the built-in defaults only, ignoring the config files and the persisted state,
+    /// with custom commands and `--action-socket` disabled. Meant for recovering from broken
+    /// startup files, which can be diagnosed with F3.
+    #[arg(long)]
+    pub safe_mode: bool,
+
+    /// Print the commands accepted by `--action-socket` along with their arguments, and exit.
+    #[arg(long)]
+    pub list_commands: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Print a completion script for the given shell.
+    Completions { shell: clap_complete::Shell },
+    /// Print the known record paths starting with the given one, for completing `--record`.
+    #[command(name = crate::completion::COMPLETE_RECORD_COMMAND, hide = true)]
+    CompleteRecord {
+        #[arg(allow_hyphen_values = true)]
+        partial: String,
+    },
+}
+
+impl Args {
+    /// Parses the arguments, with the capabilities of the terminal included in `--version`, so
+    /// that they can be attached to bug reports.
+    pub fn parse_with_capabilities(capabilities: &TerminalCapabilities) -> Self {
+        let matches = Self::command()
+            .long_version(format!("{VERSION_MESSAGE}\n\n{capabilities}"))
+            .get_matches();
+
+        match Self::from_arg_matches(&matches) {
+            Ok(args) => args,
+            Err(error) => error.exit(),
+        }
+    }
+
+    /// The path of the key bindings file given by `--keymap`, or the default one.
+    pub fn keymap_path(&self) -> PathBuf {
+        self.keymap
+            .clone()
+            .unwrap_or_else(|| crate::env::get_config_dir().join(crate::keymap::KEYMAP_FILE_NAME))
+    }
+
+    /// The path of the record to open given by `--record` or `--open-hex`, along with the
+    /// encoding of its names and the name of the argument.
+    pub fn record_path(&self) -> Option<(&str, Encoding, &'static str)> {
+        if let Some(record) = self.record.as_deref() {
+            Some((record, self.record_encoding.unwrap_or_default(), "--record"))
+        } else {
+            self.open_hex
+                .as_deref()
+                .map(|path| (path, Encoding::HEX, "--open-hex"))
+        }
+    }
+
+    /// The record to open given by `--record` or `--open-hex`, if any.
+    pub fn record_ref(&self) -> Result<Option<RecordRef>, EncodingError> {
+        self.record_path()
+            .map(|(path, encoding, _)| RecordRef::parse_path(path, encoding))
+            .transpose()
+    }
+
+    /// Checks the combination of arguments that clap cannot check by itself, e.g. whether paths
+    /// exist. Returns all problems at once, each naming the argument to fix.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (name, rate) in [
+            ("--tick-rate", self.tick_rate),
+            ("--frame-rate", self.frame_rate),
+        ] {
+            if !(rate.is_finite() && rate > 0.0) {
+                errors.push(format!("{name} must be a positive number, got `{rate}`."));
+            }
+        }
+
+        for (name, size) in [
+            ("--force-max-width", self.force_max_width),
+            ("--force-max-height", self.force_max_height),
+        ] {
+            if size == Some(0) {
+                errors.push(format!("{name} must be greater than 0."));
+            }
+        }
+
+        // The interface shows why the registry cannot be opened by itself.
+        let without_interface = self.report.is_some() || self.bench_startup.is_some();
+
+        if without_interface && !self.registry_directory.is_dir() {
+            errors.push(format!(
+                "--registry-directory `{}` is not a directory.",
+                self.registry_directory.display()
+            ));
+        } else if without_interface && !self.registry_directory.join("registry.cbor").is_file() {
+            errors.push(format!(
+                "--registry-directory `{}` does not contain a `registry.cbor` file.",
+                self.registry_directory.display()
+            ));
+        }
+
+        if let Some((record, encoding, name)) = self.record_path() {
+            if split_path(record).iter().any(String::is_empty) {
+                errors.push(format!(
+                    "{name} `{record}` must be a `/`-separated path of non-empty record names."
+                ));
+            } else if let Err(error) = RecordRef::parse_path(record, encoding) {
+                let advice = if name == "--record" {
+                    "Fix the path or choose another --record-encoding."
+                } else {
+                    "Fix the path, or use --record for other encodings."
+                };
+                errors.push(format!(
+                    "{name} `{record}` is not a valid path in the {encoding} encoding: {error}. \
+                     {advice}"
+                ));
+            }
+        }
+
+        for setting in &self.set {
+            if !setting.contains('=') {
+                errors.push(format!(
+                    "--set `{setting}` must have the form `<KEY>=<VALUE>`."
+                ));
+            }
+        }
+
+        // Only the default file may be absent, a given one is most likely mistyped.
+        if let Some(keymap) = self.keymap.as_ref()
+            && !keymap.is_file()
+        {
+            errors.push(format!("--keymap `{}` is not a file.", keymap.display()));
+        }
+
+        if let Some(latency_log) = self.latency_log.as_ref()
+            && let Some(parent) = latency_log.parent()
+            && parent != Path::new("")
+            && !parent.is_dir()
+        {
+            errors.push(format!(
+                "--latency-log `{}` is in a directory that does not exist.",
+                latency_log.display()
+            ));
+        }
+
+        if self.reuse && self.record_path().is_none() {
+            errors.push("--reuse needs --record or --open-hex.".to_string());
+        }
+
+        if self.print_effective_config && self.reuse {
+            errors.push("--print-effective-config cannot be used with --reuse.".to_string());
+        }
+
+        if self.report.is_some() && self.reuse {
+            errors.push("--report cannot be used with --reuse.".to_string());
+        }
+
+        if self.bench_startup == Some(0) {
+            errors.push("--bench-startup must be greater than 0.".to_string());
+        }
+
+        if self.bench_startup.is_some() && (self.reuse || self.report.is_some()) {
+            errors.push("--bench-startup cannot be used with --reuse or --report.".to_string());
+        }
+
+        if let Some(report) = self.report.as_ref()
+            && report != Path::new("-")
+            && let Some(parent) = report.parent()
+            && parent != Path::new("")
+            && !parent.is_dir()
+        {
+            errors.push(format!(
+                "--report `{}` is in a directory that does not exist.",
+                report.display()
+            ));
+        }
+
+        if let Some(action_socket) = self.action_socket.as_ref() {
+            if !cfg!(unix) {
+                errors.push("--action-socket is only supported on unix.".to_string());
+            } else if action_socket.exists() {
+                errors.push(format!(
+                    "--action-socket `{}` already exists.",
+                    action_socket.display()
+                ));
+            }
+        }
+
+        errors
+    }
 }
 
+/// The exit code used when the arguments are invalid, the same one clap uses.
+pub const INVALID_ARGS_EXIT_CODE: i32 = 2;
+
 pub const VERSION_MESSAGE: &str = concat!(
     env!("CARGO_PKG_VERSION"),
     "-",
@@ -35,3 +339,242 @@ pub const VERSION_MESSAGE: &str = concat!(
     env!("VERGEN_BUILD_DATE"),
     ")"
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(arguments: &[&str]) -> Result<clap::ArgMatches, clap::Error> {
+        // `-h` is taken by `--force-max-height`, which the debug assertions of clap reject along
+        // with the generated `--help`.
+        Args::command()
+            .disable_help_flag(true)
+            .try_get_matches_from(["rrr-tui-3"].iter().chain(arguments))
+    }
+
+    fn validate(arguments: &[&str]) -> Vec<String> {
+        Args::from_arg_matches(&matches(arguments).unwrap())
+            .unwrap()
+            .validate()
+    }
+
+    /// A registry directory of its own for each test, with a `registry.cbor` file unless
+    /// `with_config` is false.
+    fn registry_directory(test_name: &str, with_config: bool) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("rrr-tui-args-{}-{test_name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        if with_config {
+            std::fs::write(directory.join("registry.cbor"), []).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn default_arguments_are_valid() {
+        assert_eq!(validate(&[]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rates_must_be_positive() {
+        assert_eq!(
+            validate(&["--tick-rate", "0", "--frame-rate", "NaN"]),
+            [
+                "--tick-rate must be a positive number, got `0`.",
+                "--frame-rate must be a positive number, got `NaN`.",
+            ]
+        );
+        assert_eq!(
+            validate(&["--tick-rate=-1"]),
+            ["--tick-rate must be a positive number, got `-1`."]
+        );
+    }
+
+    #[test]
+    fn forced_sizes_must_not_be_zero() {
+        assert_eq!(
+            validate(&["--force-max-width", "0", "--force-max-height", "0"]),
+            [
+                "--force-max-width must be greater than 0.",
+                "--force-max-height must be greater than 0.",
+            ]
+        );
+        assert_eq!(validate(&["-w", "1", "-h", "1"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn registry_is_checked_without_the_interface() {
+        let missing = registry_directory("missing_registry", false).join("missing");
+        let missing = missing.to_str().unwrap();
+        assert_eq!(
+            validate(&["-d", missing, "--report", "-"]),
+            [format!(
+                "--registry-directory `{missing}` is not a directory."
+            )]
+        );
+
+        let without_config = registry_directory("without_config", false);
+        let without_config = without_config.to_str().unwrap();
+        assert_eq!(
+            validate(&["-d", without_config, "--bench-startup", "1"]),
+            [format!(
+                "--registry-directory `{without_config}` does not contain a `registry.cbor` file."
+            )]
+        );
+
+        let valid = registry_directory("valid_registry", true);
+        assert_eq!(
+            validate(&["-d", valid.to_str().unwrap(), "--report", "-"]),
+            Vec::<String>::new()
+        );
+        // The interface explains the failure by itself.
+        assert_eq!(validate(&["-d", missing]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn record_paths_must_be_valid() {
+        assert_eq!(
+            validate(&["--record", "a//b"]),
+            ["--record `a//b` must be a `/`-separated path of non-empty record names."]
+        );
+        assert_eq!(
+            validate(&["--open-hex", "61/6"]),
+            [
+                "--open-hex `61/6` is not a valid path in the Hexadecimal Byte String encoding: \
+              expected another hexadecimal digit at position 4. Fix the path, or use --record \
+              for other encodings."
+            ]
+        );
+        assert_eq!(
+            validate(&["--record", "6g", "--record-encoding", "hex"])[0],
+            "--record `6g` is not a valid path in the Hexadecimal Byte String encoding: `g` is \
+             not a hexadecimal digit at position 1. Fix the path or choose another \
+             --record-encoding."
+        );
+        assert_eq!(validate(&["--record", "a/b"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn settings_must_be_assignments() {
+        assert_eq!(
+            validate(&["--set", "theme=dark", "--set", "theme"]),
+            ["--set `theme` must have the form `<KEY>=<VALUE>`."]
+        );
+    }
+
+    #[test]
+    fn given_files_must_exist() {
+        let directory = registry_directory("files", false);
+        let keymap = directory.join("keymap.txt");
+        let latency_log = directory.join("missing").join("latency.log");
+        let report = directory.join("missing").join("report.txt");
+
+        assert_eq!(
+            validate(&[
+                "--keymap",
+                keymap.to_str().unwrap(),
+                "--latency-log",
+                latency_log.to_str().unwrap(),
+            ]),
+            [
+                format!("--keymap `{}` is not a file.", keymap.display()),
+                format!(
+                    "--latency-log `{}` is in a directory that does not exist.",
+                    latency_log.display()
+                ),
+            ]
+        );
+
+        let registry = registry_directory("files_registry", true);
+        assert_eq!(
+            validate(&[
+                "-d",
+                registry.to_str().unwrap(),
+                "--report",
+                report.to_str().unwrap()
+            ]),
+            [format!(
+                "--report `{}` is in a directory that does not exist.",
+                report.display()
+            )]
+        );
+
+        std::fs::write(&keymap, "").unwrap();
+        assert_eq!(
+            validate(&["--keymap", keymap.to_str().unwrap()]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn conflicting_modes_are_rejected() {
+        assert_eq!(
+            validate(&["--reuse"]),
+            ["--reuse needs --record or --open-hex."]
+        );
+        assert_eq!(
+            validate(&["--record", "a", "--reuse", "--print-effective-config"]),
+            ["--print-effective-config cannot be used with --reuse."]
+        );
+
+        let registry = registry_directory("conflicting_modes", true);
+        let registry = registry.to_str().unwrap();
+        assert_eq!(
+            validate(&["-d", registry, "--record", "a", "--reuse", "--report", "-"]),
+            ["--report cannot be used with --reuse."]
+        );
+        assert_eq!(
+            validate(&["-d", registry, "--bench-startup", "0"]),
+            ["--bench-startup must be greater than 0."]
+        );
+        assert_eq!(
+            validate(&["-d", registry, "--bench-startup", "1", "--report", "-"]),
+            ["--bench-startup cannot be used with --reuse or --report."]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn action_socket_must_not_exist() {
+        let directory = registry_directory("action_socket", false);
+        let socket = directory.join("actions.sock");
+        assert_eq!(
+            validate(&["--action-socket", socket.to_str().unwrap()]),
+            Vec::<String>::new()
+        );
+
+        std::fs::write(&socket, "").unwrap();
+        assert_eq!(
+            validate(&["--action-socket", socket.to_str().unwrap()]),
+            [format!(
+                "--action-socket `{}` already exists.",
+                socket.display()
+            )]
+        );
+    }
+
+    #[test]
+    fn all_errors_are_reported_at_once() {
+        assert_eq!(
+            validate(&["--tick-rate", "0", "--set", "theme", "--reuse"]).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_exit_like_clap() {
+        for arguments in [
+            &["--tick-rate", "fast"][..],
+            &["--unknown"],
+            &["--open-hex", "61", "--record", "a"],
+        ] {
+            assert_eq!(
+                matches(arguments).unwrap_err().exit_code(),
+                INVALID_ARGS_EXIT_CODE,
+                "{arguments:?}"
+            );
+        }
+        assert_eq!(INVALID_ARGS_EXIT_CODE, 2);
+    }
+}
@@ -0,0 +1,220 @@
+//! The `--bench-startup` mode, which measures the phases of a cold start without the user
+//! interface, to catch performance regressions of opening registries and records.
+//!
+//! Every iteration loads the config, opens the registry and the record and constructs the main
+//! view from scratch, so that nothing is reused between iterations. Caches of the operating
+//! system, such as the page cache, are not cleared, as that requires elevated privileges.
+
+use std::fmt::Write;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, Result};
+use ratatui::{backend::TestBackend, Terminal};
+use rrr::registry::Registry;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::action::ComponentMessage;
+use crate::annotations::Annotations;
+use crate::args::Args;
+use crate::component::{ComponentId, DefaultDrawable, DrawContext};
+use crate::components::main_view::MainView;
+use crate::config::Config;
+use crate::keymap::KeyMap;
+use crate::model::RecordRef;
+use crate::open_pipeline::{OpenError, OpenProgress, ResolveFrom};
+use crate::session::SessionStore;
+use crate::startup::Startup;
+
+/// The size of the terminal the first frame is rendered into.
+const RENDER_WIDTH: u16 = 120;
+const RENDER_HEIGHT: u16 = 40;
+
+const PHASES: [&str; 5] = [
+    "config load",
+    "registry open",
+    "record resolve",
+    "record decrypt",
+    "first render",
+];
+
+/// Runs the benchmark and prints the statistics of each phase, to stdout.
+pub async fn run(args: &Arc<Args>, iterations: usize) -> Result<()> {
+    let mut samples = vec![Vec::with_capacity(iterations); PHASES.len()];
+
+    for _ in 0..iterations {
+        for (phase_samples, duration) in samples.iter_mut().zip(run_iteration(args).await?) {
+            phase_samples.push(duration);
+        }
+    }
+
+    for phase_samples in &mut samples {
+        phase_samples.sort();
+    }
+
+    if args.bench_json {
+        let phases = PHASES
+            .iter()
+            .zip(&samples)
+            .map(|(phase, phase_samples)| {
+                json!({
+                    "phase": phase,
+                    "min_ms": as_millis(phase_samples[0]),
+                    "median_ms": as_millis(median(phase_samples)),
+                    "p95_ms": as_millis(p95(phase_samples)),
+                    "samples_ms": phase_samples.iter().copied().map(as_millis).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            json!({
+                "version": *crate::env::PROJECT_VERSION,
+                "iterations": iterations,
+                "phases": phases,
+            })
+        );
+    } else {
+        let mut table = format!(
+            "{:<16} {:>10} {:>10} {:>10}\n",
+            "phase", "min", "median", "p95"
+        );
+
+        for (phase, phase_samples) in PHASES.iter().zip(&samples) {
+            writeln!(
+                table,
+                "{phase:<16} {:>10} {:>10} {:>10}",
+                format_duration(phase_samples[0]),
+                format_duration(median(phase_samples)),
+                format_duration(p95(phase_samples)),
+            )?;
+        }
+
+        print!("{table}");
+    }
+
+    Ok(())
+}
+
+/// Starts cold and returns the duration of each of the `PHASES`.
+async fn run_iteration(args: &Arc<Args>) -> Result<[Duration; PHASES.len()]> {
+    let started_at = Instant::now();
+    let config = if args.safe_mode {
+        Config::default()
+    } else {
+        Config::new(args)?
+    };
+    let config_loaded_at = Instant::now();
+
+    let registry = Arc::new(Registry::open(args.registry_directory.clone()).await?);
+    let registry_opened_at = Instant::now();
+
+    let record_ref = args.record_ref()?.unwrap_or_else(RecordRef::root);
+    let mut resolved_at = None;
+    let loaded_record = crate::open_pipeline::open(
+        &registry,
+        &record_ref,
+        ResolveFrom::Root,
+        &mut |progress| {
+            if progress == OpenProgress::ListingVersions {
+                resolved_at = Some(Instant::now());
+            }
+        },
+        &CancellationToken::new(),
+    )
+    .await
+    .map_err(|error| match error {
+        OpenError::NotFound { .. } => eyre!(
+            "The record {} does not exist.",
+            record_ref.display_path(config.display.lossy_names)
+        ),
+        error => error.into(),
+    })?;
+    let loaded_at = Instant::now();
+    let resolved_at = resolved_at.unwrap_or(loaded_at);
+
+    // Drawn into a buffer rather than the terminal, so colors are not reduced.
+    let palette = args.theme.unwrap_or(config.display.theme).palette(u16::MAX);
+    let startup = Startup {
+        config: Arc::new(config),
+        keymap: KeyMap::default(),
+        annotations: Annotations::default(),
+        registry: Some(registry),
+        registry_summary: None,
+        session_store: SessionStore::default(),
+        session_marker: None,
+        restored_session: None,
+        upgraded_from: None,
+        startup_file_diagnosis: None,
+        warnings: Vec::new(),
+    };
+    let (action_tx, _action_rx) = mpsc::unbounded_channel();
+    // Cancels the record the main view starts opening on its own, as it is handed over below.
+    let cancellation = CancellationToken::new();
+    let mut main_view = MainView::new(
+        ComponentId::root(),
+        &action_tx,
+        &cancellation,
+        args,
+        startup,
+    )
+    .await?;
+    cancellation.cancel();
+
+    let message = ComponentMessage::RecordOpen {
+        record_ref,
+        hashed_record_key: loaded_record.hashed_record_key,
+        read_result: Some(loaded_record.record),
+        versions: loaded_record.versions,
+    };
+    let mut result = Ok(());
+    let _ = crate::component::depth_first_search_mut(
+        &mut main_view,
+        &mut |component| match component.update(message.clone()) {
+            Ok(_) => ControlFlow::Continue(()),
+            Err(error) => {
+                result = Err(error);
+                ControlFlow::Break(())
+            }
+        },
+        &mut |_| ControlFlow::Continue(()),
+    );
+    result?;
+
+    let mut terminal = Terminal::new(TestBackend::new(RENDER_WIDTH, RENDER_HEIGHT))?;
+    let mut result = Ok(());
+    terminal.draw(|frame| {
+        let area = frame.area();
+        let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+        result = main_view.default_draw(&mut context, area);
+    })?;
+    result?;
+    let rendered_at = Instant::now();
+
+    Ok([
+        config_loaded_at - started_at,
+        registry_opened_at - config_loaded_at,
+        resolved_at - registry_opened_at,
+        loaded_at - resolved_at,
+        rendered_at - loaded_at,
+    ])
+}
+
+fn median(sorted: &[Duration]) -> Duration {
+    sorted[sorted.len() / 2]
+}
+
+fn p95(sorted: &[Duration]) -> Duration {
+    sorted[(sorted.len() * 95).div_ceil(100) - 1]
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", as_millis(duration))
+}
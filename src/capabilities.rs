@@ -0,0 +1,99 @@
+//! Detection of the features of the terminal the user interface relies on.
+//! Terminals cannot be queried reliably before the user interface starts, so the detection is
+//! based on the environment, like terminfo-based programs do.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+/// The exit code used when the terminal lacks features required by the user interface.
+pub const UNSUPPORTED_TERMINAL_EXIT_CODE: i32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// The value of `TERM`, if set.
+    pub term: Option<String>,
+    /// Whether stdout, which the user interface is drawn to, is a terminal.
+    pub is_terminal: bool,
+    pub alternate_screen: bool,
+    pub cursor_positioning: bool,
+    /// The number of colors available, 0 if colors are unavailable or disabled with `NO_COLOR`.
+    pub color_count: u16,
+    pub mouse: bool,
+}
+
+impl TerminalCapabilities {
+    pub fn probe() -> Self {
+        let term = std::env::var("TERM").ok().filter(|term| !term.is_empty());
+        // The Windows console supports everything through its API, regardless of `TERM`.
+        let dumb = !cfg!(windows) && term.as_deref().is_none_or(|term| term == "dumb");
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let color_count = if dumb || no_color {
+            0
+        } else {
+            crossterm::style::available_color_count()
+        };
+        // The Linux console and hardware terminals do not report mouse events.
+        let mouse = !dumb
+            && !term
+                .as_deref()
+                .is_some_and(|term| term == "linux" || term.starts_with("vt"));
+
+        Self {
+            term,
+            is_terminal: std::io::stdout().is_terminal(),
+            alternate_screen: !dumb,
+            cursor_positioning: !dumb,
+            color_count,
+            mouse,
+        }
+    }
+
+    /// The features the user interface cannot work without, which are missing.
+    pub fn missing_required(&self) -> Vec<&'static str> {
+        [
+            (!self.is_terminal, "a terminal on stdout"),
+            (!self.alternate_screen, "the alternate screen"),
+            (!self.cursor_positioning, "cursor positioning"),
+        ]
+        .into_iter()
+        .filter_map(|(missing, feature)| missing.then_some(feature))
+        .collect()
+    }
+
+    /// Notices about the missing features the user interface works without, in a degraded way.
+    /// The mouse is not used by the user interface, so its absence is only reported.
+    pub fn degradation_notices(&self) -> Vec<String> {
+        let mut notices = Vec::new();
+
+        if self.color_count < 8 {
+            notices.push(
+                "The terminal does not support colors, highlights may be hard to tell apart."
+                    .to_string(),
+            );
+        }
+
+        notices
+    }
+}
+
+impl Display for TerminalCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let supported = |supported: bool| if supported { "yes" } else { "no" };
+
+        writeln!(f, "Terminal capabilities:")?;
+        writeln!(f, "  TERM: {}", self.term.as_deref().unwrap_or("unset"))?;
+        writeln!(f, "  stdout is a terminal: {}", supported(self.is_terminal))?;
+        writeln!(
+            f,
+            "  alternate screen: {}",
+            supported(self.alternate_screen)
+        )?;
+        writeln!(
+            f,
+            "  cursor positioning: {}",
+            supported(self.cursor_positioning)
+        )?;
+        writeln!(f, "  colors: {}", self.color_count)?;
+        write!(f, "  mouse: {}", supported(self.mouse))
+    }
+}
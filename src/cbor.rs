@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use itertools::Itertools;
 use ratatui::{
-    style::{Color, Style},
+    style::Stylize,
     text::{Line, Span},
     widgets::Row,
 };
@@ -11,8 +11,11 @@ use rrr::{
     record::RecordMetadataKey,
 };
 
+use crate::text::format_size;
+
+/// Type annotations are dimmed rather than colored, so that they are subdued in any theme.
 fn styled<'a>(string: impl Into<Cow<'a, str>>) -> Span<'a> {
-    Span::styled(string, Style::new().fg(Color::Rgb(0x3F, 0x3F, 0x3F)))
+    Span::raw(string).dim()
 }
 
 fn line<'a>(ty: impl AsRef<str>, content: impl Into<Line<'a>>) -> Line<'a> {
@@ -21,14 +24,25 @@ fn line<'a>(ty: impl AsRef<str>, content: impl Into<Line<'a>>) -> Line<'a> {
     l
 }
 
-pub fn record_metadata_to_row<'a>(key: RecordMetadataKey<'a>, value: &'a cbor::Value) -> Row<'a> {
+/// A row of the key and the value, preceded by a column with the marker of the entry's change.
+pub fn record_metadata_to_row<'a>(
+    marker: &'static str,
+    key: RecordMetadataKey<'a>,
+    value: &'a cbor::Value,
+) -> Row<'a> {
+    let key = match key {
+        RecordMetadataKey::Id(id) => Line::raw(id.to_string()),
+        RecordMetadataKey::Custom(key) => cbor_value_to_line(key.0),
+    };
+
+    Row::new([Line::raw(marker), key, cbor_value_to_line(value)])
+}
+
+/// A textual representation of the key, suitable for comparing keys across record versions.
+pub fn record_metadata_key_to_string(key: &RecordMetadataKey<'_>) -> String {
     match key {
-        RecordMetadataKey::Id(id) => {
-            Row::new([Line::raw(id.to_string()), cbor_value_to_line(value)])
-        }
-        RecordMetadataKey::Custom(key) => {
-            Row::new([cbor_value_to_line(key.0), cbor_value_to_line(value)])
-        }
+        RecordMetadataKey::Id(id) => id.to_string(),
+        RecordMetadataKey::Custom(key) => cbor_value_to_line(key.0).to_string(),
     }
 }
 
@@ -38,7 +52,10 @@ pub fn cbor_value_to_line(value: &cbor::Value) -> Line {
     }
 
     if let Some(bytes) = value.as_bytes() {
-        return line("bytes", format!("{:02x}", bytes.iter().format("")));
+        return line(
+            format!("bytes({})", format_size(bytes.len())),
+            format!("{:02x}", bytes.iter().format("")),
+        );
     }
 
     if let Some(float) = value.as_float() {
@@ -104,3 +121,76 @@ pub fn cbor_value_to_line(value: &cbor::Value) -> Line {
 
     panic!("Unrecognized type of CBOR value: {value:?}");
 }
+
+/// The value as copied to the clipboard: text as it is, and other values as JSON, so that byte
+/// strings are copied as hex, see `cbor_value_to_json`.
+pub fn cbor_value_to_clipboard_text(value: &cbor::Value) -> String {
+    match cbor_value_to_json(value) {
+        serde_json::Value::String(text) => text,
+        json => json.to_string(),
+    }
+}
+
+/// Converts the value to JSON, for consumption by other programs.
+/// Byte strings become hex strings, integers that do not fit a JSON number become decimal strings,
+/// and map keys that are not text are rendered as by `cbor_value_to_line`.
+pub fn cbor_value_to_json(value: &cbor::Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    if let Some(integer) = value.as_integer() {
+        let integer = i128::from(integer);
+
+        return i64::try_from(integer)
+            .map(Json::from)
+            .or_else(|_| u64::try_from(integer).map(Json::from))
+            .unwrap_or_else(|_| Json::String(integer.to_string()));
+    }
+
+    if let Some(bytes) = value.as_bytes() {
+        return Json::String(format!("{:02x}", bytes.iter().format("")));
+    }
+
+    if let Some(float) = value.as_float() {
+        return Json::from(float);
+    }
+
+    if let Some(text) = value.as_text() {
+        return Json::String(text.to_string());
+    }
+
+    if let Some(boolean) = value.as_bool() {
+        return Json::Bool(boolean);
+    }
+
+    if value.is_null() {
+        return Json::Null;
+    }
+
+    if let Some(array) = value.as_array() {
+        return Json::Array(array.iter().map(cbor_value_to_json).collect());
+    }
+
+    if let Some(map) = value.as_map() {
+        return Json::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = key
+                        .as_text()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| cbor_value_to_line(key).to_string());
+                    (key, cbor_value_to_json(value))
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(datetime) = value.as_datetime() {
+        return Json::String(datetime.to_rfc3339());
+    }
+
+    if let Some((_tag, inner)) = value.as_tag() {
+        return cbor_value_to_json(inner);
+    }
+
+    panic!("Unrecognized type of CBOR value: {value:?}");
+}
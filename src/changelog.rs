@@ -0,0 +1,197 @@
+//! The notable changes of each release, embedded at compile time from `changelog.json`, and shown
+//! once after upgrading to a newer version.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::Result;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::env::PROJECT_VERSION;
+
+/// Name of the file inside the state directory holding the version of the last run.
+const LAST_RUN_VERSION_FILE_NAME: &str = "last-run-version";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Release {
+    version: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Change {
+    /// The part of the application the change is grouped under, such as `content`.
+    area: String,
+    summary: String,
+}
+
+lazy_static! {
+    /// The releases, newest first.
+    static ref RELEASES: Vec<Release> =
+        serde_json::from_str(include_str!("../changelog.json"))
+            .expect("The embedded changelog is invalid.");
+}
+
+/// The numeric components of a version, without any pre-release or build suffix, for comparing
+/// versions.
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|component| component.parse().unwrap_or_default())
+        .collect()
+}
+
+/// Describes the changes of the releases after `since` up to the current version, or of all of
+/// them if `None`, grouped by area. Empty if there are no such changes.
+pub fn describe(since: Option<&str>) -> String {
+    let current = version_key(&PROJECT_VERSION);
+    let mut description = String::new();
+
+    for release in RELEASES.iter().filter(|release| {
+        let version = version_key(&release.version);
+        version <= current && since.is_none_or(|since| version > version_key(since))
+    }) {
+        let mut areas = BTreeMap::<&str, Vec<&str>>::new();
+
+        for change in &release.changes {
+            areas.entry(&change.area).or_default().push(&change.summary);
+        }
+
+        writeln!(description, "v{}", release.version).unwrap();
+
+        for (area, summaries) in areas {
+            writeln!(description, "  {area}").unwrap();
+
+            for summary in summaries {
+                writeln!(description, "    • {summary}").unwrap();
+            }
+        }
+
+        description.push('\n');
+    }
+
+    description
+}
+
+fn last_run_version_path() -> PathBuf {
+    crate::env::get_state_dir().join(LAST_RUN_VERSION_FILE_NAME)
+}
+
+/// Records that the current version ran, returning the version that ran before if the current
+/// one is newer. Nothing is returned on the first run, as everything is new then, nor after a
+/// downgrade.
+pub fn record_run() -> Result<Option<String>> {
+    record_run_to(&last_run_version_path(), &PROJECT_VERSION)
+}
+
+fn record_run_to(path: &Path, current_version: &str) -> Result<Option<String>> {
+    let last_run_version = match std::fs::read_to_string(path) {
+        Ok(version) => Some(version.trim().to_string()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error.into()),
+    };
+
+    if last_run_version.as_deref() == Some(current_version) {
+        return Ok(None);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, current_version)?;
+    Ok(last_run_version.filter(|version| version_key(version) < version_key(current_version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for the marker file of each test, as tests run in parallel.
+    fn marker_path(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-changelog-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory.join(LAST_RUN_VERSION_FILE_NAME)
+    }
+
+    fn marker(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn first_run_shows_nothing() {
+        let path = marker_path("first_run");
+
+        assert_eq!(record_run_to(&path, "0.2.0").unwrap(), None);
+        assert_eq!(marker(&path), "0.2.0");
+        // Only once, the next run being of the same version.
+        assert_eq!(record_run_to(&path, "0.2.0").unwrap(), None);
+    }
+
+    #[test]
+    fn same_version_shows_nothing() {
+        let path = marker_path("same_version");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "0.2.0\n").unwrap();
+
+        assert_eq!(record_run_to(&path, "0.2.0").unwrap(), None);
+    }
+
+    #[test]
+    fn upgrade_shows_the_changes_since_the_last_run_once() {
+        let path = marker_path("upgrade");
+        record_run_to(&path, "0.1.9").unwrap();
+
+        assert_eq!(
+            record_run_to(&path, "0.2.0").unwrap().as_deref(),
+            Some("0.1.9")
+        );
+        assert_eq!(marker(&path), "0.2.0");
+        assert_eq!(record_run_to(&path, "0.2.0").unwrap(), None);
+        // Compared by their numeric components, not as text.
+        assert_eq!(
+            record_run_to(&path, "0.10.0").unwrap().as_deref(),
+            Some("0.2.0")
+        );
+    }
+
+    #[test]
+    fn downgrade_shows_nothing_and_upgrading_again_does() {
+        let path = marker_path("downgrade");
+        record_run_to(&path, "0.2.0").unwrap();
+
+        assert_eq!(record_run_to(&path, "0.1.0").unwrap(), None);
+        assert_eq!(marker(&path), "0.1.0");
+        assert_eq!(
+            record_run_to(&path, "0.2.0").unwrap().as_deref(),
+            Some("0.1.0")
+        );
+        // Pre-release suffixes are ignored, so a pre-release of the same version is not newer.
+        assert_eq!(record_run_to(&path, "0.2.0-rc.1").unwrap(), None);
+    }
+
+    #[test]
+    fn changes_are_grouped_by_area() {
+        let description = describe(None);
+
+        assert!(description.starts_with(&format!("v{}\n", RELEASES[0].version)));
+        let areas = description
+            .lines()
+            .filter(|line| line.starts_with("  ") && !line.starts_with("    "))
+            .collect::<Vec<_>>();
+        let mut sorted = areas.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(areas, sorted);
+        // Nothing is new since the current version.
+        assert_eq!(describe(Some(&PROJECT_VERSION)), "");
+    }
+}
@@ -0,0 +1,66 @@
+//! Copying text to the clipboard through the OSC 52 escape sequence, which the terminal forwards
+//! to the clipboard of the machine it runs on, so that copying works over SSH as well. Terminals
+//! that do not support the sequence ignore it, so its failure cannot be detected.
+//!
+//! With the `native-clipboard` feature, the clipboard of the machine the application runs on is
+//! also set, unless the session is remote, for terminals without OSC 52.
+
+use std::fmt::Debug;
+use std::io::Write;
+
+use base64::Engine;
+use color_eyre::eyre::{Result, WrapErr};
+
+/// Writes the OSC 52 sequence setting the clipboard to `text`.
+fn write_osc52(writer: &mut impl Write, text: &str) -> std::io::Result<()> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    write!(writer, "\x1b]52;c;{encoded}\x07")?;
+    writer.flush()
+}
+
+#[derive(Default)]
+pub struct Clipboard {
+    /// Kept open once used, as some platforms only serve the copied text while it is open.
+    #[cfg(feature = "native-clipboard")]
+    native: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copies `text` through the terminal written to by `writer`.
+    pub fn copy(&mut self, writer: &mut impl Write, text: &str) -> Result<()> {
+        write_osc52(writer, text).wrap_err("Failed to write to the terminal.")?;
+
+        #[cfg(feature = "native-clipboard")]
+        if !is_remote_session()
+            && let Err(error) = self.copy_natively(text)
+        {
+            // The terminal may have received the text nonetheless.
+            tracing::warn!(%error, "Failed to copy to the native clipboard.");
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "native-clipboard")]
+    fn copy_natively(&mut self, text: &str) -> Result<()> {
+        let native = match self.native.as_mut() {
+            Some(native) => native,
+            None => self.native.insert(arboard::Clipboard::new()?),
+        };
+        native.set_text(text)?;
+        Ok(())
+    }
+}
+
+impl Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Clipboard").finish_non_exhaustive()
+    }
+}
+
+/// Whether the application runs on another machine than the terminal, whose clipboard is the one
+/// the user expects to copy to.
+#[cfg(feature = "native-clipboard")]
+fn is_remote_session() -> bool {
+    std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some()
+}
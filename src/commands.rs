@@ -0,0 +1,400 @@
+//! The commands which can be run through other means than key bindings, such as the action
+//! socket, with their arguments described by schemas, so that every entry point parses and
+//! validates them the same way.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+
+use serde_json::{Map, Value};
+
+use crate::action::{Action, ComponentMessage, Panel, Severity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentKind {
+    /// A `/`-separated path of non-empty UTF-8 record names, relative to the root record, with
+    /// `/` and `\` within names escaped as in `crate::model::split_path`.
+    RecordPath,
+    Text {
+        max_length: usize,
+    },
+    /// One of the listed values.
+    Choice(&'static [&'static str]),
+    /// The ID of an operation included in the errors it reports, see `crate::tasks::OperationId`.
+    ErrorId,
+}
+
+impl Display for ArgumentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RecordPath => write!(f, "record path"),
+            Self::Text { max_length } => write!(f, "text of up to {max_length} characters"),
+            Self::Choice(choices) => write!(f, "one of {}", choices.join(", ")),
+            Self::ErrorId => write!(f, "error id of 6 hex digits"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgumentSpec {
+    pub name: &'static str,
+    pub kind: ArgumentKind,
+}
+
+/// The validated arguments of a command, by name. Every argument is required.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Arguments(BTreeMap<&'static str, String>);
+
+impl Arguments {
+    fn text(&self, name: &str) -> &str {
+        self.0
+            .get(name)
+            .unwrap_or_else(|| unreachable!("The argument {name} is not in the schema."))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSpec {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub category: &'static str,
+    pub arguments: &'static [ArgumentSpec],
+    execute: fn(&Arguments) -> Action,
+}
+
+pub static COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        id: "open_record",
+        title: "Open a record",
+        category: "Navigation",
+        arguments: &[ArgumentSpec {
+            name: "path",
+            kind: ArgumentKind::RecordPath,
+        }],
+        execute: |arguments| {
+            Action::BroadcastMessage(ComponentMessage::OpenRecordPath {
+                path: arguments.text("path").to_string(),
+            })
+        },
+    },
+    CommandSpec {
+        id: "focus_panel",
+        title: "Focus a panel",
+        category: "Navigation",
+        arguments: &[ArgumentSpec {
+            name: "panel",
+            kind: ArgumentKind::Choice(&["open", "tree", "content"]),
+        }],
+        execute: |arguments| {
+            Action::BroadcastMessage(ComponentMessage::FocusPanel {
+                panel: match arguments.text("panel") {
+                    "open" => Panel::Open,
+                    "tree" => Panel::Tree,
+                    _ => Panel::Content,
+                },
+            })
+        },
+    },
+    CommandSpec {
+        id: "show_whats_new",
+        title: "Show what is new in each version",
+        category: "Interface",
+        arguments: &[],
+        execute: |_| {
+            Action::BroadcastMessage(ComponentMessage::ShowDerivedView {
+                title: "what's new".to_string(),
+                data: crate::changelog::describe(None).into_bytes(),
+            })
+        },
+    },
+    CommandSpec {
+        id: "show_notice",
+        title: "Show a notice in the header",
+        category: "Interface",
+        arguments: &[ArgumentSpec {
+            name: "message",
+            kind: ArgumentKind::Text { max_length: 500 },
+        }],
+        execute: |arguments| {
+            Action::BroadcastMessage(ComponentMessage::ShowNotice {
+                message: arguments.text("message").to_string(),
+                severity: Severity::Info,
+            })
+        },
+    },
+    CommandSpec {
+        id: "find_log_entries",
+        title: "Find log entries for error id…",
+        category: "Interface",
+        arguments: &[ArgumentSpec {
+            name: "error_id",
+            kind: ArgumentKind::ErrorId,
+        }],
+        execute: |arguments| {
+            Action::BroadcastMessage(ComponentMessage::FindLogEntries {
+                operation_id: arguments.text("error_id").to_ascii_lowercase(),
+            })
+        },
+    },
+    CommandSpec {
+        id: "list_temp_files",
+        title: "List the temporary files holding record contents",
+        category: "Interface",
+        arguments: &[],
+        execute: |_| {
+            Action::BroadcastMessage(ComponentMessage::ShowDerivedView {
+                title: "temporary files".to_string(),
+                data: crate::tempfiles::describe_alive().into_bytes(),
+            })
+        },
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    UnknownCommand {
+        id: String,
+    },
+    UnknownArgument {
+        command: &'static str,
+        argument: String,
+    },
+    MissingArgument {
+        command: &'static str,
+        argument: &'static str,
+    },
+    InvalidArgument {
+        command: &'static str,
+        argument: &'static str,
+        expected: ArgumentKind,
+    },
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand { id } => write!(f, "Unknown command `{id}`."),
+            Self::UnknownArgument { command, argument } => {
+                write!(
+                    f,
+                    "Unknown argument `{argument}` of the command `{command}`."
+                )
+            }
+            Self::MissingArgument { command, argument } => {
+                write!(
+                    f,
+                    "Missing argument `{argument}` of the command `{command}`."
+                )
+            }
+            Self::InvalidArgument {
+                command,
+                argument,
+                expected,
+            } => write!(
+                f,
+                "Invalid argument `{argument}` of the command `{command}`, expected {expected}."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl CommandSpec {
+    /// Validates the arguments against the schema of the command.
+    pub fn parse(&self, values: &Map<String, Value>) -> Result<Arguments, CommandError> {
+        if let Some(argument) = values
+            .keys()
+            .find(|name| !self.arguments.iter().any(|spec| spec.name == *name))
+        {
+            return Err(CommandError::UnknownArgument {
+                command: self.id,
+                argument: argument.clone(),
+            });
+        }
+
+        let mut arguments = Arguments::default();
+
+        for spec in self.arguments {
+            let invalid = || CommandError::InvalidArgument {
+                command: self.id,
+                argument: spec.name,
+                expected: spec.kind,
+            };
+            let Some(value) = values.get(spec.name) else {
+                return Err(CommandError::MissingArgument {
+                    command: self.id,
+                    argument: spec.name,
+                });
+            };
+            let Value::String(value) = value else {
+                return Err(invalid());
+            };
+            let valid = match spec.kind {
+                ArgumentKind::RecordPath => {
+                    !crate::model::split_path(value).iter().any(String::is_empty)
+                }
+                ArgumentKind::Text { max_length } => value.chars().count() <= max_length,
+                ArgumentKind::Choice(choices) => choices.contains(&value.as_str()),
+                ArgumentKind::ErrorId => {
+                    value.len() == 6 && value.chars().all(|char| char.is_ascii_hexdigit())
+                }
+            };
+
+            if !valid {
+                return Err(invalid());
+            }
+
+            arguments.0.insert(spec.name, value.clone());
+        }
+
+        Ok(arguments)
+    }
+
+    pub fn execute(&self, arguments: &Arguments) -> Action {
+        (self.execute)(arguments)
+    }
+}
+
+pub fn find(id: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|command| command.id == id)
+}
+
+/// Parses the command with the arguments and turns it into an action.
+pub fn parse(id: &str, values: &Map<String, Value>) -> Result<Action, CommandError> {
+    let command = find(id).ok_or_else(|| CommandError::UnknownCommand { id: id.to_string() })?;
+    let arguments = command.parse(values)?;
+    Ok(command.execute(&arguments))
+}
+
+/// Lists the commands by category, along with their arguments, for `--list-commands`.
+pub fn describe_commands() -> String {
+    let mut description = String::new();
+    let mut categories = COMMANDS
+        .iter()
+        .map(|command| command.category)
+        .collect::<Vec<_>>();
+    categories.dedup();
+
+    for category in categories {
+        writeln!(description, "{category}:").unwrap();
+
+        for command in COMMANDS
+            .iter()
+            .filter(|command| command.category == category)
+        {
+            writeln!(description, "  {}  {}", command.id, command.title).unwrap();
+
+            for argument in command.arguments {
+                writeln!(description, "    {}: {}", argument.name, argument.kind).unwrap();
+            }
+        }
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn arguments(value: Value) -> Map<String, Value> {
+        let Value::Object(arguments) = value else {
+            panic!("expected an object, got {value}");
+        };
+        arguments
+    }
+
+    fn invalid(command: &'static str, argument: &'static str) -> CommandError {
+        CommandError::InvalidArgument {
+            command,
+            argument,
+            expected: find(command).unwrap().arguments[0].kind,
+        }
+    }
+
+    #[test]
+    fn record_paths_are_split_like_everywhere_else() {
+        for path in ["a", "a/b", r"a\/b", r"a\\/b", r"\"] {
+            assert_eq!(
+                parse("open_record", &arguments(json!({ "path": path }))),
+                Ok(Action::BroadcastMessage(ComponentMessage::OpenRecordPath {
+                    path: path.to_string(),
+                })),
+                "{path}"
+            );
+        }
+
+        for path in ["", "/", "a//b", "a/", r"a\\/"] {
+            assert_eq!(
+                parse("open_record", &arguments(json!({ "path": path }))),
+                Err(invalid("open_record", "path")),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        for (command, values) in [
+            ("focus_panel", json!({ "panel": "menu" })),
+            ("focus_panel", json!({ "panel": 1 })),
+            ("show_notice", json!({ "message": "é".repeat(501) })),
+            ("find_log_entries", json!({ "error_id": "12345" })),
+            ("find_log_entries", json!({ "error_id": "12345g" })),
+        ] {
+            let argument = find(command).unwrap().arguments[0].name;
+
+            assert_eq!(
+                parse(command, &arguments(values.clone())),
+                Err(invalid(command, argument)),
+                "{values}"
+            );
+        }
+
+        assert!(parse(
+            "show_notice",
+            &arguments(json!({ "message": "é".repeat(500) }))
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn missing_and_unknown_arguments_are_rejected() {
+        assert_eq!(
+            parse("focus_panel", &Map::new()),
+            Err(CommandError::MissingArgument {
+                command: "focus_panel",
+                argument: "panel",
+            })
+        );
+        assert_eq!(
+            parse("show_whats_new", &arguments(json!({ "version": "0.1.0" }))),
+            Err(CommandError::UnknownArgument {
+                command: "show_whats_new",
+                argument: "version".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("quit", &Map::new()),
+            Err(CommandError::UnknownCommand {
+                id: "quit".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn errors_describe_the_expected_argument() {
+        assert_eq!(
+            invalid("focus_panel", "panel").to_string(),
+            "Invalid argument `panel` of the command `focus_panel`, expected one of open, tree, \
+             content."
+        );
+        assert_eq!(
+            invalid("find_log_entries", "error_id").to_string(),
+            "Invalid argument `error_id` of the command `find_log_entries`, expected error id of 6 \
+             hex digits."
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Dynamic shell completion of `--record`, from the records known without opening the registry,
+//! which only stores the hashes of record names: the records with an alias, and the ones the
+//! saved sessions were on.
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use clap_complete::Shell;
+
+use crate::annotations::Annotations;
+use crate::model::{escape_name, split_path, RecordRef};
+use crate::session::SessionStore;
+
+/// The hidden subcommand printing the completions, see `Command::CompleteRecord`.
+pub const COMPLETE_RECORD_COMMAND: &str = "__complete-record";
+
+/// Completion gives up after this long, rather than holding up the shell.
+const COMPLETION_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A record whose path can be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownRecord {
+    pub record_ref: RecordRef,
+    pub alias: Option<String>,
+}
+
+/// The records known from the annotations and the session snapshots, without duplicates.
+pub fn known_records(annotations: &Annotations, sessions: &SessionStore) -> Vec<KnownRecord> {
+    let mut known = annotations
+        .aliases()
+        .map(|(record_ref, alias)| KnownRecord {
+            record_ref,
+            alias: Some(alias.to_string()),
+        })
+        .collect::<Vec<_>>();
+
+    for snapshot in sessions.snapshots() {
+        if let Some(record_ref) = snapshot.record_ref.as_ref()
+            && !known
+                .iter()
+                .any(|known| known.record_ref.path() == record_ref.path())
+        {
+            known.push(KnownRecord {
+                record_ref: record_ref.at_version(None),
+                alias: None,
+            });
+        }
+    }
+
+    known
+}
+
+/// The byte offset of the last name of `path`, after its last separator which is not escaped.
+fn last_name_start(path: &str) -> usize {
+    let mut start = 0;
+    let mut chars = path.char_indices();
+
+    while let Some((offset, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            }
+            '/' => start = offset + 1,
+            _ => {}
+        }
+    }
+
+    start
+}
+
+/// The `/`-separated UTF-8 paths, as accepted by `--record`, completing the last name of
+/// `partial` with the names of the known records and their ancestors, sorted. If `partial` has no
+/// separator, it is also completed to the paths of the records whose alias starts with it.
+/// Names which are not valid UTF-8 cannot be typed into such paths, so they are never completed.
+pub fn complete_record_path(known: &[KnownRecord], partial: &str) -> Vec<String> {
+    let start = last_name_start(partial);
+    let (parent, last_name) = partial.split_at(start);
+    let parent_path = RecordRef::from_utf8_path(parent);
+    let depth = parent_path.path().len();
+    let prefix = split_path(last_name).swap_remove(0);
+    let mut completions = Vec::new();
+
+    for known in known {
+        let path = known.record_ref.path();
+
+        if path.len() > depth
+            && path[..depth] == *parent_path.path()
+            && let Ok(name) = std::str::from_utf8(&path[depth])
+            && name.starts_with(&prefix)
+        {
+            completions.push(format!("{parent}{}", escape_name(name)));
+        }
+
+        if start == 0
+            && !prefix.is_empty()
+            && known
+                .alias
+                .as_deref()
+                .is_some_and(|alias| alias.starts_with(&prefix))
+            && let Some(names) = path
+                .iter()
+                .map(|name| std::str::from_utf8(name).ok().map(escape_name))
+                .collect::<Option<Vec<_>>>()
+        {
+            completions.push(names.join("/"));
+        }
+    }
+
+    completions.sort();
+    completions.dedup();
+    completions
+}
+
+/// Prints the completions of `partial`, one per line. Nothing is printed if the state of the
+/// registry cannot be read in time, so that completion never breaks the shell.
+pub fn print_record_completions(registry_directory: PathBuf, partial: String) {
+    let (tx, rx) = mpsc::channel();
+
+    // Not joined, so that a stuck file system does not keep the process from exiting.
+    std::thread::spawn(move || {
+        let annotations = Annotations::load(&registry_directory).unwrap_or_default();
+        let sessions = SessionStore::load(&registry_directory).unwrap_or_default();
+        let _ = tx.send(complete_record_path(
+            &known_records(&annotations, &sessions),
+            &partial,
+        ));
+    });
+
+    if let Ok(completions) = rx.recv_timeout(COMPLETION_TIMEOUT) {
+        for completion in completions {
+            println!("{completion}");
+        }
+    }
+}
+
+/// The part of the completion script for `shell` which completes `--record` dynamically, to be
+/// appended to the script generated by `clap_complete`. Only bash and fish are supported.
+pub fn dynamic_completion_script(shell: Shell, bin_name: &str) -> Option<String> {
+    // Shell identifiers cannot contain every character of the name.
+    let identifier = bin_name.replace(|char: char| !char.is_ascii_alphanumeric(), "_");
+
+    match shell {
+        // Delegates to the completion function registered by the generated script, whichever
+        // name it has, for everything but `--record`.
+        Shell::Bash => Some(format!(
+            r#"
+_{identifier}_generated_completion=$(complete -p "{bin_name}" | sed -E 's/.*-F ([^ ]+).*/\1/')
+_{identifier}_complete_with_records() {{
+    if [[ "${{COMP_WORDS[COMP_CWORD-1]}}" == "--record" ]]; then
+        local registry_directory=. index
+        for ((index = 1; index < COMP_CWORD - 1; index++)); do
+            case "${{COMP_WORDS[index]}}" in
+                -d|--registry-directory) registry_directory="${{COMP_WORDS[index+1]}}" ;;
+            esac
+        done
+        mapfile -t COMPREPLY < <("{bin_name}" -d "$registry_directory" {COMPLETE_RECORD_COMMAND} -- "${{COMP_WORDS[COMP_CWORD]}}" 2>/dev/null)
+        return 0
+    fi
+    "$_{identifier}_generated_completion" "$@"
+}}
+complete -F _{identifier}_complete_with_records -o nosort -o bashdefault -o default "{bin_name}"
+"#
+        )),
+        Shell::Fish => Some(format!(
+            r#"
+complete -c {bin_name} -l record -f -a "({bin_name} {COMPLETE_RECORD_COMMAND} -- (commandline -ct) 2>/dev/null)"
+"#
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The annotations file of a registry, as saved, with aliases of `/docs/invoices` and of
+    /// `/docs/r\xffport`, which is not valid UTF-8.
+    const ANNOTATIONS: &str = r#"{
+        "aliases": {
+            "646f6373/696e766f69636573": { "alias": "billing", "revision": 0 },
+            "646f6373/72ff706f7274": { "alias": "broken", "revision": 2 }
+        }
+    }"#;
+
+    /// The session snapshots file of a registry, as saved, with snapshots on `/docs/index`,
+    /// `/docs/invoices` at a specific version, and on the record named `b/c` within the record
+    /// named `a/b`.
+    const SESSIONS: &str = r#"{
+        "snapshots": [
+            {
+                "taken_at": 0,
+                "record_ref": { "path": [[100, 111, 99, 115], [105, 110, 100, 101, 120]] },
+                "scroll": 0
+            },
+            {
+                "taken_at": 1,
+                "record_ref": {
+                    "path": [[100, 111, 99, 115], [105, 110, 118, 111, 105, 99, 101, 115]],
+                    "version": 3
+                },
+                "scroll": 10
+            },
+            {
+                "taken_at": 2,
+                "record_ref": { "path": [[97, 47, 98], [98, 47, 99]] },
+                "scroll": 0
+            }
+        ]
+    }"#;
+
+    fn known() -> Vec<KnownRecord> {
+        let annotations = serde_json::from_str::<Annotations>(ANNOTATIONS).unwrap();
+        let sessions = serde_json::from_str::<SessionStore>(SESSIONS).unwrap();
+        known_records(&annotations, &sessions)
+    }
+
+    #[test]
+    fn known_records_come_from_aliases_and_sessions() {
+        let known = known();
+
+        assert_eq!(known.len(), 4);
+        assert_eq!(
+            known[0],
+            KnownRecord {
+                record_ref: RecordRef::from_utf8_path("docs/invoices"),
+                alias: Some("billing".to_string()),
+            }
+        );
+        assert_eq!(known[2].record_ref, RecordRef::from_utf8_path("docs/index"));
+        assert_eq!(known[2].alias, None);
+    }
+
+    #[test]
+    fn top_level_names_are_completed() {
+        let known = known();
+
+        assert_eq!(complete_record_path(&known, ""), [r"a\/b", "docs"]);
+        assert_eq!(complete_record_path(&known, "d"), ["docs"]);
+        assert_eq!(complete_record_path(&known, "/d"), ["/docs"]);
+        assert_eq!(complete_record_path(&known, "x"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn names_of_sub_records_are_completed() {
+        let known = known();
+
+        assert_eq!(
+            complete_record_path(&known, "docs/"),
+            ["docs/index", "docs/invoices"]
+        );
+        assert_eq!(complete_record_path(&known, "docs/inv"), ["docs/invoices"]);
+        assert_eq!(
+            complete_record_path(&known, "/docs//in"),
+            ["/docs//index", "/docs//invoices"]
+        );
+        assert_eq!(
+            complete_record_path(&known, "docs/invoices/"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn escaped_names_are_completed() {
+        let known = known();
+
+        assert_eq!(complete_record_path(&known, r"a\/"), [r"a\/b"]);
+        assert_eq!(complete_record_path(&known, r"a\/b/"), [r"a\/b/b\/c"]);
+        assert_eq!(complete_record_path(&known, r"a\/b/b\/"), [r"a\/b/b\/c"]);
+    }
+
+    #[test]
+    fn aliases_are_completed_to_paths() {
+        let known = known();
+
+        assert_eq!(complete_record_path(&known, "bill"), ["docs/invoices"]);
+        // The path of `broken` cannot be typed in UTF-8.
+        assert_eq!(complete_record_path(&known, "bro"), Vec::<String>::new());
+        assert_eq!(
+            complete_record_path(&known, "docs/bill"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn names_which_are_not_utf8_are_not_completed() {
+        assert_eq!(
+            complete_record_path(&known(), "docs/r"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn last_name_start_skips_escaped_separators() {
+        assert_eq!(last_name_start(""), 0);
+        assert_eq!(last_name_start("a/b"), 2);
+        assert_eq!(last_name_start(r"a\/b"), 0);
+        assert_eq!(last_name_start(r"a\\/b"), 4);
+        assert_eq!(last_name_start("a/"), 2);
+    }
+}
@@ -1,10 +1,11 @@
 use std::{cell::RefCell, fmt::Debug, ops::ControlFlow};
 
 use color_eyre::Result;
-use ratatui::{layout::Rect, Frame};
+use ratatui::{layout::Rect, widgets::Widget, Frame};
 
 use crate::{
-    action::{Action, ComponentMessage},
+    action::{Action, ComponentMessage, Panel},
+    theme::Palette,
     tui::Event,
 };
 
@@ -167,11 +168,18 @@ impl HandleEventSuccess {
 /// Implementors of this trait can be registered with the main application loop and will be able to
 /// receive events, update state, and be rendered on the screen.
 pub trait Component: Debug {
-    /// Handle events when focused.
+    /// Handle events when focused, after the focused descendants, unless one of them absorbs the
+    /// event.
     fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
         Ok(HandleEventSuccess::unhandled())
     }
 
+    /// Handle events when focused, before the focused descendants, which do not see the event if
+    /// it is absorbed.
+    fn capture_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        Ok(HandleEventSuccess::unhandled())
+    }
+
     fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
         Ok(None)
     }
@@ -183,11 +191,39 @@ pub trait Component: Debug {
         todo!()
     }
 
+    /// Nodes of this component which are not nodes of components, such as live regions announcing
+    /// its changes, listed after the children of its node.
+    fn get_accessibility_extra_nodes(&self) -> Vec<(accesskit::NodeId, accesskit::Node)> {
+        Vec::new()
+    }
+
     /// Returns `true` iff this component can be focused such that it is able to handle events.
     fn is_focusable(&self) -> bool {
         false
     }
 
+    /// Returns `true` iff typed characters are taken as text while this component is focused, so
+    /// that they must not be taken as hotkeys.
+    fn accepts_text_input(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` iff the focus must stay within this component's subtree while it is part
+    /// of the component tree, such as for modal dialogs.
+    fn traps_focus(&self) -> bool {
+        false
+    }
+
+    /// The panel of the main view this component is, if any, which the app tracks the focus of.
+    fn get_panel(&self) -> Option<Panel> {
+        None
+    }
+
+    /// Records the state of this component in the snapshot of the user interface, which visits
+    /// every component of the tree.
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, _state: &mut crate::introspection::UiState) {}
+
     fn get_children(&self) -> Vec<&dyn Component> {
         Default::default()
     }
@@ -223,11 +259,45 @@ pub trait Component: Debug {
 pub struct DrawContext<'a, 'b: 'a> {
     frame: &'a mut Frame<'b>,
     focused_id: ComponentId,
+    palette: Palette,
+    /// The areas of the components drawn so far, for hit-testing mouse events.
+    mouse_areas: Vec<(ComponentId, Rect)>,
+    #[cfg(any(debug_assertions, test, feature = "layout-debug"))]
+    named_areas: Vec<NamedArea>,
+}
+
+/// The layer an area is drawn in.
+///
+/// Areas of the base layer are either nested in or disjoint from each other, so that the order
+/// they are drawn in does not matter. Backgrounds, such as the separating lines the titles of the
+/// panes are on, are drawn under the base layer, and overlays, such as notices drawn over the
+/// content, over it. Both may overlap anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Background,
+    Base,
+    Overlay,
+}
+
+/// An area recorded for checking the layout and for the layout debugging mode.
+#[cfg(any(debug_assertions, test, feature = "layout-debug"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedArea {
+    pub name: &'static str,
+    pub area: Rect,
+    pub layer: Layer,
 }
 
 impl<'a, 'b: 'a> DrawContext<'a, 'b> {
-    pub fn new(frame: &'a mut Frame<'b>, focused_id: ComponentId) -> Self {
-        Self { frame, focused_id }
+    pub fn new(frame: &'a mut Frame<'b>, focused_id: ComponentId, palette: Palette) -> Self {
+        Self {
+            frame,
+            focused_id,
+            palette,
+            mouse_areas: Vec::new(),
+            #[cfg(any(debug_assertions, test, feature = "layout-debug"))]
+            named_areas: Vec::new(),
+        }
     }
 
     pub fn frame(&mut self) -> &mut Frame<'b> {
@@ -237,6 +307,84 @@ impl<'a, 'b: 'a> DrawContext<'a, 'b> {
     pub fn focused_id(&self) -> ComponentId {
         self.focused_id
     }
+
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Makes the component the target of mouse events within the area, over the areas registered
+    /// before, which is usually the area of a parent component.
+    pub fn register_mouse_area(&mut self, id: ComponentId, area: Rect) {
+        if area.area() > 0 {
+            self.mouse_areas.push((id, area));
+        }
+    }
+
+    /// The areas registered with `Self::register_mouse_area`, in the order they were drawn in.
+    pub fn take_mouse_areas(&mut self) -> Vec<(ComponentId, Rect)> {
+        std::mem::take(&mut self.mouse_areas)
+    }
+
+    /// Records the area of the base layer for the layout debugging mode.
+    /// In debug builds, asserts that it does not partially overlap another area of the base layer.
+    #[inline(always)]
+    pub fn name_area(&mut self, name: &'static str, area: Rect) {
+        self.name_layered_area(name, area, Layer::Base);
+    }
+
+    /// Records the area for the layout debugging mode.
+    /// Does nothing in release builds without the `layout-debug` feature.
+    #[inline(always)]
+    pub fn name_layered_area(&mut self, name: &'static str, area: Rect, layer: Layer) {
+        #[cfg(any(debug_assertions, test, feature = "layout-debug"))]
+        {
+            let is_partial_overlap = |other: &NamedArea| {
+                let intersection = other.area.intersection(area);
+                other.layer == Layer::Base
+                    && !intersection.is_empty()
+                    && intersection != area
+                    && intersection != other.area
+            };
+            let overlapped = self
+                .named_areas
+                .iter()
+                .find(|other| layer == Layer::Base && is_partial_overlap(other));
+            debug_assert!(
+                overlapped.is_none(),
+                "The area `{name}` {area:?} partially overlaps the area `{}` {:?}, and must be \
+                 drawn as an overlay if intended.",
+                overlapped.unwrap().name,
+                overlapped.unwrap().area,
+            );
+            self.named_areas.push(NamedArea { name, area, layer });
+        }
+        #[cfg(not(any(debug_assertions, test, feature = "layout-debug")))]
+        let _ = (name, area, layer);
+    }
+
+    /// Renders the widget, recording its area of the base layer for the layout debugging mode.
+    pub fn render_named_widget(&mut self, name: &'static str, widget: impl Widget, area: Rect) {
+        self.name_area(name, area);
+        self.frame.render_widget(widget, area);
+    }
+
+    /// Renders the widget under the base layer, recording its area for the layout debugging mode.
+    /// Drawn before the areas of the base layer it is under.
+    pub fn render_background(&mut self, name: &'static str, widget: impl Widget, area: Rect) {
+        self.name_layered_area(name, area, Layer::Background);
+        self.frame.render_widget(widget, area);
+    }
+
+    /// Renders the widget over the base layer, recording its area for the layout debugging mode.
+    pub fn render_overlay(&mut self, name: &'static str, widget: impl Widget, area: Rect) {
+        self.name_layered_area(name, area, Layer::Overlay);
+        self.frame.render_widget(widget, area);
+    }
+
+    #[cfg(any(test, feature = "layout-debug"))]
+    pub fn take_named_areas(&mut self) -> Vec<NamedArea> {
+        std::mem::take(&mut self.named_areas)
+    }
 }
 
 /// A drawable element (usually a `Component`).
@@ -388,3 +536,341 @@ pub fn find_component_by_id_mut(
 
     Some((component, path))
 }
+
+/// The first component under `subtree_root` which traps the focus, if any.
+pub fn find_focus_trap(subtree_root: &dyn Component) -> Option<&dyn Component> {
+    depth_first_search(
+        subtree_root,
+        &mut |component| {
+            if component.traps_focus() {
+                ControlFlow::Break(component)
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        &mut |_| ControlFlow::Continue(()),
+    )
+    .break_value()
+}
+
+/// Returns `true` iff the panel is under `subtree_root` and can be focused.
+pub fn is_panel_focusable(subtree_root: &dyn Component, panel: Panel) -> bool {
+    depth_first_search(
+        subtree_root,
+        &mut |component| {
+            if component.get_panel() == Some(panel) {
+                ControlFlow::Break(contains_focusable_component(component))
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        &mut |_| ControlFlow::Continue(()),
+    )
+    .break_value()
+    .unwrap_or(false)
+}
+
+/// The panel containing the focused component along `focus_path` from `subtree_root`, if any.
+pub fn find_focused_panel(
+    subtree_root: &dyn Component,
+    focus_path: &ComponentIdPath,
+) -> Option<Panel> {
+    let mut component = subtree_root;
+    let mut panel = component.get_panel();
+
+    for id in focus_path.iter() {
+        let Some(child) = component
+            .get_children()
+            .into_iter()
+            .find(|child| child.get_id() == *id)
+        else {
+            break;
+        };
+
+        component = child;
+        panel = component.get_panel().or(panel);
+    }
+
+    panel
+}
+
+/// The panel containing the focus, and the one focused before it, which Ctrl+^ focuses again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PanelFocus {
+    /// The panel containing the focus, or last containing it while a component outside of the
+    /// panels, such as a dialog, is focused.
+    focused: Option<Panel>,
+    /// The panel focused before `focused`.
+    previous: Option<Panel>,
+}
+
+impl PanelFocus {
+    /// Remembers the previous panel once the focus moves to another one. Components outside of
+    /// the panels, such as dialogs, are skipped, so that they are not swapped to.
+    /// Returns whether the focused panel changed.
+    pub fn track(&mut self, subtree_root: &dyn Component, focus_path: &ComponentIdPath) -> bool {
+        let Some(panel) = find_focused_panel(subtree_root, focus_path) else {
+            return false;
+        };
+
+        if self.focused == Some(panel) {
+            return false;
+        }
+
+        self.previous = self.focused.replace(panel);
+        true
+    }
+
+    /// The panel to swap the focus to: the previously focused one, or the one following the
+    /// focused one in the layout if the previous one cannot be focused, such as once it is
+    /// unmounted.
+    pub fn find_alternate(&self, subtree_root: &dyn Component) -> Option<Panel> {
+        if let Some(previous) = self.previous
+            && is_panel_focusable(subtree_root, previous)
+        {
+            return Some(previous);
+        }
+
+        let index = Panel::ALL
+            .iter()
+            .position(|panel| Some(*panel) == self.focused)?;
+
+        Panel::ALL
+            .iter()
+            .cycle()
+            .skip(index + 1)
+            .take(Panel::ALL.len() - 1)
+            .copied()
+            .find(|panel| is_panel_focusable(subtree_root, *panel))
+    }
+}
+
+/// Returns `true` iff `subtree_root` or one of its descendants is focusable.
+pub fn contains_focusable_component(subtree_root: &dyn Component) -> bool {
+    depth_first_search(
+        subtree_root,
+        &mut |component| {
+            if component.is_focusable() {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        &mut |_| ControlFlow::Continue(()),
+    )
+    .is_break()
+}
+
+/// Returns `true` iff the component with the ID is `subtree_root` or one of its descendants.
+pub fn contains_component(subtree_root: &dyn Component, id: ComponentId) -> bool {
+    depth_first_search(
+        subtree_root,
+        &mut |component| {
+            if component.get_id() == id {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        &mut |_| ControlFlow::Continue(()),
+    )
+    .is_break()
+}
+
+/// Builds the accessibility tree of the components under `root`, whose node IDs are derived from
+/// the component IDs, with the children of each node in the order the components are traversed.
+pub fn build_accessibility_tree(
+    root: &dyn Component,
+    focused_id: ComponentId,
+) -> Result<accesskit::TreeUpdate> {
+    let mut nodes = Vec::new();
+    let result = depth_first_search(
+        root,
+        &mut |component| {
+            let mut node = match component.get_accessibility_node() {
+                Ok(node) => node,
+                Err(error) => return ControlFlow::Break(error),
+            };
+            let mut children = Vec::new();
+            let _ = for_each_child::<()>(component, |child| {
+                children.push(child.get_id().into());
+                ControlFlow::Continue(())
+            });
+            let extra_nodes = component.get_accessibility_extra_nodes();
+            children.extend(extra_nodes.iter().map(|(id, _)| *id));
+            node.set_children(children);
+            nodes.push((component.get_id().into(), node));
+            nodes.extend(extra_nodes);
+            ControlFlow::Continue(())
+        },
+        &mut |_| ControlFlow::Continue(()),
+    );
+
+    if let ControlFlow::Break(error) = result {
+        return Err(error);
+    }
+
+    Ok(accesskit::TreeUpdate {
+        nodes,
+        tree: Some(accesskit::Tree::new(root.get_id().into())),
+        focus: focused_id.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A component of a tree built by the tests.
+    #[derive(Debug, Default)]
+    struct Node {
+        id: ComponentId,
+        panel: Option<Panel>,
+        focusable: bool,
+        children: Vec<Node>,
+    }
+
+    impl Node {
+        fn panel(panel: Panel, children: Vec<Node>) -> Self {
+            Self {
+                panel: Some(panel),
+                focusable: children.is_empty(),
+                children,
+                ..Default::default()
+            }
+        }
+
+        fn focusable() -> Self {
+            Self {
+                focusable: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Component for Node {
+        fn get_id(&self) -> ComponentId {
+            self.id
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.focusable
+        }
+
+        fn traps_focus(&self) -> bool {
+            self.panel.is_none() && self.focusable
+        }
+
+        fn get_panel(&self) -> Option<Panel> {
+            self.panel
+        }
+
+        fn get_children(&self) -> Vec<&dyn Component> {
+            self.children
+                .iter()
+                .map(|child| child as &dyn Component)
+                .collect()
+        }
+
+        fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
+            self.children
+                .iter_mut()
+                .map(|child| child as &mut dyn Component)
+                .collect()
+        }
+    }
+
+    /// The root of the panels in the order of `Panel::ALL`, the content panel containing the
+    /// focusable component.
+    fn panels() -> Node {
+        Node {
+            children: vec![
+                Node::panel(Panel::Tree, Vec::new()),
+                Node::panel(Panel::Content, vec![Node::focusable()]),
+                Node::panel(Panel::Open, Vec::new()),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Moves the focus to the component at the indices of the children from the root.
+    fn focus(root: &Node, panel_focus: &mut PanelFocus, indices: &[usize]) -> bool {
+        let mut node = root;
+        let mut focus_path = ComponentIdPath::default();
+
+        for index in indices {
+            node = &node.children[*index];
+            focus_path.push(node.id);
+        }
+
+        panel_focus.track(root, &focus_path)
+    }
+
+    #[test]
+    fn focus_swaps_between_the_last_two_panels() {
+        let root = panels();
+        let mut panel_focus = PanelFocus::default();
+        assert_eq!(panel_focus.find_alternate(&root), None);
+
+        assert!(focus(&root, &mut panel_focus, &[0]));
+        // Nothing was focused before, so the next panel is.
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Content));
+
+        assert!(focus(&root, &mut panel_focus, &[2]));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+        assert!(focus(&root, &mut panel_focus, &[0]));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Open));
+
+        // Within the same panel, the previous one is kept.
+        assert!(focus(&root, &mut panel_focus, &[1, 0]));
+        assert!(!focus(&root, &mut panel_focus, &[1]));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+    }
+
+    #[test]
+    fn unmounted_previous_panel_falls_back_to_the_panel_order() {
+        let mut root = panels();
+        let mut panel_focus = PanelFocus::default();
+        focus(&root, &mut panel_focus, &[0]);
+        focus(&root, &mut panel_focus, &[1, 0]);
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+
+        // The tree is unmounted, the open pane following the content.
+        root.children.remove(0);
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Open));
+
+        // Panels that are mounted without anything focusable are skipped too.
+        root.children[1].focusable = false;
+        assert_eq!(panel_focus.find_alternate(&root), None);
+
+        root.children
+            .insert(0, Node::panel(Panel::Tree, Vec::new()));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+    }
+
+    #[test]
+    fn dialogs_are_skipped_across_opening_and_closing() {
+        let mut root = panels();
+        let mut panel_focus = PanelFocus::default();
+        focus(&root, &mut panel_focus, &[0]);
+        focus(&root, &mut panel_focus, &[1, 0]);
+
+        // A dialog is the last child of the root while open, and traps the focus.
+        root.children.push(Node::focusable());
+        assert!(find_focus_trap(&root).is_some());
+        assert!(!focus(&root, &mut panel_focus, &[3]));
+        assert_eq!(
+            find_focused_panel(&root, &ComponentIdPath(vec![root.children[3].id])),
+            None
+        );
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+
+        // Closing it focuses the content again, which is still the panel the focus is in.
+        root.children.pop();
+        assert!(!focus(&root, &mut panel_focus, &[1, 0]));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Tree));
+
+        assert!(focus(&root, &mut panel_focus, &[0]));
+        assert_eq!(panel_focus.find_alternate(&root), Some(Panel::Content));
+    }
+}
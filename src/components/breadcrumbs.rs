@@ -0,0 +1,318 @@
+//! The trail of the records from the root record to the opened one, displayed in the header.
+//!
+//! The trail is derived from the opened record of the shared `MainState`, which is replaced by the
+//! same `ComponentMessage::RecordOpen` that updates the tree and the metadata, so that they are
+//! always in sync.
+
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use color_eyre::Result;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use itertools::Itertools;
+use ratatui::{layout::Rect, style::Stylize};
+use tokio::sync::mpsc::UnboundedSender;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    action::{Action, ComponentMessage},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    encoding::Encoding,
+    model::{LossyNameDisplay, RecordRef},
+    tui::Event,
+};
+
+use super::header::{HeaderElement, Overflow};
+use super::main_view::MainState;
+
+/// Displayed between adjacent segments.
+const SEPARATOR: &str = " › ";
+
+/// Displayed in place of the collapsed segments.
+const COLLAPSED: &str = "…";
+
+/// The number of hex digits names displayed in hex are abbreviated to.
+const HEX_NAME_DIGITS: usize = 8;
+
+/// The number of segments at the end of the trail which are never collapsed: the opened record
+/// and its parent.
+const KEPT_SEGMENTS: usize = 2;
+
+/// The maximum number of segments following the root record, so that the cost of laying out the
+/// trail of deeply nested records is bounded. Longer trails do not fit a terminal anyway.
+const MAX_SEGMENTS: usize = 64;
+
+/// A segment of the trail.
+#[derive(Debug, Clone)]
+struct Segment {
+    label: String,
+    /// The depth of the record along the path of the opened record, `None` for the collapsed
+    /// segments.
+    depth: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Breadcrumbs {
+    id: ComponentId,
+    main_state: Rc<RefCell<MainState>>,
+    lossy_names: LossyNameDisplay,
+    /// The columns of the segments as last drawn, with the depths of their records, to find the
+    /// clicked one.
+    columns: RefCell<Vec<(Range<u16>, usize)>>,
+}
+
+impl Breadcrumbs {
+    pub(super) fn new(
+        id: ComponentId,
+        _tx: &UnboundedSender<Action>,
+        main_state: &Rc<RefCell<MainState>>,
+        lossy_names: LossyNameDisplay,
+    ) -> Self {
+        Self {
+            id,
+            main_state: main_state.clone(),
+            lossy_names,
+            columns: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn opened_record_ref(&self) -> Option<RecordRef> {
+        self.main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| opened_record.record_ref.clone())
+    }
+
+    /// The name of the ancestor at `depth` of the opened record, with names displayed in hex
+    /// abbreviated to their first digits.
+    fn label(&self, record_ref: &RecordRef, depth: usize) -> String {
+        let Some(name) = depth.checked_sub(1).map(|index| &record_ref.path()[index]) else {
+            return "/".to_string();
+        };
+        let hex = record_ref.encoding_hint == Encoding::HEX
+            || (self.lossy_names == LossyNameDisplay::Hex
+                && !record_ref.encoding_hint.is_lossless(name));
+
+        if !hex {
+            return record_ref.display_ancestor_name(depth, self.lossy_names);
+        }
+
+        let digits = Encoding::HEX.encode(name);
+
+        match digits.get(..HEX_NAME_DIGITS) {
+            Some(prefix) if digits.len() > HEX_NAME_DIGITS => format!("0x{prefix}{COLLAPSED}"),
+            _ => format!("0x{digits}"),
+        }
+    }
+
+    /// The forms of the trail, from the widest to the narrowest, empty if no record is opened.
+    /// Segments are collapsed from the one following the root record on, always keeping the root
+    /// record and the last segments.
+    fn forms(&self) -> Vec<Vec<Segment>> {
+        self.opened_record_ref()
+            .map(|record_ref| self.forms_of(&record_ref))
+            .unwrap_or_default()
+    }
+
+    /// The forms of the trail of `record_ref`, see `Breadcrumbs::forms`.
+    fn forms_of(&self, record_ref: &RecordRef) -> Vec<Vec<Segment>> {
+        let depth = record_ref.path().len();
+        let segment = |depth| Segment {
+            label: self.label(record_ref, depth),
+            depth: Some(depth),
+        };
+        let root = segment(0);
+        // Only the segments which are displayed in any of the forms are labelled.
+        let tail = ((depth.saturating_sub(MAX_SEGMENTS) + 1)..=depth)
+            .map(segment)
+            .collect::<Vec<_>>();
+        let mut forms = Vec::new();
+
+        if depth <= MAX_SEGMENTS {
+            forms.push(std::iter::once(root.clone()).chain(tail.clone()).collect());
+        }
+
+        let widest_collapsed = std::cmp::min(depth.saturating_sub(1), MAX_SEGMENTS);
+
+        for kept in (KEPT_SEGMENTS..=widest_collapsed).rev() {
+            let mut form = vec![root.clone()];
+            form.push(Segment {
+                label: COLLAPSED.to_string(),
+                depth: None,
+            });
+            form.extend(tail[tail.len() - kept..].iter().cloned());
+            forms.push(form);
+        }
+
+        forms
+    }
+
+    fn text(form: &[Segment]) -> String {
+        form.iter()
+            .map(|segment| segment.label.as_str())
+            .join(SEPARATOR)
+    }
+
+    /// The element of the header, `None` if no record is opened.
+    pub fn header_element(&self, priority: u8) -> Option<HeaderElement<'static>> {
+        let mut forms = self.forms().into_iter().map(|form| Self::text(&form));
+        let mut element = HeaderElement::new(forms.next()?, priority);
+
+        for form in forms {
+            element = element.or(form);
+        }
+
+        Some(element.with_overflow(Overflow::Truncate))
+    }
+}
+
+impl Component for Breadcrumbs {
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        let Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            ..
+        }) = *event
+        else {
+            return Ok(HandleEventSuccess::unhandled());
+        };
+        let Some(opened) = self.opened_record_ref() else {
+            return Ok(HandleEventSuccess::handled());
+        };
+        // The opened record itself is not opened again.
+        let Some(record_ref) = self
+            .columns
+            .borrow()
+            .iter()
+            .find(|(columns, _)| columns.contains(&column))
+            .map(|(_, depth)| *depth)
+            .filter(|depth| *depth < opened.path().len())
+            .map(|depth| opened.ancestor(depth))
+        else {
+            return Ok(HandleEventSuccess::handled());
+        };
+
+        Ok(
+            HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                ComponentMessage::OpenRecord { record_ref },
+            )),
+        )
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Navigation);
+        node.set_label("Record path");
+
+        if let Some(opened_record) = self.main_state.borrow().opened_record.as_ref() {
+            node.set_value(opened_record.record_ref.display_path(self.lossy_names));
+        }
+
+        Ok(node)
+    }
+}
+
+impl Drawable for Breadcrumbs {
+    /// The text the header laid out for the trail, see `Breadcrumbs::header_element`.
+    type Args<'a>
+        = &'a str
+    where
+        Self: 'a;
+
+    /// Styles the trail already drawn by the header into `area`, and records where its segments
+    /// are for clicks.
+    fn draw(&self, context: &mut DrawContext, area: Rect, text: Self::Args<'_>) -> Result<()> {
+        let forms = self.forms();
+        // A truncated trail is laid out like the narrowest form, which it was truncated from.
+        let Some(form) = forms
+            .iter()
+            .find(|form| Self::text(form) == text)
+            .or(forms.last())
+        else {
+            return Ok(());
+        };
+        let mut columns = Vec::new();
+        let mut x = area.x;
+        let palette = context.palette();
+        let buffer = context.frame().buffer_mut();
+
+        for (index, segment) in form.iter().enumerate() {
+            if index > 0 {
+                let width = SEPARATOR.width() as u16;
+                buffer.set_style(
+                    Rect { x, width, ..area }.intersection(area),
+                    palette.muted_style(),
+                );
+                x = x.saturating_add(width);
+            }
+
+            let width = segment.label.width() as u16;
+            let segment_area = Rect { x, width, ..area }.intersection(area);
+
+            let style = palette.breadcrumbs_style();
+
+            if index == form.len() - 1 {
+                buffer.set_style(segment_area, style.bold());
+            } else {
+                buffer.set_style(segment_area, style);
+            }
+
+            if let Some(depth) = segment.depth {
+                columns.push((x..x.saturating_add(width), depth));
+            }
+
+            x = x.saturating_add(width);
+        }
+
+        self.columns.replace(columns);
+        context.register_mouse_area(self.id, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::main_view::RegistryState;
+    use super::*;
+
+    #[test]
+    fn trail_of_a_record_10_000_deep_is_bounded() {
+        let (tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let main_state = Rc::new(RefCell::new(MainState {
+            registry: RegistryState::Opening,
+            opened_record: None,
+        }));
+        let breadcrumbs =
+            Breadcrumbs::new(ComponentId::new(), &tx, &main_state, LossyNameDisplay::Mark);
+        let record_ref = RecordRef::from_path(
+            (0..10_000)
+                .map(|index| format!("n{index}").into_bytes())
+                .collect(),
+            Encoding::UTF8,
+        );
+        let forms = breadcrumbs.forms_of(&record_ref);
+
+        // Too deep for the whole trail, so every form is collapsed, down to the kept segments.
+        assert_eq!(forms.len(), MAX_SEGMENTS - KEPT_SEGMENTS + 1);
+
+        for form in &forms {
+            assert!(form.len() <= MAX_SEGMENTS + 2);
+            assert_eq!(form[0].label, "/");
+            assert_eq!(form[0].depth, Some(0));
+            assert_eq!(form[1].label, COLLAPSED);
+            assert_eq!(form[1].depth, None);
+            assert_eq!(form.last().unwrap().label, "n9999");
+            assert_eq!(form.last().unwrap().depth, Some(10_000));
+        }
+
+        assert_eq!(
+            Breadcrumbs::text(forms.last().unwrap()),
+            "/ › … › n9998 › n9999"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::borrow::Cow;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::Span,
+};
+use tokio::sync::mpsc::UnboundedSender;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    action::{Action, ComponentMessage},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    tui::Event,
+};
+
+/// A button which broadcasts `ComponentMessage::OnButtonPress` when pressed with Space or a click.
+#[derive(Debug, Clone)]
+pub struct Button {
+    id: ComponentId,
+    label: Cow<'static, str>,
+    /// Tracked from the focus events, so that the parent can tell which button Enter applies to.
+    focused: bool,
+    action_tx: UnboundedSender<Action>,
+}
+
+impl Button {
+    pub fn new(id: ComponentId, tx: &UnboundedSender<Action>, label: Cow<'static, str>) -> Self {
+        Self {
+            id,
+            label,
+            focused: false,
+            action_tx: tx.clone(),
+        }
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// The width of the button, including its brackets.
+    pub fn width(&self) -> u16 {
+        self.label.width() as u16 + 4
+    }
+
+    fn press(&self) -> Result<HandleEventSuccess> {
+        self.action_tx
+            .send(Action::BroadcastMessage(ComponentMessage::OnButtonPress {
+                id: self.id,
+            }))?;
+        Ok(HandleEventSuccess::handled())
+    }
+}
+
+impl Component for Button {
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        match event {
+            Event::FocusGained | Event::FocusLost => {
+                self.focused = matches!(event, Event::FocusGained);
+                Ok(HandleEventSuccess::unhandled())
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(' '),
+                kind: KeyEventKind::Press,
+                ..
+            })
+            | Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                ..
+            }) => self.press(),
+            _ => Ok(HandleEventSuccess::unhandled()),
+        }
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Button);
+        node.set_label(self.label.as_ref());
+        Ok(node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ButtonArgs {
+    /// Whether the button is the one activated by Enter, which is displayed in bold.
+    pub default: bool,
+}
+
+impl Drawable for Button {
+    type Args<'a>
+        = ButtonArgs
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, mut area: Rect, args: Self::Args<'_>) -> Result<()> {
+        if area.area() == 0 {
+            return Ok(());
+        }
+
+        area.height = 1;
+        area.width = std::cmp::min(area.width, self.width());
+        context.register_mouse_area(self.id, area);
+        let mut style = if context.focused_id() == self.id {
+            context.palette().focus_style()
+        } else {
+            Style::new()
+        };
+
+        if args.default {
+            style = style.bold();
+        }
+
+        context
+            .frame()
+            .render_widget(Span::styled(format!("[ {} ]", self.label), style), area);
+
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
 use std::borrow::Cow;
 
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Rect, Size},
-    style::{Style, Stylize},
+    style::Style,
     text::{Line, Span},
 };
 use tokio::sync::mpsc::UnboundedSender;
@@ -22,6 +22,9 @@ pub struct Checkbox {
     pub checked: bool,
     string_checked: Cow<'static, str>,
     string_unchecked: Cow<'static, str>,
+    /// The role reported to assistive technology, such as `accesskit::Role::RadioButton` for the
+    /// items of a radio array.
+    accessibility_role: accesskit::Role,
     action_tx: UnboundedSender<Action>,
 }
 
@@ -41,6 +44,7 @@ impl Checkbox {
             checked,
             string_checked: "[x]".into(),
             string_unchecked: "[ ]".into(),
+            accessibility_role: accesskit::Role::CheckBox,
             action_tx: tx.clone(),
         }
     }
@@ -53,6 +57,13 @@ impl Checkbox {
         }
     }
 
+    pub fn with_accessibility_role(self, accessibility_role: accesskit::Role) -> Self {
+        Self {
+            accessibility_role,
+            ..self
+        }
+    }
+
     pub fn size(&self) -> Size {
         Size::new(
             1 + Line::from_iter([
@@ -67,6 +78,17 @@ impl Checkbox {
             1,
         )
     }
+
+    fn toggle(&mut self) -> Result<HandleEventSuccess> {
+        self.checked = !self.checked;
+        self.action_tx.send(Action::BroadcastMessage(
+            ComponentMessage::OnCheckboxToggle {
+                id: self.id,
+                new_value: self.checked,
+            },
+        ))?;
+        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+    }
 }
 
 impl Component for Checkbox {
@@ -79,23 +101,18 @@ impl Component for Checkbox {
     }
 
     fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
-        Ok(match event {
+        match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Char(' '),
                 kind: KeyEventKind::Press | KeyEventKind::Repeat,
                 ..
-            }) => {
-                self.checked = !self.checked;
-                self.action_tx.send(Action::BroadcastMessage(
-                    ComponentMessage::OnCheckboxToggle {
-                        id: self.id,
-                        new_value: self.checked,
-                    },
-                ))?;
-                HandleEventSuccess::handled().with_action(Action::Render)
-            }
-            _ => HandleEventSuccess::unhandled(),
-        })
+            })
+            | Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                ..
+            }) => self.toggle(),
+            _ => Ok(HandleEventSuccess::unhandled()),
+        }
     }
 
     fn get_id(&self) -> ComponentId {
@@ -103,7 +120,14 @@ impl Component for Checkbox {
     }
 
     fn get_accessibility_node(&self) -> Result<accesskit::Node> {
-        todo!()
+        let mut node = accesskit::Node::new(self.accessibility_role);
+        node.set_label(self.label.as_ref());
+        node.set_toggled(if self.checked {
+            accesskit::Toggled::True
+        } else {
+            accesskit::Toggled::False
+        });
+        Ok(node)
     }
 }
 
@@ -119,9 +143,13 @@ impl Drawable for Checkbox {
         }
 
         area.height = 1;
+        area.width = std::cmp::min(area.width, self.size().width);
+        context.register_mouse_area(self.id, area);
         let focused = context.focused_id() == self.id;
         let checkmark_style = if focused {
-            Style::new().reversed()
+            context.palette().focus_style()
+        } else if self.checked {
+            context.palette().selected_style()
         } else {
             Style::new()
         };
@@ -0,0 +1,1969 @@
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::{Action, ComponentMessage, Panel, Severity},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    encoding::Encoding,
+    entropy::BLOCK_SIZE,
+    model::RecordRef,
+    text::{
+        find_bytes, find_ignoring_case, format_size, hex_dump, hex_dump_bytes_per_row,
+        hex_dump_columns, parse_hex_pattern, rows_of_range, truncate_to_width, wrap_to_width,
+        TruncateMode,
+    },
+    theme::Palette,
+    tui::Event,
+};
+
+use super::main_view::MainState;
+
+/// How long a page is presented before the next one is announced in the "read from here" mode.
+const READ_PAGE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Content larger than this many bytes is only selected entirely after a confirmation.
+const SELECT_ALL_CONFIRMATION_SIZE: usize = 1024 * 1024;
+
+/// The maximum number of characters of the selection exposed to assistive technology.
+const SELECTION_DESCRIPTION_LIMIT: usize = 1000;
+
+/// How often the followed record is reloaded, to pick up its new versions.
+pub const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The minimum height of the view for the content to be displayed, instead of a notice.
+const MIN_HEIGHT: u16 = 2;
+
+/// The minimum height of the view for the search query to be displayed below the content. In
+/// smaller views, it is displayed in the status bar of the main view, see `get_status_line`.
+const MIN_INLINE_SEARCH_HEIGHT: u16 = 4;
+
+/// The minimum height of the view for the hex dump to include the ASCII gutter. In smaller views,
+/// the width is used for more bytes per row instead, so that more of the content is visible.
+const MIN_HEX_GUTTER_HEIGHT: u16 = 4;
+
+/// The minimum height of the rows for a scrollbar to be displayed when the content overflows.
+const MIN_SCROLLBAR_HEIGHT: u16 = 3;
+
+/// The number of rows scrolled by a notch of the mouse wheel.
+const MOUSE_SCROLL_ROWS: isize = 3;
+
+/// The bar of the level of entropy, from 0 to 8 bits per byte.
+fn entropy_bar(entropy: f64) -> &'static str {
+    const BARS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    BARS[((entropy / 8.0 * 7.0).round() as usize).min(BARS.len() - 1)]
+}
+
+/// Whether the scroll is kept once `updated` replaces the text `previous`, wrapped to `rows`: if the
+/// first `shown_rows`, which are displayed or above the displayed ones, begin `updated` unchanged.
+fn is_scroll_anchored(
+    previous: &str,
+    rows: &[Range<usize>],
+    shown_rows: usize,
+    updated: &str,
+) -> bool {
+    let shown = rows
+        .get(shown_rows.saturating_sub(1))
+        .or(rows.last())
+        .map_or(0, |row| row.end);
+    updated.starts_with(&previous[..shown])
+}
+
+/// A range of the content selected with the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Selection {
+    /// The byte offset where the selection started.
+    anchor: usize,
+    /// The byte offset the selection is extended from.
+    cursor: usize,
+    /// Whether the selection is extended by the movement keys without Shift, started with `v`.
+    visual: bool,
+}
+
+impl Selection {
+    fn range(&self) -> Range<usize> {
+        std::cmp::min(self.anchor, self.cursor)..std::cmp::max(self.anchor, self.cursor)
+    }
+}
+
+/// A direction the cursor of the selection is moved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CursorMovement {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Occurrences of a query in the content, updated as the query is typed in. The text is searched
+/// for ignoring case, while the hex dump is searched for a pattern of bytes written in hex.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct Search {
+    query: String,
+    /// Whether the query is still being typed in.
+    editing: bool,
+    /// Byte ranges of the matches within the text, or within the data of the hex dump, in order.
+    matches: Vec<Range<usize>>,
+    /// The index of the current match.
+    current: usize,
+    /// Whether the query is not a pattern of bytes, in the hex dump.
+    invalid: bool,
+    /// The scroll restored once the search is cancelled.
+    previous_scroll: usize,
+}
+
+/// Content derived from the opened record, such as the output of a custom command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DerivedView {
+    title: String,
+    data: Vec<u8>,
+}
+
+/// Keeps loading the newest version of a record, with the view pinned to the end of its content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Follow {
+    /// The latest version of the followed record.
+    record_ref: RecordRef,
+    /// Whether scrolling manually paused pinning the view to the end, until End is pressed.
+    paused: bool,
+}
+
+/// The rows of the content as displayed, computed for a specific width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WrapCache {
+    width: u16,
+    /// Byte ranges of the rows within the content.
+    rows: Rc<Vec<Range<usize>>>,
+}
+
+#[derive(Debug)]
+pub struct ContentView {
+    id: ComponentId,
+    main_state: Rc<RefCell<MainState>>,
+    /// The index of the first displayed row.
+    scroll: usize,
+    /// The size of the area the content was last drawn into.
+    page_height: Cell<u16>,
+    page_width: Cell<u16>,
+    /// The height of the whole area last drawn into, including the search query.
+    height: Cell<u16>,
+    /// Whether the hex dump was last laid out with the ASCII gutter.
+    hex_gutter: Cell<bool>,
+    /// Whether the scrollbar was last displayed, taking up the last column of the rows.
+    scrollbar: Cell<bool>,
+    /// The content decoded as text, so that it is not decoded again on every frame.
+    text_cache: RefCell<Option<Rc<str>>>,
+    wrap_cache: RefCell<Option<WrapCache>>,
+    /// A polite announcement for assistive technology, updated on page changes.
+    announcement: Option<String>,
+    /// The ID of the node of `announcement`, listed under the node of the view.
+    announcement_id: ComponentId,
+    /// When the current page was announced, if pages are being read sequentially.
+    reading_since: Option<Instant>,
+    search: Option<Search>,
+    /// Displayed in place of the content of the opened record, until dismissed.
+    derived_view: Option<DerivedView>,
+    /// The scroll to apply once the next record is opened, when restoring a session.
+    restored_scroll: Option<usize>,
+    selection: Option<Selection>,
+    /// Whether selecting all of the large content was requested, awaiting a confirmation.
+    select_all_pending: bool,
+    follow: Option<Follow>,
+    /// Whether the content of the opened record is displayed as a hex dump, with as many bytes per
+    /// row as fit the width. Selecting is not available in the hex dump, as its text changes with
+    /// the width, and searching matches the bytes instead.
+    hex_dump: bool,
+}
+
+impl ContentView {
+    pub(super) fn new(
+        id: ComponentId,
+        _tx: &UnboundedSender<Action>,
+        main_state: &Rc<RefCell<MainState>>,
+    ) -> Self {
+        Self {
+            id,
+            main_state: main_state.clone(),
+            scroll: 0,
+            page_height: Cell::new(1),
+            page_width: Cell::new(u16::MAX),
+            height: Cell::new(u16::MAX),
+            hex_gutter: Cell::new(true),
+            scrollbar: Cell::new(false),
+            text_cache: RefCell::new(None),
+            wrap_cache: RefCell::new(None),
+            announcement: None,
+            announcement_id: ComponentId::new(),
+            reading_since: None,
+            search: None,
+            derived_view: None,
+            restored_scroll: None,
+            selection: None,
+            select_all_pending: false,
+            follow: None,
+            hex_dump: false,
+        }
+    }
+
+    /// Switches between displaying the content as text and as a hex dump, without reloading it.
+    /// The view stays scrolled to the same part of the content. Its offsets within the text only
+    /// match the offsets within the data if the data is valid UTF-8, otherwise the view is only
+    /// scrolled close to it.
+    pub fn set_hex_dump(&mut self, hex_dump: bool) {
+        if self.hex_dump == hex_dump {
+            return;
+        }
+
+        let bytes_per_row = self.bytes_per_row();
+        let offset = if self.is_hex_dump() {
+            self.scroll * bytes_per_row
+        } else {
+            self.rows().get(self.scroll).map_or(0, |row| row.start)
+        };
+
+        self.hex_dump = hex_dump;
+        self.text_cache.replace(None);
+        self.wrap_cache.replace(None);
+        self.search = None;
+        self.selection = None;
+        self.stop_reading();
+        self.scroll = if self.is_hex_dump() {
+            offset / bytes_per_row
+        } else {
+            self.rows()
+                .partition_point(|row| row.start <= offset)
+                .saturating_sub(1)
+        };
+        self.scroll = std::cmp::min(self.scroll, self.max_scroll());
+    }
+
+    /// Whether the content is currently displayed as a hex dump. Derived views are always text.
+    fn is_hex_dump(&self) -> bool {
+        self.hex_dump && self.derived_view.is_none()
+    }
+
+    /// The number of bytes per row of the hex dump, for the width it was last drawn with.
+    fn bytes_per_row(&self) -> usize {
+        hex_dump_bytes_per_row(self.page_width.get() as usize, self.hex_gutter.get())
+    }
+
+    /// Whether the opened record is being followed, see `FOLLOW_POLL_INTERVAL`.
+    pub fn is_following(&self) -> bool {
+        self.follow.is_some()
+    }
+
+    /// Describes the follow mode, for the title of the pane.
+    pub fn get_follow_label(&self) -> Option<&'static str> {
+        self.follow.as_ref().map(|follow| {
+            if follow.paused {
+                "FOLLOWING paused, [End] to resume"
+            } else {
+                "FOLLOWING"
+            }
+        })
+    }
+
+    fn toggle_follow(&mut self) {
+        if self.follow.take().is_some() {
+            self.announcement = Some("Stopped following the record".to_string());
+            return;
+        }
+
+        let Some(record_ref) = self
+            .main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| opened_record.record_ref.at_version(None))
+        else {
+            return;
+        };
+
+        // New versions replace derived views, so they are dismissed right away.
+        if self.derived_view.is_some() {
+            self.set_derived_view(None);
+        }
+
+        self.follow = Some(Follow {
+            record_ref,
+            paused: false,
+        });
+        self.scroll_to_end();
+        self.announcement = Some("Following the record".to_string());
+    }
+
+    /// Pauses pinning the view to the end after a manual scroll.
+    fn pause_follow(&mut self) {
+        if let Some(follow) = self.follow.as_mut() {
+            follow.paused = true;
+        }
+    }
+
+    /// The index of the first displayed row when scrolled to the end.
+    fn max_scroll(&self) -> usize {
+        self.rows().len().saturating_sub(self.page_height())
+    }
+
+    fn scroll_by(&mut self, offset: isize) {
+        self.scroll = std::cmp::min(self.scroll.saturating_add_signed(offset), self.max_scroll());
+    }
+
+    /// Scrolls to the last page, resuming the follow mode.
+    fn scroll_to_end(&mut self) {
+        self.scroll = self.max_scroll();
+
+        if let Some(follow) = self.follow.as_mut() {
+            follow.paused = false;
+        }
+    }
+
+    /// Displays `data`, a new version of the followed record, keeping the view pinned to the end
+    /// unless the follow mode is paused.
+    fn show_followed_version(&mut self, data: &[u8]) {
+        let appended = self.wrap_appended(data);
+        let previous_scroll = self.scroll;
+
+        self.set_derived_view(None);
+
+        if let Some((text, wrap_cache)) = appended {
+            self.text_cache.replace(Some(text));
+            self.wrap_cache.replace(Some(wrap_cache));
+        }
+
+        if self.follow.as_ref().is_some_and(|follow| follow.paused) {
+            self.scroll = previous_scroll;
+        } else {
+            self.scroll_to_end();
+        }
+    }
+
+    /// The text and rows of `data`, reusing the rows of the current text if `data` only appends
+    /// to it, so that new versions of followed records are not wrapped from scratch.
+    fn wrap_appended(&self, data: &[u8]) -> Option<(Rc<str>, WrapCache)> {
+        if self.is_hex_dump() {
+            return None;
+        }
+
+        let previous_text = self.text_cache.borrow().clone()?;
+        let previous_wrap = self.wrap_cache.borrow().clone()?;
+        let text: Rc<str> = String::from_utf8_lossy(data).into();
+
+        if !text.starts_with(&*previous_text) {
+            return None;
+        }
+
+        // The appended text may continue the last row, so that row is wrapped again.
+        let (start, complete_rows) = match previous_wrap.rows.split_last() {
+            Some((last_row, complete_rows)) => (last_row.start, complete_rows),
+            None => (0, &[][..]),
+        };
+        let mut rows = complete_rows.to_vec();
+        rows.extend(
+            wrap_to_width(&text[start..], previous_wrap.width as usize)
+                .into_iter()
+                .map(|row| row.start + start..row.end + start),
+        );
+
+        Some((
+            text,
+            WrapCache {
+                width: previous_wrap.width,
+                rows: Rc::new(rows),
+            },
+        ))
+    }
+
+    /// The index of the first displayed row.
+    pub fn get_scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scrolls to `scroll` once the next record is opened, instead of to the top.
+    pub fn restore_scroll_on_open(&mut self, scroll: usize) {
+        self.restored_scroll = Some(scroll);
+    }
+
+    /// Keeps the scroll once `data` replaces the content of the opened record, if the displayed
+    /// rows and the ones above them are unchanged at its start, such as when content is appended.
+    pub fn anchor_scroll_on_update(&mut self, data: &[u8]) {
+        if self.derived_view.is_some() {
+            return;
+        }
+
+        let shown_rows = self.scroll + self.page_height();
+        let unchanged = if self.is_hex_dump() {
+            let shown = shown_rows.saturating_mul(self.bytes_per_row());
+            self.main_state
+                .borrow()
+                .opened_record
+                .as_ref()
+                .is_some_and(|opened_record| {
+                    let previous = &opened_record.record.data;
+                    data.starts_with(&previous[..std::cmp::min(shown, previous.len())])
+                })
+        } else {
+            is_scroll_anchored(
+                &self.get_text(),
+                &self.rows(),
+                shown_rows,
+                &String::from_utf8_lossy(data),
+            )
+        };
+
+        if unchanged {
+            self.restore_scroll_on_open(self.scroll);
+        }
+    }
+
+    fn get_text(&self) -> Rc<str> {
+        self.text_cache
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                if let Some(derived_view) = self.derived_view.as_ref() {
+                    return String::from_utf8_lossy(&derived_view.data).into();
+                }
+
+                let main_state = self.main_state.borrow();
+                let Some(opened_record) = main_state.opened_record.as_ref() else {
+                    return "".into();
+                };
+                let data = &opened_record.record.data;
+
+                if self.hex_dump {
+                    let gutter = self.hex_gutter.get();
+                    let bytes_per_row =
+                        hex_dump_bytes_per_row(self.page_width.get() as usize, gutter);
+                    return hex_dump(data, bytes_per_row, gutter).into();
+                }
+
+                // Invalid sequences are displayed as replacement characters.
+                String::from_utf8_lossy(data).into()
+            })
+            .clone()
+    }
+
+    /// The title of the derived view being displayed, if any.
+    pub fn get_derived_view_title(&self) -> Option<&str> {
+        self.derived_view
+            .as_ref()
+            .map(|derived_view| derived_view.title.as_str())
+    }
+
+    fn set_derived_view(&mut self, derived_view: Option<DerivedView>) {
+        self.derived_view = derived_view;
+        self.scroll = 0;
+        self.text_cache.replace(None);
+        self.wrap_cache.replace(None);
+        self.search = None;
+        self.selection = None;
+        self.stop_reading();
+    }
+
+    /// The soft-wrapped rows of the content, for the width it was last drawn with.
+    fn rows(&self) -> Rc<Vec<Range<usize>>> {
+        let width = self.page_width.get();
+        let mut wrap_cache = self.wrap_cache.borrow_mut();
+
+        if let Some(wrap_cache) = wrap_cache.as_ref()
+            && wrap_cache.width == width
+        {
+            return wrap_cache.rows.clone();
+        }
+
+        // The rows of the hex dump are laid out for the width by the text itself.
+        if self.is_hex_dump() {
+            self.text_cache.replace(None);
+        }
+
+        let rows = Rc::new(wrap_to_width(&self.get_text(), width as usize));
+        *wrap_cache = Some(WrapCache {
+            width,
+            rows: rows.clone(),
+        });
+        rows
+    }
+
+    fn page_height(&self) -> usize {
+        std::cmp::max(1, self.page_height.get() as usize)
+    }
+
+    fn page_count(&self) -> usize {
+        std::cmp::max(1, self.rows().len().div_ceil(self.page_height()))
+    }
+
+    /// The 0-based index of the page containing the first displayed row.
+    fn page_index(&self) -> usize {
+        self.scroll / self.page_height()
+    }
+
+    fn get_page_text(&self) -> String {
+        let text = self.get_text();
+        self.rows()
+            .iter()
+            .skip(self.scroll)
+            .take(self.page_height())
+            .map(|row| &text[row.clone()])
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn get_page_label(&self) -> String {
+        format!("page {} of {}", self.page_index() + 1, self.page_count())
+    }
+
+    /// Scrolls to the page at `page_index`, returning `false` if there is no such page.
+    fn go_to_page(&mut self, page_index: usize) -> bool {
+        if page_index >= self.page_count() {
+            return false;
+        }
+
+        self.scroll = page_index * self.page_height();
+        self.announcement = Some(if self.reading_since.is_some() {
+            self.get_page_text()
+        } else {
+            format!("Page {} of {}", page_index + 1, self.page_count())
+        });
+        true
+    }
+
+    fn stop_reading(&mut self) {
+        self.reading_since = None;
+    }
+
+    /// Finds the matches of the query as it is typed in, starting with the first one at or after
+    /// the first row displayed when the search was started.
+    fn update_matches(&mut self) {
+        let Some((query, previous_scroll)) = self
+            .search
+            .as_ref()
+            .map(|search| (search.query.clone(), search.previous_scroll))
+        else {
+            return;
+        };
+        let found = if self.is_hex_dump() {
+            parse_hex_pattern(&query).map(|pattern| {
+                let matches = self
+                    .main_state
+                    .borrow()
+                    .opened_record
+                    .as_ref()
+                    .map(|opened_record| find_bytes(&opened_record.record.data, &pattern))
+                    .unwrap_or_default();
+
+                (matches, previous_scroll * self.bytes_per_row())
+            })
+        } else {
+            let scroll_offset = self
+                .rows()
+                .get(previous_scroll)
+                .map(|row| row.start)
+                .unwrap_or(0);
+
+            Some((find_ignoring_case(&self.get_text(), &query), scroll_offset))
+        };
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+
+        search.invalid = found.is_none();
+        let (matches, scroll_offset) = found.unwrap_or_default();
+        search.current = matches
+            .iter()
+            .position(|found| found.start >= scroll_offset)
+            .unwrap_or(0);
+        search.matches = matches;
+    }
+
+    /// Stops editing the query and scrolls to the current match.
+    fn run_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+
+        search.editing = false;
+        self.scroll_to_current_match();
+    }
+
+    /// Removes the search, scrolling back to where it was started.
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.scroll = std::cmp::min(search.previous_scroll, self.max_scroll());
+        }
+    }
+
+    /// Moves to the next match, or the previous one if `forward` is `false`, wrapping around.
+    fn go_to_match(&mut self, forward: bool) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+
+        if search.matches.is_empty() {
+            return;
+        }
+
+        search.current = if forward {
+            (search.current + 1) % search.matches.len()
+        } else {
+            (search.current + search.matches.len() - 1) % search.matches.len()
+        };
+        self.scroll_to_current_match();
+    }
+
+    /// Scrolls so that the first row of the current match is centered.
+    fn scroll_to_current_match(&mut self) {
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        let Some(found) = search.matches.get(search.current) else {
+            self.announcement = Some(format!("No matches for {}", search.query));
+            return;
+        };
+        let row_index = if self.is_hex_dump() {
+            found.start / self.bytes_per_row()
+        } else {
+            rows_of_range(&self.rows(), found).start
+        };
+
+        self.scroll = row_index.saturating_sub(self.page_height() / 2);
+        self.announcement = Some(format!(
+            "Match {} of {}",
+            search.current + 1,
+            search.matches.len()
+        ));
+    }
+
+    /// Moves the cursor of the selection, starting a selection at the first displayed row if
+    /// there is none, and scrolls to keep the cursor displayed.
+    fn extend_selection(&mut self, movement: CursorMovement) {
+        let text = self.get_text();
+        let rows = self.rows();
+        let selection = self.selection.get_or_insert_with(|| {
+            let start = rows.get(self.scroll).map(|row| row.start).unwrap_or(0);
+            Selection {
+                anchor: start,
+                cursor: start,
+                visual: false,
+            }
+        });
+        let cursor = selection.cursor;
+        let row_index = std::cmp::min(
+            rows.partition_point(|row| row.end < cursor),
+            rows.len().saturating_sub(1),
+        );
+
+        selection.cursor = match movement {
+            CursorMovement::Left => text[..cursor]
+                .chars()
+                .next_back()
+                .map_or(cursor, |character| cursor - character.len_utf8()),
+            CursorMovement::Right => text[cursor..]
+                .chars()
+                .next()
+                .map_or(cursor, |character| cursor + character.len_utf8()),
+            CursorMovement::Up | CursorMovement::Down => {
+                let target_index = if movement == CursorMovement::Up {
+                    row_index.checked_sub(1)
+                } else {
+                    Some(row_index + 1).filter(|index| *index < rows.len())
+                };
+
+                match (
+                    rows.get(row_index),
+                    target_index.and_then(|index| rows.get(index)),
+                ) {
+                    (Some(row), Some(target)) => {
+                        let column = text[row.start..cursor].chars().count();
+                        text[target.clone()]
+                            .char_indices()
+                            .nth(column)
+                            .map_or(target.end, |(offset, _)| target.start + offset)
+                    }
+                    // Moving past the first or last row moves to the start or end of the content.
+                    _ if movement == CursorMovement::Up => 0,
+                    _ => text.len(),
+                }
+            }
+        };
+
+        let cursor_row_index = std::cmp::min(
+            rows.partition_point(|row| row.end < selection.cursor),
+            rows.len().saturating_sub(1),
+        );
+
+        if cursor_row_index < self.scroll {
+            self.scroll = cursor_row_index;
+        } else if cursor_row_index >= self.scroll + self.page_height() {
+            self.scroll = cursor_row_index + 1 - self.page_height();
+        }
+    }
+
+    /// Selects the whole content, unless it is large and this is not the confirmation.
+    fn select_all(&mut self, confirmed: bool) {
+        let length = self.get_text().len();
+
+        if length > SELECT_ALL_CONFIRMATION_SIZE && !confirmed {
+            self.select_all_pending = true;
+            self.announcement = Some(format!(
+                "The content has {}, press Ctrl+A again to select all of it",
+                format_size(length)
+            ));
+            return;
+        }
+
+        self.selection = Some(Selection {
+            anchor: 0,
+            cursor: length,
+            visual: false,
+        });
+        self.announcement = Some(format!("Selected all {}", format_size(length)));
+    }
+
+    /// Selects the current match of the search, if any.
+    fn select_current_match(&mut self) -> bool {
+        if self.is_hex_dump() {
+            return false;
+        }
+
+        let Some(found) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.matches.get(search.current))
+        else {
+            return false;
+        };
+
+        self.selection = Some(Selection {
+            anchor: found.start,
+            cursor: found.end,
+            visual: false,
+        });
+        true
+    }
+
+    fn is_selecting_visually(&self) -> bool {
+        self.selection.is_some_and(|selection| selection.visual)
+    }
+
+    /// Starts a visual selection at the first displayed row, or continues the current selection
+    /// visually, or ends the visual selection.
+    fn toggle_visual_selection(&mut self) {
+        match self.selection {
+            Some(Selection { visual: true, .. }) => self.selection = None,
+            Some(ref mut selection) => selection.visual = true,
+            None => {
+                let start = self.rows().get(self.scroll).map_or(0, |row| row.start);
+                self.selection = Some(Selection {
+                    anchor: start,
+                    cursor: start,
+                    visual: true,
+                });
+            }
+        }
+    }
+
+    /// Describes the visual selection, for the title of the pane.
+    pub fn get_selection_label(&self) -> Option<String> {
+        self.selection
+            .filter(|selection| selection.visual)
+            .map(|selection| {
+                format!(
+                    "VISUAL {}, [y] to copy",
+                    format_size(selection.range().len())
+                )
+            })
+    }
+
+    /// Copies the selection, ending it, or the whole content in the encoding it is displayed in.
+    fn copy(&mut self) -> Action {
+        let text = match self.selection.take() {
+            Some(selection) => self
+                .get_text()
+                .get(selection.range())
+                .unwrap_or_default()
+                .to_string(),
+            None if self.is_hex_dump() => self
+                .main_state
+                .borrow()
+                .opened_record
+                .as_ref()
+                .map_or_else(String::new, |opened_record| {
+                    Encoding::HEX.encode(&opened_record.record.data)
+                }),
+            None => self.get_text().to_string(),
+        };
+
+        Action::BroadcastMessage(if text.is_empty() {
+            ComponentMessage::ShowNotice {
+                message: "There is nothing to copy.".to_string(),
+                severity: Severity::Info,
+            }
+        } else {
+            ComponentMessage::CopyToClipboard {
+                text,
+                requester: self.id,
+            }
+        })
+    }
+
+    /// Splits the row into spans, highlighting the parts that are selected or covered by matches.
+    fn get_row_line<'a>(&self, text: &'a str, row: &Range<usize>, palette: Palette) -> Line<'a> {
+        let mut spans = Vec::new();
+        let selected = self
+            .selection
+            .as_ref()
+            .map(Selection::range)
+            .map(|selected| {
+                std::cmp::max(selected.start, row.start)..std::cmp::min(selected.end, row.end)
+            })
+            .filter(|selected| selected.start < selected.end);
+
+        if let Some(selected) = selected {
+            self.push_match_spans(
+                &mut spans,
+                text,
+                row.start..selected.start,
+                Style::new(),
+                palette,
+            );
+            self.push_match_spans(
+                &mut spans,
+                text,
+                selected.clone(),
+                palette.selection_style(),
+                palette,
+            );
+            self.push_match_spans(
+                &mut spans,
+                text,
+                selected.end..row.end,
+                Style::new(),
+                palette,
+            );
+        } else {
+            self.push_match_spans(&mut spans, text, row.clone(), Style::new(), palette);
+        }
+
+        Line::from(spans)
+    }
+
+    /// Splits the range into spans with `style`, highlighting the parts that are covered by
+    /// matches.
+    fn push_match_spans<'a>(
+        &self,
+        spans: &mut Vec<Span<'a>>,
+        text: &'a str,
+        range: Range<usize>,
+        style: Style,
+        palette: Palette,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+
+        let Some(search) = self.search.as_ref() else {
+            spans.push(Span::styled(&text[range], style));
+            return;
+        };
+        let mut position = range.start;
+        // Only the matches overlapping the range are visited, as there may be many of them.
+        let first_index = search
+            .matches
+            .partition_point(|found| found.end <= range.start);
+
+        for (index, found) in search.matches.iter().enumerate().skip(first_index) {
+            if found.start >= range.end {
+                break;
+            }
+
+            let start = std::cmp::max(found.start, range.start);
+            let end = std::cmp::min(found.end, range.end);
+
+            spans.push(Span::styled(&text[position..start], style));
+            spans.push(Span::styled(
+                &text[start..end],
+                palette.match_style(index == search.current).patch(style),
+            ));
+            position = end;
+        }
+
+        spans.push(Span::styled(&text[position..range.end], style));
+    }
+
+    /// Splits the row of the hex dump at `row_index` into spans, highlighting the bytes covered by
+    /// matches in both the hex column and the ASCII gutter.
+    fn get_hex_row_line<'a>(
+        &self,
+        text: &'a str,
+        row_index: usize,
+        row: &Range<usize>,
+        palette: Palette,
+    ) -> Line<'a> {
+        let Some(search) = self.search.as_ref() else {
+            return Line::raw(&text[row.clone()]);
+        };
+        let bytes_per_row = self.bytes_per_row();
+        let row_bytes = (row_index * bytes_per_row)..((row_index + 1) * bytes_per_row);
+        let mut hex_highlights = Vec::new();
+        let mut gutter_highlights = Vec::new();
+        let first_index = search
+            .matches
+            .partition_point(|found| found.end <= row_bytes.start);
+
+        for (index, found) in search.matches.iter().enumerate().skip(first_index) {
+            if found.start >= row_bytes.end {
+                break;
+            }
+
+            let bytes = (std::cmp::max(found.start, row_bytes.start) - row_bytes.start)
+                ..(std::cmp::min(found.end, row_bytes.end) - row_bytes.start);
+            let (hex, gutter) = hex_dump_columns(bytes, bytes_per_row, self.hex_gutter.get());
+            hex_highlights.push((hex, index));
+            gutter_highlights.extend(gutter.map(|gutter| (gutter, index)));
+        }
+
+        let mut spans = Vec::new();
+        let mut position = row.start;
+
+        for (highlight, index) in hex_highlights.into_iter().chain(gutter_highlights) {
+            let start = std::cmp::min(row.start + highlight.start, row.end);
+            let end = std::cmp::min(row.start + highlight.end, row.end);
+
+            spans.push(Span::raw(&text[position..start]));
+            spans.push(Span::styled(
+                &text[start..end],
+                palette.match_style(index == search.current),
+            ));
+            position = end;
+        }
+
+        spans.push(Span::raw(&text[position..row.end]));
+        Line::from(spans)
+    }
+
+    fn get_search_line(&self, palette: Palette) -> Option<Line<'_>> {
+        let search = self.search.as_ref()?;
+        let mut spans = vec![
+            Span::raw("/"),
+            Span::styled(search.query.as_str(), palette.input_style()),
+        ];
+
+        if search.editing {
+            spans.push(Span::styled(" ", palette.cursor_style()));
+        }
+
+        let status = if search.invalid {
+            Some("  (not a hex byte pattern)".to_string())
+        } else if search.query.is_empty() {
+            None
+        } else if search.matches.is_empty() {
+            Some("  (no matches)".to_string())
+        } else {
+            Some(format!(
+                "  ({}/{})",
+                search.current + 1,
+                search.matches.len()
+            ))
+        };
+
+        spans.extend(status.map(|status| Span::styled(status, palette.muted_style())));
+
+        Some(Line::from(spans))
+    }
+
+    fn is_search_inline(&self) -> bool {
+        self.height.get() >= MIN_INLINE_SEARCH_HEIGHT
+    }
+
+    /// The line to display in the status bar, when the view is too small to display it itself.
+    pub fn get_status_line(&self, palette: Palette) -> Option<Line<'_>> {
+        if self.is_search_inline() {
+            return None;
+        }
+
+        self.get_search_line(palette)
+    }
+
+    /// Handles the keys while the search query is being typed in.
+    fn handle_search_editing_event(&mut self, event: &Event) -> HandleEventSuccess {
+        let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) = event
+        else {
+            return HandleEventSuccess::unhandled();
+        };
+        let Some(search) = self.search.as_mut() else {
+            return HandleEventSuccess::unhandled();
+        };
+
+        match code {
+            KeyCode::Char(character) => {
+                search.query.push(*character);
+                self.update_matches();
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Enter => self.run_search(),
+            KeyCode::Esc => self.cancel_search(),
+            _ => return HandleEventSuccess::unhandled(),
+        }
+
+        HandleEventSuccess::handled().with_action(Action::Render)
+    }
+
+    /// The live region node announcing page changes.
+    fn get_announcement_node(&self) -> Option<accesskit::Node> {
+        let announcement = self.announcement.as_ref()?;
+        let mut node = accesskit::Node::new(accesskit::Role::Status);
+        node.set_live(accesskit::Live::Polite);
+        node.set_value(announcement.as_str());
+        Some(node)
+    }
+}
+
+impl Component for ContentView {
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn get_panel(&self) -> Option<Panel> {
+        Some(Panel::Content)
+    }
+
+    fn accepts_text_input(&self) -> bool {
+        self.search.as_ref().is_some_and(|search| search.editing)
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            ComponentMessage::RecordOpen {
+                record_ref,
+                read_result,
+                ..
+            } => {
+                let follow = self
+                    .follow
+                    .take()
+                    .filter(|follow| follow.record_ref == record_ref.at_version(None));
+
+                if let Some(follow) = follow
+                    && let Some(read_result) = read_result.as_ref()
+                {
+                    self.follow = Some(follow);
+                    self.show_followed_version(&read_result.data);
+                    return Ok(Some(Action::Render));
+                }
+
+                self.set_derived_view(None);
+                self.announcement = None;
+
+                if let Some(scroll) = self.restored_scroll.take() {
+                    self.scroll = scroll;
+                }
+
+                None
+            }
+            ComponentMessage::ShowError { .. } if self.follow.is_some() => {
+                // Otherwise the error would be shown again on every poll.
+                self.follow = None;
+                self.announcement = Some("Stopped following the record".to_string());
+                None
+            }
+            ComponentMessage::ShowDerivedView { title, data } => {
+                self.announcement = Some(format!("Showing the output of {title}"));
+                self.set_derived_view(Some(DerivedView { title, data }));
+                Some(Action::Render)
+            }
+            ComponentMessage::OnTick => {
+                if let Some(reading_since) = self.reading_since
+                    && reading_since.elapsed() >= READ_PAGE_INTERVAL
+                {
+                    self.reading_since = Some(Instant::now());
+
+                    if !self.go_to_page(self.page_index() + 1) {
+                        self.stop_reading();
+                    }
+
+                    Some(Action::Render)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // Scrolled even while the search is edited or another component is focused.
+        if let Event::Mouse(MouseEvent {
+            kind: kind @ (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown),
+            ..
+        }) = event
+        {
+            self.stop_reading();
+            self.pause_follow();
+            self.scroll_by(if *kind == MouseEventKind::ScrollUp {
+                -MOUSE_SCROLL_ROWS
+            } else {
+                MOUSE_SCROLL_ROWS
+            });
+            return Ok(HandleEventSuccess::handled().with_action(Action::Render));
+        }
+
+        if self.search.as_ref().is_some_and(|search| search.editing) {
+            return Ok(self.handle_search_editing_event(event));
+        }
+
+        let select_all_pending = std::mem::take(&mut self.select_all_pending);
+
+        Ok(match event {
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down),
+                modifiers: modifiers @ (KeyModifiers::SHIFT | KeyModifiers::NONE),
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) if !self.is_hex_dump()
+                && (*modifiers == KeyModifiers::SHIFT || self.is_selecting_visually()) =>
+            {
+                self.stop_reading();
+                self.extend_selection(match code {
+                    KeyCode::Left => CursorMovement::Left,
+                    KeyCode::Right => CursorMovement::Right,
+                    KeyCode::Up => CursorMovement::Up,
+                    _ => CursorMovement::Down,
+                });
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) if !self.is_hex_dump() => {
+                self.stop_reading();
+                self.select_all(select_all_pending);
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) => HandleEventSuccess::handled()
+                .with_action(Action::BroadcastMessage(ComponentMessage::PromptExport)),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('v'),
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.select_current_match() => {
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('v'),
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                ..
+            }) if !self.is_hex_dump() => {
+                self.stop_reading();
+                self.toggle_visual_selection();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let action = self.copy();
+                HandleEventSuccess::handled().with_action(action)
+            }
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::Up | KeyCode::Down),
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                self.stop_reading();
+                self.pause_follow();
+                self.scroll_by(if *code == KeyCode::Up { -1 } else { 1 });
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Home | KeyCode::Char('g'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.stop_reading();
+                self.pause_follow();
+                self.scroll = 0;
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::PageDown,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                self.stop_reading();
+                self.pause_follow();
+                self.go_to_page(self.page_index() + 1);
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::PageUp,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                self.stop_reading();
+                self.pause_follow();
+                self.go_to_page(self.page_index().saturating_sub(1));
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::End | KeyCode::Char('G'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.stop_reading();
+                self.scroll_to_end();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Backspace,
+                kind: KeyEventKind::Press,
+                ..
+            }) => match self.main_state.borrow().open_parent_action() {
+                Some(action) => HandleEventSuccess::handled().with_action(action),
+                None => HandleEventSuccess::unhandled(),
+            },
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('F'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.stop_reading();
+                self.toggle_follow();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('r'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                // Read from here.
+                self.reading_since = Some(Instant::now());
+                self.go_to_page(self.page_index());
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('/'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.stop_reading();
+                self.search = Some(Search {
+                    editing: true,
+                    previous_scroll: self.scroll,
+                    ..Default::default()
+                });
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(character @ ('n' | 'N')),
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) if self.search.is_some() => {
+                self.pause_follow();
+                self.go_to_match(*character == 'n');
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.reading_since.is_some() => {
+                self.stop_reading();
+                self.announcement = None;
+                HandleEventSuccess::handled()
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.selection.is_some() => {
+                self.selection = None;
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.search.is_some() => {
+                self.cancel_search();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.derived_view.is_some() => {
+                self.set_derived_view(None);
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            _ => HandleEventSuccess::unhandled(),
+        })
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Document);
+        node.set_label(format!("Record content, {}", self.get_page_label()));
+        node.set_value(self.get_page_text());
+
+        // Offsets are in characters of the whole content, which may span multiple pages.
+        if let Some(selection) = self.selection.as_ref() {
+            let text = self.get_text();
+            let character_index = |offset: usize| text[..offset].chars().count();
+            let selected = &text[selection.range()];
+            let mut description = selected
+                .chars()
+                .take(SELECTION_DESCRIPTION_LIMIT)
+                .collect::<String>();
+
+            if description.len() < selected.len() {
+                description.push('…');
+            }
+
+            node.set_description(format!("selected: {description}"));
+            node.set_text_selection(accesskit::TextSelection {
+                anchor: accesskit::TextPosition {
+                    node: self.id.into(),
+                    character_index: character_index(selection.anchor),
+                },
+                focus: accesskit::TextPosition {
+                    node: self.id.into(),
+                    character_index: character_index(selection.cursor),
+                },
+            });
+        }
+
+        Ok(node)
+    }
+
+    fn get_accessibility_extra_nodes(&self) -> Vec<(accesskit::NodeId, accesskit::Node)> {
+        self.get_announcement_node()
+            .map(|node| (self.announcement_id.into(), node))
+            .into_iter()
+            .collect()
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.content = Some(crate::introspection::ContentState {
+            derived_view: self.get_derived_view_title().map(str::to_string),
+            hex_dump: self.is_hex_dump(),
+            scroll: self.get_scroll(),
+            following: self.is_following(),
+        });
+    }
+}
+
+impl Drawable for ContentView {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, area: Rect, (): Self::Args<'_>) -> Result<()> {
+        self.height.set(area.height);
+        context.register_mouse_area(self.id, area);
+
+        if area.height < MIN_HEIGHT {
+            self.page_height.set(area.height);
+            let (notice, _) = truncate_to_width(
+                "content hidden — enlarge the terminal",
+                area.width as usize,
+                TruncateMode::End,
+            );
+            let notice = Span::styled(notice, context.palette().muted_style());
+            context.render_named_widget("content hidden", notice, area);
+            return Ok(());
+        }
+
+        let hex_gutter = area.height >= MIN_HEX_GUTTER_HEIGHT;
+
+        if self.hex_gutter.replace(hex_gutter) != hex_gutter && self.is_hex_dump() {
+            self.wrap_cache.replace(None);
+        }
+
+        let search_line = self
+            .get_search_line(context.palette())
+            .filter(|_| self.is_search_inline());
+        let [area_rows, area_search] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(search_line.is_some() as u16),
+            ])
+            .areas(area);
+
+        context.name_area("content rows", area_rows);
+        self.page_height.set(area_rows.height);
+        self.page_width.set(area_rows.width);
+
+        if self.main_state.borrow().opened_record.is_some() {
+            let stats = self
+                .main_state
+                .borrow()
+                .opened_record
+                .as_ref()
+                .and_then(|opened_record| opened_record.stats.clone())
+                .filter(|_| self.is_hex_dump() && area_rows.width > 2);
+            // The entropy of the blocks the rows of the hex dump are in, followed by a space.
+            let area_sparkline = Rect {
+                width: stats.is_some() as u16,
+                ..area_rows
+            };
+            let area_rows = Rect {
+                x: area_rows.x.saturating_add(2 * area_sparkline.width),
+                width: area_rows.width.saturating_sub(2 * area_sparkline.width),
+                ..area_rows
+            };
+
+            // Whether the content overflows depends on the width left by the scrollbar, so the
+            // previous layout is tried first, to avoid wrapping the content twice per frame. The
+            // rows are computed first, as they may invalidate the text of the hex dump.
+            let mut scrollbar = self.scrollbar.get();
+            let rows = loop {
+                self.page_width
+                    .set(area_rows.width.saturating_sub(scrollbar as u16));
+                let rows = self.rows();
+                let overflows = area_rows.height >= MIN_SCROLLBAR_HEIGHT
+                    && rows.len() > area_rows.height as usize;
+
+                if overflows == scrollbar {
+                    break rows;
+                }
+
+                scrollbar = overflows;
+            };
+            self.scrollbar.set(scrollbar);
+
+            if scrollbar {
+                let mut scrollbar_state =
+                    ScrollbarState::new(rows.len().saturating_sub(area_rows.height as usize) + 1)
+                        .position(self.scroll)
+                        .viewport_content_length(area_rows.height as usize);
+                let style = context.palette().scrollbar_style();
+                context.frame().render_stateful_widget(
+                    Scrollbar::new(ScrollbarOrientation::VerticalRight).style(style),
+                    area_rows,
+                    &mut scrollbar_state,
+                );
+            }
+
+            let area_rows = Rect {
+                width: self.page_width.get(),
+                ..area_rows
+            };
+
+            if let Some(stats) = stats {
+                let bytes_per_row =
+                    hex_dump_bytes_per_row(area_rows.width as usize, self.hex_gutter.get());
+                let lines = (self.scroll..rows.len())
+                    .take(area_rows.height as usize)
+                    .map(|row_index| {
+                        let block_index = row_index * bytes_per_row / BLOCK_SIZE;
+                        let entropy = stats.block_entropies.get(block_index).copied();
+                        Line::raw(entropy.map_or(" ", entropy_bar))
+                    })
+                    .collect::<Vec<_>>();
+                context.render_named_widget(
+                    "entropy sparkline",
+                    Paragraph::new(Text::from(lines)).style(context.palette().muted_style()),
+                    area_sparkline,
+                );
+            }
+
+            let text = self.get_text();
+            let palette = context.palette();
+            let lines = rows
+                .iter()
+                .enumerate()
+                .skip(self.scroll)
+                .take(area_rows.height as usize)
+                .map(|(row_index, row)| {
+                    if self.is_hex_dump() {
+                        self.get_hex_row_line(&text, row_index, row, palette)
+                    } else {
+                        self.get_row_line(&text, row, palette)
+                    }
+                })
+                .collect::<Vec<_>>();
+            context
+                .frame()
+                .render_widget(Paragraph::new(Text::from(lines)), area_rows);
+        }
+
+        if let Some(search_line) = search_line {
+            context.render_named_widget("search", search_line, area_search);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::main_view::RegistryState;
+    use super::*;
+
+    fn is_anchored(previous: &str, shown_rows: usize, updated: &str) -> bool {
+        is_scroll_anchored(previous, &wrap_to_width(previous, 8), shown_rows, updated)
+    }
+
+    #[test]
+    fn scroll_is_kept_when_content_is_appended() {
+        assert!(is_anchored(
+            "one\ntwo\nthree\n",
+            2,
+            "one\ntwo\nthree\nfour\n"
+        ));
+        assert!(is_anchored(
+            "one\ntwo\nthree",
+            3,
+            "one\ntwo\nthree and more"
+        ));
+    }
+
+    #[test]
+    fn scroll_is_kept_when_only_rows_below_the_displayed_ones_change() {
+        assert!(is_anchored("one\ntwo\nthree\n", 2, "one\ntwo\nchanged\n"));
+    }
+
+    #[test]
+    fn scroll_is_reset_when_displayed_rows_change() {
+        assert!(!is_anchored(
+            "one\ntwo\nthree\n",
+            2,
+            "one\nchanged\nthree\n"
+        ));
+        // Rows above the displayed ones changing would shift the displayed ones.
+        assert!(!is_anchored(
+            "one\ntwo\nthree\n",
+            3,
+            "inserted\none\ntwo\nthree\n"
+        ));
+    }
+
+    #[test]
+    fn scroll_of_wrapped_rows_is_kept_by_row() {
+        // Wrapped to 8 columns, the first row ends within the first line.
+        let previous = "a long first line\nsecond\n";
+
+        assert!(is_anchored(previous, 1, "a long f, and then changed"));
+        assert!(!is_anchored(previous, 2, "a long f, and then changed"));
+    }
+
+    /// A view of the text, with room for all of its rows.
+    fn view_of(text: &str) -> ContentView {
+        let (action_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let main_state = Rc::new(RefCell::new(MainState {
+            registry: RegistryState::Opening,
+            opened_record: None,
+        }));
+        let mut view = ContentView::new(ComponentId::new(), &action_tx, &main_state);
+        view.set_derived_view(Some(DerivedView {
+            title: "test".to_string(),
+            data: text.as_bytes().to_vec(),
+        }));
+        view.page_width.set(80);
+        view.page_height.set(10);
+        view
+    }
+
+    fn press(view: &mut ContentView, code: KeyCode, modifiers: KeyModifiers) {
+        assert!(
+            view.handle_event(&Event::Key(KeyEvent::new(code, modifiers)))
+                .unwrap()
+                .absorb
+        );
+    }
+
+    /// The selected text and its anchor and focus in characters, as exposed to accesskit.
+    fn accessible_selection(view: &ContentView) -> Option<(String, usize, usize)> {
+        let node = view.get_accessibility_node().unwrap();
+        let selection = node.text_selection()?;
+        let node_id: accesskit::NodeId = view.id.into();
+        assert_eq!(selection.anchor.node, node_id);
+        assert_eq!(selection.focus.node, node_id);
+
+        Some((
+            node.description()?.to_string(),
+            selection.anchor.character_index,
+            selection.focus.character_index,
+        ))
+    }
+
+    fn selected(text: &str, anchor: usize, focus: usize) -> Option<(String, usize, usize)> {
+        Some((format!("selected: {text}"), anchor, focus))
+    }
+
+    #[test]
+    fn shift_arrows_expose_the_selection_in_characters() {
+        let mut view = view_of("aé💥x\n日本語\nwörld");
+        assert_eq!(accessible_selection(&view), None);
+
+        for _ in 0..3 {
+            press(&mut view, KeyCode::Right, KeyModifiers::SHIFT);
+        }
+        assert_eq!(accessible_selection(&view), selected("aé💥", 0, 3));
+
+        // The column is kept in characters, the second row being shorter than it.
+        press(&mut view, KeyCode::Down, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("aé💥x\n日本語", 0, 8));
+
+        press(&mut view, KeyCode::Left, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("aé💥x\n日本", 0, 7));
+
+        press(&mut view, KeyCode::Down, KeyModifiers::SHIFT);
+        assert_eq!(
+            accessible_selection(&view),
+            selected("aé💥x\n日本語\nwö", 0, 11)
+        );
+
+        // Moving past the last row moves to the end of the content.
+        press(&mut view, KeyCode::Down, KeyModifiers::SHIFT);
+        press(&mut view, KeyCode::Right, KeyModifiers::SHIFT);
+        assert_eq!(
+            accessible_selection(&view),
+            selected("aé💥x\n日本語\nwörld", 0, 14)
+        );
+
+        // The column is clamped to the second row, and kept from there.
+        press(&mut view, KeyCode::Up, KeyModifiers::SHIFT);
+        press(&mut view, KeyCode::Up, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("aé💥", 0, 3));
+
+        // Past the first row and the start of the content, the selection is empty.
+        press(&mut view, KeyCode::Up, KeyModifiers::SHIFT);
+        press(&mut view, KeyCode::Left, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("", 0, 0));
+
+        press(&mut view, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(accessible_selection(&view), None);
+    }
+
+    #[test]
+    fn selection_extended_backwards_has_its_focus_before_its_anchor() {
+        let mut view = view_of("aé💥x\n日本語\nwörld");
+        // Started at the first displayed row.
+        view.scroll = 2;
+
+        press(&mut view, KeyCode::Left, KeyModifiers::SHIFT);
+        press(&mut view, KeyCode::Left, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("語\n", 9, 7));
+
+        press(&mut view, KeyCode::Up, KeyModifiers::SHIFT);
+        assert_eq!(accessible_selection(&view), selected("💥x\n日本語\n", 9, 2));
+    }
+
+    #[test]
+    fn select_all_and_visual_selection_expose_the_selection() {
+        let mut view = view_of("aé💥x\n日本語");
+
+        press(&mut view, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(accessible_selection(&view), selected("aé💥x\n日本語", 0, 8));
+
+        press(&mut view, KeyCode::Esc, KeyModifiers::NONE);
+        press(&mut view, KeyCode::Char('v'), KeyModifiers::NONE);
+        press(&mut view, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut view, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(accessible_selection(&view), selected("aé", 0, 2));
+    }
+
+    /// Lines numbered from 1 to `count`, as successive versions of a log-like record append.
+    fn log_lines(count: usize) -> String {
+        (1..=count).map(|line| format!("line {line}\n")).collect()
+    }
+
+    /// A view following a record whose content is `text`, with three rows per page.
+    fn followed_view(text: &str) -> ContentView {
+        let mut view = view_of("");
+        view.set_derived_view(None);
+        view.page_width.set(20);
+        view.page_height.set(3);
+        view.text_cache.replace(Some(text.into()));
+        view.follow = Some(Follow {
+            record_ref: RecordRef::from_utf8_path("log"),
+            paused: false,
+        });
+        view.scroll_to_end();
+        view
+    }
+
+    #[test]
+    fn followed_view_stays_pinned_to_the_end_of_new_versions() {
+        let mut view = followed_view(&log_lines(5));
+        assert_eq!(view.get_scroll(), view.max_scroll());
+        assert_eq!(view.get_follow_label(), Some("FOLLOWING"));
+
+        for text in [
+            log_lines(8),
+            log_lines(12),
+            // Continuing the last row, past the width.
+            log_lines(12) + "a row longer than the width",
+        ] {
+            view.show_followed_version(text.as_bytes());
+
+            assert_eq!(view.get_scroll(), view.max_scroll());
+            assert!(view.get_scroll() > 0);
+            // The rows reused from the previous version are the ones wrapping from scratch gives.
+            assert_eq!(*view.rows(), wrap_to_width(&text, 20));
+        }
+    }
+
+    #[test]
+    fn scrolling_pauses_the_followed_view_until_end_is_pressed() {
+        let mut view = followed_view(&log_lines(5));
+        view.show_followed_version(log_lines(8).as_bytes());
+
+        press(&mut view, KeyCode::Up, KeyModifiers::NONE);
+        let paused_scroll = view.get_scroll();
+        assert_eq!(paused_scroll, view.max_scroll() - 1);
+        assert_eq!(
+            view.get_follow_label(),
+            Some("FOLLOWING paused, [End] to resume")
+        );
+
+        // New versions keep the scroll while paused.
+        view.show_followed_version(log_lines(12).as_bytes());
+        assert_eq!(view.get_scroll(), paused_scroll);
+        assert!(view.get_scroll() < view.max_scroll());
+
+        press(&mut view, KeyCode::End, KeyModifiers::NONE);
+        assert_eq!(view.get_scroll(), view.max_scroll());
+        assert_eq!(view.get_follow_label(), Some("FOLLOWING"));
+
+        view.show_followed_version(log_lines(20).as_bytes());
+        assert_eq!(view.get_scroll(), view.max_scroll());
+    }
+
+    /// The rows of the view drawn into `width`×`height` cells.
+    fn draw_view(view: &ContentView, width: u16, height: u16) -> Vec<String> {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| {
+                let palette = crate::config::Config::default()
+                    .display
+                    .theme
+                    .palette(u16::MAX);
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                view.draw(&mut context, Rect::new(0, 0, width, height), ())
+                    .unwrap();
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn view_in_1_to_6_rows() {
+        let mut view = view_of(&log_lines(3));
+        press(&mut view, KeyCode::Char('/'), KeyModifiers::NONE);
+        for c in "line".chars() {
+            press(&mut view, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        press(&mut view, KeyCode::Enter, KeyModifiers::NONE);
+        let palette = crate::config::Config::default()
+            .display
+            .theme
+            .palette(u16::MAX);
+
+        // Too small for any content, a notice is displayed instead.
+        assert_eq!(
+            draw_view(&view, 40, 1),
+            ["content hidden — enlarge the terminal"]
+        );
+        assert_eq!(draw_view(&view, 20, 1), ["content hidden — en…"]);
+        assert_eq!(view.page_height.get(), 1);
+
+        for height in 2..=6 {
+            let rows = draw_view(&view, 40, height);
+            let inline = height >= MIN_INLINE_SEARCH_HEIGHT;
+
+            assert!(rows.iter().all(|row| !row.starts_with("content hidden")));
+            // The search is displayed below the content, or by the main view in the footer.
+            assert_eq!(
+                rows.last().unwrap() == "/line  (1/3)",
+                inline,
+                "{height}: {rows:?}"
+            );
+            assert_eq!(view.get_status_line(palette).is_none(), inline, "{height}");
+            assert_eq!(view.page_height.get(), height - inline as u16, "{height}");
+            assert_eq!(
+                view.hex_gutter.get(),
+                height >= MIN_HEX_GUTTER_HEIGHT,
+                "{height}"
+            );
+        }
+    }
+
+    #[test]
+    fn status_line_is_the_search_line_of_small_views() {
+        let mut view = view_of(&log_lines(3));
+        let palette = crate::config::Config::default()
+            .display
+            .theme
+            .palette(u16::MAX);
+        draw_view(&view, 40, 3);
+        assert!(view.get_status_line(palette).is_none());
+
+        press(&mut view, KeyCode::Char('/'), KeyModifiers::NONE);
+        press(&mut view, KeyCode::Char('x'), KeyModifiers::NONE);
+        let status_line = view.get_status_line(palette).unwrap();
+        assert_eq!(status_line.to_string(), "/x   (no matches)");
+    }
+
+    /// 10,000 numbered lines without a trailing newline, 1,000 pages of 10 rows.
+    fn long_fixture() -> String {
+        fixture_lines(1, 10_000)
+    }
+
+    /// The lines of the fixture numbered from `first` to `last`.
+    fn fixture_lines(first: usize, last: usize) -> String {
+        (first..=last)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The announcement, as listed under the node of the view in the accessibility tree.
+    fn announcement(view: &ContentView) -> Option<String> {
+        let update = crate::component::build_accessibility_tree(view, view.id).unwrap();
+        let announcement_id = accesskit::NodeId::from(view.announcement_id);
+        let (_, node) = update.nodes.iter().find(|(id, _)| *id == announcement_id)?;
+        assert!(update.nodes[0].1.children().contains(&announcement_id));
+        assert_eq!(node.live(), Some(accesskit::Live::Polite));
+        node.value().map(str::to_string)
+    }
+
+    #[test]
+    fn accessibility_node_presents_the_current_page_of_long_content() {
+        let mut view = view_of(&long_fixture());
+        let page = |view: &ContentView| {
+            let node = view.get_accessibility_node().unwrap();
+            (
+                node.label().unwrap().to_string(),
+                node.value().unwrap().to_string(),
+            )
+        };
+
+        assert_eq!(
+            page(&view),
+            (
+                "Record content, page 1 of 1000".to_string(),
+                fixture_lines(1, 10)
+            )
+        );
+        assert_eq!(announcement(&view), None);
+
+        press(&mut view, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(
+            page(&view),
+            (
+                "Record content, page 2 of 1000".to_string(),
+                fixture_lines(11, 20)
+            )
+        );
+        assert_eq!(announcement(&view), Some("Page 2 of 1000".to_string()));
+
+        press(&mut view, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(page(&view).1, fixture_lines(1, 10));
+        assert_eq!(announcement(&view), Some("Page 1 of 1000".to_string()));
+
+        press(&mut view, KeyCode::End, KeyModifiers::NONE);
+        assert_eq!(
+            page(&view),
+            (
+                "Record content, page 1000 of 1000".to_string(),
+                fixture_lines(9991, 10_000)
+            )
+        );
+
+        // There is no page to announce past the last one.
+        press(&mut view, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(page(&view).1, fixture_lines(9991, 10_000));
+        assert_eq!(announcement(&view), Some("Page 1 of 1000".to_string()));
+    }
+
+    #[test]
+    fn reading_from_here_announces_the_following_pages_until_stopped() {
+        let mut view = view_of(&long_fixture());
+        let next_page = |view: &mut ContentView| {
+            view.reading_since = Instant::now().checked_sub(READ_PAGE_INTERVAL);
+            view.update(ComponentMessage::OnTick).unwrap()
+        };
+
+        press(&mut view, KeyCode::PageDown, KeyModifiers::NONE);
+        press(&mut view, KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(announcement(&view), Some(fixture_lines(11, 20)));
+
+        // The page is presented for a while before the next one is announced.
+        assert!(view.update(ComponentMessage::OnTick).unwrap().is_none());
+        assert_eq!(announcement(&view), Some(fixture_lines(11, 20)));
+
+        for page in 2..5 {
+            assert!(next_page(&mut view).is_some());
+            assert_eq!(
+                announcement(&view),
+                Some(fixture_lines(page * 10 + 1, page * 10 + 10))
+            );
+            assert_eq!(
+                view.get_accessibility_node().unwrap().value(),
+                announcement(&view).as_deref()
+            );
+        }
+
+        press(&mut view, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(announcement(&view), None);
+        assert!(view.reading_since.is_none());
+        assert_eq!(view.get_scroll(), 40);
+
+        // Reading stops after the last page.
+        press(&mut view, KeyCode::End, KeyModifiers::NONE);
+        press(&mut view, KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(announcement(&view), Some(fixture_lines(9991, 10_000)));
+        next_page(&mut view);
+        assert!(view.reading_since.is_none());
+        assert_eq!(view.get_scroll(), 9990);
+    }
+}
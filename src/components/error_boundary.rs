@@ -0,0 +1,456 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+
+use color_eyre::eyre::{Report, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+use tracing::error;
+
+use crate::{
+    action::{Action, ComponentMessage, Panel},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    tui::Event,
+};
+
+/// Reconstructs the wrapped component with the given ID.
+type Factory<C> = Box<dyn Fn(ComponentId) -> Result<C>>;
+
+/// Wraps a panel, so that an error returned by it or a panic while drawing it only replaces the
+/// panel with a placeholder, instead of taking down the whole user interface.
+/// The panel can then be reset, reconstructing it with the factory it was mounted with.
+pub struct ErrorBoundary<C> {
+    inner: C,
+    factory: Factory<C>,
+    /// The failure of the panel, set while drawing if it failed then.
+    failure: RefCell<Option<String>>,
+    showing_details: bool,
+}
+
+impl<C: Component> ErrorBoundary<C> {
+    pub fn new(
+        id: ComponentId,
+        factory: impl Fn(ComponentId) -> Result<C> + 'static,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: factory(id)?,
+            factory: Box::new(factory),
+            failure: RefCell::new(None),
+            showing_details: false,
+        })
+    }
+
+    fn is_failed(&self) -> bool {
+        self.failure.borrow().is_some()
+    }
+
+    fn fail(&self, stage: &str, error: &Report) {
+        error!(id = ?self.inner.get_id(), ?error, "The panel failed to {stage}.");
+        self.failure.replace(Some(format!("{error:#}")));
+    }
+
+    fn reset(&mut self) {
+        match (self.factory)(self.inner.get_id()) {
+            Ok(inner) => {
+                self.inner = inner;
+                self.failure.replace(None);
+                self.showing_details = false;
+            }
+            Err(error) => self.fail("reset", &error),
+        }
+    }
+}
+
+impl<C> Deref for ErrorBoundary<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<C> DerefMut for ErrorBoundary<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<C: Debug> Debug for ErrorBoundary<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorBoundary")
+            .field("inner", &self.inner)
+            .field("failure", &self.failure)
+            .field("showing_details", &self.showing_details)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Component> Component for ErrorBoundary<C> {
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        if self.is_failed() {
+            return Ok(match event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    self.reset();
+                    HandleEventSuccess::handled().with_action(Action::Render)
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('d'),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    self.showing_details = !self.showing_details;
+                    HandleEventSuccess::handled().with_action(Action::Render)
+                }
+                _ => HandleEventSuccess::unhandled(),
+            });
+        }
+
+        self.inner.handle_event(event).or_else(|error| {
+            self.fail("handle an event", &error);
+            Ok(HandleEventSuccess::handled().with_action(Action::Render))
+        })
+    }
+
+    fn capture_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        if self.is_failed() {
+            return Ok(HandleEventSuccess::unhandled());
+        }
+
+        self.inner.capture_event(event).or_else(|error| {
+            self.fail("handle an event", &error);
+            Ok(HandleEventSuccess::handled().with_action(Action::Render))
+        })
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        if self.is_failed() {
+            return Ok(None);
+        }
+
+        self.inner.update(message).or_else(|error| {
+            self.fail("update", &error);
+            Ok(Some(Action::Render))
+        })
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.inner.get_id()
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        if let Some(failure) = self.failure.borrow().as_ref() {
+            let mut node = accesskit::Node::new(accesskit::Role::Alert);
+            node.set_label("Panel crashed, press r to reset");
+            node.set_description(failure.as_str());
+            return Ok(node);
+        }
+
+        self.inner.get_accessibility_node()
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        match self.failure.borrow().as_ref() {
+            Some(failure) => state.failed_panels.push(failure.clone()),
+            None => self.inner.introspect(state),
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        // Focusable while failed, so that the panel can be reset.
+        self.is_failed() || self.inner.is_focusable()
+    }
+
+    fn accepts_text_input(&self) -> bool {
+        !self.is_failed() && self.inner.accepts_text_input()
+    }
+
+    fn get_panel(&self) -> Option<Panel> {
+        self.inner.get_panel()
+    }
+
+    fn get_children(&self) -> Vec<&dyn Component> {
+        if self.is_failed() {
+            return Vec::new();
+        }
+
+        self.inner.get_children()
+    }
+
+    fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
+        if self.is_failed() {
+            return Vec::new();
+        }
+
+        self.inner.get_children_mut()
+    }
+}
+
+impl<C: Component + Drawable> Drawable for ErrorBoundary<C> {
+    type Args<'a>
+        = C::Args<'a>
+    where
+        Self: 'a;
+
+    fn draw(
+        &self,
+        context: &mut DrawContext,
+        area: Rect,
+        extra_args: Self::Args<'_>,
+    ) -> Result<()> {
+        if !self.is_failed() {
+            match crate::errors::catch_panic(|| self.inner.draw(context, area, extra_args)) {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(error)) | Err(error) => self.fail("draw", &error),
+            }
+        }
+
+        let failure = self.failure.borrow();
+        let palette = context.palette();
+        let mut lines = vec![palette.error("Panel crashed — press r to reset, d for details")];
+
+        if self.showing_details
+            && let Some(failure) = failure.as_ref()
+        {
+            lines.push(Line::default());
+            lines.extend(failure.lines().map(|line| Line::from(line.to_string())));
+        }
+
+        let focused = context.focused_id() == self.get_id();
+        context.frame().render_widget(Clear, area);
+        context.frame().render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().border_style(if focused {
+                    Style::new().fg(palette.error)
+                } else {
+                    Style::new().fg(palette.error).dim()
+                })),
+            area,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use color_eyre::eyre::eyre;
+    use crossterm::event::KeyModifiers;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use super::*;
+    use crate::theme::Theme;
+
+    /// How a `FailingPanel` fails.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Failure {
+        None,
+        ErrorOnUpdate,
+        ErrorOnEvent,
+        ErrorOnDraw,
+        PanicOnDraw,
+    }
+
+    #[derive(Debug)]
+    struct FailingPanel {
+        id: ComponentId,
+        failure: Failure,
+    }
+
+    impl Component for FailingPanel {
+        fn handle_event(&mut self, _event: &Event) -> Result<HandleEventSuccess> {
+            if self.failure == Failure::ErrorOnEvent {
+                return Err(eyre!("The event could not be handled."));
+            }
+
+            Ok(HandleEventSuccess::unhandled())
+        }
+
+        fn update(&mut self, _message: ComponentMessage) -> Result<Option<Action>> {
+            if self.failure == Failure::ErrorOnUpdate {
+                return Err(eyre!("The update failed."));
+            }
+
+            Ok(None)
+        }
+
+        fn get_id(&self) -> ComponentId {
+            self.id
+        }
+
+        fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+            Ok(accesskit::Node::new(accesskit::Role::Pane))
+        }
+
+        fn is_focusable(&self) -> bool {
+            true
+        }
+    }
+
+    impl Drawable for FailingPanel {
+        type Args<'a> = ();
+
+        fn draw(&self, context: &mut DrawContext, area: Rect, (): ()) -> Result<()> {
+            match self.failure {
+                Failure::ErrorOnDraw => Err(eyre!("The panel could not be drawn.")),
+                Failure::PanicOnDraw => panic!("The panel panicked while drawing."),
+                _ => {
+                    context
+                        .frame()
+                        .render_widget(Paragraph::new("panel content"), area);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// A boundary around a panel which fails with `failure` until it is reset, which the number
+    /// of constructions counts.
+    fn boundary(failure: Failure) -> (ErrorBoundary<FailingPanel>, Rc<Cell<usize>>) {
+        let constructions = Rc::new(Cell::new(0));
+        let boundary = ErrorBoundary::new(ComponentId::new(), {
+            let constructions = constructions.clone();
+            move |id| {
+                constructions.set(constructions.get() + 1);
+                Ok(FailingPanel {
+                    id,
+                    failure: if constructions.get() == 1 {
+                        failure
+                    } else {
+                        Failure::None
+                    },
+                })
+            }
+        })
+        .unwrap();
+        (boundary, constructions)
+    }
+
+    fn render(boundary: &ErrorBoundary<FailingPanel>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                let mut context =
+                    DrawContext::new(frame, boundary.get_id(), Theme::default().palette(u16::MAX));
+                boundary.draw(&mut context, area, ()).unwrap();
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn press(boundary: &mut ErrorBoundary<FailingPanel>, character: char) {
+        boundary
+            .handle_event(&Event::Key(KeyEvent::new(
+                KeyCode::Char(character),
+                KeyModifiers::NONE,
+            )))
+            .unwrap();
+    }
+
+    #[test]
+    fn working_panel_is_drawn() {
+        let (boundary, _) = boundary(Failure::None);
+
+        assert!(render(&boundary).starts_with("panel content"));
+        assert!(!boundary.is_failed());
+    }
+
+    #[test]
+    fn errors_while_updating_are_caught() {
+        let (mut boundary, _) = boundary(Failure::ErrorOnUpdate);
+
+        assert_eq!(
+            boundary.update(ComponentMessage::OnTick).unwrap(),
+            Some(Action::Render)
+        );
+        assert!(boundary.is_failed());
+        // The failed panel is no longer updated.
+        assert_eq!(boundary.update(ComponentMessage::OnTick).unwrap(), None);
+        assert!(render(&boundary).contains("Panel crashed — press r to reset, d for details"));
+    }
+
+    #[test]
+    fn errors_while_handling_events_are_caught() {
+        let (mut boundary, _) = boundary(Failure::ErrorOnEvent);
+
+        press(&mut boundary, 'x');
+
+        assert!(boundary.is_failed());
+        assert_eq!(
+            boundary.failure.borrow().as_deref(),
+            Some("The event could not be handled.")
+        );
+    }
+
+    #[test]
+    fn errors_while_drawing_are_caught() {
+        let (boundary, _) = boundary(Failure::ErrorOnDraw);
+
+        assert!(render(&boundary).contains("Panel crashed"));
+        assert_eq!(
+            boundary.failure.borrow().as_deref(),
+            Some("The panel could not be drawn.")
+        );
+    }
+
+    #[test]
+    fn panics_while_drawing_are_caught() {
+        let (boundary, _) = boundary(Failure::PanicOnDraw);
+
+        assert!(render(&boundary).contains("Panel crashed"));
+        assert_eq!(
+            boundary.failure.borrow().as_deref(),
+            Some("Panicked: The panel panicked while drawing.")
+        );
+        assert!(boundary.get_children().is_empty());
+        assert!(boundary.is_focusable());
+    }
+
+    #[test]
+    fn details_are_shown_on_request() {
+        let (mut boundary, _) = boundary(Failure::ErrorOnDraw);
+        assert!(!render(&boundary).contains("The panel could not be drawn."));
+
+        press(&mut boundary, 'd');
+
+        assert!(render(&boundary).contains("The panel could not be drawn."));
+    }
+
+    #[test]
+    fn failed_panel_is_reset() {
+        let (mut boundary, constructions) = boundary(Failure::PanicOnDraw);
+        let id = boundary.get_id();
+        render(&boundary);
+
+        press(&mut boundary, 'r');
+
+        assert_eq!(constructions.get(), 2);
+        assert!(!boundary.is_failed());
+        assert_eq!(boundary.get_id(), id);
+        assert!(render(&boundary).starts_with("panel content"));
+    }
+}
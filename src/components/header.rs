@@ -0,0 +1,338 @@
+//! The single header row, whose elements shrink in the order of their priority when the terminal
+//! is too narrow for all of them, so that new elements are fitted in through one place.
+
+use std::borrow::Cow;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Style, text::Span, widgets::Widget};
+use unicode_width::UnicodeWidthStr;
+
+use crate::text::{truncate_to_width, TruncateMode};
+
+/// The number of cells between adjacent elements.
+const GAP_WIDTH: usize = 2;
+
+/// How an element shrinks once even its narrowest form does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Kept as it is, and clipped at the edge of the header if nothing else can shrink.
+    #[default]
+    Keep,
+    /// Truncated with an ellipsis, and left out once not even the ellipsis fits.
+    Truncate,
+    /// Left out.
+    Omit,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderElement<'a> {
+    /// The forms of the element, from the widest to the narrowest.
+    forms: Vec<Cow<'a, str>>,
+    style: Style,
+    /// Elements of a lower priority shrink first, down to nothing if they may be left out.
+    priority: u8,
+    overflow: Overflow,
+}
+
+impl<'a> HeaderElement<'a> {
+    pub fn new(text: impl Into<Cow<'a, str>>, priority: u8) -> Self {
+        Self {
+            forms: vec![text.into()],
+            style: Style::new(),
+            priority,
+            overflow: Overflow::default(),
+        }
+    }
+
+    /// Adds a narrower form, displayed once the wider ones do not fit.
+    pub fn or(mut self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.forms.push(text.into());
+        self
+    }
+
+    pub fn with_style(self, style: Style) -> Self {
+        Self { style, ..self }
+    }
+
+    pub fn with_overflow(self, overflow: Overflow) -> Self {
+        Self { overflow, ..self }
+    }
+}
+
+/// The elements of the header, laid out from left to right.
+#[derive(Debug, Clone, Default)]
+pub struct Header<'a> {
+    elements: Vec<HeaderElement<'a>>,
+}
+
+impl<'a> Header<'a> {
+    /// Returns the index of the element, for finding it in `Header::place`.
+    pub fn push(&mut self, element: HeaderElement<'a>) -> usize {
+        self.elements.push(element);
+        self.elements.len() - 1
+    }
+
+    /// The text of each element fitted into `width` cells, or `None` for the elements left out.
+    /// Elements of equal priority shrink from right to left.
+    pub fn layout(&self, width: usize) -> Vec<Option<String>> {
+        let mut texts = self
+            .elements
+            .iter()
+            .map(|element| Some(element.forms[0].to_string()))
+            .collect::<Vec<_>>();
+        let mut form_indices = vec![0; self.elements.len()];
+        let mut order = (0..self.elements.len()).rev().collect::<Vec<_>>();
+        order.sort_by_key(|index| self.elements[*index].priority);
+
+        for index in order {
+            let element = &self.elements[index];
+
+            loop {
+                let excess = total_width(&texts).saturating_sub(width);
+
+                if excess == 0 {
+                    return texts;
+                }
+
+                if form_indices[index] + 1 < element.forms.len() {
+                    form_indices[index] += 1;
+                    texts[index] = Some(element.forms[form_indices[index]].to_string());
+                    continue;
+                }
+
+                let Some(text) = texts[index].as_ref() else {
+                    break;
+                };
+
+                match element.overflow {
+                    Overflow::Keep => break,
+                    Overflow::Truncate if text.width() > excess => {
+                        let (text, _) =
+                            truncate_to_width(text, text.width() - excess, TruncateMode::End);
+                        texts[index] = Some(text).filter(|text| !text.is_empty());
+                    }
+                    Overflow::Truncate | Overflow::Omit => texts[index] = None,
+                }
+            }
+        }
+
+        texts
+    }
+
+    /// The area and the text of each element fitted into `area`, or `None` for the elements left
+    /// out. See `Header::layout`.
+    pub fn place(&self, area: Rect) -> Vec<Option<(Rect, String)>> {
+        let mut x = area.x;
+
+        self.layout(area.width as usize)
+            .into_iter()
+            .map(|text| {
+                let text = text?;
+
+                if x >= area.right() {
+                    return None;
+                }
+
+                let width = text.width() as u16;
+                let placed = Rect {
+                    x,
+                    width: std::cmp::min(width, area.right().saturating_sub(x)),
+                    ..area
+                };
+                x = x.saturating_add(width + GAP_WIDTH as u16);
+                Some((placed, text))
+            })
+            .collect()
+    }
+}
+
+/// The width of the displayed texts, along with the gaps between them.
+fn total_width(texts: &[Option<String>]) -> usize {
+    let displayed = texts.iter().flatten().collect::<Vec<_>>();
+    let gaps = displayed.len().saturating_sub(1) * GAP_WIDTH;
+    displayed.iter().map(|text| text.width()).sum::<usize>() + gaps
+}
+
+impl Widget for Header<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        for (element, placed) in self.elements.iter().zip(self.place(area)) {
+            if let Some((area, text)) = placed {
+                Span::styled(text, element.style).render(area, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The elements of the header of the main view, each of the optional ones if enabled.
+    fn header(breadcrumbs: bool, safe_mode: bool, spinner: bool, status: bool) -> Header<'static> {
+        let mut header = Header::default();
+        header.push(
+            HeaderElement::new("RRR TUI v0.1.0", 2)
+                .or("v0.1.0")
+                .or("v0.1"),
+        );
+
+        if breadcrumbs {
+            header.push(
+                HeaderElement::new("/ › projects › rrr › notes › today", 3)
+                    .or("/ › … › notes › today")
+                    .or("/ › … › today")
+                    .with_overflow(Overflow::Truncate),
+            );
+        }
+
+        if safe_mode {
+            header.push(
+                HeaderElement::new("[SAFE MODE] F3: diagnose startup files", 3)
+                    .or("[SAFE MODE] F3"),
+            );
+        }
+
+        if spinner {
+            header.push(HeaderElement::new("⠋ Opening the record…", 1).or("⠋"));
+        }
+
+        if status {
+            header.push(
+                HeaderElement::new("Opened projects/rrr/notes/today", 0)
+                    .with_overflow(Overflow::Truncate),
+            );
+        }
+
+        header
+    }
+
+    /// Every combination of the optional elements, along with whether each is enabled.
+    fn combinations() -> impl Iterator<Item = (Header<'static>, [bool; 4])> {
+        (0..16).map(|bits| {
+            let enabled = [0, 1, 2, 3].map(|bit| bits & (1 << bit) != 0);
+            (
+                header(enabled[0], enabled[1], enabled[2], enabled[3]),
+                enabled,
+            )
+        })
+    }
+
+    #[test]
+    fn every_combination_fits_at_40_80_and_160() {
+        for width in [40, 80, 160] {
+            let area = Rect::new(3, 1, width, 1);
+
+            for (header, enabled) in combinations() {
+                let placed = header.place(area);
+                let texts = header.layout(width as usize);
+                assert_eq!(placed.len(), header.elements.len());
+                assert!(total_width(&texts) <= width as usize, "{width} {enabled:?}");
+
+                let mut x = area.x;
+                for (rect, text) in placed.iter().flatten() {
+                    assert!(rect.x >= x, "{width} {enabled:?} {placed:?}");
+                    assert!(area.contains(rect.as_position()));
+                    assert!(rect.right() <= area.right());
+                    assert_eq!(rect.width as usize, text.width());
+                    x = rect.right() + GAP_WIDTH as u16;
+                }
+
+                // The version and the safe mode badge are never left out.
+                assert!(placed[0].is_some(), "{width} {enabled:?}");
+                if enabled[1] {
+                    let index = 1 + enabled[0] as usize;
+                    assert!(placed[index]
+                        .as_ref()
+                        .unwrap()
+                        .1
+                        .starts_with("[SAFE MODE] F3"));
+                }
+
+                // Everything fits in its widest form.
+                if width == 160 {
+                    for (element, text) in header.elements.iter().zip(&texts) {
+                        assert_eq!(text.as_deref(), Some(&*element.forms[0]));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn elements_shrink_in_the_order_of_their_priority() {
+        let header = header(true, true, true, true);
+
+        assert_eq!(
+            header.layout(40),
+            [
+                Some("v0.1".to_string()),
+                Some("/ › … › today".to_string()),
+                Some("[SAFE MODE] F3".to_string()),
+                Some("⠋".to_string()),
+                None,
+            ]
+        );
+        // The breadcrumbs shrink after the version, the notice being left out first.
+        assert_eq!(
+            header.layout(80),
+            [
+                Some("v0.1".to_string()),
+                Some("/ › projects › rrr › notes › today".to_string()),
+                Some("[SAFE MODE] F3".to_string()),
+                Some("⠋".to_string()),
+                None,
+            ]
+        );
+        assert_eq!(
+            header
+                .place(Rect::new(0, 0, 80, 1))
+                .into_iter()
+                .map(|placed| placed.map(|(rect, _)| (rect.x, rect.width)))
+                .collect::<Vec<_>>(),
+            [
+                Some((0, 4)),
+                Some((6, 34)),
+                Some((42, 14)),
+                Some((58, 1)),
+                None
+            ]
+        );
+        assert_eq!(
+            header.layout(160),
+            [
+                Some("RRR TUI v0.1.0".to_string()),
+                Some("/ › projects › rrr › notes › today".to_string()),
+                Some("[SAFE MODE] F3: diagnose startup files".to_string()),
+                Some("⠋ Opening the record…".to_string()),
+                Some("Opened projects/rrr/notes/today".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn notice_is_truncated_to_the_remaining_cells() {
+        assert_eq!(
+            header(true, false, false, true).layout(80)[2].as_deref(),
+            Some("Opened projects/rrr/notes/t…")
+        );
+        assert_eq!(
+            header(false, false, false, true).layout(40)[1].as_deref(),
+            Some("Opened projects/rrr/not…")
+        );
+    }
+
+    #[test]
+    fn elements_that_keep_their_text_are_clipped_at_the_edge() {
+        let placed = header(false, true, false, false).place(Rect::new(0, 0, 10, 1));
+
+        assert_eq!(
+            placed,
+            [
+                Some((Rect::new(0, 0, 4, 1), "v0.1".to_string())),
+                Some((Rect::new(6, 0, 4, 1), "[SAFE MODE] F3".to_string())),
+            ]
+        );
+    }
+}
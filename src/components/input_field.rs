@@ -1,18 +1,25 @@
+use std::cell::Cell;
 use std::ops::Range;
+use std::time::{Duration, Instant};
 
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
-    layout::Rect,
-    style::{Color, Style, Stylize},
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
     text::{Line, Span},
 };
 use tokio::sync::mpsc::UnboundedSender;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
-    action::Action,
+    action::{Action, ComponentMessage},
     component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
     tui::Event,
+    words::WordBoundaries,
 };
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -70,11 +77,50 @@ impl TryFrom<KeyCode> for CursorMoveDirection {
     }
 }
 
+/// How long content cleared with Esc can be restored.
+const RESTORE_WINDOW: Duration = Duration::from_secs(5);
+
+/// The minimum width left for the content when the length counter is shown.
+const MIN_CONTENT_WIDTH_WITH_COUNTER: u16 = 16;
+
+/// The length of the content, shown at the right edge of the focused field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthCounter {
+    pub label: String,
+    /// Whether the content is valid, the counter is highlighted otherwise.
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InputFieldArgs {
+    pub length_counter: Option<LengthCounter>,
+    /// The byte range of the content which is invalid, underlined in the error style.
+    pub invalid_range: Option<Range<usize>>,
+}
+
+/// Content cleared with Esc, which can be restored for a while.
+#[derive(Debug, Clone, PartialEq)]
+struct ClearedContent {
+    content: String,
+    cleared_at: Instant,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct InputField {
     id: ComponentId,
     cursor: Cursor,
     content: String,
+    word_boundaries: WordBoundaries,
+    /// Whether Esc clears the content, instead of being passed on to the parent components.
+    clear_on_escape: bool,
+    cleared: Option<ClearedContent>,
+    /// The byte offset of the first displayed grapheme cluster, updated while drawing to keep the
+    /// cursor in view.
+    scroll: Cell<usize>,
+    /// The area the content was last drawn into, to find the clicked grapheme cluster.
+    area: Cell<Rect>,
+    /// The label reported to assistive technology.
+    label: Option<&'static str>,
 }
 
 impl InputField {
@@ -86,6 +132,57 @@ impl InputField {
             id,
             cursor: Cursor::default(),
             content: String::new(),
+            word_boundaries: WordBoundaries::default(),
+            clear_on_escape: false,
+            cleared: None,
+            scroll: Cell::new(0),
+            area: Cell::new(Rect::default()),
+            label: None,
+        }
+    }
+
+    pub fn with_label(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    pub fn with_word_boundaries(self, word_boundaries: WordBoundaries) -> Self {
+        Self {
+            word_boundaries,
+            ..self
+        }
+    }
+
+    /// Makes Esc clear the content, if there is any. Another Esc or Ctrl+Z shortly after restores
+    /// it. Otherwise, Esc is passed on to the parent components.
+    pub fn with_clear_on_escape(self) -> Self {
+        Self {
+            clear_on_escape: true,
+            ..self
+        }
+    }
+
+    /// The cleared content, if it can still be restored.
+    fn get_restorable(&self) -> Option<&str> {
+        self.cleared
+            .as_ref()
+            .filter(|cleared| cleared.cleared_at.elapsed() < RESTORE_WINDOW)
+            .map(|cleared| cleared.content.as_str())
+    }
+
+    fn clear(&mut self) {
+        self.cleared = Some(ClearedContent {
+            content: std::mem::take(&mut self.content),
+            cleared_at: Instant::now(),
+        });
+        self.cursor = Cursor::default();
+    }
+
+    fn restore(&mut self) {
+        if let Some(cleared) = self.cleared.take() {
+            self.set_content(cleared.content);
         }
     }
 
@@ -113,37 +210,59 @@ impl InputField {
     }
 
     fn insert(&mut self, string: &str) {
+        self.cleared = None;
         let result = self.delete_selection();
         self.content.insert_str(result.cursor_position, string);
         self.cursor = Cursor::at(result.cursor_position + string.len());
     }
 
+    /// Inserts pasted text, without the control characters it may contain, such as line breaks.
+    fn paste(&mut self, string: &str) {
+        let string = string
+            .chars()
+            .filter(|character| !character.is_control())
+            .collect::<String>();
+        self.insert(&string);
+    }
+
     fn remove(&mut self, key: RemoveKeyCode) {
+        self.cleared = None;
         let result = self.delete_selection();
 
         if result.selection_deleted {
             self.cursor = Cursor::at(result.cursor_position);
         } else {
-            let delete_position = match key {
-                RemoveKeyCode::Backspace => {
-                    self.get_move_cursor_position(result.cursor_position, CursorMoveDirection::Left)
-                }
-                RemoveKeyCode::Delete => {
-                    if result.cursor_position < self.content.len() {
-                        Some(result.cursor_position)
-                    } else {
-                        None
-                    }
-                }
+            let position = result.cursor_position;
+            let delete_range = match key {
+                RemoveKeyCode::Backspace => self
+                    .get_move_cursor_position(position, CursorMoveDirection::Left)
+                    .map(|start| start..position),
+                RemoveKeyCode::Delete => self
+                    .get_move_cursor_position(position, CursorMoveDirection::Right)
+                    .map(|end| position..end),
             };
 
-            if let Some(delete_position) = delete_position {
-                self.content.remove(delete_position);
-                self.cursor = Cursor::at(delete_position);
+            if let Some(delete_range) = delete_range {
+                self.content.replace_range(delete_range.clone(), "");
+                self.cursor = Cursor::at(delete_range.start);
             }
         }
     }
 
+    /// Deletes the selection, if there is one, or the content from `start` up to the cursor.
+    fn remove_to(&mut self, start: usize) {
+        self.cleared = None;
+        let result = self.delete_selection();
+
+        if !result.selection_deleted {
+            self.content
+                .replace_range(start..result.cursor_position, "");
+            self.cursor = Cursor::at(start);
+        } else {
+            self.cursor = Cursor::at(result.cursor_position);
+        }
+    }
+
     fn get_move_cursor_delta(
         &self,
         position: usize,
@@ -151,10 +270,14 @@ impl InputField {
     ) -> Option<isize> {
         let (prefix, suffix) = self.content.split_at(position);
         match direction {
-            CursorMoveDirection::Left => {
-                prefix.chars().next_back().map(|c| -(c.len_utf8() as isize))
-            }
-            CursorMoveDirection::Right => suffix.chars().next().map(|c| c.len_utf8() as isize),
+            CursorMoveDirection::Left => prefix
+                .graphemes(true)
+                .next_back()
+                .map(|grapheme| -(grapheme.len() as isize)),
+            CursorMoveDirection::Right => suffix
+                .graphemes(true)
+                .next()
+                .map(|grapheme| grapheme.len() as isize),
         }
     }
 
@@ -167,9 +290,114 @@ impl InputField {
             .map(|delta| (position as isize + delta) as usize)
     }
 
+    fn get_move_cursor_position_by(
+        &self,
+        position: usize,
+        direction: CursorMoveDirection,
+        by_word: bool,
+    ) -> Option<usize> {
+        if !by_word {
+            return self.get_move_cursor_position(position, direction);
+        }
+
+        Some(match direction {
+            CursorMoveDirection::Left => self
+                .word_boundaries
+                .previous_word_start(&self.content, position),
+            CursorMoveDirection::Right => {
+                self.word_boundaries.next_word_end(&self.content, position)
+            }
+        })
+    }
+
     pub fn get_content(&self) -> &str {
         &self.content
     }
+
+    /// The byte offset of the first grapheme cluster to display in `width` cells, so that the
+    /// cursor is in view, preferring to keep the previous offset.
+    fn scroll_to_cursor(&self, width: usize) -> usize {
+        let cursor = self.cursor.end;
+        let mut scroll = std::cmp::min(self.scroll.get(), cursor);
+
+        // The offset may be out of date if the content was replaced.
+        if !self.content.is_char_boundary(scroll) {
+            scroll = 0;
+        }
+
+        // The cursor is drawn over the grapheme cluster after it, or after the end of the content.
+        let cursor_grapheme_width = self.content[cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(1, UnicodeWidthStr::width);
+
+        while scroll < cursor
+            && self.content[scroll..cursor].width() + cursor_grapheme_width > width
+        {
+            scroll = self
+                .get_move_cursor_position(scroll, CursorMoveDirection::Right)
+                .unwrap_or(cursor);
+        }
+
+        // Reveals the content before the offset as long as it fits, such as after deleting.
+        while let Some(previous) = self.get_move_cursor_position(scroll, CursorMoveDirection::Left)
+            && self.content[previous..cursor].width() + cursor_grapheme_width <= width
+        {
+            scroll = previous;
+        }
+
+        self.scroll.set(scroll);
+        scroll
+    }
+
+    /// The position of the grapheme cluster drawn at the column, relative to the drawn area, or
+    /// the end of the content if there is none.
+    fn get_position_at_column(&self, column: usize) -> usize {
+        let scroll = Some(self.scroll.get())
+            .filter(|scroll| self.content.is_char_boundary(*scroll))
+            .unwrap_or_default();
+        let mut x = 0;
+
+        for (index, grapheme) in self.content[scroll..].grapheme_indices(true) {
+            x += grapheme.width();
+
+            if x > column {
+                return scroll + index;
+            }
+        }
+
+        self.content.len()
+    }
+
+    /// Highlights the part of the content within `range` which is in view, over the drawn content.
+    fn draw_invalid_range(&self, context: &mut DrawContext, area: Rect, range: Range<usize>) {
+        let scroll = self.scroll.get();
+        let end = std::cmp::min(range.end, self.content.len());
+        let start = std::cmp::max(range.start, scroll);
+
+        let (Some(before), Some(invalid)) = (
+            self.content.get(scroll..start),
+            self.content.get(start..end),
+        ) else {
+            return;
+        };
+        // An empty invalid name is highlighted as a single cell.
+        let highlighted = Rect {
+            x: area.x.saturating_add(before.width() as u16),
+            width: std::cmp::max(invalid.width() as u16, 1),
+            ..area
+        }
+        .intersection(area);
+        let style = context.palette().error_style().underlined();
+        context.frame().buffer_mut().set_style(highlighted, style);
+    }
+
+    /// Replaces the content, placing the cursor at its end.
+    pub fn set_content(&mut self, content: impl Into<String>) {
+        self.cleared = None;
+        self.content = content.into();
+        self.cursor = Cursor::at(self.content.len());
+    }
 }
 
 impl Component for InputField {
@@ -177,8 +405,95 @@ impl Component for InputField {
         true
     }
 
+    fn accepts_text_input(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            // Remove the ghost of the cleared content once it can no longer be restored.
+            ComponentMessage::OnTick
+                if self.cleared.is_some() && self.get_restorable().is_none() =>
+            {
+                self.cleared = None;
+                Some(Action::Render)
+            }
+            _ => None,
+        })
+    }
+
     fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
         Ok(match event {
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.clear_on_escape && !self.content.is_empty() => {
+                self.clear();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            })
+            | Event::Key(KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.content.is_empty() && self.get_restorable().is_some() => {
+                self.restore();
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            // Nothing to restore, rather than inserting the character.
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) => HandleEventSuccess::handled(),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('w'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                let start = self
+                    .word_boundaries
+                    .previous_word_start(&self.content, self.cursor.end);
+                self.remove_to(start);
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('u'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.remove_to(0);
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::Home | KeyCode::End),
+                kind: KeyEventKind::Press,
+                modifiers,
+                ..
+            }) => {
+                let new_position = if *code == KeyCode::Home {
+                    0
+                } else {
+                    self.content.len()
+                };
+
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    self.cursor.end = new_position;
+                } else {
+                    self.cursor = Cursor::at(new_position);
+                }
+
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
             Event::Key(KeyEvent {
                 code: KeyCode::Char(character),
                 kind: KeyEventKind::Press | KeyEventKind::Repeat,
@@ -210,24 +525,22 @@ impl Component for InputField {
                 modifiers,
                 ..
             }) => {
-                if modifiers.contains(KeyModifiers::SHIFT) {
-                    let direction =
-                        CursorMoveDirection::try_from(*code).unwrap_or_else(|()| unreachable!());
+                let direction =
+                    CursorMoveDirection::try_from(*code).unwrap_or_else(|()| unreachable!());
+                let by_word = modifiers.contains(KeyModifiers::CONTROL);
 
+                if modifiers.contains(KeyModifiers::SHIFT) {
                     if let Some(new_position) =
-                        self.get_move_cursor_position(self.cursor.end, direction)
+                        self.get_move_cursor_position_by(self.cursor.end, direction, by_word)
                     {
                         self.cursor.end = new_position;
                     }
                 } else {
                     let minmax = self.cursor.minmax();
 
-                    if minmax.is_empty() {
-                        let direction = CursorMoveDirection::try_from(*code)
-                            .unwrap_or_else(|()| unreachable!());
-
+                    if minmax.is_empty() || by_word {
                         if let Some(new_position) =
-                            self.get_move_cursor_position(minmax.start, direction)
+                            self.get_move_cursor_position_by(self.cursor.end, direction, by_word)
                         {
                             self.cursor = Cursor::at(new_position);
                         }
@@ -242,8 +555,25 @@ impl Component for InputField {
 
                 HandleEventSuccess::handled().with_action(Action::Render)
             }
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                modifiers,
+                ..
+            }) => {
+                let position =
+                    self.get_position_at_column(column.saturating_sub(self.area.get().x) as usize);
+
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    self.cursor.end = position;
+                } else {
+                    self.cursor = Cursor::at(position);
+                }
+
+                HandleEventSuccess::handled().with_action(Action::Render)
+            }
             Event::Paste(paste_string) => {
-                self.insert(paste_string);
+                self.paste(paste_string);
                 HandleEventSuccess::handled().with_action(Action::Render)
             }
             Event::FocusGained => {
@@ -260,15 +590,39 @@ impl Component for InputField {
     fn get_id(&self) -> ComponentId {
         self.id
     }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::TextInput);
+
+        if let Some(label) = self.label {
+            node.set_label(label);
+        }
+
+        node.set_value(self.content.as_str());
+
+        // Offsets are in characters, rather than bytes.
+        let character_index = |offset: usize| self.content[..offset].chars().count();
+        node.set_text_selection(accesskit::TextSelection {
+            anchor: accesskit::TextPosition {
+                node: self.id.into(),
+                character_index: character_index(self.cursor.start),
+            },
+            focus: accesskit::TextPosition {
+                node: self.id.into(),
+                character_index: character_index(self.cursor.end),
+            },
+        });
+        Ok(node)
+    }
 }
 
 impl Drawable for InputField {
     type Args<'a>
-        = ()
+        = InputFieldArgs
     where
         Self: 'a;
 
-    fn draw(&self, context: &mut DrawContext, mut area: Rect, (): Self::Args<'_>) -> Result<()> {
+    fn draw(&self, context: &mut DrawContext, mut area: Rect, args: Self::Args<'_>) -> Result<()> {
         if area.area() == 0 {
             return Ok(());
         }
@@ -277,40 +631,250 @@ impl Drawable for InputField {
 
         let focused = context.focused_id() == self.id;
 
+        let counter = args.length_counter.map(|counter| {
+            if counter.valid {
+                Line::styled(counter.label, context.palette().muted_style())
+            } else {
+                context.palette().error(counter.label)
+            }
+        });
+
+        // The counter is hidden in narrow fields, so that it does not hide the content.
+        if focused
+            && let Some(counter) = counter
+            && area.width >= counter.width() as u16 + 1 + MIN_CONTENT_WIDTH_WITH_COUNTER
+        {
+            let [area_content, area_counter] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Length(counter.width() as u16 + 1),
+            ])
+            .areas(area);
+
+            context
+                .frame()
+                .render_widget(counter.right_aligned(), area_counter);
+            area = area_content;
+        }
+
+        self.area.set(area);
+        context.register_mouse_area(self.id, area);
+        let palette = context.palette();
+        let style = palette.input_style();
+
         if focused {
+            // Scrolled horizontally to keep the cursor in view, so offsets are shifted by that.
+            let scroll = self.scroll_to_cursor(area.width as usize);
+            let visible = &self.content[scroll..];
             let minmax = self.cursor.minmax();
+            let minmax = minmax.start.saturating_sub(scroll)..minmax.end - scroll;
+            let cursor_x = area
+                .x
+                .saturating_add(visible[..self.cursor.end - scroll].width() as u16);
+
+            if cursor_x < area.right() {
+                context.frame().set_cursor_position((cursor_x, area.y));
+            }
 
             if minmax.is_empty() {
-                let mut spans = vec![Span::styled(&self.content[..minmax.start], Style::new())];
-                if minmax.start < self.content.len() {
-                    let mut chars = self.content[minmax.start..].chars();
+                let mut spans = vec![Span::styled(&visible[..minmax.start], style)];
+                if minmax.start < visible.len() {
+                    let mut graphemes = visible[minmax.start..].graphemes(true);
+                    let cursor_grapheme = graphemes.next().unwrap_or_default();
+                    spans.extend([
+                        Span::styled(cursor_grapheme, palette.cursor_style()),
+                        Span::styled(graphemes.as_str(), style),
+                    ]);
+                } else if let Some(restorable) = self.get_restorable() {
+                    let mut chars = restorable.chars();
                     let cursor_char = chars.next().into_iter().collect::<String>();
-                    let remaining = chars.collect::<String>();
                     spans.extend([
-                        Span::styled(cursor_char, Style::new().reversed()),
-                        Span::styled(remaining, Style::new()),
+                        Span::styled(cursor_char, palette.cursor_style().dim()),
+                        Span::styled(chars.as_str(), palette.muted_style()),
                     ]);
                 } else {
-                    spans.push(Span::styled(" ", Style::new().reversed()));
+                    spans.push(Span::styled(" ", palette.cursor_style()));
                 }
                 context.frame().render_widget(Line::from(spans), area);
             } else {
                 let spans = vec![
-                    Span::styled(&self.content[..minmax.start], Style::new()),
+                    Span::styled(&visible[..minmax.start], style),
                     Span::styled(
-                        &self.content[minmax.start..minmax.end],
-                        Style::new().white().bg(Color::Rgb(0x5F, 0x5F, 0x5F)),
+                        &visible[minmax.start..minmax.end],
+                        palette.selection_style(),
                     ),
-                    Span::styled(&self.content[minmax.end..], Style::new()),
+                    Span::styled(&visible[minmax.end..], style),
                 ];
                 context.frame().render_widget(Line::from(spans), area);
             }
         } else {
+            // Drawn from the start, which clicks are relative to.
+            self.scroll.set(0);
             context
                 .frame()
-                .render_widget(Span::styled(&self.content, Style::new()), area);
+                .render_widget(Span::styled(&self.content, style), area);
+        }
+
+        if let Some(invalid_range) = args.invalid_range {
+            self.draw_invalid_range(context, area, invalid_range);
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, style::Modifier, Terminal};
+
+    use super::*;
+    use crate::theme::Theme;
+
+    fn field() -> InputField {
+        let (action_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let mut field = InputField::new(ComponentId::new(), &action_tx).with_clear_on_escape();
+        field.set_content("0123abcd");
+        field
+    }
+
+    fn press(field: &mut InputField, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        field
+            .handle_event(&Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap()
+            .absorb
+    }
+
+    fn escape(field: &mut InputField) -> bool {
+        press(field, KeyCode::Esc, KeyModifiers::NONE)
+    }
+
+    /// Moves the time of clearing back past the restore window.
+    fn expire_restore_window(field: &mut InputField) {
+        let cleared = field.cleared.as_mut().unwrap();
+        cleared.cleared_at = Instant::now().checked_sub(RESTORE_WINDOW).unwrap();
+    }
+
+    /// Draws the focused field, returning its row.
+    fn draw(field: &InputField) -> ratatui::buffer::Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(12, 1)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                let mut context =
+                    DrawContext::new(frame, field.id, Theme::default().palette(u16::MAX));
+                field
+                    .draw(&mut context, area, InputFieldArgs::default())
+                    .unwrap();
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    #[test]
+    fn escape_clears_the_content_and_escape_again_restores_it() {
+        let mut field = field();
+
+        assert!(escape(&mut field));
+        assert_eq!(field.get_content(), "");
+        assert_eq!(field.get_restorable(), Some("0123abcd"));
+
+        assert!(escape(&mut field));
+        assert_eq!(field.get_content(), "0123abcd");
+        assert_eq!(field.get_restorable(), None);
+
+        // The restored content is cleared again by the next Esc.
+        assert!(escape(&mut field));
+        assert_eq!(field.get_content(), "");
+    }
+
+    #[test]
+    fn ctrl_z_restores_the_cleared_content() {
+        let mut field = field();
+
+        assert!(escape(&mut field));
+        assert!(press(&mut field, KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(field.get_content(), "0123abcd");
+
+        // With nothing left to restore, Ctrl+Z does nothing rather than inserting the character.
+        assert!(escape(&mut field));
+        assert!(escape(&mut field));
+        field.set_content("");
+        assert!(press(&mut field, KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(field.get_content(), "");
+    }
+
+    #[test]
+    fn cleared_content_is_forgotten_once_the_restore_window_passes() {
+        let mut field = field();
+
+        assert!(escape(&mut field));
+        expire_restore_window(&mut field);
+        assert_eq!(field.get_restorable(), None);
+        // Passed on to the parent components, as in an empty field.
+        assert!(!escape(&mut field));
+        assert_eq!(field.get_content(), "");
+
+        assert!(matches!(
+            field.update(ComponentMessage::OnTick).unwrap(),
+            Some(Action::Render)
+        ));
+        assert_eq!(field.cleared, None);
+        assert!(field.update(ComponentMessage::OnTick).unwrap().is_none());
+    }
+
+    #[test]
+    fn editing_after_clearing_forgets_the_cleared_content() {
+        let mut field = field();
+
+        assert!(escape(&mut field));
+        assert!(press(&mut field, KeyCode::Char('x'), KeyModifiers::NONE));
+        assert_eq!(field.get_content(), "x");
+        assert_eq!(field.cleared, None);
+
+        // Undoing the edit by hand leaves nothing to restore.
+        assert!(press(&mut field, KeyCode::Backspace, KeyModifiers::NONE));
+        assert!(press(&mut field, KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(field.get_content(), "");
+        assert!(!escape(&mut field));
+
+        // Neither does replacing the content.
+        field.set_content("abc");
+        assert!(escape(&mut field));
+        field.set_content("def");
+        field.set_content("");
+        assert!(!escape(&mut field));
+        assert_eq!(field.get_content(), "");
+    }
+
+    #[test]
+    fn escape_is_passed_on_unless_the_field_clears_on_escape() {
+        let (action_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let mut field = InputField::new(ComponentId::new(), &action_tx);
+        field.set_content("abc");
+
+        assert!(!escape(&mut field));
+        assert_eq!(field.get_content(), "abc");
+    }
+
+    #[test]
+    fn cleared_content_is_drawn_as_a_ghost() {
+        let mut field = field();
+        let buffer = draw(&field);
+        assert_eq!(buffer[(0, 0)].symbol(), "0");
+        assert!(!buffer[(1, 0)].modifier.contains(Modifier::DIM));
+
+        assert!(escape(&mut field));
+        let buffer = draw(&field);
+        let row: String = (0..8).map(|x| buffer[(x, 0)].symbol()).collect();
+        assert_eq!(row, "0123abcd");
+        assert!(buffer[(0, 0)].modifier.contains(Modifier::DIM));
+        assert_eq!(
+            buffer[(1, 0)].fg,
+            Theme::default().palette(u16::MAX).muted_style().fg.unwrap()
+        );
+
+        expire_restore_window(&mut field);
+        let buffer = draw(&field);
+        assert_eq!(buffer[(0, 0)].symbol(), " ");
+        assert_eq!(buffer[(1, 0)].symbol(), " ");
+    }
+}
@@ -0,0 +1,361 @@
+//! The events logged while the application runs, displayed over the bottom of the screen and
+//! toggled with F12. With the `layout-debug` feature, the layout debugging mode is toggled from
+//! the view too.
+//!
+//! The events are captured by `crate::logging` into a channel, which is drained on every tick
+//! whether the view is displayed or not, so that the tasks logging them never wait for the user
+//! interface, and only the most recent entries are kept.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear},
+};
+use tokio::sync::mpsc::{Receiver, UnboundedSender};
+use tracing::Level;
+
+use crate::{
+    action::{Action, ComponentMessage},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    logging::LogEntry,
+    theme::Palette,
+    tui::Event,
+};
+
+/// The levels the displayed entries are filtered by in turn, from the most verbose one.
+const LEVELS: [Level; 5] = [
+    Level::TRACE,
+    Level::DEBUG,
+    Level::INFO,
+    Level::WARN,
+    Level::ERROR,
+];
+
+/// The number of rows scrolled by the mouse wheel.
+const WHEEL_SCROLL_ROWS: usize = 3;
+
+#[derive(Debug)]
+pub struct LogView {
+    id: ComponentId,
+    /// `None` if the events were taken by another view, or logging was not initialized.
+    events: Option<Receiver<LogEntry>>,
+    entries: VecDeque<LogEntry>,
+    /// The maximum number of entries kept, the oldest ones are dropped beyond it.
+    capacity: usize,
+    visible: bool,
+    /// Tracked from the focus events, so that the main view can tell whether Esc closes the view.
+    focused: bool,
+    /// The least severe level of the displayed entries.
+    level: Level,
+    /// Only the entries of the operation are displayed, if any, see `ComponentMessage::FindLogEntries`.
+    operation_id: Option<String>,
+    /// The number of displayed entries scrolled past from the most recent one, 0 to follow the
+    /// new entries.
+    scroll: usize,
+    /// The number of rows of entries as last drawn, to scroll by pages.
+    page_height: Cell<usize>,
+}
+
+impl LogView {
+    pub fn new(id: ComponentId, _tx: &UnboundedSender<Action>, capacity: usize) -> Self {
+        Self {
+            id,
+            events: crate::logging::take_captured_events(),
+            entries: VecDeque::new(),
+            capacity: std::cmp::max(capacity, 1),
+            visible: false,
+            focused: false,
+            level: LEVELS[0],
+            operation_id: None,
+            scroll: 0,
+            page_height: Cell::new(1),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.scroll = 0;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Moves the captured events into the entries, returning whether any of them is displayed.
+    fn drain(&mut self) -> bool {
+        // Taken meanwhile, so that the entries are checked against the filters.
+        let Some(mut events) = self.events.take() else {
+            return false;
+        };
+        let mut displayed = 0;
+
+        while let Ok(entry) = events.try_recv() {
+            if self.is_displayed(&entry) {
+                displayed += 1;
+            }
+
+            self.entries.push_back(entry);
+
+            if self.entries.len() > self.capacity {
+                self.entries.pop_front();
+            }
+        }
+
+        self.events = Some(events);
+
+        // The scrolled to entries stay in place as new ones arrive.
+        if self.scroll > 0 {
+            self.scroll = std::cmp::min(self.scroll + displayed, self.max_scroll());
+        }
+
+        displayed > 0
+    }
+
+    fn is_displayed(&self, entry: &LogEntry) -> bool {
+        entry.level <= self.level
+            && self
+                .operation_id
+                .as_ref()
+                .is_none_or(|operation_id| entry.operation_id.as_ref() == Some(operation_id))
+    }
+
+    fn displayed_entries(&self) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        self.entries.iter().filter(|entry| self.is_displayed(entry))
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.displayed_entries()
+            .count()
+            .saturating_sub(self.page_height.get())
+    }
+
+    fn scroll_by(&mut self, rows: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(rows)
+            .min(self.max_scroll());
+    }
+
+    fn cycle_level(&mut self) {
+        let index = LEVELS
+            .iter()
+            .position(|level| *level == self.level)
+            .unwrap_or_default();
+        self.level = LEVELS[(index + 1) % LEVELS.len()];
+        self.scroll = 0;
+    }
+
+    fn level_style(palette: &Palette, level: Level) -> Style {
+        match level {
+            Level::ERROR => palette.error_style(),
+            Level::WARN => palette.warning_style(),
+            Level::INFO => palette.info_style(),
+            Level::DEBUG => Style::new(),
+            _ => palette.muted_style(),
+        }
+    }
+
+    fn entry_line<'a>(palette: &Palette, entry: &'a LogEntry) -> Line<'a> {
+        Line::from_iter([
+            Span::styled(format_timestamp(entry.timestamp), palette.muted_style()),
+            Span::raw(" "),
+            Span::styled(
+                format!("{:<5}", entry.level.as_str()),
+                Self::level_style(palette, entry.level),
+            ),
+            Span::raw(" "),
+            Span::styled(entry.target.as_str(), palette.muted_style()),
+            Span::raw(" "),
+            Span::styled(
+                entry
+                    .operation_id
+                    .as_ref()
+                    .map_or_else(String::new, |operation_id| format!("[{operation_id}] ")),
+                palette.muted_style(),
+            ),
+            // Line breaks would be drawn over the following rows.
+            Span::raw(entry.message.replace(['\n', '\r'], " ")),
+        ])
+    }
+}
+
+/// The time of day in UTC, with milliseconds.
+fn format_timestamp(timestamp: SystemTime) -> String {
+    let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % (24 * 60 * 60);
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / (60 * 60),
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+impl Component for LogView {
+    fn is_focusable(&self) -> bool {
+        self.visible
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            ComponentMessage::OnTick if self.drain() && self.visible => Some(Action::Render),
+            ComponentMessage::FindLogEntries { operation_id } => {
+                // All the entries of the operation, whatever their level.
+                self.level = LEVELS[0];
+                self.operation_id = Some(operation_id);
+                self.scroll = 0;
+                Some(Action::Render)
+            }
+            _ => None,
+        })
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        let page_height = self.page_height.get() as isize;
+
+        match event {
+            Event::FocusGained | Event::FocusLost => {
+                self.focused = matches!(event, Event::FocusGained);
+                return Ok(HandleEventSuccess::unhandled());
+            }
+            Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => match code {
+                KeyCode::Up => self.scroll_by(1),
+                KeyCode::Down => self.scroll_by(-1),
+                KeyCode::PageUp => self.scroll_by(page_height),
+                KeyCode::PageDown => self.scroll_by(-page_height),
+                KeyCode::Home => self.scroll = self.max_scroll(),
+                KeyCode::End => self.scroll = 0,
+                KeyCode::Char('l') => self.cycle_level(),
+                KeyCode::Char('c') if self.operation_id.is_some() => {
+                    self.operation_id = None;
+                    self.scroll = 0;
+                }
+                #[cfg(feature = "layout-debug")]
+                KeyCode::Char('o') => {
+                    return Ok(HandleEventSuccess::handled().with_action(Action::ToggleLayoutDebug));
+                }
+                _ => return Ok(HandleEventSuccess::unhandled()),
+            },
+            Event::Mouse(MouseEvent {
+                kind: kind @ (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown),
+                ..
+            }) => {
+                let rows = WHEEL_SCROLL_ROWS as isize;
+                self.scroll_by(if *kind == MouseEventKind::ScrollUp {
+                    rows
+                } else {
+                    -rows
+                });
+            }
+            // Clicks do not reach the components behind the view.
+            Event::Mouse(_) => return Ok(HandleEventSuccess::handled()),
+            _ => return Ok(HandleEventSuccess::unhandled()),
+        }
+
+        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Log);
+        node.set_label("Log");
+        Ok(node)
+    }
+}
+
+impl Drawable for LogView {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, area: Rect, (): Self::Args<'_>) -> Result<()> {
+        let palette = context.palette();
+        let layout_debug_hint = if cfg!(feature = "layout-debug") {
+            ", outline areas [o]"
+        } else {
+            ""
+        };
+        let operation_hint = self
+            .operation_id
+            .as_ref()
+            .map_or_else(String::new, |operation_id| {
+                format!(", error id {operation_id} [c to clear]")
+            });
+        let title = format!(
+            "Log [F12], {} and above [l]{operation_hint}, {} entries{layout_debug_hint}",
+            self.level,
+            self.displayed_entries().count()
+        );
+        let title_style = if self.focused {
+            palette.focus_style()
+        } else {
+            palette.title_style()
+        };
+        let block = Block::new()
+            .borders(Borders::TOP)
+            .border_style(palette.separator_style())
+            .title(Line::styled(title, title_style));
+        let area_rows = block.inner(area);
+
+        context.frame().render_widget(Clear, area);
+        context.render_overlay("log", block, area);
+        context.register_mouse_area(self.id, area);
+        self.page_height
+            .set(std::cmp::max(area_rows.height as usize, 1));
+
+        let rows = self
+            .displayed_entries()
+            .rev()
+            .skip(self.scroll)
+            .take(area_rows.height as usize)
+            .collect::<Vec<_>>();
+
+        if rows.is_empty() {
+            context.frame().render_widget(
+                Line::styled("no events at this level", palette.muted_style()),
+                area_rows,
+            );
+            return Ok(());
+        }
+
+        // The most recent entry is at the bottom.
+        let top = area_rows
+            .y
+            .saturating_add(area_rows.height.saturating_sub(rows.len() as u16));
+
+        for (index, entry) in rows.into_iter().rev().enumerate() {
+            let area_row = Rect {
+                y: top.saturating_add(index as u16),
+                height: 1,
+                ..area_rows
+            }
+            .intersection(area_rows);
+            context
+                .frame()
+                .render_widget(Self::entry_line(&palette, entry), area_row);
+        }
+
+        Ok(())
+    }
+}
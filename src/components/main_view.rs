@@ -1,41 +1,147 @@
 use std::cell::RefCell;
-use std::fmt::Display;
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use color_eyre::eyre::{eyre, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::prelude::*;
-use ratatui::widgets::Table;
-use rrr::record::{
-    HashedRecordKey, RecordKey, RecordName, RecordReadVersionSuccess,
-    SuccessionNonce, RECORD_NAME_ROOT,
-};
+use ratatui::widgets::{Clear, Row, Table};
+use rrr::cbor;
+use rrr::record::{HashedRecordKey, RecordReadVersionSuccess, SuccessionNonce};
 use rrr::registry::Registry;
 use rrr::utils::fd_lock::ReadLock;
-use rrr::utils::serde::BytesOrAscii;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, info_span, Instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::action::{Action, ComponentMessage};
+use crate::action::{
+    Action, ComponentMessage, Deferred, Feedback, Panel, Severity, SharedRegistry,
+};
+use crate::annotations::Annotations;
 use crate::args::Args;
+use crate::cbor::{cbor_value_to_json, record_metadata_key_to_string};
 use crate::component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess};
+use crate::config::{BusyPolicy, Config, CustomCommand, CustomCommandInput};
+use crate::encoding::Encoding;
+use crate::entropy::ContentStats;
 use crate::env::PROJECT_VERSION;
+use crate::history::{CachedRecord, History, HistoryDirection, HistoryEntry};
+use crate::model::{parse_relative_path, InvalidPathName, LossyNameDisplay, RecordRef};
+use crate::open_pipeline::{OpenError, OpenProgress, ResolveFrom};
+use crate::registry_summary::RegistrySummary;
+use crate::session::{SessionSnapshot, SessionStore, SNAPSHOT_INTERVAL};
+use crate::startup::{Startup, StartupPhase};
+use crate::tasks::{OperationId, TaskId};
+use crate::text::{
+    format_size, invalid_utf8_warning, truncate_to_width, wrap_to_width, wrap_words_to_width,
+    TruncateMode,
+};
+use crate::theme::ERROR_MARKER;
 use crate::tui::Event;
 
-use super::input_field::InputField;
-use super::radio_array::RadioArray;
-
+use super::breadcrumbs::Breadcrumbs;
+use super::content_view::{ContentView, FOLLOW_POLL_INTERVAL};
+use super::error_boundary::ErrorBoundary;
+use super::header::{Header, HeaderElement};
+use super::input_field::{InputField, InputFieldArgs, LengthCounter};
+use super::log_view::LogView;
+use super::metadata_view::MetadataView;
+use super::modal::{Modal, ModalRequest, ModalResult};
+use super::radio_array::{RadioArray, RadioArrayArgs};
+use super::record_tree::RecordTree;
+use super::spinner_field::Spinner;
+use super::status_bar::StatusBar;
+use super::version_selector::VersionSelector;
+
+/// The directions in which the lines of a box-drawing character extend from its center.
+const LINE_UP: u8 = 1 << 0;
+const LINE_RIGHT: u8 = 1 << 1;
+const LINE_DOWN: u8 = 1 << 2;
+const LINE_LEFT: u8 = 1 << 3;
+
+/// The characters of the light box-drawing set, by the directions their lines extend in.
+const LINE_SYMBOLS: [(u8, &str); 15] = [
+    (LINE_UP, "╵"),
+    (LINE_RIGHT, "╶"),
+    (LINE_DOWN, "╷"),
+    (LINE_LEFT, "╴"),
+    (LINE_LEFT | LINE_RIGHT, "─"),
+    (LINE_UP | LINE_DOWN, "│"),
+    (LINE_RIGHT | LINE_DOWN, "┌"),
+    (LINE_LEFT | LINE_DOWN, "┐"),
+    (LINE_UP | LINE_RIGHT, "└"),
+    (LINE_UP | LINE_LEFT, "┘"),
+    (LINE_UP | LINE_DOWN | LINE_RIGHT, "├"),
+    (LINE_UP | LINE_DOWN | LINE_LEFT, "┤"),
+    (LINE_LEFT | LINE_RIGHT | LINE_DOWN, "┬"),
+    (LINE_LEFT | LINE_RIGHT | LINE_UP, "┴"),
+    (LINE_UP | LINE_RIGHT | LINE_DOWN | LINE_LEFT, "┼"),
+];
+
+/// The rounded corners of blocks, which are joined like the square ones.
+const ROUNDED_LINE_SYMBOLS: [(u8, &str); 4] = [
+    (LINE_RIGHT | LINE_DOWN, "╭"),
+    (LINE_LEFT | LINE_DOWN, "╮"),
+    (LINE_UP | LINE_RIGHT, "╰"),
+    (LINE_UP | LINE_LEFT, "╯"),
+];
+
+/// A line along a gap between areas, which joins the lines already drawn where it crosses them,
+/// starts or ends on them, such as `┬` where it starts on a horizontal line.
 #[derive(Clone)]
 pub struct LineSpacer {
     direction: Direction,
-    begin: &'static str,
-    inner: &'static str,
-    end: &'static str,
-    merged: &'static str,
+    style: Style,
+}
+
+impl LineSpacer {
+    pub const fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            style: Style::new(),
+        }
+    }
+
+    pub fn with_style(&self, style: Style) -> Self {
+        Self {
+            style,
+            ..self.clone()
+        }
+    }
+
+    /// Draws the part of the line extending in `directions` into the cell, joined with the line
+    /// already drawn there. Characters other than lines are overwritten.
+    fn join(&self, cell: &mut buffer::Cell, directions: u8) {
+        let axis = match self.direction {
+            Direction::Horizontal => LINE_LEFT | LINE_RIGHT,
+            Direction::Vertical => LINE_UP | LINE_DOWN,
+        };
+        let existing = LINE_SYMBOLS
+            .iter()
+            .chain(ROUNDED_LINE_SYMBOLS.iter())
+            .find(|(_, symbol)| *symbol == cell.symbol())
+            .map_or(0, |(directions, _)| *directions);
+        // A line which only starts or ends in the cell, joining nothing, is drawn through it.
+        let directions = match existing | directions {
+            joined if joined.count_ones() > 1 => joined,
+            _ => axis,
+        };
+        let symbol = LINE_SYMBOLS
+            .iter()
+            .find(|(other, _)| *other == directions)
+            .map_or(symbols::line::CROSS, |(_, symbol)| *symbol);
+
+        cell.set_symbol(symbol).set_style(self.style);
+    }
 }
 
 impl Widget for LineSpacer {
+    /// Draws the line along the first row or column of the area, depending on the direction.
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
@@ -45,386 +151,2330 @@ impl Widget for LineSpacer {
                 && (self.direction == Direction::Vertical || area.height == 1)
         );
 
-        let start_position = area.as_position();
-
         if area.width == 0 || area.height == 0 {
             return;
         }
 
-        if area.width <= 1 && area.height <= 1 {
-            buf[start_position].set_symbol(self.merged);
-            return;
-        }
+        let (length, forward, backward) = match self.direction {
+            Direction::Horizontal => (area.width, LINE_RIGHT, LINE_LEFT),
+            Direction::Vertical => (area.height, LINE_DOWN, LINE_UP),
+        };
 
-        buf[start_position].set_symbol(self.begin);
+        for offset in 0..length {
+            let position = match self.direction {
+                Direction::Horizontal => Position::new(area.x + offset, area.y),
+                Direction::Vertical => Position::new(area.x, area.y + offset),
+            };
+            let mut directions = 0;
 
-        match self.direction {
-            Direction::Horizontal => {
-                let end_position: Position =
-                    (start_position.x + area.width - 1, start_position.y).into();
-                buf[end_position].set_symbol(self.end);
-                for x in (start_position.x + 1)..end_position.x {
-                    let position = (x, start_position.y);
-                    buf[position].set_symbol(self.inner);
-                }
+            if offset > 0 {
+                directions |= backward;
             }
-            Direction::Vertical => {
-                let end_position: Position =
-                    (start_position.x, start_position.y + area.height - 1).into();
-                buf[end_position].set_symbol(self.end);
-                for y in (start_position.y + 1)..end_position.y {
-                    let position = (start_position.x, y);
-                    buf[position].set_symbol(self.inner);
-                }
+
+            if offset + 1 < length {
+                directions |= forward;
             }
+
+            self.join(&mut buf[position], directions);
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Encoding {
-    Utf8,
-    Hex,
+#[derive(Debug, Clone)]
+pub(super) struct OpenedRecord {
+    pub(super) record_ref: RecordRef,
+    pub(super) hashed_record_key: HashedRecordKey,
+    pub(super) record: Arc<RecordReadVersionSuccess>, // Rc'd for cheaper cloning
+    /// The versions of the record that were listed, oldest first.
+    pub(super) versions: Vec<u64>,
+    /// Computed in the background after the record is opened, see `MainView::analyze_content`.
+    pub(super) stats: Option<Arc<ContentStats>>,
 }
 
-impl Display for Encoding {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Utf8 => write!(f, "UTF-8"),
-            Self::Hex => write!(f, "Hexadecimal Byte String"),
-        }
+impl OpenedRecord {
+    /// The displayed version, the latest one listed unless a specific version was opened.
+    pub(super) fn version(&self) -> Option<u64> {
+        self.record_ref
+            .version
+            .or_else(|| self.versions.last().copied())
     }
 }
 
+/// The registry is opened in the background once the interface is shown, so that a failure to
+/// open it is displayed instead of ending the application.
 #[derive(Debug, Clone)]
-struct OpenedRecord {
-    hashed_record_key: HashedRecordKey,
-    record: Arc<RecordReadVersionSuccess>, // Rc'd for cheaper cloning
+pub(super) enum RegistryState {
+    Opening,
+    /// The error with its causes.
+    Failed(String),
+    Opened(Arc<Registry<ReadLock>>),
 }
 
 #[derive(Debug, Clone)]
-struct MainState {
-    registry: Arc<Registry<ReadLock>>,
-    opened_record: Option<OpenedRecord>,
+pub(super) struct MainState {
+    pub(super) registry: RegistryState,
+    pub(super) opened_record: Option<OpenedRecord>,
 }
 
 impl MainState {
-    async fn get_current_succession_nonce(&self) -> SuccessionNonce {
-        if let Some(opened_record) = self.opened_record.as_ref() {
+    /// The opened registry, or an error while it is not opened.
+    pub(super) fn registry(&self) -> Result<Arc<Registry<ReadLock>>> {
+        match &self.registry {
+            RegistryState::Opening => Err(eyre!("The registry is still being opened.")),
+            RegistryState::Failed(error) => Err(eyre!("{error}")),
+            RegistryState::Opened(registry) => Ok(registry.clone()),
+        }
+    }
+
+    /// Opens the parent of the opened record, as Backspace does in the tree and the content.
+    /// `None` if the root record or no record is opened.
+    pub(super) fn open_parent_action(&self) -> Option<Action> {
+        let record_ref = self.opened_record.as_ref()?.record_ref.parent()?;
+        Some(Action::BroadcastMessage(ComponentMessage::OpenRecord {
+            record_ref,
+        }))
+    }
+
+    /// Where to resolve the descendant at `record_ref` of the opened record from, so that only the
+    /// names following the path of the opened record are resolved. Falls back to the root record
+    /// if the opened record is not an ancestor of it, such as after it was replaced.
+    pub(super) async fn resolve_from_opened_record(
+        &self,
+        record_ref: &RecordRef,
+    ) -> Result<ResolveFrom> {
+        let Some(opened_record) = self.opened_record.as_ref().filter(|opened_record| {
+            record_ref
+                .path()
+                .strip_prefix(opened_record.record_ref.path())
+                .is_some_and(|names| !names.is_empty())
+        }) else {
+            return Ok(ResolveFrom::Root);
+        };
+
+        Ok(ResolveFrom::Ancestor {
+            depth: opened_record.record_ref.path().len(),
+            succession_nonce: self.get_current_succession_nonce().await?,
+        })
+    }
+
+    async fn get_current_succession_nonce(&self) -> Result<SuccessionNonce> {
+        let registry = self.registry()?;
+
+        Ok(if let Some(opened_record) = self.opened_record.as_ref() {
             // This should be a pretty brief operation.
             opened_record
                 .hashed_record_key
-                .derive_succession_nonce(&self.registry.config.kdf)
-                .await
-                .unwrap()
+                .derive_succession_nonce(&registry.config.kdf)
+                .await?
         } else {
-            self.registry
+            registry
                 .config
                 .kdf
                 .get_root_record_predecessor_nonce()
                 .clone()
+        })
+    }
+}
+
+/// How long changes are highlighted after the opened record is reloaded.
+const CHANGES_HIGHLIGHT_DURATION: Duration = Duration::from_secs(5);
+
+/// How often the opened record is checked for new versions, unless it is followed, which picks
+/// them up by itself.
+const NEW_VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Below this width, the labels of the bottom pane are stacked above their fields, so that the
+/// fields keep enough room for typing.
+const STACKED_BOTTOM_MAX_WIDTH: u16 = 50;
+
+/// The minimum width of the interface, below which a notice is displayed in its place.
+const MIN_WIDTH: u16 = 24;
+
+/// The minimum height of the content pane, title included, which is kept before any of the top
+/// panes are displayed.
+const MIN_CONTENT_HEIGHT: u16 = 4;
+
+/// The height of the top panes, title included, when there is room for them.
+const TOP_HEIGHT: u16 = 7;
+
+/// The minimum height of each of the top panes, title included, below which they are hidden.
+const MIN_TOP_PANE_HEIGHT: u16 = 3;
+
+/// The width of the tree and overview panes, when displayed side by side with the metadata.
+const SIDE_PANE_WIDTH: u16 = 16;
+
+/// The minimum width of the metadata pane, below which the overview pane is hidden, and then the
+/// tree is stacked above the metadata.
+const MIN_METADATA_WIDTH: u16 = 24;
+
+/// The areas of a pane: its title on the first row, on the separating line above the pane, and
+/// the rows of its content below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PaneLayout {
+    area: Rect,
+    title: Rect,
+    content: Rect,
+}
+
+impl PaneLayout {
+    /// Allocates the rows of the pane in `area`, its title starting `title_offset_x` columns in.
+    fn new(area: Rect, title_offset_x: u16) -> Self {
+        let [mut title, content] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Fill(1)])
+            .areas(area);
+
+        title.x = title.x.saturating_add(title_offset_x);
+        title.width = title.width.saturating_sub(title_offset_x);
+
+        Self {
+            area,
+            title,
+            content,
+        }
+    }
+}
+
+/// The areas of the panes, which depend on the size of the terminal.
+#[derive(Debug)]
+struct MainLayout {
+    header: Rect,
+    /// `None` if there is no room for the top panes.
+    tree: Option<PaneLayout>,
+    metadata: Option<PaneLayout>,
+    /// `None` if there is no room for the overview, which is the first pane to be hidden.
+    overview: Option<PaneLayout>,
+    /// The titles of the content and bottom panes are aligned with the metadata pane when it is
+    /// beside the tree.
+    content: PaneLayout,
+    bottom: PaneLayout,
+    footer: Rect,
+    /// Horizontal lines along the first row of each, joined by the vertical ones.
+    horizontal_spacers: Vec<Rect>,
+    vertical_spacers: Vec<Rect>,
+}
+
+/// Metadata entries which differ between two versions of the same record.
+#[derive(Debug)]
+pub struct MetadataChanges {
+    expires_at: Instant,
+    /// Keys of entries that were added or changed their value.
+    pub(super) changed_keys: HashSet<String>,
+    /// Entries that are no longer present.
+    pub(super) removed: Vec<(String, cbor::Value)>,
+}
+
+impl MetadataChanges {
+    fn between(
+        previous: &RecordReadVersionSuccess,
+        current: &RecordReadVersionSuccess,
+    ) -> Option<Self> {
+        let snapshot = |record: &RecordReadVersionSuccess| {
+            record
+                .metadata
+                .iter_with_semantic_keys()
+                .map(|(key, value)| (record_metadata_key_to_string(&key), value.clone()))
+                .collect::<Vec<_>>()
+        };
+        let previous_entries = snapshot(previous);
+        let current_entries = snapshot(current);
+        let changed_keys = current_entries
+            .iter()
+            .filter(|entry| !previous_entries.contains(entry))
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>();
+        let removed = previous_entries
+            .into_iter()
+            .filter(|(key, _)| {
+                !current_entries
+                    .iter()
+                    .any(|(current_key, _)| current_key == key)
+            })
+            .collect::<Vec<_>>();
+
+        info!(
+            changed = changed_keys.len(),
+            removed = removed.len(),
+            content_changed = previous.data != current.data,
+            "Reloaded the opened record."
+        );
+
+        if changed_keys.is_empty() && removed.is_empty() {
+            return None;
         }
+
+        Some(Self {
+            expires_at: Instant::now() + CHANGES_HIGHLIGHT_DURATION,
+            changed_keys,
+            removed,
+        })
+    }
+}
+
+/// A version of the opened record newer than the displayed one, kept until the user updates to
+/// it, so that the content is not replaced while being read.
+#[derive(Debug)]
+struct NewerVersion {
+    hashed_record_key: HashedRecordKey,
+    record: RecordReadVersionSuccess,
+    versions: Vec<u64>,
+}
+
+impl NewerVersion {
+    /// The number of the newest version, as listed by the registry.
+    fn version(&self) -> u64 {
+        // A newer version is only held back if any versions are listed.
+        self.versions.last().copied().unwrap_or_default()
     }
 }
 
+/// Drawn over the first row of the content while a newer version is held back.
+fn newer_version_banner(version: u64) -> String {
+    format!(" newer version available (v{version}), press u to update, d to diff ")
+}
+
+/// The lines of `newer` compared to those of `previous`, each prefixed with `- ` if removed, `+ `
+/// if added, or two spaces if unchanged. Content which is not UTF-8 is compared lossily.
+fn version_diff(previous: &[u8], newer: &[u8]) -> String {
+    let (previous, newer) = (
+        String::from_utf8_lossy(previous),
+        String::from_utf8_lossy(newer),
+    );
+    let (previous, newer) = (
+        previous.lines().collect::<Vec<_>>(),
+        newer.lines().collect::<Vec<_>>(),
+    );
+
+    diff::slice(&previous, &newer)
+        .into_iter()
+        .map(|line| match line {
+            diff::Result::Left(line) => format!("- {line}\n"),
+            diff::Result::Right(line) => format!("+ {line}\n"),
+            diff::Result::Both(line, _) => format!("  {line}\n"),
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct MainView {
     id: ComponentId,
+    action_tx: UnboundedSender<Action>,
     args: Arc<Args>,
-    pane_open: PaneOpen,
+    config: Arc<Config>,
+    tree: RecordTree,
+    breadcrumbs: Breadcrumbs,
+    /// Drawn in place of the overview while focused.
+    version_selector: VersionSelector,
+    pane_open: ErrorBoundary<PaneOpen>,
+    content_view: ErrorBoundary<ContentView>,
+    metadata_view: MetadataView,
     state: Rc<RefCell<MainState>>,
+    metadata_changes: Option<MetadataChanges>,
+    newer_version: Option<NewerVersion>,
+    /// When the opened record was last checked for new versions, or opened.
+    last_version_check_at: Option<Instant>,
+    annotations: Annotations,
+    /// The summary saved when the registry was last closed, drawn while it is being opened and
+    /// reconciled with it once the first record is opened.
+    stale_summary: Option<RegistrySummary>,
+    history: History,
+    /// The index of the history entry being opened again, until the next record is opened, which
+    /// is another one if opening it failed.
+    history_navigation: Option<usize>,
+    /// Edits the alias of the opened record, only part of the component tree while editing.
+    alias_field: InputField,
+    editing_alias: bool,
+    /// Edits the path to export the content of the opened record to, only part of the component
+    /// tree while editing.
+    export_path_field: InputField,
+    editing_export_path: bool,
+    /// The dialog asking whether to overwrite the file the content is exported to, and its path.
+    export_overwrite: Option<(ComponentId, PathBuf)>,
+    /// The dialog asking whether to copy a large text to the clipboard, the component to focus
+    /// once it closes, and the text.
+    copy_confirmation: Option<(ComponentId, ComponentId, String)>,
+    /// Opens the requested record, or the root one, once the registry is opened.
+    initial_operation: Option<PaneOpenOperation>,
+    /// Always part of the component tree, so that it takes the logged events while hidden.
+    log_view: LogView,
+    /// Only part of the component tree while open, as the last child.
+    modal: Option<Modal>,
+    /// The panel Ctrl+^ focuses, hinted at in the footer.
+    alternate_panel: Option<Panel>,
+    /// Highlights do not expire while the terminal is not focused, so that they are not missed.
+    terminal_focused: bool,
+    status_bar: StatusBar,
+    session_store: SessionStore,
+    /// When the last session snapshot was taken, `None` if one is due.
+    last_snapshot_at: Option<Instant>,
+    /// Present in safe mode, shown with F3.
+    startup_file_diagnosis: Option<String>,
+    /// The changes since the version that ran before, shown once the first record is opened, as
+    /// opening records dismisses derived views.
+    whats_new: Option<String>,
+    /// The tasks shown as pending, in the order they started in.
+    pending_tasks: Vec<(TaskId, String)>,
+    /// Shows the label of the most recently started pending task.
+    spinner: Spinner<'static>,
+    /// Cancels the tasks running in the background, such as custom commands.
+    cancellation: CancellationToken,
+    /// Cancels the analysis of the content of the previously opened record.
+    analysis_cancellation: Option<CancellationToken>,
+    /// When the followed record was last reloaded.
+    last_follow_poll_at: Option<Instant>,
 }
 
 impl MainView {
     pub async fn new(
         id: ComponentId,
         tx: &UnboundedSender<Action>,
+        cancellation: &CancellationToken,
         args: &Arc<Args>,
+        startup: Startup,
     ) -> Result<Self>
     where
         Self: Sized,
     {
-        tracing::trace!(dir=?args.registry_directory);
-        let registry = Arc::new(
-            Registry::open(args.registry_directory.clone())
-                .await
-                .unwrap(),
-        );
-        let state = Rc::new(RefCell::new(MainState {
+        let Startup {
+            config,
+            keymap: _,
+            annotations,
             registry,
+            registry_summary,
+            session_store,
+            session_marker: _,
+            restored_session,
+            upgraded_from,
+            startup_file_diagnosis,
+            warnings,
+        } = startup;
+        let state = Rc::new(RefCell::new(MainState {
+            registry: match registry {
+                Some(registry) => RegistryState::Opened(registry),
+                None => RegistryState::Opening,
+            },
             opened_record: None,
         }));
-        let mut pane_open = PaneOpen::new(ComponentId::new(), tx, &state)?;
+        let mut pane_open = {
+            let (tx, state, config) = (tx.clone(), state.clone(), config.clone());
+            let cancellation = cancellation.clone();
+            ErrorBoundary::new(ComponentId::new(), move |id| {
+                PaneOpen::new(id, &tx, &cancellation, &state, &config)
+            })?
+        };
+        let mut content_view = {
+            let (tx, state) = (tx.clone(), state.clone());
+            ErrorBoundary::new(ComponentId::new(), move |id| {
+                Ok(ContentView::new(id, &tx, &state))
+            })?
+        };
+        content_view.set_hex_dump(config.open.default_encoding == Encoding::HEX);
+        let mut status_bar = StatusBar::new(ComponentId::new());
+        let notice = match warnings.as_slice() {
+            [] => None,
+            [warning] => Some(warning.clone()),
+            [warning, rest @ ..] => Some(format!(
+                "{warning} (and {} more problems, see the log)",
+                rest.len()
+            )),
+        };
+
+        if let Some(notice) = notice {
+            status_bar.show(notice, Severity::Info);
+        }
+
+        // Attempt to open the default root record, unless another one was requested or restored.
+        let restored_record_ref = restored_session.and_then(|snapshot| {
+            let record_ref = snapshot.record_ref?;
+            content_view.restore_scroll_on_open(snapshot.scroll);
+            Some(record_ref)
+        });
+        let initial_operation = match args.record_ref()? {
+            Some(record_ref) => PaneOpenOperation::OpenDeepestRecord { record_ref },
+            None => PaneOpenOperation::OpenRecord {
+                record_ref: restored_record_ref.unwrap_or_else(RecordRef::root),
+            },
+        };
+        let registry_opened = matches!(state.borrow().registry, RegistryState::Opened(_));
+        let initial_operation = if registry_opened {
+            pane_open.start_operation(initial_operation);
+            None
+        } else {
+            let (args, action_tx) = (args.clone(), tx.clone());
+            crate::tasks::spawn_tracked(
+                cancellation,
+                tx,
+                StartupPhase::OpenRegistry.to_string(),
+                info_span!("open registry"),
+                move |operation_id| async move {
+                    let result = crate::startup::open_registry(&args)
+                        .await
+                        .map(|registry| SharedRegistry(Arc::new(registry)))
+                        .map_err(|error| operation_id.tag_error(format!("{error:#}")));
+                    // Sending only fails once the application quits.
+                    let _ = action_tx.send(Action::SendMessage {
+                        id,
+                        message: ComponentMessage::RegistryOpened { result },
+                    });
+                },
+            );
+            Some(initial_operation)
+        };
+
+        let mut tree = RecordTree::new(
+            ComponentId::new(),
+            tx,
+            &state,
+            config.display.lossy_names,
+            config.display.max_tree_expansion_depth,
+            config.display.tree_previews,
+        );
+        let stale_summary = registry_summary.filter(|_| !registry_opened);
 
-        pane_open.spawn_open_record_task_with_record_name(RECORD_NAME_ROOT); // Attempt to open the default root record.
+        if let Some(summary) = stale_summary.as_ref() {
+            for record_ref in summary.pinned_roots.iter().chain(&summary.last_record) {
+                tree.insert_stale(record_ref);
+            }
+        }
 
         Ok(Self {
             id,
+            action_tx: tx.clone(),
             args: args.clone(),
+            tree,
+            breadcrumbs: Breadcrumbs::new(
+                ComponentId::new(),
+                tx,
+                &state,
+                config.display.lossy_names,
+            ),
+            version_selector: VersionSelector::new(ComponentId::new(), tx, &state),
+            metadata_view: MetadataView::new(ComponentId::new(), tx, &state),
             state,
             pane_open,
+            content_view,
+            metadata_changes: None,
+            newer_version: None,
+            last_version_check_at: None,
+            annotations,
+            stale_summary,
+            history: History::new(
+                config.open.max_history_length,
+                config.open.max_history_cache_bytes,
+            ),
+            history_navigation: None,
+            alias_field: InputField::new(ComponentId::new(), tx)
+                .with_word_boundaries(config.editing.word_boundaries())
+                .with_label("Alias"),
+            editing_alias: false,
+            export_path_field: InputField::new(ComponentId::new(), tx)
+                .with_word_boundaries(config.editing.word_boundaries())
+                .with_label("Export Path"),
+            editing_export_path: false,
+            export_overwrite: None,
+            copy_confirmation: None,
+            initial_operation,
+            log_view: LogView::new(ComponentId::new(), tx, args.log_view_capacity),
+            modal: None,
+            alternate_panel: None,
+            terminal_focused: true,
+            status_bar,
+            session_store,
+            last_snapshot_at: None,
+            startup_file_diagnosis,
+            whats_new: upgraded_from
+                .map(|version| crate::changelog::describe(Some(&version)))
+                .filter(|whats_new| !whats_new.is_empty()),
+            pending_tasks: Vec::new(),
+            spinner: Spinner::new(ComponentId::new(), tx, "".into()),
+            cancellation: cancellation.child_token(),
+            analysis_cancellation: None,
+            last_follow_poll_at: None,
+            config,
         })
     }
 
-    fn pane_areas(area: Rect, title_offset_x: u16) -> (Rect, Rect) {
-        let [mut title, content] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Fill(1)])
-            .areas(area);
+    /// Analyzes the content of the opened record in the background, cancelling the analysis of
+    /// the previously opened one.
+    fn analyze_content(
+        &mut self,
+        hashed_record_key: HashedRecordKey,
+        record: Arc<RecordReadVersionSuccess>,
+    ) -> Deferred {
+        if let Some(cancellation) = self.analysis_cancellation.take() {
+            cancellation.cancel();
+        }
 
-        title.x += title_offset_x;
-        title.width = title.width.saturating_sub(title_offset_x);
+        let cancellation = self.cancellation.child_token();
+        self.analysis_cancellation = Some(cancellation.clone());
 
-        (title, content)
-    }
+        let future = {
+            let cancellation = cancellation.clone();
+            async move {
+                let stats = tokio::task::spawn_blocking(move || {
+                    crate::entropy::analyze(&record.data, &cancellation)
+                })
+                .await
+                .inspect_err(|error| warn!(%error, "Failed to analyze the content."))
+                .ok()
+                .flatten()
+                .map(Arc::new);
 
-    fn draw_header(&self, context: &mut DrawContext, area_header: Rect) -> Result<()> {
-        context.frame().render_widget(
-            Span::raw(format!("RRR TUI v{}", *PROJECT_VERSION)),
-            area_header,
-        );
-        Ok(())
-    }
+                ComponentMessage::ContentAnalyzed {
+                    hashed_record_key,
+                    stats,
+                }
+            }
+        };
 
-    fn draw_pane_tree(&self, context: &mut DrawContext, area: Rect) -> Result<()> {
-        let (area_title, _area_content) = Self::pane_areas(area, 0);
-        context
-            .frame()
-            .render_widget(Span::raw("[T]ree"), area_title);
-        Ok(())
+        Deferred::new(self.id, future).with_cancellation(&cancellation)
     }
 
-    fn draw_pane_metadata(&self, context: &mut DrawContext, area: Rect) -> Result<()> {
-        let (area_title, area_content) = Self::pane_areas(area, 0);
+    /// Records the scroll of the content and the selected encoding in the current history entry,
+    /// before another record is opened.
+    fn leave_history_entry(&mut self) {
+        let scroll = self.content_view.get_scroll();
+        let encoding = *self.pane_open.encoding_radio_array.get_checked();
 
-        if let Some(opened_record) = self.state.borrow().opened_record.as_ref() {
-            let metadata_table = Table::new(
-                opened_record
-                    .record
-                    .metadata
-                    .iter_with_semantic_keys()
-                    .map(|(key, value)| crate::cbor::record_metadata_to_row(key, value)),
-                [Constraint::Length(16), Constraint::Fill(1)],
-            );
+        if let Some(entry) = self.history.current_mut() {
+            entry.scroll = scroll;
+            entry.encoding = encoding;
+        }
+    }
 
-            context.frame().render_widget(metadata_table, area_content);
+    /// Records the opened record in the history, unless it is the current entry, such as when it
+    /// is reloaded, or the entry being gone to.
+    fn record_history_entry(&mut self, record_ref: RecordRef, cached: CachedRecord) {
+        if let Some(index) = self.history_navigation.take()
+            && self
+                .history
+                .get(index)
+                .is_some_and(|entry| entry.record_ref == record_ref)
+        {
+            self.history.go_to(index, cached);
+            return;
         }
 
-        context
-            .frame()
-            .render_widget(Span::raw("Record [M]etadata"), area_title);
+        if let Some(entry) = self.history.current_mut()
+            && entry.record_ref == record_ref
+        {
+            entry.cached = Some(cached);
+            return;
+        }
 
-        Ok(())
+        self.leave_history_entry();
+        self.history.push(HistoryEntry {
+            record_ref,
+            scroll: 0,
+            encoding: *self.pane_open.encoding_radio_array.get_checked(),
+            cached: Some(cached),
+        });
     }
 
-    fn draw_pane_overview(&self, context: &mut DrawContext, area: Rect) -> Result<()> {
-        let (area_title, _area_content) = Self::pane_areas(area, 0);
-        context
-            .frame()
-            .render_widget(Span::raw("[O]verview"), area_title);
-        Ok(())
-    }
+    /// Opens the record of the neighbouring history entry, restoring the scroll of its content and
+    /// the selected encoding. Records that are still cached are not resolved again.
+    fn go_through_history(&mut self, direction: HistoryDirection) -> Result<Option<Action>> {
+        let Some(index) = self.history.neighbour(direction) else {
+            let message = match direction {
+                HistoryDirection::Back => "No record to go back to.",
+                HistoryDirection::Forward => "No record to go forward to.",
+            };
+            return Ok(self.show_notice(message.to_string(), Severity::Info));
+        };
 
-    fn draw_pane_content(
-        &self,
-        context: &mut DrawContext,
-        area: Rect,
-        title_offset_x: u16,
-    ) -> Result<()> {
-        let (area_title, area_content) = Self::pane_areas(area, title_offset_x);
+        // The cached record would be taken for the result of the operation in progress.
+        if self.pane_open.busy {
+            return Ok(self.show_notice(
+                "Another record is being opened.".to_string(),
+                Severity::Info,
+            ));
+        }
 
-        context
-            .frame()
-            .render_widget(Span::raw("Record [C]ontent"), area_title);
+        self.leave_history_entry();
+        let entry = self
+            .history
+            .get(index)
+            .cloned()
+            .ok_or_else(|| eyre!("The history has no entry {index}."))?;
+        self.history_navigation = Some(index);
+        self.pane_open
+            .encoding_radio_array
+            .set_checked(&entry.encoding)?;
+        self.content_view.restore_scroll_on_open(entry.scroll);
+
+        match entry.cached {
+            Some(cached) => Ok(Some(Action::BroadcastMessage(
+                ComponentMessage::RecordOpen {
+                    record_ref: entry.record_ref,
+                    hashed_record_key: cached.hashed_record_key,
+                    read_result: Some((*cached.record).clone()),
+                    versions: cached.versions,
+                },
+            ))),
+            None => {
+                self.pane_open
+                    .start_operation(PaneOpenOperation::OpenRecord {
+                        record_ref: entry.record_ref,
+                    });
+                Ok(Some(Action::Render))
+            }
+        }
+    }
 
-        if let Some(opened_record) = self.state.borrow().opened_record.as_ref() {
-            context.frame().render_widget(
-                Text::raw(String::from_utf8_lossy(&opened_record.record.data)), // TODO: Other formats
-                area_content,
+    /// Displays the newer version of the opened record, keeping the scroll if the displayed part
+    /// of the content is unchanged.
+    fn update_to_newer_version(&mut self) -> Result<HandleEventSuccess> {
+        // The version would be taken for the result of the operation in progress.
+        if self.pane_open.busy {
+            return Ok(
+                HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                    ComponentMessage::ShowNotice {
+                        message: "Another record is being opened.".to_string(),
+                        severity: Severity::Info,
+                    },
+                )),
             );
         }
 
-        Ok(())
-    }
+        // A specific version that was opened is left for the latest one.
+        let (Some(record_ref), Some(newer_version)) = (
+            self.opened_record_ref()
+                .map(|record_ref| record_ref.at_version(None)),
+            self.newer_version.take(),
+        ) else {
+            return Ok(HandleEventSuccess::handled());
+        };
 
-    /*
-    fn draw_pane_open(
-        &self,
-        frame: &mut Frame,
-        area: Rect,
-        focused_id: ComponentId,
-        title_offset_x: u16,
-    ) -> Result<()> {
-        let (area_title, area_content) = Self::pane_areas(area, title_offset_x);
+        self.content_view
+            .anchor_scroll_on_update(&newer_version.record.data);
+
+        Ok(
+            HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                ComponentMessage::RecordOpen {
+                    record_ref,
+                    hashed_record_key: newer_version.hashed_record_key,
+                    read_result: Some(newer_version.record),
+                    versions: newer_version.versions,
+                },
+            )),
+        )
+    }
 
-        frame.render_widget(Span::raw("Open Sub-Record [Enter]"), area_title);
+    /// Shows how the newer version differs from the displayed one, which stays held back.
+    fn show_newer_version_diff(&self) -> HandleEventSuccess {
+        let state = self.state.borrow();
+        let (Some(opened_record), Some(newer_version)) =
+            (state.opened_record.as_ref(), self.newer_version.as_ref())
+        else {
+            return HandleEventSuccess::handled();
+        };
 
-        let layout_bottom_lines = Layout::default()
-            .direction(Direction::Horizontal)
-            .spacing(1)
-            .constraints([Constraint::Length(11), Constraint::Fill(1)]);
-        let [area_record_name, area_encoding] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Length(1)])
-            .areas(area_content);
-        let [area_record_name_label, area_record_name_field] =
-            layout_bottom_lines.areas(area_record_name);
-        let [area_encoding_label, area_encoding_field] = layout_bottom_lines.areas(area_encoding);
+        HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+            ComponentMessage::ShowDerivedView {
+                title: format!(
+                    "the diff of v{} and v{}",
+                    opened_record.version().unwrap_or_default(),
+                    newer_version.version()
+                ),
+                data: version_diff(&opened_record.record.data, &newer_version.record.data)
+                    .into_bytes(),
+            },
+        ))
+    }
 
-        frame.render_widget(Span::raw("Record Name"), area_record_name_label);
-        self.record_name_field
-            .draw(frame, area_record_name_field, focused_id, ())
-            .unwrap();
-        frame.render_widget(Span::raw("Encoding"), area_encoding_label);
-        self.encoding_radio_array
-            .draw(frame, area_encoding_field, focused_id, ())?;
-        // let [area_encoding_utf8, area_encoding_hex] = Layout::default()
-        //     .direction(Direction::Horizontal)
-        //     .spacing(2)
-        //     .constraints([Constraint::Length(9), Constraint::Fill(1)])
-        //     .areas(area_encoding_field);
-        // self.encoding_utf8_checkbox
-        //     .draw(frame, area_encoding_utf8, focused_id)
-        //     .unwrap();
-        // self.encoding_hex_checkbox
-        //     .draw(frame, area_encoding_hex, focused_id)
-        //     .unwrap();
-        Ok(())
+    fn show_notice(&mut self, message: String, severity: Severity) -> Option<Action> {
+        self.status_bar.show(message, severity);
+        Some(Action::Render)
     }
-    */
-}
 
-impl Component for MainView {
-    fn update(&mut self, message: ComponentMessage) -> Result<Option<crate::action::Action>> {
-        match message {
-            ComponentMessage::RecordOpen {
-                hashed_record_key,
-                read_result: Some(read_result),
-            } => {
-                self.state.borrow_mut().opened_record = Some(OpenedRecord {
-                    hashed_record_key,
-                    record: Arc::new(read_result),
-                });
-                Ok(Some(Action::Render))
-            }
-            _ => Ok(None),
+    /// Records the opened record and the scroll of its content, if a snapshot is due.
+    fn take_session_snapshot_if_due(&mut self) {
+        if self
+            .last_snapshot_at
+            .is_some_and(|last_snapshot_at| last_snapshot_at.elapsed() < SNAPSHOT_INTERVAL)
+        {
+            return;
         }
-    }
 
-    fn get_id(&self) -> ComponentId {
-        self.id
+        let record_ref = self
+            .state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| opened_record.record_ref.clone());
+        self.session_store.push(SessionSnapshot::new(
+            record_ref,
+            self.content_view.get_scroll(),
+        ));
+        self.last_snapshot_at = Some(Instant::now());
     }
 
-    fn get_children(&self) -> Vec<&dyn Component> {
-        vec![&self.pane_open]
-    }
+    /// Summarizes the registry as known to the interface, to be drawn while it is being opened
+    /// on the next start.
+    fn summarize_registry(&self) -> RegistrySummary {
+        let last_record = self.opened_record_ref();
+        let mut pinned_roots = self.tree.pinned_roots();
+
+        // The tree may not have taken the opened record yet.
+        if let Some(root) = last_record
+            .as_ref()
+            .filter(|record_ref| !record_ref.is_root())
+            .map(|record_ref| record_ref.ancestor(1))
+            && !pinned_roots.contains(&root)
+        {
+            pinned_roots.push(root);
+        }
 
-    fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
-        vec![&mut self.pane_open]
+        RegistrySummary::new(
+            crate::env::get_registry_id(&self.args.registry_directory),
+            self.tree.record_count(),
+            pinned_roots,
+            last_record,
+        )
     }
 
-    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
-        let mut node = accesskit::Node::new(accesskit::Role::Group);
-        node.set_children(vec![]);
-        Ok(node)
+    /// Saves the summary of the opened registry, unless the summary it was started with is yet
+    /// to be reconciled, which would lose its records.
+    fn save_registry_summary(&self) {
+        if self.args.safe_mode
+            || self.stale_summary.is_some()
+            || !matches!(self.state.borrow().registry, RegistryState::Opened(_))
+        {
+            return;
+        }
+
+        if let Err(error) = self
+            .summarize_registry()
+            .save(&self.args.registry_directory)
+        {
+            error!(?error, "Failed to save the registry summary.");
+        }
     }
-}
 
-impl Drawable for MainView {
-    type Args<'a>
-        = ()
-    where
-        Self: 'a;
+    fn find_custom_command(&self, key: char) -> Option<&CustomCommand> {
+        self.config
+            .custom_commands
+            .iter()
+            .find(|command| command.key == Some(key))
+    }
 
-    fn draw(&self, context: &mut DrawContext, mut area: Rect, (): Self::Args<'_>) -> Result<()> {
-        const SPACER_HORIZONTAL: LineSpacer = LineSpacer {
-            direction: Direction::Horizontal,
-            begin: symbols::line::HORIZONTAL,
-            inner: symbols::line::HORIZONTAL,
-            end: symbols::line::HORIZONTAL,
-            merged: symbols::line::HORIZONTAL,
-        };
-        const SPACER_VERTICAL_FORKED: LineSpacer = LineSpacer {
-            direction: Direction::Vertical,
-            begin: symbols::line::HORIZONTAL_DOWN,
-            inner: symbols::line::VERTICAL,
-            end: symbols::line::HORIZONTAL_UP,
-            merged: symbols::line::HORIZONTAL,
+    /// The input of a custom command, taken from the opened record.
+    fn get_custom_command_input(&self, input: CustomCommandInput) -> Result<Option<Vec<u8>>> {
+        let state = self.state.borrow();
+        let Some(opened_record) = state.opened_record.as_ref() else {
+            return Ok(None);
         };
 
-        if let Some(force_max_width) = self.args.force_max_width.as_ref() {
-            area.width = std::cmp::min(area.width, *force_max_width);
-        }
+        Ok(Some(match input {
+            CustomCommandInput::Content => opened_record.record.data.to_vec(),
+            CustomCommandInput::Name => opened_record
+                .record_ref
+                .path()
+                .last()
+                .cloned()
+                .unwrap_or_default(),
+            CustomCommandInput::Metadata => {
+                let metadata = opened_record
+                    .record
+                    .metadata
+                    .iter_with_semantic_keys()
+                    .map(|(key, value)| {
+                        (
+                            record_metadata_key_to_string(&key),
+                            cbor_value_to_json(value),
+                        )
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                serde_json::to_vec_pretty(&metadata)?
+            }
+        }))
+    }
+
+    fn run_custom_command(&self, command: CustomCommand) -> Result<HandleEventSuccess> {
+        let Some(input) = self.get_custom_command_input(command.input)? else {
+            return Ok(
+                HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                    ComponentMessage::ShowNotice {
+                        message: format!(
+                            "Custom command {:?} needs an opened record.",
+                            command.name
+                        ),
+                        severity: Severity::Info,
+                    },
+                )),
+            );
+        };
+
+        if command.suspend && self.config.never_write_plaintext {
+            return Ok(
+                HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                    ComponentMessage::ShowNotice {
+                        message: format!(
+                            "Custom command {:?} needs a temporary file, which \
+                             `never_write_plaintext` forbids.",
+                            command.name
+                        ),
+                        severity: Severity::Info,
+                    },
+                )),
+            );
+        }
+
+        if command.suspend {
+            return Ok(
+                HandleEventSuccess::handled().with_action(Action::RunInTerminal { command, input })
+            );
+        }
+
+        let action_tx = self.action_tx.clone();
+        crate::tasks::spawn_tracked(
+            &self.cancellation,
+            &self.action_tx,
+            format!("Running {:?}", command.name),
+            info_span!("custom_command"),
+            move |operation_id| async move {
+                let (messages, feedback) =
+                    match crate::custom_command::run_captured(&command, input).await {
+                        Ok(output) => {
+                            let mut messages = vec![ComponentMessage::ShowDerivedView {
+                                title: command.name.clone(),
+                                data: output.stdout,
+                            }];
+
+                            if !output.stderr.trim().is_empty() {
+                                messages.push(ComponentMessage::ShowNotice {
+                                    message: format!(
+                                        "Custom command {:?}: {}",
+                                        command.name,
+                                        output.stderr.trim()
+                                    ),
+                                    severity: Severity::Info,
+                                });
+                            }
+
+                            (messages, Feedback::Completion)
+                        }
+                        Err(error) => {
+                            warn!(?error, "Custom command failed.");
+                            let messages = vec![ComponentMessage::ShowNotice {
+                                message: operation_id.tag_error(format!("{error:#}")),
+                                severity: Severity::Error,
+                            }];
+                            (messages, Feedback::Error)
+                        }
+                    };
+
+                // Sending only fails once the application quits.
+                for message in messages {
+                    let _ = action_tx.send(Action::BroadcastMessage(message));
+                }
+
+                let _ = action_tx.send(Action::Feedback(feedback));
+            },
+        );
+
+        Ok(HandleEventSuccess::handled())
+    }
+
+    /// The file name suggested for exporting the record, its name in the selected encoding.
+    fn default_export_file_name(&self, record_ref: &RecordRef) -> String {
+        let encoding = self.pane_open.encoding_radio_array.get_checked();
+        let name = match record_ref.path().last() {
+            Some(name) if !name.is_empty() => encoding.encode(name),
+            _ => "root".to_string(),
+        };
+
+        name.replace(['/', '\\', '\0'], "_")
+    }
+
+    /// Writes the exact content of the opened record to the file at `path` in the background,
+    /// asking whether to overwrite the file if it exists, unless `overwrite`.
+    fn spawn_export(&self, path: PathBuf, overwrite: bool) {
+        let Some(record) = self
+            .state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| opened_record.record.clone())
+        else {
+            return;
+        };
+
+        let action_tx = self.action_tx.clone();
+        crate::tasks::spawn_tracked(
+            &self.cancellation,
+            &self.action_tx,
+            "Exporting the content",
+            info_span!("export content"),
+            move |operation_id| async move {
+                let (message, feedback) = match write_export(&path, &record.data, overwrite).await {
+                    Ok(()) => {
+                        info!(path = %path.display(), "Exported the content.");
+                        let message = ComponentMessage::ShowNotice {
+                            message: format!(
+                                "Exported {} to {}.",
+                                format_size(record.data.len()),
+                                path.display()
+                            ),
+                            severity: Severity::Info,
+                        };
+                        (message, Some(Feedback::Completion))
+                    }
+                    Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                        (ComponentMessage::ExportTargetExists { path }, None)
+                    }
+                    Err(error) => {
+                        warn!(?error, path = %path.display(), "Failed to export the content.");
+                        let message = ComponentMessage::ShowNotice {
+                            message: operation_id.tag_error(format!(
+                                "Failed to export the content to {}: {error}",
+                                path.display()
+                            )),
+                            severity: Severity::Error,
+                        };
+                        (message, Some(Feedback::Error))
+                    }
+                };
+
+                // Sending only fails once the application quits.
+                let _ = action_tx.send(Action::BroadcastMessage(message));
+
+                if let Some(feedback) = feedback {
+                    let _ = action_tx.send(Action::Feedback(feedback));
+                }
+            },
+        );
+    }
+
+    fn draw_export_prompt(&self, context: &mut DrawContext, area: Rect) -> Result<()> {
+        let [area_label, area_field] = Layout::default()
+            .direction(Direction::Horizontal)
+            .spacing(1)
+            .constraints([Constraint::Length(10), Constraint::Fill(1)])
+            .areas(area);
+
+        context.frame().render_widget(Clear, area);
+        context
+            .frame()
+            .render_widget(Span::raw("Export to"), area_label);
+        self.export_path_field
+            .draw(context, area_field, Default::default())
+    }
+
+    /// Shows the log view focused, or hides it and focuses the content.
+    fn toggle_log_view(&mut self) -> HandleEventSuccess {
+        let visible = !self.log_view.is_visible();
+        self.log_view.set_visible(visible);
+        let focused_id = if visible {
+            self.log_view.get_id()
+        } else {
+            self.content_view.get_id()
+        };
+
+        HandleEventSuccess::handled().with_action(Action::FocusComponent(focused_id))
+    }
+
+    /// Opens the dialog, cancelling the one that is open, if any.
+    fn open_modal(&mut self, request: ModalRequest) -> Result<Option<Action>> {
+        let modal = Modal::new(ComponentId::new(), &self.action_tx, request);
+        let focus_id = modal.get_initial_focus_id();
+
+        if let Some(replaced) = self.modal.replace(modal) {
+            self.action_tx
+                .send(Action::BroadcastMessage(ComponentMessage::ModalClosed {
+                    id: replaced.get_id(),
+                    requester: replaced.get_requester(),
+                    result: ModalResult::Cancelled,
+                }))?;
+        }
+
+        Ok(Some(Action::FocusComponent(focus_id)))
+    }
+
+    /// The height the interface needs at `width`: the header, the content, the bottom pane and
+    /// the footer.
+    fn min_height(width: u16) -> u16 {
+        1 + MIN_CONTENT_HEIGHT + PaneOpen::height(width) + 1
+    }
+
+    /// Lays out the panes, hiding or stacking the top ones as the terminal gets smaller, so that
+    /// the content and the bottom pane stay usable. `None` if the terminal is too small for them.
+    fn layout(area: Rect) -> Option<MainLayout> {
+        if area.width < MIN_WIDTH || area.height < Self::min_height(area.width) {
+            return None;
+        }
+
+        let [header, rest, bottom, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(PaneOpen::height(area.width)),
+                Constraint::Length(1),
+            ])
+            .areas(area);
+        let stacked = area.width < SIDE_PANE_WIDTH + 1 + MIN_METADATA_WIDTH;
+        let with_overview = area.width >= 2 * (SIDE_PANE_WIDTH + 1) + MIN_METADATA_WIDTH;
+        let (max_top_height, min_top_height) = if stacked {
+            (TOP_HEIGHT + 1, 2 * MIN_TOP_PANE_HEIGHT)
+        } else {
+            (TOP_HEIGHT, MIN_TOP_PANE_HEIGHT)
+        };
+        let top_height = std::cmp::min(
+            max_top_height,
+            rest.height.saturating_sub(MIN_CONTENT_HEIGHT),
+        );
+        let top_height = if top_height >= min_top_height {
+            top_height
+        } else {
+            0
+        };
+        let [top, content] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(top_height), Constraint::Fill(1)])
+            .areas(rest);
+        let mut layout = MainLayout {
+            header,
+            tree: None,
+            metadata: None,
+            overview: None,
+            content: PaneLayout::new(content, 0),
+            bottom: PaneLayout::new(bottom, 0),
+            footer,
+            horizontal_spacers: vec![content, bottom, footer],
+            vertical_spacers: Vec::new(),
+        };
+
+        if top_height == 0 {
+            return Some(layout);
+        }
+
+        layout.horizontal_spacers.push(top);
+
+        if stacked {
+            let [tree, metadata] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Fill(1), Constraint::Fill(1)])
+                .areas(top);
+            layout.tree = Some(PaneLayout::new(tree, 0));
+            layout.metadata = Some(PaneLayout::new(metadata, 0));
+            layout.horizontal_spacers.push(metadata);
+            return Some(layout);
+        }
+
+        let mut constraints = vec![Constraint::Length(SIDE_PANE_WIDTH), Constraint::Fill(1)];
+
+        if with_overview {
+            constraints.push(Constraint::Length(SIDE_PANE_WIDTH));
+        }
+
+        let layout_top = Layout::default()
+            .direction(Direction::Horizontal)
+            .spacing(1)
+            .constraints(constraints);
+        let (areas, spacers) = layout_top.split_with_spacers(top);
+        let title_offset_x = areas[1].x.saturating_sub(area.x);
+        layout.tree = Some(PaneLayout::new(areas[0], 0));
+        layout.metadata = Some(PaneLayout::new(areas[1], 0));
+        layout.overview = areas.get(2).map(|overview| PaneLayout::new(*overview, 0));
+        layout.content = PaneLayout::new(content, title_offset_x);
+        layout.bottom = PaneLayout::new(bottom, title_offset_x);
+        // The spacers in between the panes, reaching down to the line above the content.
+        layout.vertical_spacers = spacers[1..spacers.len().saturating_sub(1)]
+            .iter()
+            .map(|spacer| Rect {
+                height: spacer.height.saturating_add(1),
+                ..*spacer
+            })
+            .collect();
+
+        Some(layout)
+    }
+
+    /// Draws the notice displayed in place of the interface, when the terminal is too small.
+    fn draw_too_small(context: &mut DrawContext, area: Rect) {
+        let message = format!(
+            "Terminal too small, need at least {MIN_WIDTH}×{}",
+            Self::min_height(std::cmp::max(area.width, MIN_WIDTH))
+        );
+        let lines = wrap_words_to_width(&message, area.width as usize);
+        let y = area
+            .y
+            .saturating_add(area.height.saturating_sub(lines.len() as u16) / 2);
+        let style = context.palette().warning_style();
+
+        for (index, range) in lines.into_iter().enumerate() {
+            let area_line = Rect {
+                y: y.saturating_add(index as u16),
+                height: 1,
+                ..area
+            }
+            .intersection(area);
+            context.frame().render_widget(
+                Line::styled(message[range].trim(), style).centered(),
+                area_line,
+            );
+        }
+    }
+
+    /// Draws a single line of text, truncated if it does not fit.
+    fn draw_title(context: &mut DrawContext, title: &str, area: Rect) {
+        let (title, _) = truncate_to_width(title, area.width as usize, TruncateMode::End);
+        let title = Span::styled(title, context.palette().title_style());
+        context.frame().render_widget(title, area);
+    }
+
+    /// Draws the title of a pane, reversed while the component of the pane is focused, so that
+    /// the focused pane can be told apart when traversing the focus with [Tab]. Clicking the title
+    /// focuses the component.
+    fn draw_pane_title(
+        context: &mut DrawContext,
+        name: &'static str,
+        title: &str,
+        area: Rect,
+        id: ComponentId,
+    ) {
+        context.name_area(name, area);
+        context.register_mouse_area(id, area);
+        let (title, _) = truncate_to_width(title, area.width as usize, TruncateMode::End);
+        let style = if context.focused_id() == id {
+            context.palette().focus_style()
+        } else {
+            context.palette().title_style()
+        };
+        context
+            .frame()
+            .render_widget(Span::styled(title, style), area);
+    }
+
+    fn draw_header(&self, context: &mut DrawContext, area_header: Rect) -> Result<()> {
+        let version = PROJECT_VERSION.as_str();
+        let short_version = version.split(['.', '-', '+']).take(2).collect::<Vec<_>>();
+        let mut header = Header::default();
+        header.push(
+            HeaderElement::new(format!("RRR TUI v{version}"), 2)
+                .or(format!("v{version}"))
+                .or(format!("v{}", short_version.join("."))),
+        );
+        // Shrunk after the version, and collapsed to the root record and the last segments.
+        let breadcrumbs_index = self
+            .breadcrumbs
+            .header_element(3)
+            .map(|element| header.push(element));
+
+        if self.args.safe_mode {
+            header.push(
+                HeaderElement::new("[SAFE MODE] F3: diagnose startup files", 3)
+                    .or("[SAFE MODE] F3")
+                    .with_style(context.palette().error_style()),
+            );
+        }
+
+        if !self.pending_tasks.is_empty() {
+            let symbol = self.spinner.get_symbol();
+            header.push(
+                HeaderElement::new(format!("{symbol} {}…", self.spinner.get_text()), 1).or(symbol),
+            );
+        }
+
+        if let Some(element) = self.status_bar.header_element(context.palette()) {
+            header.push(element);
+        }
+
+        let placed = header.place(area_header);
+        context.frame().render_widget(header, area_header);
+
+        if let Some((area, text)) = breadcrumbs_index.and_then(|index| placed[index].as_ref()) {
+            self.breadcrumbs.draw(context, *area, text)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_pane_tree(&self, context: &mut DrawContext, pane: PaneLayout) -> Result<()> {
+        let title = if self.tree.is_stale() {
+            "[T]ree (stale)"
+        } else {
+            "[T]ree"
+        };
+        Self::draw_pane_title(context, "tree title", title, pane.title, self.tree.get_id());
+        self.tree.draw(context, pane.content, ())
+    }
+
+    fn draw_pane_metadata(&self, context: &mut DrawContext, pane: PaneLayout) -> Result<()> {
+        self.metadata_view
+            .draw(context, pane.content, self.metadata_changes.as_ref())?;
+
+        let title = match self
+            .state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .and_then(|opened_record| opened_record.stats.as_ref())
+        {
+            Some(stats) => format!("Record Metadata, content {}", stats.summary()),
+            None => "Record Metadata".to_string(),
+        };
+        Self::draw_pane_title(
+            context,
+            "metadata title",
+            &title,
+            pane.title,
+            self.metadata_view.get_id(),
+        );
+
+        Ok(())
+    }
+
+    fn opened_record_ref(&self) -> Option<RecordRef> {
+        self.state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| opened_record.record_ref.clone())
+    }
+
+    fn draw_pane_overview(&self, context: &mut DrawContext, pane: PaneLayout) -> Result<()> {
+        let area_content = pane.content;
+        let selector_id = self.version_selector.get_id();
+        let stale_summary = self
+            .stale_summary
+            .as_ref()
+            .filter(|_| matches!(self.state.borrow().registry, RegistryState::Opening));
+        let title = if stale_summary.is_some() {
+            "Overview (stale)"
+        } else {
+            "Overview"
+        };
+        Self::draw_pane_title(context, "overview title", title, pane.title, selector_id);
+
+        if context.focused_id() == selector_id {
+            self.version_selector.draw(context, area_content, ())?;
+        } else if let Some(opened_record) = self.state.borrow().opened_record.as_ref() {
+            let [area_alias, area_table] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Fill(1)])
+                .areas(area_content);
+            let [area_alias_label, area_alias_value] = Layout::default()
+                .direction(Direction::Horizontal)
+                .spacing(1)
+                .constraints([Constraint::Length(8), Constraint::Fill(1)])
+                .areas(area_alias);
+
+            context
+                .frame()
+                .render_widget(Span::raw("Alias"), area_alias_label);
+
+            if self.editing_alias {
+                self.alias_field
+                    .draw(context, area_alias_value, Default::default())?;
+            } else {
+                let alias = match self.annotations.get_alias(&opened_record.record_ref) {
+                    Some(alias) if self.annotations.is_alias_shared(&opened_record.record_ref) => {
+                        Line::from_iter([
+                            Span::raw(alias),
+                            Span::styled(" (shared)", context.palette().warning_style()),
+                        ])
+                    }
+                    Some(alias) => Line::raw(alias),
+                    None => Line::styled("none, press F2 to set", context.palette().muted_style()),
+                };
+                context.frame().render_widget(alias, area_alias_value);
+            }
+
+            let data = &opened_record.record.data;
+            let mut rows = vec![
+                Row::new([
+                    Line::raw("Name"),
+                    Line::raw(
+                        opened_record
+                            .record_ref
+                            .display_path(self.config.display.lossy_names),
+                    ),
+                ]),
+                Row::new([Line::raw("Size"), Line::raw(format_size(data.len()))]),
+                Row::new([
+                    Line::raw("Version"),
+                    match self.newer_version.as_ref() {
+                        Some(newer_version) => Line::from_iter([
+                            Span::raw(VersionSelector::describe(opened_record)),
+                            Span::styled(
+                                format!(", v{} available", newer_version.version()),
+                                context.palette().warning_style(),
+                            ),
+                        ]),
+                        None => Line::raw(VersionSelector::describe(opened_record)),
+                    },
+                ]),
+            ];
+
+            if let Some(warning) =
+                invalid_utf8_warning(opened_record.record_ref.has_invalid_utf8_name(), data)
+            {
+                rows.push(Row::new([
+                    Line::raw("Warning"),
+                    Line::styled(warning, context.palette().warning_style()),
+                ]));
+            }
+
+            let value_width = area_content.width.saturating_sub(9) as usize;
+            let (registry_directory, _) = truncate_to_width(
+                &self.args.registry_directory.display().to_string(),
+                value_width,
+                TruncateMode::Start,
+            );
+            rows.push(Row::new([
+                Line::raw("Registry"),
+                Line::raw(registry_directory),
+            ]));
+
+            // A record is only opened once the registry is.
+            if let RegistryState::Opened(registry) = &self.state.borrow().registry {
+                rows.extend([
+                    Row::new([
+                        Line::raw("Hash"),
+                        Line::styled(
+                            format!("{:?}", registry.config.hash),
+                            context.palette().muted_style(),
+                        ),
+                    ]),
+                    Row::new([
+                        Line::raw("KDF"),
+                        Line::styled(
+                            format!("{:?}", registry.config.kdf),
+                            context.palette().muted_style(),
+                        ),
+                    ]),
+                ]);
+            }
+
+            let overview_table = Table::new(rows, [Constraint::Length(8), Constraint::Fill(1)]);
+
+            context.frame().render_widget(overview_table, area_table);
+        } else if let Some(summary) = stale_summary {
+            self.draw_stale_summary(context, area_content, summary);
+        } else {
+            self.draw_registry_state(context, area_content);
+        }
+
+        Ok(())
+    }
+
+    /// Describes the registry as it was last closed in the overview, while it is being opened.
+    fn draw_stale_summary(&self, context: &mut DrawContext, area: Rect, summary: &RegistrySummary) {
+        let muted_style = context.palette().muted_style();
+        let value_width = area.width.saturating_sub(9) as usize;
+        let last_record = match summary.last_record.as_ref() {
+            Some(record_ref) => record_ref.display_path(self.config.display.lossy_names),
+            None => "none".to_string(),
+        };
+        let (last_record, _) = truncate_to_width(&last_record, value_width, TruncateMode::Start);
+        let rows = [
+            Row::new([Line::raw("Registry"), Line::styled("opening…", muted_style)]),
+            Row::new([
+                Line::raw("Records"),
+                Line::raw(format!("{} known", summary.record_count)),
+            ]),
+            Row::new([Line::raw("Opened"), Line::raw(summary.age())]),
+            Row::new([Line::raw("Last"), Line::raw(last_record)]),
+        ];
+        let table = Table::new(rows, [Constraint::Length(8), Constraint::Fill(1)]);
+
+        context.frame().render_widget(table, area);
+    }
+
+    /// Describes the state of the registry in the overview, while no record is opened.
+    fn draw_registry_state(&self, context: &mut DrawContext, area: Rect) {
+        let palette = context.palette();
+        let (message, style) = match &self.state.borrow().registry {
+            RegistryState::Opening => ("opening the registry…".to_string(), palette.muted_style()),
+            RegistryState::Failed(error) => {
+                (format!("{ERROR_MARKER} {error}"), palette.error_style())
+            }
+            RegistryState::Opened(_) => ("loading…".to_string(), palette.muted_style()),
+        };
+
+        // The error may be long, as it includes the path and the causes.
+        for (index, range) in wrap_to_width(&message, area.width as usize)
+            .into_iter()
+            .take(area.height as usize)
+            .enumerate()
+        {
+            let area_line = Rect {
+                y: area.y.saturating_add(index as u16),
+                height: 1,
+                ..area
+            }
+            .intersection(area);
+            context
+                .frame()
+                .render_widget(Line::styled(&message[range], style), area_line);
+        }
+    }
+
+    fn draw_pane_content(&self, context: &mut DrawContext, pane: PaneLayout) -> Result<()> {
+        let area_content = pane.content;
+        let mut title = match self.content_view.get_derived_view_title() {
+            Some(title) => format!("Record [C]ontent, output of {title} [Esc]"),
+            None => "Record [C]ontent".to_string(),
+        };
+
+        if let Some(follow_label) = self.content_view.get_follow_label() {
+            title.push_str(&format!(" ({follow_label})"));
+        }
+
+        if let Some(selection_label) = self.content_view.get_selection_label() {
+            title.push_str(&format!(" ({selection_label})"));
+        }
+
+        Self::draw_pane_title(
+            context,
+            "content title",
+            &title,
+            pane.title,
+            self.content_view.get_id(),
+        );
+
+        self.content_view.draw(context, area_content, ())?;
+
+        // Drawn over the first row of the content, which stays displayed until updated.
+        if let Some(newer_version) = self.newer_version.as_ref() {
+            let area_banner = Rect {
+                height: std::cmp::min(area_content.height, 1),
+                ..area_content
+            };
+            context.frame().render_widget(Clear, area_banner);
+            context.render_overlay(
+                "newer version banner",
+                Line::styled(
+                    newer_version_banner(newer_version.version()),
+                    context.palette().info_style().reversed(),
+                ),
+                area_banner,
+            );
+        }
+
+        Ok(())
+    }
+
+    /*
+    fn draw_pane_open(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        focused_id: ComponentId,
+        title_offset_x: u16,
+    ) -> Result<()> {
+        let (area_title, area_content) = Self::pane_areas(area, title_offset_x);
+
+        frame.render_widget(Span::raw("Open Sub-Record [Enter]"), area_title);
+
+        let layout_bottom_lines = Layout::default()
+            .direction(Direction::Horizontal)
+            .spacing(1)
+            .constraints([Constraint::Length(11), Constraint::Fill(1)]);
+        let [area_record_name, area_encoding] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .areas(area_content);
+        let [area_record_name_label, area_record_name_field] =
+            layout_bottom_lines.areas(area_record_name);
+        let [area_encoding_label, area_encoding_field] = layout_bottom_lines.areas(area_encoding);
+
+        frame.render_widget(Span::raw("Record Name"), area_record_name_label);
+        self.record_name_field
+            .draw(frame, area_record_name_field, focused_id, ())?;
+        frame.render_widget(Span::raw("Encoding"), area_encoding_label);
+        self.encoding_radio_array
+            .draw(frame, area_encoding_field, focused_id, ())?;
+        // let [area_encoding_utf8, area_encoding_hex] = Layout::default()
+        //     .direction(Direction::Horizontal)
+        //     .spacing(2)
+        //     .constraints([Constraint::Length(9), Constraint::Fill(1)])
+        //     .areas(area_encoding_field);
+        // self.encoding_utf8_checkbox
+        //     .draw(frame, area_encoding_utf8, focused_id)
+        //     .unwrap();
+        // self.encoding_hex_checkbox
+        //     .draw(frame, area_encoding_hex, focused_id)
+        //     .unwrap();
+        Ok(())
+    }
+    */
+}
+
+impl Component for MainView {
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<crate::action::Action>> {
+        // Sent after the content view processed the record, so that it is not dismissed by it.
+        if let ComponentMessage::RecordOpen { .. } = message
+            && let Some(whats_new) = self.whats_new.take()
+        {
+            self.action_tx.send(Action::BroadcastMessage(
+                ComponentMessage::ShowDerivedView {
+                    title: format!("what's new in v{}", *PROJECT_VERSION),
+                    data: whats_new.into_bytes(),
+                },
+            ))?;
+        }
+
+        match message {
+            ComponentMessage::RecordOpen {
+                record_ref,
+                hashed_record_key,
+                read_result: Some(read_result),
+                versions,
+            } => {
+                let mut state = self.state.borrow_mut();
+
+                // Followed records are reloaded periodically, which must not interrupt editing.
+                if state
+                    .opened_record
+                    .as_ref()
+                    .is_none_or(|previous| previous.hashed_record_key != hashed_record_key)
+                {
+                    self.editing_alias = false;
+                    self.editing_export_path = false;
+                }
+                self.metadata_changes = state
+                    .opened_record
+                    .as_ref()
+                    .filter(|previous| previous.hashed_record_key == hashed_record_key)
+                    .and_then(|previous| MetadataChanges::between(&previous.record, &read_result));
+                // Reused while the content stays the same, such as when following a record.
+                let stats = state
+                    .opened_record
+                    .as_ref()
+                    .filter(|previous| previous.record.data == read_result.data)
+                    .and_then(|previous| previous.stats.clone());
+                let record = Arc::new(read_result);
+                let analyze = stats.is_none();
+                state.opened_record = Some(OpenedRecord {
+                    record_ref: record_ref.clone(),
+                    hashed_record_key: hashed_record_key.clone(),
+                    record: record.clone(),
+                    versions: versions.clone(),
+                    stats,
+                });
+                drop(state);
+
+                // The first opened record confirms the registry is as summarized or not.
+                if let Some(stale_summary) = self.stale_summary.take() {
+                    let changes = self.summarize_registry().changes_since(&stale_summary);
+
+                    if !changes.is_empty() {
+                        info!(?changes, "The registry changed since it was summarized.");
+                    }
+
+                    self.tree.reconcile(
+                        &changes,
+                        Some(&record_ref),
+                        Instant::now() + CHANGES_HIGHLIGHT_DURATION,
+                    );
+                }
+
+                self.record_history_entry(
+                    record_ref,
+                    CachedRecord {
+                        hashed_record_key: hashed_record_key.clone(),
+                        record: record.clone(),
+                        versions,
+                    },
+                );
+
+                if analyze {
+                    let deferred = self.analyze_content(hashed_record_key, record);
+                    self.action_tx.send(Action::Defer(deferred))?;
+                }
+
+                self.newer_version = None;
+                self.last_version_check_at = Some(Instant::now());
+                // Taken on the next tick, once the content view has processed the record.
+                self.last_snapshot_at = None;
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::ContentAnalyzed {
+                hashed_record_key,
+                stats,
+            } => {
+                let mut state = self.state.borrow_mut();
+
+                match state.opened_record.as_mut() {
+                    Some(opened_record) if opened_record.hashed_record_key == hashed_record_key => {
+                        opened_record.stats = stats;
+                        Ok(Some(Action::Render))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            ComponentMessage::NewVersionChecked {
+                hashed_record_key,
+                read_result: Some(record),
+                versions,
+            } if self
+                .state
+                .borrow()
+                .opened_record
+                .as_ref()
+                .is_some_and(|opened_record| {
+                    opened_record.hashed_record_key == hashed_record_key
+                }) =>
+            {
+                info!(
+                    version_count = versions.len(),
+                    "A newer version of the opened record is available."
+                );
+                self.newer_version = Some(NewerVersion {
+                    hashed_record_key,
+                    record,
+                    versions,
+                });
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::OnTerminalFocusChange { focused } => {
+                self.terminal_focused = focused;
+
+                if focused && let Some(changes) = self.metadata_changes.as_mut() {
+                    changes.expires_at = std::cmp::max(
+                        changes.expires_at,
+                        Instant::now() + CHANGES_HIGHLIGHT_DURATION,
+                    );
+                }
+
+                Ok(None)
+            }
+            ComponentMessage::AlternatePanelChanged { panel } => {
+                self.alternate_panel = panel;
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::FocusPanel { panel } => {
+                Ok(Some(Action::FocusComponent(match panel {
+                    Panel::Open => self.pane_open.get_id(),
+                    Panel::Tree => self.tree.get_id(),
+                    Panel::Content => self.content_view.get_id(),
+                })))
+            }
+            ComponentMessage::RecordOpen {
+                record_ref,
+                read_result: None,
+                ..
+            } => Ok(self.show_notice(
+                format!(
+                    "Record {} not found.",
+                    record_ref.display_path(self.config.display.lossy_names)
+                ),
+                Severity::Info,
+            )),
+            ComponentMessage::GoThroughHistory { direction } if self.modal.is_none() => {
+                self.go_through_history(direction)
+            }
+            ComponentMessage::PromptExport => {
+                let Some(record_ref) = self.opened_record_ref() else {
+                    return Ok(self.show_notice(
+                        "Exporting needs an opened record.".to_string(),
+                        Severity::Info,
+                    ));
+                };
+
+                self.export_path_field
+                    .set_content(self.default_export_file_name(&record_ref));
+                self.editing_export_path = true;
+                Ok(Some(Action::FocusComponent(
+                    self.export_path_field.get_id(),
+                )))
+            }
+            ComponentMessage::FindLogEntries { .. } if !self.log_view.is_visible() => {
+                self.log_view.set_visible(true);
+                Ok(Some(Action::FocusComponent(self.log_view.get_id())))
+            }
+            ComponentMessage::ExportTargetExists { path } => {
+                let request = ModalRequest::new(
+                    self.id,
+                    "Overwrite?",
+                    format!("{} already exists.", path.display()),
+                )
+                .with_buttons(&["Overwrite", "Cancel"], 1);
+                let action = self.open_modal(request)?;
+                self.export_overwrite = self.modal.as_ref().map(|modal| (modal.get_id(), path));
+                Ok(action)
+            }
+            ComponentMessage::CopyToClipboard { text, requester } => {
+                if text.len() <= self.config.clipboard.confirmation_size {
+                    return Ok(Some(Action::CopyToClipboard { text }));
+                }
+
+                let request = ModalRequest::new(
+                    self.id,
+                    "Copy?",
+                    format!("Copy {} to the clipboard?", format_size(text.len())),
+                )
+                .with_buttons(&["Copy", "Cancel"], 1);
+                let action = self.open_modal(request)?;
+                self.copy_confirmation = self
+                    .modal
+                    .as_ref()
+                    .map(|modal| (modal.get_id(), requester, text));
+                Ok(action)
+            }
+            ComponentMessage::RegistryOpened { result } => match result {
+                Ok(SharedRegistry(registry)) => {
+                    info!("Registry opened.");
+                    self.state.borrow_mut().registry = RegistryState::Opened(registry);
+
+                    if let Some(operation) = self.initial_operation.take() {
+                        self.pane_open.start_operation(operation);
+                    }
+
+                    Ok(Some(Action::Render))
+                }
+                Err(error) => {
+                    error!(%error, "Failed to open the registry.");
+                    self.state.borrow_mut().registry = RegistryState::Failed(error.clone());
+                    Ok(self.show_notice(error, Severity::Error))
+                }
+            },
+            ComponentMessage::OpenModal { request } => self.open_modal(request),
+            ComponentMessage::ModalClosed {
+                id,
+                requester,
+                result,
+            } => {
+                if self
+                    .modal
+                    .as_ref()
+                    .is_some_and(|modal| modal.get_id() == id)
+                {
+                    self.modal = None;
+                }
+
+                if requester != self.id {
+                    return Ok(Some(Action::Render));
+                }
+
+                if let Some((_, requester, text)) = self
+                    .copy_confirmation
+                    .take_if(|(modal_id, _, _)| *modal_id == id)
+                {
+                    if let ModalResult::Button { index: 0, .. } = result {
+                        self.action_tx.send(Action::CopyToClipboard { text })?;
+                    }
+
+                    return Ok(Some(Action::FocusComponent(requester)));
+                }
+
+                if let Some((_, path)) = self
+                    .export_overwrite
+                    .take_if(|(modal_id, _)| *modal_id == id)
+                    && let ModalResult::Button { index: 0, .. } = result
+                {
+                    self.spawn_export(path, true);
+                }
+
+                Ok(Some(Action::FocusComponent(self.content_view.get_id())))
+            }
+            ComponentMessage::OnRadioArrayChange { id }
+                if id == self.pane_open.encoding_radio_array.get_id() =>
+            {
+                let hex_dump = *self.pane_open.encoding_radio_array.get_checked() == Encoding::HEX;
+                self.content_view.set_hex_dump(hex_dump);
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::TaskStarted { id, label } => {
+                self.spinner.set_text(label.clone());
+                self.pending_tasks.push((id, label));
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::TaskFinished { id } => {
+                self.pending_tasks.retain(|(task_id, _)| *task_id != id);
+
+                if let Some((_, label)) = self.pending_tasks.last() {
+                    self.spinner.set_text(label.clone());
+                }
+
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::OnExit => {
+                self.save_registry_summary();
+                Ok(None)
+            }
+            ComponentMessage::OnTick => {
+                self.take_session_snapshot_if_due();
+
+                // Animated only while something is pending.
+                let pending = !self.pending_tasks.is_empty();
+
+                if pending {
+                    self.spinner.advance();
+                }
+
+                if self.content_view.is_following()
+                    && !self.pane_open.busy
+                    && self
+                        .last_follow_poll_at
+                        .is_none_or(|polled_at| polled_at.elapsed() >= FOLLOW_POLL_INTERVAL)
+                {
+                    self.last_follow_poll_at = Some(Instant::now());
+                    self.pane_open
+                        .start_operation(PaneOpenOperation::Reload { quiet: true });
+                } else if !self.content_view.is_following()
+                    && !self.pane_open.busy
+                    && self.newer_version.is_none()
+                    && self.last_version_check_at.is_some_and(|checked_at| {
+                        checked_at.elapsed() >= NEW_VERSION_CHECK_INTERVAL
+                    })
+                {
+                    self.last_version_check_at = Some(Instant::now());
+                    self.pane_open
+                        .start_operation(PaneOpenOperation::CheckForNewVersion);
+                }
+
+                if self.terminal_focused
+                    && self
+                        .metadata_changes
+                        .as_ref()
+                        .is_some_and(|changes| changes.expires_at <= Instant::now())
+                {
+                    self.metadata_changes = None;
+                    Ok(Some(Action::Render))
+                } else {
+                    Ok(pending.then_some(Action::Render))
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn capture_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // The open dialog handles everything by itself.
+        if self.modal.is_some() {
+            return Ok(HandleEventSuccess::unhandled());
+        }
+
+        match event {
+            // Dismisses the error before the focused component could handle the key, such as by
+            // canceling the opening of a record.
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.status_bar.dismiss_error() => {
+                Ok(HandleEventSuccess::handled().with_action(Action::Render))
+            }
+            _ => Ok(HandleEventSuccess::unhandled()),
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // The open dialog handles everything by itself.
+        if self.modal.is_some() {
+            return Ok(HandleEventSuccess::unhandled());
+        }
+
+        match event {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(key),
+                modifiers: KeyModifiers::ALT,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.find_custom_command(*key).is_some() => {
+                match self.find_custom_command(*key).cloned() {
+                    Some(command) => self.run_custom_command(command),
+                    None => Ok(HandleEventSuccess::unhandled()),
+                }
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('u'),
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.newer_version.is_some() => self.update_to_newer_version(),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.newer_version.is_some() => Ok(self.show_newer_version_diff()),
+            Event::Key(KeyEvent {
+                code: KeyCode::F(12),
+                kind: KeyEventKind::Press,
+                modifiers: KeyModifiers::NONE,
+                ..
+            }) => Ok(self.toggle_log_view()),
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.log_view.is_focused() => Ok(self.toggle_log_view()),
+            Event::Key(KeyEvent {
+                code: KeyCode::F(5),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.pane_open
+                    .start_operation(PaneOpenOperation::Reload { quiet: false });
+                Ok(HandleEventSuccess::handled().with_action(Action::Render))
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::F(3),
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.startup_file_diagnosis.is_some() => {
+                let diagnosis = self.startup_file_diagnosis.clone().unwrap_or_default();
+                Ok(
+                    HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                        ComponentMessage::ShowDerivedView {
+                            title: "startup file diagnosis".to_string(),
+                            data: diagnosis.into_bytes(),
+                        },
+                    )),
+                )
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::F(2),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let Some(record_ref) = self.opened_record_ref() else {
+                    return Ok(HandleEventSuccess::unhandled());
+                };
+
+                self.alias_field
+                    .set_content(self.annotations.get_alias(&record_ref).unwrap_or_default());
+                self.editing_alias = true;
+                Ok(HandleEventSuccess::handled()
+                    .with_action(Action::FocusComponent(self.alias_field.get_id())))
+            }
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::Enter | KeyCode::Esc),
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.editing_alias => {
+                if *code == KeyCode::Enter
+                    && let Some(record_ref) = self.opened_record_ref()
+                {
+                    self.annotations
+                        .set_alias(&record_ref, self.alias_field.get_content());
+
+                    if let Err(error) = self.annotations.save() {
+                        error!(?error, "Failed to save the annotations.");
+                    }
+                }
+
+                self.editing_alias = false;
+                Ok(HandleEventSuccess::handled()
+                    .with_action(Action::FocusComponent(self.content_view.get_id())))
+            }
+            Event::Key(KeyEvent {
+                code: code @ (KeyCode::Enter | KeyCode::Esc),
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.editing_export_path => {
+                let path = self.export_path_field.get_content().trim();
+
+                if *code == KeyCode::Enter {
+                    if path.is_empty() {
+                        return Ok(HandleEventSuccess::handled());
+                    }
+
+                    self.spawn_export(PathBuf::from(path), false);
+                }
+
+                self.editing_export_path = false;
+                Ok(HandleEventSuccess::handled()
+                    .with_action(Action::FocusComponent(self.content_view.get_id())))
+            }
+            _ => Ok(HandleEventSuccess::unhandled()),
+        }
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_children(&self) -> Vec<&dyn Component> {
+        // The tree comes last, so that the open pane is focused initially.
+        let mut children: Vec<&dyn Component> = vec![
+            &self.pane_open,
+            &self.content_view,
+            &self.metadata_view,
+            &self.version_selector,
+            &self.tree,
+            &self.breadcrumbs,
+            &self.status_bar,
+        ];
+
+        if self.editing_alias {
+            children.push(&self.alias_field);
+        }
+
+        if self.editing_export_path {
+            children.push(&self.export_path_field);
+        }
+
+        children.push(&self.log_view);
+
+        if let Some(modal) = self.modal.as_ref() {
+            children.push(modal);
+        }
+
+        children
+    }
+
+    fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
+        let mut children: Vec<&mut dyn Component> = vec![
+            &mut self.pane_open,
+            &mut self.content_view,
+            &mut self.metadata_view,
+            &mut self.version_selector,
+            &mut self.tree,
+            &mut self.breadcrumbs,
+            &mut self.status_bar,
+        ];
+
+        if self.editing_alias {
+            children.push(&mut self.alias_field);
+        }
+
+        if self.editing_export_path {
+            children.push(&mut self.export_path_field);
+        }
+
+        children.push(&mut self.log_view);
+
+        if let Some(modal) = self.modal.as_mut() {
+            children.push(modal);
+        }
+
+        children
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Window);
+        node.set_label(format!("RRR TUI v{}", *PROJECT_VERSION));
+
+        if let Some(text) = self.status_bar.text() {
+            node.set_description(text);
+        }
+
+        Ok(node)
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.opened_record = self
+            .state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map(|opened_record| crate::introspection::OpenedRecordState {
+                path: opened_record
+                    .record_ref
+                    .display_path(self.config.display.lossy_names),
+                version_count: opened_record.versions.len(),
+                size: opened_record.record.data.len(),
+                metadata_rows: opened_record
+                    .record
+                    .metadata
+                    .iter_with_semantic_keys()
+                    .count(),
+                alias: self
+                    .annotations
+                    .get_alias(&opened_record.record_ref)
+                    .map(str::to_string),
+            });
+        state.pending_tasks = self
+            .pending_tasks
+            .iter()
+            .map(|(_, label)| label.clone())
+            .collect();
+    }
+}
+
+impl Drawable for MainView {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, mut area: Rect, (): Self::Args<'_>) -> Result<()> {
+        const SPACER_HORIZONTAL: LineSpacer = LineSpacer::new(Direction::Horizontal);
+        const SPACER_VERTICAL: LineSpacer = LineSpacer::new(Direction::Vertical);
+
+        if let Some(force_max_width) = self.args.force_max_width.as_ref() {
+            area.width = std::cmp::min(area.width, *force_max_width);
+        }
 
         if let Some(force_max_height) = self.args.force_max_height.as_ref() {
             area.height = std::cmp::min(area.height, *force_max_height);
         }
 
-        let [area_header, area_top, area_content, area_bottom, area_footer] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(7),
-                Constraint::Fill(1),
-                Constraint::Length(3),
-                Constraint::Length(1),
-            ])
-            .areas(area);
-        let layout_top = Layout::default()
-            .direction(Direction::Horizontal)
-            .spacing(1)
-            .constraints([
-                Constraint::Length(8),
-                Constraint::Fill(1),
-                Constraint::Length(16),
-            ]);
-        let [area_tree, area_metadata, area_overview] = layout_top.areas(area_top);
-        let [_, area_top_spacer_0, area_top_spacer_1, _] = layout_top.spacers(area_top);
+        context.name_area("main", area);
 
-        context
-            .frame()
-            .render_widget(SPACER_HORIZONTAL.clone(), area_top);
-        context
-            .frame()
-            .render_widget(SPACER_HORIZONTAL.clone(), area_content);
-        context
-            .frame()
-            .render_widget(SPACER_HORIZONTAL.clone(), area_bottom);
-        context
-            .frame()
-            .render_widget(SPACER_HORIZONTAL.clone(), area_footer);
-        context.frame().render_widget(
-            SPACER_VERTICAL_FORKED.clone(),
-            Rect {
-                height: area_top_spacer_0.height + 1,
-                ..area_top_spacer_0
-            },
-        );
-        context.frame().render_widget(
-            SPACER_VERTICAL_FORKED.clone(),
-            Rect {
-                height: area_top_spacer_1.height + 1,
-                ..area_top_spacer_1
-            },
-        );
+        let Some(layout) = Self::layout(area) else {
+            Self::draw_too_small(context, area);
+            return Ok(());
+        };
+        let MainLayout {
+            header: area_header,
+            footer: area_footer,
+            ..
+        } = layout;
+
+        context.name_area("header", area_header);
+        context.name_area("content", layout.content.area);
+        context.name_area("bottom", layout.bottom.area);
+        context.name_area("footer", area_footer);
+
+        let separator_style = context.palette().separator_style();
+
+        for spacer in &layout.horizontal_spacers {
+            context.render_background(
+                "separator",
+                SPACER_HORIZONTAL.with_style(separator_style),
+                Rect {
+                    height: std::cmp::min(spacer.height, 1),
+                    ..*spacer
+                },
+            );
+        }
+
+        // Reaching down into the separator below, to join it.
+        for spacer in &layout.vertical_spacers {
+            context.render_background(
+                "vertical separator",
+                SPACER_VERTICAL.with_style(separator_style),
+                *spacer,
+            );
+        }
+
+        if let Some(pane) = layout.tree {
+            context.name_area("tree", pane.area);
+            self.draw_pane_tree(context, pane)?;
+        }
+
+        if let Some(pane) = layout.metadata {
+            context.name_area("metadata", pane.area);
+            self.draw_pane_metadata(context, pane)?;
+        }
+
+        if let Some(pane) = layout.overview {
+            context.name_area("overview", pane.area);
+            self.draw_pane_overview(context, pane)?;
+        }
+
+        self.draw_pane_content(context, layout.content)?;
+
+        if let Some((position, length)) = self.history.position() {
+            let history = format!(" history {position}/{length} ");
+            let width = std::cmp::min(history.len() as u16, area_footer.width);
+            context.render_named_widget(
+                "history position",
+                Span::styled(history, context.palette().muted_style()),
+                Rect {
+                    width,
+                    ..area_footer
+                },
+            );
+        }
+
+        if let Some(panel) = self.alternate_panel {
+            let hint = format!(" Ctrl+^: {panel} ");
+            let width = std::cmp::min(hint.len() as u16, area_footer.width);
+            context.render_named_widget(
+                "alternate panel hint",
+                Span::styled(hint, context.palette().muted_style()),
+                Rect {
+                    x: area_footer.right().saturating_sub(width),
+                    width,
+                    ..area_footer
+                },
+            );
+        }
+
+        // The footer serves as the status bar of the content view, when it is too small.
+        if let Some(status_line) = self.content_view.get_status_line(context.palette()) {
+            context.frame().render_widget(Clear, area_footer);
+            context.render_overlay("status", status_line, area_footer);
+        }
+
+        // The export prompt takes the place of the status bar while it is shown.
+        if self.editing_export_path {
+            self.draw_export_prompt(context, area_footer)?;
+        }
 
-        self.draw_pane_tree(context, area_tree)?;
-        self.draw_pane_metadata(context, area_metadata)?;
-        self.draw_pane_overview(context, area_overview)?;
-        self.draw_pane_content(context, area_content, area_metadata.x)?;
         self.pane_open.draw(
             context,
-            area_bottom,
+            layout.bottom.content,
             PaneOpenArgs {
-                title_offset_x: area_metadata.x,
+                area_title: layout.bottom.title,
             },
         )?;
         self.draw_header(context, area_header)?;
 
+        // Over the bottom third of the screen, so that the content stays in view.
+        if self.log_view.is_visible() {
+            let height = std::cmp::min(std::cmp::max(area.height / 3, 3), area.height);
+            let area_log = Rect {
+                y: area.bottom().saturating_sub(height),
+                height,
+                ..area
+            };
+            self.log_view.draw(context, area_log, ())?;
+        }
+
+        if let Some(modal) = self.modal.as_ref() {
+            modal.draw(context, area, ())?;
+        }
+
         Ok(())
     }
 }
 
+/// Writes the data to a new file at `path`, or over an existing one if `overwrite`.
+async fn write_export(path: &Path, data: &[u8], overwrite: bool) -> std::io::Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true);
+
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    let mut file = options.open(path).await?;
+    file.write_all(data).await?;
+    file.flush().await
+}
+
+/// An operation of `PaneOpen` which produces a `ComponentMessage::RecordOpen`.
+#[derive(Debug)]
+enum PaneOpenOperation {
+    /// Open a record by resolving its path from the root record.
+    OpenRecord { record_ref: RecordRef },
+    /// Open the records along the path in turn, stopping at the deepest one that exists.
+    OpenDeepestRecord { record_ref: RecordRef },
+    /// Open a sub-record of the currently opened record.
+    OpenSubRecord { record_ref: RecordRef },
+    /// Load the displayed version of the opened record again, without drawing attention to the
+    /// completion if `quiet`.
+    Reload { quiet: bool },
+    /// Load another version of the opened record, `None` for the latest one.
+    OpenVersion { version: Option<u64> },
+    /// Load the latest version of the opened record if it is newer than the displayed one,
+    /// producing a `ComponentMessage::NewVersionChecked` instead.
+    CheckForNewVersion,
+}
+
+/// Whether the sub-record named in the record name field exists, probed in the background while
+/// the name is typed.
+#[derive(Debug)]
+struct Probe {
+    record_ref: RecordRef,
+    /// Cancelled once the name changes.
+    cancellation: CancellationToken,
+    /// `None` until probed, or if probing failed.
+    exists: Option<bool>,
+}
+
 #[derive(Debug)]
 struct PaneOpen {
     id: ComponentId,
@@ -432,95 +2482,530 @@ struct PaneOpen {
     main_state: Rc<RefCell<MainState>>,
     record_name_field: InputField,
     encoding_radio_array: RadioArray<Encoding>,
+    busy_policy: BusyPolicy,
+    lossy_names: LossyNameDisplay,
+    /// Whether an operation is in progress.
+    busy: bool,
+    /// The operation to start once the current one finishes.
+    queued_operation: Option<PaneOpenOperation>,
+    /// Cancels the tasks of the pane, a child of the token of the application.
+    tasks_cancellation: CancellationToken,
+    /// Cancels the operation in progress, a child of `tasks_cancellation`.
+    cancellation: CancellationToken,
+    progress: Option<OpenProgress>,
+    probe: Option<Probe>,
 }
 
 impl PaneOpen {
     pub fn new(
         id: ComponentId,
         action_tx: &UnboundedSender<Action>,
+        cancellation: &CancellationToken,
         main_state: &Rc<RefCell<MainState>>,
+        config: &Config,
     ) -> Result<Self> {
+        let tasks_cancellation = cancellation.child_token();
+
         Ok(Self {
             id,
             action_tx: action_tx.clone(),
             main_state: main_state.clone(),
-            record_name_field: InputField::new(ComponentId::new(), action_tx),
+            record_name_field: InputField::new(ComponentId::new(), action_tx)
+                .with_word_boundaries(config.editing.word_boundaries())
+                .with_clear_on_escape()
+                .with_label("Record Name"),
             encoding_radio_array: RadioArray::new(
                 ComponentId::new(),
                 action_tx,
-                vec![Encoding::Utf8, Encoding::Hex],
-                &Encoding::Utf8,
+                Encoding::all().collect(),
+                &config.open.default_encoding,
                 Direction::Horizontal,
-            ),
+            )
+            .with_label("Encoding"),
+            busy_policy: config.open.busy_policy,
+            lossy_names: config.display.lossy_names,
+            busy: false,
+            queued_operation: None,
+            cancellation: tasks_cancellation.child_token(),
+            tasks_cancellation,
+            progress: None,
+            probe: None,
         })
     }
 
-    fn get_record_name(&self) -> RecordName {
-        match self.encoding_radio_array.get_checked() {
-            Encoding::Utf8 => BytesOrAscii(self.record_name_field.get_content().as_bytes().into()),
-            Encoding::Hex => todo!(),
+    fn is_stacked(width: u16) -> bool {
+        width < STACKED_BOTTOM_MAX_WIDTH
+    }
+
+    /// The height of the pane, title included, when drawn with the given width.
+    fn height(width: u16) -> u16 {
+        if Self::is_stacked(width) {
+            5
+        } else {
+            3
+        }
+    }
+
+    /// The names of the path in the record name field, see `parse_relative_path`.
+    fn get_record_names(&self) -> Result<Vec<Vec<u8>>, InvalidPathName> {
+        parse_relative_path(
+            self.record_name_field.get_content(),
+            *self.encoding_radio_array.get_checked(),
+        )
+    }
+
+    /// The operation opening the descendant at the path in the record name field.
+    fn get_open_sub_record_operation(&self) -> Result<PaneOpenOperation, InvalidPathName> {
+        let record_names = self.get_record_names()?;
+        let encoding = *self.encoding_radio_array.get_checked();
+        let descendant = |record_ref: &RecordRef| {
+            record_names
+                .into_iter()
+                .fold(record_ref.clone(), |record_ref, record_name| {
+                    record_ref.child(record_name, encoding)
+                })
+        };
+
+        Ok(
+            if let Some(opened_record) = self.main_state.borrow().opened_record.as_ref() {
+                PaneOpenOperation::OpenSubRecord {
+                    record_ref: descendant(&opened_record.record_ref),
+                }
+            } else {
+                PaneOpenOperation::OpenRecord {
+                    record_ref: descendant(&RecordRef::root()),
+                }
+            },
+        )
+    }
+
+    /// Starts probing whether the sub-record named in the record name field exists, if the name
+    /// changed since the last probe, whose work is cancelled.
+    fn probe_sub_record(&mut self) -> Option<Deferred> {
+        let record_ref = match self.get_open_sub_record_operation() {
+            Ok(PaneOpenOperation::OpenSubRecord { record_ref })
+                if !self.record_name_field.get_content().is_empty() =>
+            {
+                Some(record_ref)
+            }
+            _ => None,
+        };
+
+        if self.probe.as_ref().map(|probe| &probe.record_ref) == record_ref.as_ref() {
+            return None;
+        }
+
+        if let Some(probe) = self.probe.take() {
+            probe.cancellation.cancel();
+        }
+
+        let record_ref = record_ref?;
+        // Probed once the registry is opened.
+        let registry = self.main_state.borrow().registry().ok()?;
+        let cancellation = self.tasks_cancellation.child_token();
+        let main_state = self.main_state.borrow().clone();
+        self.probe = Some(Probe {
+            record_ref: record_ref.clone(),
+            cancellation: cancellation.clone(),
+            exists: None,
+        });
+
+        let future = {
+            let cancellation = cancellation.clone();
+            async move {
+                let exists = match main_state.resolve_from_opened_record(&record_ref).await {
+                    Ok(from) => {
+                        crate::open_pipeline::exists(&registry, &record_ref, from, &cancellation)
+                            .await
+                            .inspect_err(|error| debug!(%error, "Failed to probe the sub-record."))
+                            .ok()
+                    }
+                    Err(error) => {
+                        debug!(%error, "Failed to derive the succession nonce.");
+                        None
+                    }
+                };
+
+                ComponentMessage::SubRecordProbed { record_ref, exists }
+            }
+        };
+
+        Some(Deferred::new(self.id, future).with_cancellation(&cancellation))
+    }
+
+    /// Starts the operation, unless another one is still in progress, in which case it is queued
+    /// or rejected, depending on the configured `BusyPolicy`.
+    fn start_operation(&mut self, operation: PaneOpenOperation) {
+        if self.busy {
+            match self.busy_policy {
+                BusyPolicy::Queue => {
+                    debug!(
+                        ?operation,
+                        "Queued an operation until the current one finishes."
+                    );
+                    self.queued_operation = Some(operation);
+                }
+                BusyPolicy::Reject => {
+                    warn!(
+                        ?operation,
+                        "Rejected an operation, another one is in progress."
+                    );
+                }
+            }
+
+            return;
+        }
+
+        match operation {
+            PaneOpenOperation::OpenRecord { record_ref } => self.spawn_open_record_task(record_ref),
+            PaneOpenOperation::OpenDeepestRecord { record_ref } => {
+                self.spawn_open_deepest_record_task(record_ref)
+            }
+            PaneOpenOperation::OpenSubRecord { record_ref } => {
+                self.spawn_open_sub_record_task(record_ref)
+            }
+            PaneOpenOperation::Reload { quiet } => {
+                if !self.spawn_reload_record_task(quiet) {
+                    return;
+                }
+            }
+            PaneOpenOperation::OpenVersion { version } => {
+                if !self.spawn_open_version_task(version) {
+                    return;
+                }
+            }
+            PaneOpenOperation::CheckForNewVersion => {
+                if !self.spawn_check_for_new_version_task() {
+                    return;
+                }
+            }
+        }
+
+        self.busy = true;
+    }
+
+    /// Marks the current operation as finished and starts the queued one, if any.
+    fn finish_operation(&mut self) {
+        self.busy = false;
+        self.progress = None;
+
+        if let Some(operation) = self.queued_operation.take() {
+            self.start_operation(operation);
+        }
+    }
+
+    /// Runs an operation producing a message to broadcast, or `ComponentMessage::ShowError` if
+    /// the operation fails. Operations are shown as pending with the label, unless `quiet`, whose
+    /// completions do not draw attention either. Only operations shown as pending tag their
+    /// errors with an `OperationId`, the quiet ones are not started by the user.
+    fn spawn_operation(
+        &self,
+        span: tracing::Span,
+        label: &'static str,
+        quiet: bool,
+        operation: impl Future<Output = Result<ComponentMessage>> + Send + 'static,
+    ) {
+        let action_tx = self.action_tx.clone();
+        let task = move |operation_id: Option<OperationId>| async move {
+            let (message, feedback) = match operation.await {
+                Ok(message) => (message, Feedback::Completion),
+                Err(error) => {
+                    error!(?error, "Operation failed.");
+                    let error = format!("{error:#}");
+                    let message = ComponentMessage::ShowError {
+                        error: match operation_id {
+                            Some(operation_id) => operation_id.tag_error(error),
+                            None => error,
+                        },
+                    };
+                    (message, Feedback::Error)
+                }
+            };
+
+            // Sending only fails once the application quits.
+            let _ = action_tx.send(Action::BroadcastMessage(message));
+
+            if !quiet || feedback == Feedback::Error {
+                let _ = action_tx.send(Action::Feedback(feedback));
+            }
+        };
+
+        // Spawned with the token of the pane rather than of the operation, so that a cancelled
+        // operation still reports its cancellation.
+        if quiet {
+            crate::tasks::spawn(&self.tasks_cancellation, task(None).instrument(span));
+        } else {
+            crate::tasks::spawn_tracked(
+                &self.tasks_cancellation,
+                &self.action_tx,
+                label,
+                span,
+                |operation_id| task(Some(operation_id)),
+            );
         }
     }
 
-    fn spawn_open_record_task(&mut self) {
-        let record_name = self.get_record_name();
-        self.spawn_open_record_task_with_record_name(record_name);
+    /// Runs the open pipeline for the record, producing a `ComponentMessage::RecordOpen`.
+    fn spawn_open_task(
+        &mut self,
+        span: tracing::Span,
+        label: &'static str,
+        record_ref: RecordRef,
+        from: impl Future<Output = Result<ResolveFrom>> + Send + 'static,
+        quiet: bool,
+    ) {
+        let registry = self.main_state.borrow().registry();
+        let action_tx = self.action_tx.clone();
+        self.cancellation = self.tasks_cancellation.child_token();
+        let cancellation = self.cancellation.clone();
+        self.spawn_operation(span, label, quiet, async move {
+            let registry = registry?;
+            let from = from.await?;
+            let mut progress = |progress| {
+                let _ = action_tx.send(Action::BroadcastMessage(
+                    ComponentMessage::RecordOpenProgress { progress },
+                ));
+            };
+            let result = crate::open_pipeline::open(
+                &registry,
+                &record_ref,
+                from,
+                &mut progress,
+                &cancellation,
+            )
+            .await;
+
+            match result {
+                Ok(loaded_record) => Ok(ComponentMessage::RecordOpen {
+                    record_ref,
+                    hashed_record_key: loaded_record.hashed_record_key,
+                    read_result: Some(loaded_record.record),
+                    versions: loaded_record.versions,
+                }),
+                Err(OpenError::NotFound { hashed_record_key }) => {
+                    Ok(ComponentMessage::RecordOpen {
+                        record_ref,
+                        hashed_record_key,
+                        read_result: None,
+                        versions: Vec::new(),
+                    })
+                }
+                Err(error) => Err(error.into()),
+            }
+        });
     }
 
-    fn spawn_open_record_task_with_record_name(&mut self, record_name: RecordName) {
-        // The main state is being cloned just because `MainState::get_current_succession_nonce`
+    fn spawn_open_sub_record_task(&mut self, record_ref: RecordRef) {
+        // The main state is being cloned just because `MainState::resolve_from_opened_record`
         // is an async function that needs to be awaited from within an async block.
         // If this function ever becomes async, it should be moved up out of the async task.
         let main_state_clone = self.main_state.borrow().clone();
+        let record_ref_clone = record_ref.clone();
+        self.spawn_open_task(
+            info_span!("open record task"),
+            "Opening the sub-record",
+            record_ref,
+            async move {
+                main_state_clone
+                    .resolve_from_opened_record(&record_ref_clone)
+                    .await
+            },
+            false,
+        );
+    }
+
+    /// Opens the record by resolving its path starting from the root record, rather than the
+    /// currently opened one.
+    fn spawn_open_record_task(&mut self, record_ref: RecordRef) {
+        self.spawn_open_task(
+            info_span!("open record path task"),
+            "Opening the record",
+            record_ref,
+            std::future::ready(Ok(ResolveFrom::Root)),
+            false,
+        );
+    }
+
+    /// Opens the records along the path in turn, like opening each sub-record manually, and
+    /// reports the name of the path which could not be opened, if any.
+    fn spawn_open_deepest_record_task(&mut self, record_ref: RecordRef) {
+        let registry = self.main_state.borrow().registry();
+        let lossy_names = self.lossy_names;
         let action_tx = self.action_tx.clone();
-        tokio::spawn(
+        self.cancellation = self.tasks_cancellation.child_token();
+        let cancellation = self.cancellation.clone();
+        self.spawn_operation(
+            info_span!("open deepest record task"),
+            "Opening the record",
+            false,
             async move {
-                let registry = &*main_state_clone.registry;
-                let current_succession_nonce =
-                    main_state_clone.get_current_succession_nonce().await;
-                let record_key = RecordKey {
-                    predecessor_nonce: current_succession_nonce,
-                    record_name,
+                let registry = registry?;
+                let mut progress = |progress| {
+                    let _ = action_tx.send(Action::BroadcastMessage(
+                        ComponentMessage::RecordOpenProgress { progress },
+                    ));
                 };
-                // TODO: Handle errors by displaying an error message
-                let (hashed_record_key, read_result) =
-                    Self::open_record(record_key, registry).await.unwrap();
+                let deepest = crate::open_pipeline::open_deepest(
+                    &registry,
+                    &record_ref,
+                    &mut progress,
+                    &cancellation,
+                )
+                .await?;
+
+                if let Some((index, error)) = deepest.failure {
+                    let failed_record_ref = RecordRef::root()
+                        .child(record_ref.path()[index].clone(), record_ref.encoding_hint);
+                    let _ =
+                        action_tx.send(Action::BroadcastMessage(ComponentMessage::ShowNotice {
+                            message: format!(
+                                "Failed to open {:?}, name {} of {} of the path: {error} \
+                                 Opened {} instead.",
+                                failed_record_ref.display_name(lossy_names),
+                                index + 1,
+                                record_ref.path().len(),
+                                deepest.record_ref.display_path(lossy_names),
+                            ),
+                            severity: Severity::Error,
+                        }));
+                }
 
-                debug!(?read_result, "Sending read result.");
+                Ok(ComponentMessage::RecordOpen {
+                    record_ref: deepest.record_ref,
+                    hashed_record_key: deepest.loaded_record.hashed_record_key,
+                    read_result: Some(deepest.loaded_record.record),
+                    versions: deepest.loaded_record.versions,
+                })
+            },
+        );
+    }
 
-                action_tx
-                    .send(Action::BroadcastMessage(ComponentMessage::RecordOpen {
-                        hashed_record_key,
-                        read_result,
-                    }))
-                    .unwrap();
-            }
-            .instrument(info_span!("open record task")),
+    /// Loads the displayed version of the opened record again, which is the latest one unless a
+    /// specific version was opened. Returns `false` if no record is opened.
+    fn spawn_reload_record_task(&mut self, quiet: bool) -> bool {
+        let main_state = self.main_state.borrow();
+        let Some(opened_record) = main_state.opened_record.as_ref() else {
+            return false;
+        };
+        let record_ref = opened_record.record_ref.clone();
+        let hashed_record_key = opened_record.hashed_record_key.clone();
+        drop(main_state);
+        self.spawn_open_task(
+            info_span!("reload record task"),
+            "Reloading the record",
+            record_ref,
+            std::future::ready(Ok(ResolveFrom::Resolved(hashed_record_key))),
+            quiet,
+        );
+        true
+    }
+
+    /// Loads another version of the opened record, whose key is already known.
+    /// Returns `false` if no record is opened.
+    fn spawn_open_version_task(&mut self, version: Option<u64>) -> bool {
+        let main_state = self.main_state.borrow();
+        let Some(opened_record) = main_state.opened_record.as_ref() else {
+            return false;
+        };
+        let record_ref = opened_record.record_ref.at_version(version);
+        let hashed_record_key = opened_record.hashed_record_key.clone();
+        drop(main_state);
+        self.spawn_open_task(
+            info_span!("open record version task"),
+            "Loading the version",
+            record_ref,
+            std::future::ready(Ok(ResolveFrom::Resolved(hashed_record_key))),
+            false,
         );
+        true
     }
 
-    async fn open_record(
-        record_key: RecordKey,
-        registry: &Registry<ReadLock>,
-    ) -> Result<(HashedRecordKey, Option<RecordReadVersionSuccess>)> {
-        let hashed_record_key = record_key.hash(&registry.config.hash).await?;
-        let versions = registry
-            .list_record_versions(&hashed_record_key, 4, 4)
-            .await?;
-        let Some(latest_version) = versions.last() else {
-            return Ok((hashed_record_key, None));
+    fn spawn_check_for_new_version_task(&mut self) -> bool {
+        let main_state = self.main_state.borrow();
+        let Some(opened_record) = main_state.opened_record.as_ref() else {
+            return false;
         };
-        let record = registry
-            .load_record(&hashed_record_key, latest_version.record_version, 4)
-            .await?
-            .ok_or_else(|| eyre!("Failed to load the latest root record version."))?;
-        Ok((hashed_record_key, Some(record)))
+        let hashed_record_key = opened_record.hashed_record_key.clone();
+        let versions = opened_record.versions.clone();
+        let Ok(registry) = main_state.registry() else {
+            return false;
+        };
+        drop(main_state);
+        self.cancellation = self.tasks_cancellation.child_token();
+        let cancellation = self.cancellation.clone();
+        self.spawn_operation(
+            info_span!("check for new version task"),
+            "Checking for a new version",
+            true,
+            async move {
+                let newer = crate::open_pipeline::open_if_newer(
+                    &registry,
+                    &hashed_record_key,
+                    versions.len(),
+                    &mut |_| (),
+                    &cancellation,
+                )
+                .await?;
+
+                Ok(match newer {
+                    Some(loaded_record) => ComponentMessage::NewVersionChecked {
+                        hashed_record_key,
+                        read_result: Some(loaded_record.record),
+                        versions: loaded_record.versions,
+                    },
+                    None => ComponentMessage::NewVersionChecked {
+                        hashed_record_key,
+                        read_result: None,
+                        versions,
+                    },
+                })
+            },
+        );
+        true
     }
 }
 
 impl Component for PaneOpen {
+    fn get_panel(&self) -> Option<Panel> {
+        Some(Panel::Open)
+    }
+
     fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
-        Ok(None)
+        match message {
+            ComponentMessage::OpenRecordPath { path } => {
+                self.start_operation(PaneOpenOperation::OpenRecord {
+                    record_ref: RecordRef::from_utf8_path(&path),
+                });
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::OpenRecord { record_ref } => {
+                self.start_operation(PaneOpenOperation::OpenRecord { record_ref });
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::OpenRecordVersion { version } => {
+                self.start_operation(PaneOpenOperation::OpenVersion { version });
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::RecordOpenProgress { progress } if self.busy => {
+                self.progress = Some(progress);
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::RecordOpen { .. }
+            | ComponentMessage::NewVersionChecked { .. }
+            | ComponentMessage::ShowError { .. } => {
+                self.finish_operation();
+                Ok(Some(Action::Render))
+            }
+            ComponentMessage::OnTick => Ok(self.probe_sub_record().map(Action::Defer)),
+            ComponentMessage::SubRecordProbed { record_ref, exists } => match self.probe.as_mut() {
+                Some(probe) if probe.record_ref == record_ref => {
+                    probe.exists = exists;
+                    Ok(Some(Action::Render))
+                }
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
     }
 
     fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
@@ -531,8 +3016,33 @@ impl Component for PaneOpen {
                 modifiers: KeyModifiers::NONE,
                 ..
             }) => {
-                self.spawn_open_record_task();
-                Ok(HandleEventSuccess::handled().with_action(Action::Render))
+                // The name is kept on failure, so that it can be corrected.
+                match self.get_open_sub_record_operation() {
+                    Ok(operation) => {
+                        self.start_operation(operation);
+                        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+                    }
+                    Err(error) => {
+                        warn!(%error, "Invalid record name.");
+                        Ok(
+                            HandleEventSuccess::handled().with_action(Action::BroadcastMessage(
+                                ComponentMessage::ShowNotice {
+                                    message: format!("Invalid record name: {error}."),
+                                    severity: Severity::Info,
+                                },
+                            )),
+                        )
+                    }
+                }
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) if self.busy => {
+                self.queued_operation = None;
+                self.cancellation.cancel();
+                Ok(HandleEventSuccess::handled())
             }
             _ => Ok(HandleEventSuccess::unhandled()),
         }
@@ -546,13 +3056,36 @@ impl Component for PaneOpen {
         vec![&self.record_name_field, &self.encoding_radio_array]
     }
 
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Region);
+        node.set_label("Open Sub-Record");
+
+        if self.busy {
+            node.set_busy();
+        }
+
+        Ok(node)
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.open_pane = Some(crate::introspection::OpenPaneState {
+            record_name: self.record_name_field.get_content().to_string(),
+            encoding: self.encoding_radio_array.get_checked().to_string(),
+            busy: self.busy,
+            progress: self.progress.map(|progress| progress.to_string()),
+            queued: self.queued_operation.is_some(),
+        });
+    }
+
     fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
         vec![&mut self.record_name_field, &mut self.encoding_radio_array]
     }
 }
 
 struct PaneOpenArgs {
-    title_offset_x: u16,
+    /// The row of the title, above the area of the fields.
+    area_title: Rect,
 }
 
 impl Drawable for PaneOpen {
@@ -567,36 +3100,855 @@ impl Drawable for PaneOpen {
         area: Rect,
         extra_args: Self::Args<'_>,
     ) -> Result<()> {
-        let (area_title, area_content) = MainView::pane_areas(area, extra_args.title_offset_x);
+        let PaneOpenArgs { area_title } = extra_args;
+        context.name_area("bottom title", area_title);
+        let invalid_name = self.get_record_names().err();
 
-        context
-            .frame()
-            .render_widget(Span::raw("Open Sub-Record [Enter]"), area_title);
+        MainView::draw_title(
+            context,
+            &match (self.busy, self.progress) {
+                (true, Some(progress)) => {
+                    format!("Open Sub-Record [Enter] (opening, {progress}… [Esc] to cancel)")
+                }
+                (true, None) => "Open Sub-Record [Enter] (opening… [Esc] to cancel)".to_string(),
+                (false, _) => match (
+                    invalid_name.as_ref(),
+                    self.probe.as_ref().and_then(|probe| probe.exists),
+                ) {
+                    (Some(invalid_name), _) => {
+                        format!("Open Sub-Record [Enter] ({})", invalid_name.error.message)
+                    }
+                    (None, Some(true)) => "Open Sub-Record [Enter] (exists)".to_string(),
+                    (None, Some(false)) => "Open Sub-Record [Enter] (not found)".to_string(),
+                    (None, None) => "Open Sub-Record [Enter]".to_string(),
+                },
+            },
+            area_title,
+        );
 
-        let layout_bottom_lines = Layout::default()
-            .direction(Direction::Horizontal)
-            .spacing(1)
-            .constraints([Constraint::Length(11), Constraint::Fill(1)]);
-        let [area_record_name, area_encoding] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Length(1)])
-            .areas(area_content);
-        let [area_record_name_label, area_record_name_field] =
-            layout_bottom_lines.areas(area_record_name);
-        let [area_encoding_label, area_encoding_field] = layout_bottom_lines.areas(area_encoding);
+        let stacked = Self::is_stacked(area.width);
+        let [area_record_name_label, area_record_name_field, area_encoding_label, area_encoding_field] =
+            if stacked {
+                Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1); 4])
+                    .areas(area)
+            } else {
+                let layout_bottom_lines = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .spacing(1)
+                    .constraints([Constraint::Length(11), Constraint::Fill(1)]);
+                let [area_record_name, area_encoding] = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Length(1)])
+                    .areas(area);
+                let [area_record_name_label, area_record_name_field] =
+                    layout_bottom_lines.areas(area_record_name);
+                let [area_encoding_label, area_encoding_field] =
+                    layout_bottom_lines.areas(area_encoding);
+                [
+                    area_record_name_label,
+                    area_record_name_field,
+                    area_encoding_label,
+                    area_encoding_field,
+                ]
+            };
+
+        context.render_named_widget(
+            "record name label",
+            Span::raw("Record Name"),
+            area_record_name_label,
+        );
+        context.name_area("record name field", area_record_name_field);
+        let encoding = self.encoding_radio_array.get_checked();
+        let record_name = self.record_name_field.get_content();
+        self.record_name_field.draw(
+            context,
+            area_record_name_field,
+            InputFieldArgs {
+                length_counter: Some(LengthCounter {
+                    label: encoding.describe_length(record_name),
+                    valid: invalid_name.is_none(),
+                }),
+                invalid_range: invalid_name.map(|invalid_name| invalid_name.range),
+            },
+        )?;
+        context.render_named_widget("encoding label", Span::raw("Encoding"), area_encoding_label);
+        context.name_area("encoding field", area_encoding_field);
+        self.encoding_radio_array.draw(
+            context,
+            area_encoding_field,
+            RadioArrayArgs { compact: stacked },
+        )?;
 
-        context
-            .frame()
-            .render_widget(Span::raw("Record Name"), area_record_name_label);
-        self.record_name_field
-            .draw(context, area_record_name_field, ())
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use crate::component::{self, ComponentIdPath, Layer};
+    use crate::introspection::UiState;
+
+    use super::*;
+
+    /// Renders the spacer over the lines, returning the resulting buffer.
+    fn render_spacer(lines: &[&str], direction: Direction, area: Rect) -> Buffer {
+        let mut buffer = Buffer::with_lines(lines.iter().copied());
+        LineSpacer::new(direction).render(area, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn line_spacer_joins_the_lines_it_starts_and_ends_on() {
+        let lines = ["┌───┐", "│   │", "└───┘"];
+
+        assert_eq!(
+            render_spacer(&lines, Direction::Vertical, Rect::new(2, 0, 1, 3)),
+            Buffer::with_lines(["┌─┬─┐", "│ │ │", "└─┴─┘"])
+        );
+        assert_eq!(
+            render_spacer(&lines, Direction::Horizontal, Rect::new(0, 1, 5, 1)),
+            Buffer::with_lines(["┌───┐", "├───┤", "└───┘"])
+        );
+    }
+
+    #[test]
+    fn line_spacer_crosses_the_lines_it_passes_through() {
+        let lines = ["┌─┬─┐", "│ │ │", "└─┴─┘"];
+
+        assert_eq!(
+            render_spacer(&lines, Direction::Horizontal, Rect::new(0, 1, 5, 1)),
+            Buffer::with_lines(["┌─┬─┐", "├─┼─┤", "└─┴─┘"])
+        );
+    }
+
+    #[test]
+    fn line_spacer_joins_rounded_corners() {
+        assert_eq!(
+            render_spacer(
+                &["╭───╮  ", "│   │  ", "╰───╯  "],
+                Direction::Horizontal,
+                Rect::new(4, 2, 3, 1)
+            ),
+            Buffer::with_lines(["╭───╮  ", "│   │  ", "╰───┴──"])
+        );
+        assert_eq!(
+            render_spacer(
+                &["   ", "╭──", "│  "],
+                Direction::Vertical,
+                Rect::new(0, 0, 1, 3)
+            ),
+            Buffer::with_lines(["│  ", "├──", "│  "])
+        );
+        assert_eq!(
+            render_spacer(
+                &["╭───╮", "│   │", "╰───╯"],
+                Direction::Vertical,
+                Rect::new(0, 0, 1, 3)
+            ),
+            Buffer::with_lines(["┌───╮", "│   │", "└───╯"])
+        );
+    }
+
+    #[test]
+    fn line_spacer_overwrites_other_characters() {
+        assert_eq!(
+            render_spacer(&["abc"], Direction::Horizontal, Rect::new(0, 0, 3, 1)),
+            Buffer::with_lines(["───"])
+        );
+        assert_eq!(
+            render_spacer(&["a", "b"], Direction::Vertical, Rect::new(0, 0, 1, 2)),
+            Buffer::with_lines(["│", "│"])
+        );
+    }
+
+    #[test]
+    fn version_diff_marks_the_changed_lines() {
+        assert_eq!(
+            version_diff(b"one\ntwo\nthree\n", b"one\n2\nthree\nfour\n"),
+            "  one\n- two\n+ 2\n  three\n+ four\n"
+        );
+        assert_eq!(version_diff(b"same", b"same"), "  same\n");
+    }
+
+    #[test]
+    fn version_diff_of_invalid_utf8_does_not_fail() {
+        assert_eq!(version_diff(b"\xff\n", b"\xff\nok\n"), "  \u{fffd}\n+ ok\n");
+    }
+
+    #[test]
+    fn newer_version_banner_offers_both_actions() {
+        assert_eq!(
+            newer_version_banner(7),
+            " newer version available (v7), press u to update, d to diff "
+        );
+    }
+
+    /// An open pane of a registry still being opened, on which every operation fails promptly,
+    /// together with the receiver of its actions.
+    fn busy_pane(
+        busy_policy: BusyPolicy,
+    ) -> (PaneOpen, tokio::sync::mpsc::UnboundedReceiver<Action>) {
+        let (action_tx, action_rx) = tokio::sync::mpsc::unbounded_channel();
+        let main_state = Rc::new(RefCell::new(MainState {
+            registry: RegistryState::Opening,
+            opened_record: None,
+        }));
+        let mut config = Config::default();
+        config.open.busy_policy = busy_policy;
+        let pane = PaneOpen::new(
+            ComponentId::new(),
+            &action_tx,
+            &CancellationToken::new(),
+            &main_state,
+            &config,
+        )
+        .unwrap();
+
+        (pane, action_rx)
+    }
+
+    fn open_record(path: &str) -> ComponentMessage {
+        ComponentMessage::OpenRecord {
+            record_ref: RecordRef::from_utf8_path(path),
+        }
+    }
+
+    /// Waits for the result of an operation, which is delivered to the pane the way the main
+    /// view broadcasts it. Returns the number of tasks started in the meantime.
+    async fn deliver_result(
+        pane: &mut PaneOpen,
+        action_rx: &mut tokio::sync::mpsc::UnboundedReceiver<Action>,
+    ) -> usize {
+        let mut tasks_started = 0;
+
+        loop {
+            let action = tokio::time::timeout(Duration::from_secs(5), action_rx.recv())
+                .await
+                .expect("the operation did not finish")
+                .unwrap();
+
+            match action {
+                Action::BroadcastMessage(ComponentMessage::TaskStarted { .. }) => {
+                    tasks_started += 1;
+                }
+                Action::BroadcastMessage(message @ ComponentMessage::ShowError { .. }) => {
+                    pane.update(message).unwrap();
+                    return tasks_started;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn starts_another_task(
+        action_rx: &mut tokio::sync::mpsc::UnboundedReceiver<Action>,
+    ) -> bool {
+        tokio::task::yield_now().await;
+        std::iter::from_fn(|| action_rx.try_recv().ok()).any(|action| {
+            matches!(
+                action,
+                Action::BroadcastMessage(ComponentMessage::TaskStarted { .. })
+            )
+        })
+    }
+
+    #[tokio::test]
+    async fn operations_requested_while_busy_are_queued_one_deep() {
+        let (mut pane, mut action_rx) = busy_pane(BusyPolicy::Queue);
+
+        pane.update(open_record("a")).unwrap();
+        pane.update(open_record("b")).unwrap();
+        pane.update(open_record("c")).unwrap();
+        assert!(pane.busy);
+        // The last request replaces the queued one.
+        assert!(matches!(
+            &pane.queued_operation,
+            Some(PaneOpenOperation::OpenRecord { record_ref })
+                if *record_ref == RecordRef::from_utf8_path("c")
+        ));
+
+        // The queued operation starts once the first one finishes, and not before.
+        assert_eq!(deliver_result(&mut pane, &mut action_rx).await, 1);
+        assert!(pane.busy);
+        assert!(pane.queued_operation.is_none());
+
+        assert_eq!(deliver_result(&mut pane, &mut action_rx).await, 1);
+        assert!(!pane.busy);
+        assert!(!starts_another_task(&mut action_rx).await);
+    }
+
+    #[tokio::test]
+    async fn operations_requested_while_busy_are_rejected() {
+        let (mut pane, mut action_rx) = busy_pane(BusyPolicy::Reject);
+
+        pane.update(open_record("a")).unwrap();
+        pane.update(open_record("b")).unwrap();
+        assert!(pane.busy);
+        assert!(pane.queued_operation.is_none());
+
+        assert_eq!(deliver_result(&mut pane, &mut action_rx).await, 1);
+        assert!(!pane.busy);
+
+        // Requests are accepted again once the guard is released.
+        pane.update(open_record("b")).unwrap();
+        assert!(pane.busy);
+        assert_eq!(deliver_result(&mut pane, &mut action_rx).await, 1);
+        assert!(!pane.busy);
+    }
+
+    #[tokio::test]
+    async fn escape_while_busy_drops_the_queued_operation() {
+        let (mut pane, mut action_rx) = busy_pane(BusyPolicy::Queue);
+
+        pane.update(open_record("a")).unwrap();
+        pane.update(open_record("b")).unwrap();
+        let escape = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(pane.handle_event(&escape).unwrap().absorb);
+        assert!(pane.queued_operation.is_none());
+        assert!(pane.cancellation.is_cancelled());
+
+        // The cancelled operation still reports back, releasing the guard without starting
+        // the dropped one.
+        assert_eq!(deliver_result(&mut pane, &mut action_rx).await, 1);
+        assert!(!pane.busy);
+        assert!(!starts_another_task(&mut action_rx).await);
+    }
+
+    /// Draws the open pane with a record name where the main view lays it out in a terminal of
+    /// the width, returning the rows of the pane with their styles reset.
+    fn draw_bottom_pane(width: u16, height: u16) -> Buffer {
+        use ratatui::backend::TestBackend;
+
+        let (mut pane, _action_rx) = busy_pane(BusyPolicy::Queue);
+        pane.record_name_field.set_content("a/b");
+        let layout = MainView::layout(Rect::new(0, 0, width, height)).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| {
+                let palette = Config::default().display.theme.palette(u16::MAX);
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                pane.draw(
+                    &mut context,
+                    layout.bottom.content,
+                    PaneOpenArgs {
+                        area_title: layout.bottom.title,
+                    },
+                )
+                .unwrap();
+            })
             .unwrap();
-        context
-            .frame()
-            .render_widget(Span::raw("Encoding"), area_encoding_label);
-        self.encoding_radio_array
-            .draw(context, area_encoding_field, ())?;
 
-        Ok(())
+        // The pane is right above the footer.
+        let area_bottom = layout.bottom.area;
+        assert_eq!(area_bottom.bottom(), height - 1);
+        let mut buffer = Buffer::empty(Rect::new(0, 0, width, area_bottom.height));
+        for Position { x, y } in buffer.area.positions() {
+            buffer[(x, y)] = terminal.backend().buffer()[(x, y + area_bottom.y)].clone();
+        }
+        buffer.set_style(buffer.area, Style::reset());
+        buffer
+    }
+
+    #[test]
+    fn bottom_pane_stacks_its_labels_at_40_columns() {
+        assert_eq!(
+            draw_bottom_pane(40, 30),
+            Buffer::with_lines([
+                "Open Sub-Record [Enter]                 ",
+                "Record Name                             ",
+                "a/b                                     ",
+                "Encoding                                ",
+                "(x) UTF-8 [Space] to cycle              ",
+            ])
+        );
+    }
+
+    #[test]
+    fn bottom_pane_places_its_labels_beside_the_fields_at_80_columns() {
+        assert_eq!(
+            draw_bottom_pane(80, 30),
+            Buffer::with_lines([
+                "                 Open Sub-Record [Enter]                                        ",
+                "Record Name a/b                                                                 ",
+                "Encoding    (x) UTF-8  ( ) Hexadecimal Byte String                              ",
+            ])
+        );
+    }
+
+    #[test]
+    fn bottom_pane_grows_below_the_stacking_width() {
+        let bottom_height = |width| {
+            MainView::layout(Rect::new(0, 0, width, 30))
+                .unwrap()
+                .bottom
+                .area
+                .height
+        };
+
+        assert_eq!(bottom_height(STACKED_BOTTOM_MAX_WIDTH - 1), 5);
+        assert_eq!(bottom_height(STACKED_BOTTOM_MAX_WIDTH), 3);
+        // The interface needs the room for the taller pane first.
+        assert_eq!(
+            MainView::min_height(STACKED_BOTTOM_MAX_WIDTH - 1),
+            MainView::min_height(STACKED_BOTTOM_MAX_WIDTH) + 2
+        );
+    }
+
+    #[tokio::test]
+    async fn editing_the_name_cancels_its_probe() {
+        let (mut pane, _action_rx) = busy_pane(BusyPolicy::Queue);
+        let record_ref = RecordRef::from_utf8_path("a");
+        let cancellation = pane.tasks_cancellation.child_token();
+        pane.record_name_field.set_content("a");
+        pane.probe = Some(Probe {
+            record_ref: record_ref.clone(),
+            cancellation: cancellation.clone(),
+            exists: None,
+        });
+
+        pane.record_name_field.set_content("ab");
+        pane.update(ComponentMessage::OnTick).unwrap();
+
+        assert!(cancellation.is_cancelled());
+        assert!(pane.probe.is_none());
+        assert!(!pane.tasks_cancellation.is_cancelled());
+        // A result of the cancelled probe arriving anyway is ignored.
+        assert_eq!(
+            pane.update(ComponentMessage::SubRecordProbed {
+                record_ref,
+                exists: Some(true),
+            })
+            .unwrap(),
+            None
+        );
+    }
+
+    fn draw_too_small(width: u16, height: u16) -> Buffer {
+        use ratatui::backend::TestBackend;
+
+        let area = Rect::new(0, 0, width, height);
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| {
+                let palette = Config::default().display.theme.palette(u16::MAX);
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                MainView::draw_too_small(&mut context, area);
+            })
+            .unwrap();
+
+        let mut buffer = terminal.backend().buffer().clone();
+        buffer.set_style(area, Style::reset());
+        buffer
+    }
+
+    #[test]
+    fn interface_is_not_laid_out_in_1_to_6_rows() {
+        for width in [MIN_WIDTH, STACKED_BOTTOM_MAX_WIDTH - 1, 80, 160] {
+            for height in 1..=6 {
+                assert!(
+                    MainView::layout(Rect::new(0, 0, width, height)).is_none(),
+                    "{width}×{height}"
+                );
+            }
+
+            let min_height = MainView::min_height(width);
+            assert!(min_height > 6);
+            assert!(MainView::layout(Rect::new(0, 0, width, min_height - 1)).is_none());
+            let layout = MainView::layout(Rect::new(0, 0, width, min_height)).unwrap();
+            assert_eq!(layout.content.area.height, MIN_CONTENT_HEIGHT);
+        }
+
+        assert!(MainView::layout(Rect::new(0, 0, MIN_WIDTH - 1, 30)).is_none());
+    }
+
+    #[test]
+    fn too_small_notice_in_1_to_6_rows() {
+        assert_eq!(
+            draw_too_small(40, 1),
+            Buffer::with_lines(["Terminal too small, need at least 24×11 "])
+        );
+        assert_eq!(
+            draw_too_small(80, 2),
+            Buffer::with_lines([
+                "                     Terminal too small, need at least 24×9                     ",
+                "                                                                                ",
+            ])
+        );
+        assert_eq!(
+            draw_too_small(24, 3),
+            Buffer::with_lines([
+                "Terminal too small, need",
+                "     at least 24×11     ",
+                "                        ",
+            ])
+        );
+        assert_eq!(
+            draw_too_small(12, 4),
+            Buffer::with_lines([
+                "Terminal too",
+                "small, need ",
+                "  at least  ",
+                "   24×11    "
+            ])
+        );
+        assert_eq!(
+            draw_too_small(30, 5),
+            Buffer::with_lines([
+                "                              ",
+                " Terminal too small, need at  ",
+                "         least 24×11          ",
+                "                              ",
+                "                              ",
+            ])
+        );
+        assert_eq!(
+            draw_too_small(12, 6),
+            Buffer::with_lines([
+                "            ",
+                "Terminal too",
+                "small, need ",
+                "  at least  ",
+                "   24×11    ",
+                "            ",
+            ])
+        );
+        // The lines that do not fit are cut off.
+        assert_eq!(draw_too_small(12, 1), Buffer::with_lines(["Terminal too"]));
+    }
+
+    #[test]
+    fn too_small_notice_is_styled_as_a_warning() {
+        use ratatui::backend::TestBackend;
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+        let palette = Config::default().display.theme.palette(u16::MAX);
+        terminal
+            .draw(|frame| {
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                MainView::draw_too_small(&mut context, Rect::new(0, 0, 40, 5));
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer[(0, 2)].symbol(), "T");
+        assert_eq!(buffer[(0, 2)].fg, palette.warning_style().fg.unwrap());
+    }
+
+    /// The main view of the application started on `registry_directory`, without a registry,
+    /// which it opens in the background.
+    async fn started_main_view(
+        registry_directory: &std::path::Path,
+    ) -> (
+        MainView,
+        UnboundedSender<Action>,
+        tokio::sync::mpsc::UnboundedReceiver<Action>,
+    ) {
+        use clap::{CommandFactory, FromArgMatches};
+
+        // `-h` is taken by `--force-max-height`, see the tests of `Args`.
+        let matches = Args::command()
+            .disable_help_flag(true)
+            .try_get_matches_from([
+                "rrr-tui-3".as_ref(),
+                "--registry-directory".as_ref(),
+                registry_directory.as_os_str(),
+            ])
+            .unwrap();
+        let args = Arc::new(Args::from_arg_matches(&matches).unwrap());
+        let startup = Startup {
+            config: Arc::new(Config::default()),
+            keymap: crate::keymap::KeyMap::default(),
+            annotations: Annotations::default(),
+            registry: None,
+            registry_summary: None,
+            session_store: SessionStore::default(),
+            session_marker: None,
+            restored_session: None,
+            upgraded_from: None,
+            startup_file_diagnosis: None,
+            warnings: Vec::new(),
+        };
+        let (action_tx, action_rx) = tokio::sync::mpsc::unbounded_channel();
+        let main_view = MainView::new(
+            ComponentId::root(),
+            &action_tx,
+            &CancellationToken::new(),
+            &args,
+            startup,
+        )
+        .await
+        .unwrap();
+
+        (main_view, action_tx, action_rx)
+    }
+
+    /// Handles the actions of the main view the way the app does, until the state of the user
+    /// interface satisfies `done`, which is returned.
+    async fn run_until(
+        main_view: &mut MainView,
+        action_tx: &UnboundedSender<Action>,
+        action_rx: &mut tokio::sync::mpsc::UnboundedReceiver<Action>,
+        focus_path: &ComponentIdPath,
+        done: impl Fn(&UiState) -> bool,
+    ) -> UiState {
+        let cancellation = CancellationToken::new();
+
+        loop {
+            let ui_state = crate::introspection::snapshot(main_view, focus_path).unwrap();
+
+            if done(&ui_state) {
+                return ui_state;
+            }
+
+            let action = tokio::time::timeout(Duration::from_secs(5), action_rx.recv())
+                .await
+                .unwrap_or_else(|_| panic!("the state was not reached: {ui_state:#?}"))
+                .unwrap();
+            let (id, message) = match action {
+                Action::BroadcastMessage(message) => (None, message),
+                Action::SendMessage { id, message } => (Some(id), message),
+                Action::ShowStatus { severity, text } => (
+                    None,
+                    ComponentMessage::ShowNotice {
+                        message: text,
+                        severity,
+                    },
+                ),
+                Action::Defer(deferred) => {
+                    deferred.spawn(&cancellation, action_tx);
+                    continue;
+                }
+                _ => continue,
+            };
+            let mut actions = Vec::new();
+
+            if let Some(id) = id {
+                if let Some((component, _)) = component::find_component_by_id_mut(main_view, id) {
+                    actions.extend(component.update(message).unwrap());
+                }
+            } else {
+                let _ = component::depth_first_search_mut::<()>(
+                    main_view,
+                    &mut |component| {
+                        actions.extend(component.update(message.clone()).unwrap());
+                        ControlFlow::Continue(())
+                    },
+                    &mut |_| ControlFlow::Continue(()),
+                );
+            }
+
+            for action in actions {
+                action_tx.send(action).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn record_open_is_reflected_in_the_ui_state() {
+        let registry_directory = std::env::temp_dir().join(format!(
+            "rrr-tui-main-view-{}-record-open",
+            std::process::id()
+        ));
+        let (mut main_view, action_tx, mut action_rx) =
+            started_main_view(&registry_directory).await;
+        let record_name_field_id = main_view.pane_open.record_name_field.get_id();
+        let (_, focus_path) =
+            component::find_component_by_id_mut(&mut main_view, record_name_field_id).unwrap();
+
+        // The registry is opened in the background.
+        let ui_state = run_until(
+            &mut main_view,
+            &action_tx,
+            &mut action_rx,
+            &focus_path,
+            |ui_state| !ui_state.pending_tasks.is_empty(),
+        )
+        .await;
+        assert_eq!(
+            ui_state.pending_tasks,
+            [StartupPhase::OpenRegistry.to_string()]
+        );
+        assert_eq!(ui_state.opened_record, None);
+        assert_eq!(
+            ui_state.focus_path.last().unwrap().label.as_deref(),
+            Some("Record Name")
+        );
+
+        // There is no registry in the directory.
+        let ui_state = run_until(
+            &mut main_view,
+            &action_tx,
+            &mut action_rx,
+            &focus_path,
+            |ui_state| ui_state.pending_tasks.is_empty(),
+        )
+        .await;
+        let notice = ui_state.notice.unwrap();
+        assert_eq!(notice.severity, "Error");
+        assert!(
+            notice.message.contains("Failed to open the registry"),
+            "{notice:?}"
+        );
+
+        main_view.pane_open.record_name_field.set_content("a/b");
+        let handled = main_view
+            .pane_open
+            .handle_event(&Event::Key(KeyEvent::new(
+                KeyCode::Enter,
+                KeyModifiers::NONE,
+            )))
+            .unwrap();
+        assert!(handled.absorb);
+        let ui_state = crate::introspection::snapshot(&main_view, &focus_path).unwrap();
+        assert_eq!(
+            ui_state.open_pane,
+            Some(crate::introspection::OpenPaneState {
+                record_name: "a/b".to_string(),
+                encoding: Encoding::UTF8.to_string(),
+                busy: true,
+                progress: None,
+                queued: false,
+            })
+        );
+
+        // The record cannot be opened without the registry, and the name is kept to retry.
+        let ui_state = run_until(
+            &mut main_view,
+            &action_tx,
+            &mut action_rx,
+            &focus_path,
+            |ui_state| {
+                !ui_state.open_pane.as_ref().unwrap().busy && ui_state.pending_tasks.is_empty()
+            },
+        )
+        .await;
+        assert_eq!(ui_state.opened_record, None);
+        assert_eq!(ui_state.open_pane.unwrap().record_name, "a/b");
+        assert_eq!(ui_state.notice.unwrap().severity, "Error");
+        assert!(ui_state.failed_panels.is_empty());
+    }
+
+    /// The areas named while drawing the main view in a terminal of `width`×`height`.
+    async fn named_areas(width: u16, height: u16) -> Vec<component::NamedArea> {
+        use ratatui::backend::TestBackend;
+
+        let registry_directory = std::env::temp_dir().join(format!(
+            "rrr-tui-main-view-{}-named-areas",
+            std::process::id()
+        ));
+        let (main_view, _action_tx, _action_rx) = started_main_view(&registry_directory).await;
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let mut named_areas = Vec::new();
+        terminal
+            .draw(|frame| {
+                let palette = Config::default().display.theme.palette(u16::MAX);
+                let area = frame.area();
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                main_view.draw(&mut context, area, ()).unwrap();
+                named_areas = context.take_named_areas();
+            })
+            .unwrap();
+        named_areas
+    }
+
+    /// The pairs of areas that overlap without one being nested in the other.
+    fn partial_overlaps(
+        named_areas: &[component::NamedArea],
+    ) -> Vec<(&component::NamedArea, &component::NamedArea)> {
+        named_areas
+            .iter()
+            .enumerate()
+            .flat_map(|(index, area)| {
+                named_areas[index + 1..]
+                    .iter()
+                    .map(move |other| (area, other))
+            })
+            .filter(|(area, other)| {
+                let intersection = area.area.intersection(other.area);
+                !intersection.is_empty() && intersection != area.area && intersection != other.area
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn only_the_separators_overlap_other_areas_at_100x30() {
+        let named_areas = named_areas(100, 30).await;
+
+        assert_eq!(
+            named_areas
+                .iter()
+                .map(|named_area| (named_area.name, named_area.area, named_area.layer))
+                .collect::<Vec<_>>(),
+            [
+                ("main", Rect::new(0, 0, 100, 30), Layer::Base),
+                ("header", Rect::new(0, 0, 100, 1), Layer::Base),
+                ("content", Rect::new(0, 8, 100, 18), Layer::Base),
+                ("bottom", Rect::new(0, 26, 100, 3), Layer::Base),
+                ("footer", Rect::new(0, 29, 100, 1), Layer::Base),
+                ("separator", Rect::new(0, 8, 100, 1), Layer::Background),
+                ("separator", Rect::new(0, 26, 100, 1), Layer::Background),
+                ("separator", Rect::new(0, 29, 100, 1), Layer::Background),
+                ("separator", Rect::new(0, 1, 100, 1), Layer::Background),
+                (
+                    "vertical separator",
+                    Rect::new(16, 1, 1, 8),
+                    Layer::Background
+                ),
+                (
+                    "vertical separator",
+                    Rect::new(83, 1, 1, 8),
+                    Layer::Background
+                ),
+                ("tree", Rect::new(0, 1, 16, 7), Layer::Base),
+                ("tree title", Rect::new(0, 1, 16, 1), Layer::Base),
+                ("metadata", Rect::new(17, 1, 66, 7), Layer::Base),
+                ("metadata title", Rect::new(17, 1, 66, 1), Layer::Base),
+                ("overview", Rect::new(84, 1, 16, 7), Layer::Base),
+                ("overview title", Rect::new(84, 1, 16, 1), Layer::Base),
+                // Aligned with the metadata pane, on the separator above the content.
+                ("content title", Rect::new(17, 8, 83, 1), Layer::Base),
+                ("content rows", Rect::new(0, 9, 100, 17), Layer::Base),
+                ("bottom title", Rect::new(17, 26, 83, 1), Layer::Base),
+                ("record name label", Rect::new(0, 27, 11, 1), Layer::Base),
+                ("record name field", Rect::new(12, 27, 88, 1), Layer::Base),
+                ("encoding label", Rect::new(0, 28, 11, 1), Layer::Base),
+                ("encoding field", Rect::new(12, 28, 88, 1), Layer::Base),
+            ]
+        );
+
+        let overlaps = partial_overlaps(&named_areas);
+
+        // Only the overlaps declared by drawing one of the areas under or over the base layer.
+        for (area, other) in &overlaps {
+            assert!(
+                area.layer != Layer::Base || other.layer != Layer::Base,
+                "{area:?} {other:?}"
+            );
+        }
+
+        assert_eq!(
+            overlaps
+                .iter()
+                .map(|(area, other)| (area.name, other.name))
+                .collect::<Vec<_>>(),
+            [
+                // The vertical separators reach down to join the separator above the content.
+                ("content", "vertical separator"),
+                ("content", "vertical separator"),
+                ("separator", "vertical separator"),
+                ("separator", "vertical separator"),
+                ("separator", "vertical separator"),
+                ("separator", "vertical separator"),
+                // The top panes have their titles on the separator below the header.
+                ("separator", "tree"),
+                ("separator", "metadata"),
+                ("separator", "overview"),
+                ("vertical separator", "content title"),
+            ]
+        );
     }
 }
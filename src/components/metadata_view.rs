@@ -0,0 +1,436 @@
+//! The metadata entries of the opened record, with the entries that changed since the previous
+//! version highlighted. While focused, a cursor highlights an entry, whose value `y` copies.
+//! Entries that do not fit are scrolled to with the cursor, or with the mouse wheel.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Row, Table},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::{Action, ComponentMessage, Severity},
+    cbor::{
+        cbor_value_to_clipboard_text, cbor_value_to_line, record_metadata_key_to_string,
+        record_metadata_to_row,
+    },
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    theme::{ADDED_MARKER, REMOVED_MARKER, SELECTED_MARKER},
+    tui::Event,
+};
+
+use super::main_view::{MainState, MetadataChanges};
+
+/// The number of rows scrolled by a notch of the mouse wheel.
+const MOUSE_SCROLL_ROWS: isize = 1;
+
+/// The rows of entries displayed in the pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Window {
+    /// The index of the first displayed row.
+    scroll: usize,
+    /// The number of displayed rows.
+    shown: usize,
+    /// The number of rows below the displayed ones, summarized in the last row of the pane.
+    hidden: usize,
+}
+
+impl Window {
+    /// The window of `row_count` rows in `height` rows of the pane, scrolled to `scroll`, or as
+    /// near to it as keeps the `cursor` displayed.
+    fn new(row_count: usize, height: usize, scroll: usize, cursor: Option<usize>) -> Self {
+        if row_count <= height {
+            return Self {
+                scroll: 0,
+                shown: row_count,
+                hidden: 0,
+            };
+        }
+
+        let max_scroll = row_count - height;
+        // Unless scrolled to the end, the last row tells how many rows are not shown.
+        let shown_above_summary = height.saturating_sub(1);
+        let mut scroll = std::cmp::min(scroll, max_scroll);
+
+        if let Some(cursor) = cursor {
+            if cursor < scroll {
+                scroll = cursor;
+            } else if scroll < max_scroll && cursor >= scroll + shown_above_summary {
+                scroll =
+                    std::cmp::min((cursor + 1).saturating_sub(shown_above_summary), max_scroll);
+            }
+        }
+
+        if scroll == max_scroll {
+            Self {
+                scroll,
+                shown: height,
+                hidden: 0,
+            }
+        } else {
+            Self {
+                scroll,
+                shown: shown_above_summary,
+                hidden: row_count - scroll - shown_above_summary,
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MetadataView {
+    id: ComponentId,
+    main_state: Rc<RefCell<MainState>>,
+    /// The index of the highlighted entry, kept across versions of the record, so that the same
+    /// entry stays highlighted while a followed record is reloaded.
+    cursor: usize,
+    /// The number of rows of entries as last drawn, to scroll by pages.
+    page_height: Cell<usize>,
+    /// The number of rows of entries, including the removed ones, as last drawn.
+    row_count: Cell<usize>,
+    /// The index of the first displayed row, updated while drawing to keep the cursor in view.
+    scroll: Cell<usize>,
+}
+
+impl MetadataView {
+    pub(super) fn new(
+        id: ComponentId,
+        _tx: &UnboundedSender<Action>,
+        main_state: &Rc<RefCell<MainState>>,
+    ) -> Self {
+        Self {
+            id,
+            main_state: main_state.clone(),
+            cursor: 0,
+            page_height: Cell::new(1),
+            row_count: Cell::new(0),
+            scroll: Cell::new(0),
+        }
+    }
+
+    fn entry_count(&self) -> usize {
+        self.main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map_or(0, |opened_record| {
+                opened_record
+                    .record
+                    .metadata
+                    .iter_with_semantic_keys()
+                    .count()
+            })
+    }
+
+    /// The index of the highlighted entry, within the entries of the opened record.
+    fn cursor(&self) -> usize {
+        std::cmp::min(self.cursor, self.entry_count().saturating_sub(1))
+    }
+
+    fn move_cursor(&mut self, rows: isize) {
+        self.cursor = self.cursor().saturating_add_signed(rows);
+        self.cursor = self.cursor();
+    }
+
+    /// Scrolls by `rows`, moving the cursor along if it would no longer be displayed.
+    fn scroll_by(&mut self, rows: isize) {
+        let window = Window::new(
+            self.row_count.get(),
+            self.page_height.get(),
+            self.scroll.get().saturating_add_signed(rows),
+            None,
+        );
+        self.scroll.set(window.scroll);
+        self.cursor = self.cursor().clamp(
+            window.scroll,
+            std::cmp::max(
+                window.scroll,
+                (window.scroll + window.shown).saturating_sub(1),
+            ),
+        );
+    }
+
+    /// Copies the value of the highlighted entry.
+    fn copy_value(&self) -> Action {
+        let text = self
+            .main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .and_then(|opened_record| {
+                opened_record
+                    .record
+                    .metadata
+                    .iter_with_semantic_keys()
+                    .nth(self.cursor())
+                    .map(|(_, value)| cbor_value_to_clipboard_text(value))
+            });
+
+        Action::BroadcastMessage(match text {
+            Some(text) => ComponentMessage::CopyToClipboard {
+                text,
+                requester: self.id,
+            },
+            None => ComponentMessage::ShowNotice {
+                message: "The record has no metadata to copy.".to_string(),
+                severity: Severity::Info,
+            },
+        })
+    }
+}
+
+impl Component for MetadataView {
+    fn is_focusable(&self) -> bool {
+        self.main_state.borrow().opened_record.is_some()
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // Scrolled even while another component is focused.
+        if let Event::Mouse(MouseEvent {
+            kind: kind @ (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown),
+            ..
+        }) = event
+        {
+            self.scroll_by(if *kind == MouseEventKind::ScrollUp {
+                -MOUSE_SCROLL_ROWS
+            } else {
+                MOUSE_SCROLL_ROWS
+            });
+            return Ok(HandleEventSuccess::handled().with_action(Action::Render));
+        }
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: kind @ (KeyEventKind::Press | KeyEventKind::Repeat),
+            ..
+        }) = event
+        else {
+            return Ok(HandleEventSuccess::unhandled());
+        };
+        let page_height = self.page_height.get() as isize;
+
+        match code {
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page_height),
+            KeyCode::PageDown => self.move_cursor(page_height),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = usize::MAX,
+            KeyCode::Char('y') if *kind == KeyEventKind::Press => {
+                return Ok(HandleEventSuccess::handled().with_action(self.copy_value()));
+            }
+            _ => return Ok(HandleEventSuccess::unhandled()),
+        }
+
+        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Table);
+        node.set_label("Record metadata");
+
+        if let Some(opened_record) = self.main_state.borrow().opened_record.as_ref()
+            && let Some((key, value)) = opened_record
+                .record
+                .metadata
+                .iter_with_semantic_keys()
+                .nth(self.cursor())
+        {
+            node.set_value(format!(
+                "{}: {}",
+                record_metadata_key_to_string(&key),
+                cbor_value_to_line(value)
+            ));
+        }
+
+        Ok(node)
+    }
+}
+
+impl Drawable for MetadataView {
+    /// The changes since the previous version of the record, if they are still highlighted.
+    type Args<'a>
+        = Option<&'a MetadataChanges>
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, area: Rect, changes: Self::Args<'_>) -> Result<()> {
+        let main_state = self.main_state.borrow();
+        let Some(opened_record) = main_state.opened_record.as_ref() else {
+            return Ok(());
+        };
+        let palette = context.palette();
+        let focused = context.focused_id() == self.id;
+        let cursor = self.cursor();
+        let mut rows = opened_record
+            .record
+            .metadata
+            .iter_with_semantic_keys()
+            .enumerate()
+            .map(|(index, (key, value))| {
+                let changed = changes.is_some_and(|changes| {
+                    changes
+                        .changed_keys
+                        .contains(&record_metadata_key_to_string(&key))
+                });
+                let selected = focused && index == cursor;
+                let marker = if selected {
+                    SELECTED_MARKER
+                } else if changed {
+                    ADDED_MARKER
+                } else {
+                    " "
+                };
+                let mut style = Style::new();
+
+                if changed {
+                    style = style.bold().bg(palette.changed);
+                }
+
+                if selected {
+                    style = style.patch(palette.focus_style());
+                }
+
+                record_metadata_to_row(marker, key, value).style(style)
+            })
+            .chain(changes.into_iter().flat_map(|changes| {
+                changes.removed.iter().map(|(key, value)| {
+                    Row::new([
+                        Line::raw(REMOVED_MARKER),
+                        Line::raw(key.as_str()),
+                        cbor_value_to_line(value),
+                    ])
+                    .style(Style::new().crossed_out())
+                })
+            }))
+            .collect::<Vec<_>>();
+        let height = area.height as usize;
+        self.page_height.set(std::cmp::max(height, 1));
+        self.row_count.set(rows.len());
+        let window = Window::new(
+            rows.len(),
+            height,
+            self.scroll.get(),
+            focused.then_some(cursor),
+        );
+        self.scroll.set(window.scroll);
+
+        if rows.is_empty() {
+            rows.push(Row::new([
+                Line::default(),
+                Line::styled("none", palette.muted_style()),
+            ]));
+        } else {
+            rows.drain(..window.scroll);
+            rows.truncate(window.shown);
+
+            if window.hidden > 0 {
+                rows.push(Row::new([
+                    Line::default(),
+                    Line::styled(
+                        format!("… and {} more", window.hidden),
+                        palette.muted_style(),
+                    ),
+                ]));
+            }
+        }
+
+        let metadata_table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ],
+        );
+
+        context.frame().render_widget(metadata_table, area);
+        context.register_mouse_area(self.id, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::main_view::RegistryState;
+    use super::*;
+
+    fn window(row_count: usize, scroll: usize, cursor: Option<usize>) -> (usize, usize, usize) {
+        let Window {
+            scroll,
+            shown,
+            hidden,
+        } = Window::new(row_count, 6, scroll, cursor);
+        (scroll, shown, hidden)
+    }
+
+    #[test]
+    fn rows_that_fit_are_not_scrolled() {
+        assert_eq!(window(0, 3, None), (0, 0, 0));
+        assert_eq!(window(6, 3, Some(5)), (0, 6, 0));
+    }
+
+    #[test]
+    fn rows_that_do_not_fit_are_scrolled_and_summarized() {
+        // The last row tells how many rows follow, until scrolled to the end.
+        assert_eq!(window(10, 0, None), (0, 5, 5));
+        assert_eq!(window(10, 3, None), (3, 5, 2));
+        assert_eq!(window(10, 4, None), (4, 6, 0));
+        assert_eq!(window(10, 100, None), (4, 6, 0));
+    }
+
+    #[test]
+    fn scroll_keeps_the_cursor_displayed() {
+        assert_eq!(window(10, 0, Some(4)), (0, 5, 5));
+        assert_eq!(window(10, 0, Some(5)), (1, 5, 4));
+        assert_eq!(window(10, 0, Some(7)), (3, 5, 2));
+        assert_eq!(window(10, 0, Some(9)), (4, 6, 0));
+        assert_eq!(window(10, 4, Some(2)), (2, 5, 3));
+        // Within the displayed rows, the scroll is kept.
+        assert_eq!(window(10, 2, Some(4)), (2, 5, 3));
+    }
+
+    #[test]
+    fn mouse_wheel_scrolls_while_unfocused() {
+        let (action_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let main_state = Rc::new(RefCell::new(MainState {
+            registry: RegistryState::Opening,
+            opened_record: None,
+        }));
+        let mut view = MetadataView::new(ComponentId::new(), &action_tx, &main_state);
+        // As last drawn, 10 rows in 6.
+        view.row_count.set(10);
+        view.page_height.set(6);
+        let wheel = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+
+        for expected_scroll in [1, 2, 3, 4, 4] {
+            let success = view
+                .handle_event(&wheel(MouseEventKind::ScrollDown))
+                .unwrap();
+            assert!(success.absorb);
+            assert_eq!(view.scroll.get(), expected_scroll);
+        }
+
+        view.handle_event(&wheel(MouseEventKind::ScrollUp)).unwrap();
+        assert_eq!(view.scroll.get(), 3);
+    }
+}
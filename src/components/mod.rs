@@ -1,8 +1,19 @@
 
 
 
+pub mod breadcrumbs;
+pub mod button;
 pub mod checkbox;
+pub mod content_view;
+pub mod error_boundary;
+pub mod header;
 pub mod input_field;
+pub mod log_view;
 pub mod main_view;
+pub mod metadata_view;
+pub mod modal;
 pub mod radio_array;
+pub mod record_tree;
 pub mod spinner_field;
+pub mod status_bar;
+pub mod version_selector;
@@ -0,0 +1,350 @@
+//! Modal dialogs, drawn over the rest of the interface, which keep the focus to themselves until
+//! they are closed.
+//!
+//! Any component can request a dialog with `ComponentMessage::OpenModal`, and receives the result
+//! as `ComponentMessage::ModalClosed`. The open dialog is hosted by the main view.
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+use tokio::sync::mpsc::UnboundedSender;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    action::{Action, ComponentMessage},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    tui::Event,
+};
+
+use super::button::{Button, ButtonArgs};
+use super::input_field::InputField;
+
+/// The maximum width of a dialog, borders included.
+const MAX_WIDTH: u16 = 60;
+
+/// The number of cells between adjacent buttons.
+const BUTTON_SPACING: u16 = 2;
+
+/// What a dialog asks for, sent along with `ComponentMessage::OpenModal`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModalRequest {
+    /// The component the result is sent to.
+    pub requester: ComponentId,
+    pub title: String,
+    pub message: String,
+    /// The initial content of a text input, or `None` for a dialog without one.
+    pub input: Option<String>,
+    /// The labels of the buttons, from left to right.
+    pub buttons: Vec<String>,
+    /// The index of the button activated by Enter, unless another button is focused.
+    pub default_button: usize,
+}
+
+impl ModalRequest {
+    /// A dialog with a single OK button.
+    pub fn new(
+        requester: ComponentId,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            requester,
+            title: title.into(),
+            message: message.into(),
+            input: None,
+            buttons: vec!["OK".to_string()],
+            default_button: 0,
+        }
+    }
+
+    pub fn with_input(self, content: impl Into<String>) -> Self {
+        Self {
+            input: Some(content.into()),
+            ..self
+        }
+    }
+
+    pub fn with_buttons(self, buttons: &[&str], default_button: usize) -> Self {
+        Self {
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            default_button,
+            ..self
+        }
+    }
+}
+
+/// How a dialog was closed, sent back with `ComponentMessage::ModalClosed`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModalResult {
+    /// The button at `index` was activated, with the content of the input, if any.
+    Button { index: usize, input: Option<String> },
+    /// Closed with Esc, or replaced by another dialog.
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct Modal {
+    id: ComponentId,
+    requester: ComponentId,
+    title: String,
+    message: String,
+    input_field: Option<InputField>,
+    buttons: Vec<Button>,
+    default_button: usize,
+}
+
+impl Modal {
+    pub fn new(id: ComponentId, tx: &UnboundedSender<Action>, request: ModalRequest) -> Self {
+        let input_field = request.input.map(|content| {
+            let mut input_field = InputField::new(ComponentId::new(), tx).with_label("Input");
+            input_field.set_content(content);
+            input_field
+        });
+
+        Self {
+            id,
+            requester: request.requester,
+            title: request.title,
+            message: request.message,
+            input_field,
+            buttons: request
+                .buttons
+                .into_iter()
+                .map(|label| Button::new(ComponentId::new(), tx, label.into()))
+                .collect(),
+            default_button: request.default_button,
+        }
+    }
+
+    pub fn get_requester(&self) -> ComponentId {
+        self.requester
+    }
+
+    /// The component to focus once the dialog is opened: the input, if any, or the default
+    /// button.
+    pub fn get_initial_focus_id(&self) -> ComponentId {
+        self.input_field
+            .as_ref()
+            .map(|input_field| input_field.get_id())
+            .or_else(|| {
+                self.buttons
+                    .get(self.default_button)
+                    .map(|button| button.get_id())
+            })
+            .unwrap_or(self.id)
+    }
+
+    /// The result of the dialog is broadcast, so that the host removes the dialog as well.
+    fn close(&self, result: ModalResult) -> Action {
+        Action::BroadcastMessage(ComponentMessage::ModalClosed {
+            id: self.id,
+            requester: self.requester,
+            result,
+        })
+    }
+
+    fn activate(&self, index: usize) -> Action {
+        self.close(ModalResult::Button {
+            index,
+            input: self
+                .input_field
+                .as_ref()
+                .map(|input_field| input_field.get_content().to_string()),
+        })
+    }
+
+    /// The number of rows the message takes when wrapped to `width`, approximately.
+    fn message_height(&self, width: u16) -> u16 {
+        self.message
+            .lines()
+            .map(|line| std::cmp::max(1, line.width().div_ceil(width.max(1) as usize)) as u16)
+            .sum()
+    }
+}
+
+impl Component for Modal {
+    fn traps_focus(&self) -> bool {
+        true
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // Taken before the focused child sees the key.
+        match event {
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc,
+                kind: KeyEventKind::Press,
+                ..
+            }) => Ok(HandleEventSuccess::handled().with_action(self.close(ModalResult::Cancelled))),
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let index = self
+                    .buttons
+                    .iter()
+                    .position(Button::is_focused)
+                    .unwrap_or(self.default_button);
+                Ok(HandleEventSuccess::handled().with_action(self.activate(index)))
+            }
+            _ => Ok(HandleEventSuccess::unhandled()),
+        }
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        match message {
+            ComponentMessage::OnButtonPress { id } => Ok(self
+                .buttons
+                .iter()
+                .position(|button| button.get_id() == id)
+                .map(|index| self.activate(index))),
+            _ => Ok(None),
+        }
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Dialog);
+        node.set_label(self.title.as_str());
+        node.set_description(self.message.as_str());
+        node.set_modal();
+        Ok(node)
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.modal = Some(crate::introspection::ModalState {
+            title: self.title.clone(),
+            message: self.message.clone(),
+            input: self
+                .input_field
+                .as_ref()
+                .map(|input_field| input_field.get_content().to_string()),
+            buttons: self
+                .buttons
+                .iter()
+                .map(|button| button.get_label().to_string())
+                .collect(),
+        });
+    }
+
+    fn get_children(&self) -> Vec<&dyn Component> {
+        let mut children: Vec<&dyn Component> = Vec::new();
+
+        if let Some(input_field) = self.input_field.as_ref() {
+            children.push(input_field);
+        }
+
+        children.extend(self.buttons.iter().map(|button| button as &dyn Component));
+        children
+    }
+
+    fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
+        let mut children: Vec<&mut dyn Component> = Vec::new();
+
+        if let Some(input_field) = self.input_field.as_mut() {
+            children.push(input_field);
+        }
+
+        children.extend(
+            self.buttons
+                .iter_mut()
+                .map(|button| button as &mut dyn Component),
+        );
+        children
+    }
+}
+
+impl Drawable for Modal {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    /// Draws the dialog centered within the area, over the dimmed rest of the interface.
+    fn draw(&self, context: &mut DrawContext, area: Rect, (): Self::Args<'_>) -> Result<()> {
+        context
+            .frame()
+            .buffer_mut()
+            .set_style(area, Style::new().dim());
+
+        let width = std::cmp::min(area.width, MAX_WIDTH);
+        let input_height = if self.input_field.is_some() { 2 } else { 0 };
+        let height = std::cmp::min(
+            area.height,
+            2 + self.message_height(width.saturating_sub(2)) + input_height + 2,
+        );
+        let area_modal = Rect {
+            x: area.x.saturating_add(area.width.saturating_sub(width) / 2),
+            y: area
+                .y
+                .saturating_add(area.height.saturating_sub(height) / 2),
+            width,
+            height,
+        };
+        let block = Block::bordered()
+            .border_style(context.palette().separator_style())
+            .title(
+                Line::raw(format!(" {} ", self.title))
+                    .style(context.palette().title_style().bold()),
+            );
+        let area_inner = block.inner(area_modal);
+
+        context.frame().render_widget(Clear, area_modal);
+        context.render_overlay("modal", block, area_modal);
+        // Clicks within the dialog do not reach the components behind it.
+        context.register_mouse_area(self.id, area_modal);
+
+        let [area_message, area_input, _, area_buttons] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(input_height.saturating_sub(1)),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(area_inner);
+
+        context.frame().render_widget(
+            Paragraph::new(self.message.as_str()).wrap(Wrap { trim: true }),
+            area_message,
+        );
+
+        if let Some(input_field) = self.input_field.as_ref() {
+            input_field.draw(context, area_input, Default::default())?;
+        }
+
+        let buttons_width = self.buttons.iter().map(Button::width).sum::<u16>()
+            + self.buttons.len().saturating_sub(1) as u16 * BUTTON_SPACING;
+        let mut x = area_buttons.x + area_buttons.width.saturating_sub(buttons_width) / 2;
+
+        for (index, button) in self.buttons.iter().enumerate() {
+            if x >= area_buttons.right() {
+                break;
+            }
+
+            button.draw(
+                context,
+                Rect {
+                    x,
+                    width: area_buttons.right() - x,
+                    ..area_buttons
+                },
+                ButtonArgs {
+                    default: index == self.default_button,
+                },
+            )?;
+            x = x.saturating_add(button.width() + BUTTON_SPACING);
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,18 @@
-use std::{fmt::Debug, ops::ControlFlow};
+use std::{cell::Cell, fmt::Debug, ops::ControlFlow};
 
 use color_eyre::Result;
-use ratatui::layout::{Direction, Layout, Rect};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::Span,
+};
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::{Action, ComponentMessage},
-    component::{Component, ComponentId, DrawContext, Drawable},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
     components::checkbox::Checkbox,
+    tui::Event,
 };
 
 #[derive(Debug, Clone)]
@@ -20,6 +25,16 @@ where
     checked_index: usize,
     action_tx: UnboundedSender<Action>,
     layout_direction: Direction,
+    /// The label reported to assistive technology.
+    label: Option<&'static str>,
+    /// Whether the array was last drawn compact, with only the checked item shown.
+    /// Only the shown item can be focused, and toggling it checks the next one.
+    compact: Cell<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RadioArrayArgs {
+    pub compact: bool,
 }
 
 impl<T> RadioArray<T>
@@ -54,13 +69,62 @@ where
                         item.to_string().into(),
                         index == checked_index,
                     )
-                    .with_checkbox("(x)".into(), "( )".into());
+                    .with_checkbox("(x)".into(), "( )".into())
+                    .with_accessibility_role(accesskit::Role::RadioButton);
                     (item, checkbox)
                 })
                 .collect(),
             checked_index,
             action_tx: tx.clone(),
             layout_direction,
+            label: None,
+            compact: Cell::new(false),
+        }
+    }
+
+    pub fn with_label(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// The items which are drawn, and can therefore be focused.
+    fn shown_items(&self) -> &[(T, Checkbox)] {
+        if self.compact.get() {
+            std::slice::from_ref(&self.items[self.checked_index])
+        } else {
+            &self.items
+        }
+    }
+
+    fn shown_items_mut(&mut self) -> &mut [(T, Checkbox)] {
+        if self.compact.get() {
+            std::slice::from_mut(&mut self.items[self.checked_index])
+        } else {
+            &mut self.items
+        }
+    }
+
+    /// Checks the item at `index`, announcing the change.
+    fn check(&mut self, index: usize) -> Result<()> {
+        self.checked_index = index;
+
+        for (index, (_, checkbox)) in self.items.iter_mut().enumerate() {
+            checkbox.checked = index == self.checked_index;
+        }
+
+        self.action_tx.send(Action::BroadcastMessage(
+            ComponentMessage::OnRadioArrayChange { id: self.id },
+        ))?;
+        Ok(())
+    }
+
+    /// Checks `item`, announcing the change unless it is already checked.
+    pub fn set_checked(&mut self, item: &T) -> Result<()> {
+        match self.items.iter().position(|(other, _)| other == item) {
+            Some(index) if index != self.checked_index => self.check(index),
+            _ => Ok(()),
         }
     }
 
@@ -87,7 +151,21 @@ where
 {
     fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
         Ok(match message {
-            ComponentMessage::OnCheckboxToggle { id, new_value: _ } => {
+            ComponentMessage::OnCheckboxToggle { id, new_value: _ }
+                if self.compact.get() && self.items[self.checked_index].1.get_id() == id =>
+            {
+                // Cycles to the next item, which replaces the toggled one, focus included.
+                self.check((self.checked_index + 1) % self.items.len())?;
+                Some(Action::FocusComponent(
+                    self.items[self.checked_index].1.get_id(),
+                ))
+            }
+            ComponentMessage::OnCheckboxToggle { id, new_value: _ }
+                if self
+                    .items
+                    .iter()
+                    .any(|(_, checkbox)| checkbox.get_id() == id) =>
+            {
                 for (index, (_, checkbox)) in self.items.iter_mut().enumerate() {
                     if checkbox.get_id() == id {
                         checkbox.checked = true;
@@ -97,25 +175,69 @@ where
                     }
                 }
 
-                None
+                Some(Action::BroadcastMessage(
+                    ComponentMessage::OnRadioArrayChange { id: self.id },
+                ))
             }
             _ => None,
         })
     }
 
+    /// Moves the selection with the arrow keys along the layout direction, wrapping around.
+    /// Handled before the focused item sees the keys, as the focus follows the selection.
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) = event
+        else {
+            return Ok(HandleEventSuccess::unhandled());
+        };
+        let forward = match (self.layout_direction, code) {
+            (Direction::Horizontal, KeyCode::Left) | (Direction::Vertical, KeyCode::Up) => false,
+            (Direction::Horizontal, KeyCode::Right) | (Direction::Vertical, KeyCode::Down) => true,
+            _ => return Ok(HandleEventSuccess::unhandled()),
+        };
+        let count = self.items.len();
+        self.check(if forward {
+            (self.checked_index + 1) % count
+        } else {
+            (self.checked_index + count - 1) % count
+        })?;
+
+        Ok(
+            HandleEventSuccess::handled().with_action(Action::FocusComponent(
+                self.items[self.checked_index].1.get_id(),
+            )),
+        )
+    }
+
     fn get_id(&self) -> ComponentId {
         self.id
     }
 
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::RadioGroup);
+
+        if let Some(label) = self.label {
+            node.set_label(label);
+        }
+
+        node.set_value(self.get_checked().to_string());
+        Ok(node)
+    }
+
     fn get_children(&self) -> Vec<&dyn Component> {
-        self.items
+        self.shown_items()
             .iter()
             .map(|(_, checkbox)| checkbox as &dyn Component)
             .collect()
     }
 
     fn get_children_mut(&mut self) -> Vec<&mut dyn Component> {
-        self.items
+        self.shown_items_mut()
             .iter_mut()
             .map(|(_, checkbox)| checkbox as &mut dyn Component)
             .collect()
@@ -125,7 +247,7 @@ where
         &'a self,
         f: &mut dyn FnMut(&'a dyn Component) -> std::ops::ControlFlow<()>,
     ) -> std::ops::ControlFlow<()> {
-        for (_, checkbox) in &self.items {
+        for (_, checkbox) in self.shown_items() {
             (f)(checkbox)?;
         }
 
@@ -136,7 +258,7 @@ where
         &'a mut self,
         f: &mut dyn FnMut(&'a mut dyn Component) -> ControlFlow<()>,
     ) -> ControlFlow<()> {
-        for (_, checkbox) in &mut self.items {
+        for (_, checkbox) in self.shown_items_mut() {
             (f)(checkbox)?;
         }
 
@@ -149,33 +271,76 @@ where
     T: ToString + Clone + PartialEq + Debug,
 {
     type Args<'a>
-        = ()
+        = RadioArrayArgs
     where
         Self: 'a;
 
-    fn draw<'a>(&'a self, context: &mut DrawContext, area: Rect, (): Self::Args<'a>) -> Result<()> {
+    fn draw<'a>(
+        &'a self,
+        context: &mut DrawContext,
+        area: Rect,
+        extra_args: Self::Args<'a>,
+    ) -> Result<()> {
+        self.compact.set(extra_args.compact);
+
         if area.area() == 0 {
             return Ok(());
         }
 
-        let (areas, _) = Layout::new(
-            self.layout_direction,
-            self.items.iter().map(|(_, checkbox)| {
-                let size = checkbox.size();
-                match self.layout_direction {
-                    Direction::Horizontal => size.width,
-                    Direction::Vertical => size.height,
-                }
-            }),
-        )
-        .spacing(match self.layout_direction {
-            Direction::Horizontal => 2,
-            Direction::Vertical => 0,
-        })
-        .split_with_spacers(area);
+        if extra_args.compact {
+            let (_, checkbox) = self.get_checked_entry();
+            let [area_checkbox, area_hint] = Layout::new(
+                Direction::Horizontal,
+                [
+                    Constraint::Length(checkbox.size().width),
+                    Constraint::Fill(1),
+                ],
+            )
+            .spacing(1)
+            .areas(area);
+
+            checkbox.draw(context, area_checkbox, ())?;
+            let style = context.palette().muted_style();
+            context
+                .frame()
+                .render_widget(Span::styled("[Space] to cycle", style), area_hint);
+            return Ok(());
+        }
+
+        // Laid out one after another, so that the items that do not fit are truncated or left out,
+        // instead of all of them being squeezed.
+        let mut position = match self.layout_direction {
+            Direction::Horizontal => area.x,
+            Direction::Vertical => area.y,
+        };
+
+        for (_, checkbox) in &self.items {
+            let checkbox_area = match self.layout_direction {
+                Direction::Horizontal => Rect {
+                    x: position,
+                    width: std::cmp::min(
+                        checkbox.size().width,
+                        area.right().saturating_sub(position),
+                    ),
+                    height: 1,
+                    ..area
+                },
+                Direction::Vertical => Rect {
+                    y: position,
+                    height: std::cmp::min(1, area.bottom().saturating_sub(position)),
+                    ..area
+                },
+            };
+
+            if checkbox_area.area() == 0 {
+                break;
+            }
 
-        for ((_, checkbox), checkbox_area) in self.items.iter().zip(areas.iter()) {
-            checkbox.draw(context, *checkbox_area, ())?;
+            checkbox.draw(context, checkbox_area, ())?;
+            position = match self.layout_direction {
+                Direction::Horizontal => position.saturating_add(checkbox_area.width + 2),
+                Direction::Vertical => position.saturating_add(1),
+            };
         }
 
         Ok(())
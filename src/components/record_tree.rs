@@ -0,0 +1,1011 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Instant;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::Paragraph,
+};
+use rrr::registry::Registry;
+use rrr::utils::fd_lock::ReadLock;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::{
+    action::{Action, ComponentMessage, Deferred, Panel},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    encoding::Encoding,
+    model::{LossyNameDisplay, RecordRef},
+    open_pipeline::{OpenError, ResolveFrom},
+    registry_summary::SummaryChanges,
+    text::{preview_content, truncate_to_width, TruncateMode},
+    theme::{Palette, ADDED_MARKER, SELECTED_MARKER},
+    tui::Event,
+};
+
+use super::main_view::MainState;
+
+/// The index of a node in `RecordTree::nodes`.
+type NodeIndex = usize;
+
+/// The node of the root record, which is always in the tree.
+const ROOT: NodeIndex = 0;
+
+/// The cells between the widest name and the previews.
+const PREVIEW_GAP: usize = 2;
+
+/// The width previews are loaded with, truncated further to the width of the tree when drawn.
+const PREVIEW_MAX_WIDTH: usize = 80;
+
+/// The content of a record as previewed next to its name in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentPreview {
+    /// The first characters of the content if it is printable text, or its size otherwise.
+    Summary(String),
+    /// The content could not be decrypted.
+    Locked,
+    /// The record could not be loaded for any other reason, drawn without a preview.
+    Unavailable,
+}
+
+impl ContentPreview {
+    /// The preview of the loaded content, or of the error the record failed to load with.
+    fn new(content: Result<&[u8], &OpenError>) -> Self {
+        match content {
+            Ok(data) => Self::Summary(preview_content(data, PREVIEW_MAX_WIDTH)),
+            Err(OpenError::DecryptFailed) => Self::Locked,
+            Err(_) => Self::Unavailable,
+        }
+    }
+
+    /// Loads the latest version of the record at `record_ref` to preview it.
+    async fn load(
+        registry: &Registry<ReadLock>,
+        record_ref: &RecordRef,
+        from: ResolveFrom,
+        cancellation: &CancellationToken,
+    ) -> Self {
+        let result =
+            crate::open_pipeline::open(registry, record_ref, from, &mut |_| (), cancellation).await;
+
+        if let Err(error) = &result {
+            debug!(?record_ref, %error, "Failed to load the preview.");
+        }
+
+        Self::new(
+            result
+                .as_ref()
+                .map(|loaded_record| loaded_record.record.data.as_slice()),
+        )
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            Self::Summary(summary) => summary,
+            Self::Locked => "locked",
+            Self::Unavailable => "",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    /// The name of the record, empty for the root record.
+    name: Vec<u8>,
+    /// The encoding the name is displayed in, that of the record the node was added for.
+    encoding_hint: Encoding,
+    parent: Option<NodeIndex>,
+    /// The sub-records, ordered by their names.
+    children: BTreeMap<Vec<u8>, NodeIndex>,
+    depth: usize,
+}
+
+/// The records opened so far, as a tree rooted in the root record.
+/// The sub-records of a record cannot be listed without knowing their names, so only the records
+/// that were opened are known.
+/// Nodes only hold their own name, and the tree is flattened without recursion, so that the cost
+/// of a record grows linearly with its depth.
+#[derive(Debug)]
+pub struct RecordTree {
+    id: ComponentId,
+    action_tx: UnboundedSender<Action>,
+    main_state: Rc<RefCell<MainState>>,
+    lossy_names: LossyNameDisplay,
+    /// The depth up to which the tree is expanded to reveal the opened record.
+    max_expansion_depth: usize,
+    nodes: Vec<Node>,
+    collapsed: HashSet<NodeIndex>,
+    /// Nodes drawn from the registry summary that were not opened since, drawn as such until the
+    /// registry is opened.
+    stale: HashSet<NodeIndex>,
+    /// Nodes that changed since the registry summary, highlighted until `changes_expire_at`.
+    changed: HashSet<NodeIndex>,
+    changes_expire_at: Option<Instant>,
+    /// The node the cursor is on.
+    cursor: NodeIndex,
+    /// The index of the first displayed row, updated while drawing to keep the cursor in view.
+    scroll: Cell<usize>,
+    /// The area the rows were last drawn into, to find the clicked row.
+    area: Cell<Rect>,
+    /// Whether the previews of the content of the records are drawn next to their names.
+    show_previews: bool,
+    /// The previews loaded so far, `None` while being loaded.
+    previews: HashMap<NodeIndex, Option<ContentPreview>>,
+    /// The nodes drawn since the previews were last loaded, whose previews are loaded on the next
+    /// tick rather than while drawing, so that the names are drawn right away.
+    drawn: RefCell<Vec<NodeIndex>>,
+    /// Cancels the loading of the previews once they are hidden.
+    previews_cancellation: CancellationToken,
+}
+
+impl RecordTree {
+    pub(super) fn new(
+        id: ComponentId,
+        action_tx: &UnboundedSender<Action>,
+        main_state: &Rc<RefCell<MainState>>,
+        lossy_names: LossyNameDisplay,
+        max_expansion_depth: usize,
+        show_previews: bool,
+    ) -> Self {
+        Self {
+            id,
+            action_tx: action_tx.clone(),
+            main_state: main_state.clone(),
+            lossy_names,
+            max_expansion_depth,
+            nodes: vec![Node {
+                name: Vec::new(),
+                encoding_hint: RecordRef::root().encoding_hint,
+                parent: None,
+                children: BTreeMap::new(),
+                depth: 0,
+            }],
+            collapsed: HashSet::new(),
+            stale: HashSet::new(),
+            changed: HashSet::new(),
+            changes_expire_at: None,
+            cursor: ROOT,
+            scroll: Cell::new(0),
+            area: Cell::new(Rect::default()),
+            show_previews,
+            previews: HashMap::new(),
+            drawn: RefCell::new(Vec::new()),
+            previews_cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Adds the record along with the records leading to it, returning its node. The nodes added
+    /// are the last ones in `nodes`.
+    fn insert(&mut self, record_ref: &RecordRef) -> NodeIndex {
+        let mut index = ROOT;
+
+        for name in record_ref.path() {
+            index = match self.nodes[index].children.get(name) {
+                Some(child) => *child,
+                None => {
+                    let child = self.nodes.len();
+                    let depth = self.nodes[index].depth + 1;
+                    self.nodes[index].children.insert(name.clone(), child);
+                    self.nodes.push(Node {
+                        name: name.clone(),
+                        encoding_hint: record_ref.encoding_hint,
+                        parent: Some(index),
+                        children: BTreeMap::new(),
+                        depth,
+                    });
+                    child
+                }
+            };
+        }
+
+        index
+    }
+
+    /// Adds the record from the registry summary, along with the records leading to it, marking
+    /// the added nodes as stale.
+    pub fn insert_stale(&mut self, record_ref: &RecordRef) {
+        let added = self.nodes.len();
+        self.insert(record_ref);
+        self.stale.extend(added..self.nodes.len());
+    }
+
+    /// The node of the record, if it is in the tree.
+    fn find(&self, path: &[Vec<u8>]) -> Option<NodeIndex> {
+        path.iter().try_fold(ROOT, |index, name| {
+            self.nodes[index].children.get(name).copied()
+        })
+    }
+
+    /// The node followed by its ancestors, up to the root record.
+    fn ancestors(&self, index: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        std::iter::successors(Some(index), |index| self.nodes[*index].parent)
+    }
+
+    /// The reference to the latest version of the record of the node.
+    fn record_ref(&self, index: NodeIndex) -> RecordRef {
+        let mut path = self
+            .ancestors(index)
+            .filter(|ancestor| *ancestor != ROOT)
+            .map(|ancestor| self.nodes[ancestor].name.clone())
+            .collect::<Vec<_>>();
+        path.reverse();
+        RecordRef::from_path(path, self.nodes[index].encoding_hint)
+    }
+
+    /// Whether any nodes from the registry summary are still to be confirmed by opening it.
+    pub fn is_stale(&self) -> bool {
+        !self.stale.is_empty()
+    }
+
+    /// Keeps the nodes from the registry summary once the registry is opened, highlighting the
+    /// ones that changed since, along with the opened record if it is not the one last opened.
+    pub fn reconcile(
+        &mut self,
+        changes: &SummaryChanges,
+        opened_record_ref: Option<&RecordRef>,
+        expires_at: Instant,
+    ) {
+        self.stale.clear();
+        self.changed = changes
+            .added_roots
+            .iter()
+            .chain(opened_record_ref.filter(|_| changes.last_record_changed))
+            .filter_map(|record_ref| self.find(record_ref.path()))
+            .collect();
+        self.changes_expire_at = (!self.changed.is_empty()).then_some(expires_at);
+    }
+
+    /// The number of records in the tree.
+    pub fn record_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The records directly under the root record.
+    pub fn pinned_roots(&self) -> Vec<RecordRef> {
+        self.nodes[ROOT]
+            .children
+            .values()
+            .map(|child| self.record_ref(*child))
+            .collect()
+    }
+
+    fn has_children(&self, index: NodeIndex) -> bool {
+        !self.nodes[index].children.is_empty()
+    }
+
+    /// The displayed nodes from top to bottom, skipping the descendants of collapsed nodes.
+    /// Flattened with a stack of the nodes to visit rather than by recursion, however deep the
+    /// tree is.
+    fn visible_nodes(&self) -> Vec<NodeIndex> {
+        let mut visible = Vec::new();
+        let mut stack = vec![ROOT];
+
+        while let Some(index) = stack.pop() {
+            visible.push(index);
+
+            if !self.collapsed.contains(&index) {
+                stack.extend(self.nodes[index].children.values().rev());
+            }
+        }
+
+        visible
+    }
+
+    fn cursor_index(&self) -> usize {
+        self.visible_nodes()
+            .iter()
+            .position(|index| *index == self.cursor)
+            .unwrap_or_default()
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        let visible = self.visible_nodes();
+        let index = self
+            .cursor_index()
+            .saturating_add_signed(offset)
+            .min(visible.len() - 1);
+        self.cursor = visible[index];
+    }
+
+    /// Collapses the node under the cursor, or moves the cursor to its parent.
+    fn collapse_or_leave(&mut self) {
+        if self.has_children(self.cursor) && !self.collapsed.contains(&self.cursor) {
+            self.collapsed.insert(self.cursor);
+        } else if let Some(parent) = self.nodes[self.cursor].parent {
+            self.cursor = parent;
+        }
+    }
+
+    /// Expands the node under the cursor, or moves the cursor to its first child.
+    fn expand_or_enter(&mut self) {
+        if self.collapsed.remove(&self.cursor) {
+            return;
+        }
+
+        if self.has_children(self.cursor) {
+            self.move_cursor(1);
+        }
+    }
+
+    /// Adds the opened record, no longer stale along with the records leading to it, and reveals
+    /// it. Returns its node.
+    fn open(&mut self, record_ref: &RecordRef) -> NodeIndex {
+        let index = self.insert(record_ref);
+
+        for ancestor in self.ancestors(index).collect::<Vec<_>>() {
+            self.stale.remove(&ancestor);
+        }
+
+        self.reveal(index);
+        index
+    }
+
+    /// Shows or hides the previews. The previews still being loaded once hidden are cancelled, to
+    /// be loaded again once shown.
+    fn toggle_previews(&mut self) {
+        self.show_previews = !self.show_previews;
+
+        if !self.show_previews {
+            self.previews_cancellation.cancel();
+            self.previews_cancellation = CancellationToken::new();
+            self.previews.retain(|_, preview| preview.is_some());
+            self.drawn.take();
+        }
+    }
+
+    /// Starts loading the previews of the drawn nodes which are not loaded yet, once the registry
+    /// is opened. Only the drawn nodes are previewed, so that the work is bounded by the height
+    /// of the tree rather than by the number of records.
+    fn load_previews(&mut self) -> Result<()> {
+        if !self.show_previews {
+            return Ok(());
+        }
+
+        let Ok(registry) = self.main_state.borrow().registry() else {
+            return Ok(());
+        };
+
+        for index in self.drawn.take() {
+            if self.previews.contains_key(&index) {
+                continue;
+            }
+
+            self.previews.insert(index, None);
+            let record_ref = self.record_ref(index);
+            let registry = registry.clone();
+            // Cloned to be awaited within the async block, see `spawn_open_sub_record_task`.
+            let main_state = self.main_state.borrow().clone();
+            let cancellation = self.previews_cancellation.clone();
+            let future = async move {
+                let preview = match main_state.resolve_from_opened_record(&record_ref).await {
+                    Ok(from) => {
+                        ContentPreview::load(&registry, &record_ref, from, &cancellation).await
+                    }
+                    Err(error) => {
+                        debug!(%error, "Failed to derive the succession nonce.");
+                        ContentPreview::Unavailable
+                    }
+                };
+
+                ComponentMessage::ContentPreviewLoaded {
+                    record_ref,
+                    preview,
+                }
+            };
+
+            self.action_tx.send(Action::Defer(
+                Deferred::new(self.id, future).with_cancellation(&self.previews_cancellation),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// The column the previews of the `rows` are drawn from, after the widest of their names, but
+    /// leaving at least a third of the `width` to the previews.
+    fn preview_column(&self, rows: &[NodeIndex], width: usize, focused: bool) -> usize {
+        let widest = rows
+            .iter()
+            .map(|index| self.get_label(*index, width, focused).1)
+            .max()
+            .unwrap_or_default();
+        std::cmp::min(widest + PREVIEW_GAP, width * 2 / 3)
+    }
+
+    /// Reveals the node of the opened record. Records deeper than the tree is expanded to are
+    /// revealed by their deepest expanded ancestor instead, which is collapsed.
+    fn reveal(&mut self, index: NodeIndex) {
+        let depth = self.nodes[index].depth;
+        let revealed_depth = std::cmp::min(depth, self.max_expansion_depth);
+        let revealed = self.ancestors(index).nth(depth - revealed_depth).unwrap();
+
+        for ancestor in self.ancestors(revealed).skip(1).collect::<Vec<_>>() {
+            self.collapsed.remove(&ancestor);
+        }
+
+        self.cursor = revealed;
+
+        if revealed_depth < depth {
+            self.collapsed.insert(revealed);
+        }
+    }
+
+    /// The label of the node, truncated to `width`, along with its width.
+    fn get_label(&self, index: NodeIndex, width: usize, focused: bool) -> (String, usize) {
+        let node = &self.nodes[index];
+        let name = RecordRef::from_path(
+            node.parent
+                .map(|_| vec![node.name.clone()])
+                .unwrap_or_default(),
+            node.encoding_hint,
+        );
+        let marker = if !self.has_children(index) {
+            " "
+        } else if self.collapsed.contains(&index) {
+            "▸"
+        } else {
+            "▾"
+        };
+        let label = format!(
+            "{}{}{marker}{}",
+            if focused && index == self.cursor {
+                SELECTED_MARKER
+            } else if self.changed.contains(&index) {
+                ADDED_MARKER
+            } else {
+                " "
+            },
+            // Deep nodes are indented no further than the width.
+            " ".repeat(std::cmp::min(node.depth, width)),
+            name.display_name(self.lossy_names)
+        );
+        truncate_to_width(&label, width, TruncateMode::End)
+    }
+
+    /// The line of the node, `opened` if it is the node of the opened record. Its preview is drawn
+    /// from `preview_column`, if the previews are shown.
+    fn get_line(
+        &self,
+        index: NodeIndex,
+        width: usize,
+        preview_column: Option<usize>,
+        focused: bool,
+        opened: bool,
+        palette: Palette,
+    ) -> Line<'static> {
+        let label_width = preview_column.map_or(width, |column| column.saturating_sub(PREVIEW_GAP));
+        let (label, label_width) = self.get_label(index, label_width, focused);
+        let selected = focused && index == self.cursor;
+        let changed = self.changed.contains(&index);
+        let mut style = Style::new();
+
+        if opened {
+            style = style.bold();
+        }
+
+        if self.stale.contains(&index) {
+            style = style.patch(palette.muted_style());
+        }
+
+        if changed {
+            style = style.bold().bg(palette.changed);
+        }
+
+        if selected {
+            style = style.patch(palette.focus_style());
+        }
+
+        let mut spans = vec![Span::styled(label, style)];
+
+        if let Some(column) = preview_column
+            && let Some(Some(preview)) = self.previews.get(&index)
+        {
+            let (preview, _) = truncate_to_width(
+                preview.text(),
+                width.saturating_sub(column),
+                TruncateMode::End,
+            );
+            spans.extend([
+                Span::raw(" ".repeat(column - label_width)),
+                Span::styled(preview, palette.muted_style()),
+            ]);
+        }
+
+        Line::from(spans)
+    }
+}
+
+impl Component for RecordTree {
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn get_panel(&self) -> Option<Panel> {
+        Some(Panel::Tree)
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            ComponentMessage::RecordOpen {
+                record_ref,
+                read_result: Some(record),
+                ..
+            } => {
+                let index = self.open(&record_ref);
+                self.previews
+                    .insert(index, Some(ContentPreview::new(Ok(&record.data))));
+                Some(Action::Render)
+            }
+            ComponentMessage::ContentPreviewLoaded {
+                record_ref,
+                preview,
+            } => {
+                // Nodes are never removed, but the preview of the opened record may have been
+                // set from its content in the meantime.
+                let index = self.find(record_ref.path());
+
+                if let Some(loaded) = index.and_then(|index| self.previews.get_mut(&index))
+                    && loaded.is_none()
+                {
+                    *loaded = Some(preview);
+                }
+
+                self.show_previews.then_some(Action::Render)
+            }
+            ComponentMessage::OnTick => {
+                self.load_previews()?;
+
+                if self
+                    .changes_expire_at
+                    .is_some_and(|expires_at| expires_at <= Instant::now())
+                {
+                    self.changed.clear();
+                    self.changes_expire_at = None;
+                    Some(Action::Render)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        // Moves the cursor to the clicked row, which is not in the area when the title is clicked.
+        if let Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            ..
+        }) = *event
+        {
+            let area = self.area.get();
+
+            if !area.contains((column, row).into()) {
+                return Ok(HandleEventSuccess::unhandled());
+            }
+
+            if let Some(index) = self
+                .visible_nodes()
+                .get(self.scroll.get() + (row - area.y) as usize)
+            {
+                self.cursor = *index;
+            }
+
+            return Ok(HandleEventSuccess::handled().with_action(Action::Render));
+        }
+
+        let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) = event
+        else {
+            return Ok(HandleEventSuccess::unhandled());
+        };
+
+        match code {
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Left => self.collapse_or_leave(),
+            KeyCode::Right => self.expand_or_enter(),
+            KeyCode::Char('p') => self.toggle_previews(),
+            KeyCode::Backspace => {
+                if let Some(action) = self.main_state.borrow().open_parent_action() {
+                    self.action_tx.send(action)?;
+                }
+            }
+            KeyCode::Enter => {
+                self.action_tx
+                    .send(Action::BroadcastMessage(ComponentMessage::OpenRecord {
+                        record_ref: self.record_ref(self.cursor),
+                    }))?;
+            }
+            _ => return Ok(HandleEventSuccess::unhandled()),
+        }
+
+        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Tree);
+        node.set_label("Opened records");
+        node.set_value(self.record_ref(self.cursor).display_path(self.lossy_names));
+        Ok(node)
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.tree = Some(crate::introspection::TreeState {
+            rows: self
+                .visible_nodes()
+                .into_iter()
+                .map(|index| self.record_ref(index).display_path(self.lossy_names))
+                .collect(),
+            cursor: self.cursor_index(),
+        });
+    }
+}
+
+impl Drawable for RecordTree {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    fn draw(&self, context: &mut DrawContext, area: Rect, (): Self::Args<'_>) -> Result<()> {
+        self.area.set(area);
+        context.register_mouse_area(self.id, area);
+
+        if area.area() == 0 {
+            return Ok(());
+        }
+
+        let page_height = area.height as usize;
+        let cursor_index = self.cursor_index();
+        let scroll = self
+            .scroll
+            .get()
+            .clamp(cursor_index.saturating_sub(page_height - 1), cursor_index);
+        self.scroll.set(scroll);
+
+        let focused = context.focused_id() == self.id;
+        let opened = self
+            .main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .and_then(|opened_record| self.find(opened_record.record_ref.path()));
+        let width = area.width as usize;
+        let rows = self
+            .visible_nodes()
+            .into_iter()
+            .skip(scroll)
+            .take(page_height)
+            .collect::<Vec<_>>();
+        let preview_column = self
+            .show_previews
+            .then(|| self.preview_column(&rows, width, focused));
+        let lines = rows
+            .iter()
+            .map(|index| {
+                self.get_line(
+                    *index,
+                    width,
+                    preview_column,
+                    focused,
+                    opened == Some(*index),
+                    context.palette(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if self.show_previews {
+            self.drawn.replace(rows);
+        }
+
+        context
+            .frame()
+            .render_widget(Paragraph::new(Text::from(lines)), area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, buffer::Buffer, style::Color, Terminal};
+
+    use super::super::main_view::RegistryState;
+    use super::*;
+    use crate::theme::Theme;
+
+    /// The depth of the records of the stress tests.
+    const DEEP: usize = 10_000;
+
+    fn empty_tree(max_expansion_depth: usize) -> RecordTree {
+        let (action_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let main_state = Rc::new(RefCell::new(MainState {
+            registry: RegistryState::Opening,
+            opened_record: None,
+        }));
+        RecordTree::new(
+            ComponentId::new(),
+            &action_tx,
+            &main_state,
+            LossyNameDisplay::Mark,
+            max_expansion_depth,
+            false,
+        )
+    }
+
+    /// A tree of `a`, `a/b` and `c`, with the cursor on `a` and `c` added since the summary.
+    fn tree() -> RecordTree {
+        let mut tree = empty_tree(usize::MAX);
+        tree.insert(&RecordRef::from_utf8_path("a/b"));
+        tree.insert(&RecordRef::from_utf8_path("c"));
+        tree.reconcile(
+            &SummaryChanges {
+                added_roots: vec![RecordRef::from_utf8_path("c")],
+                ..Default::default()
+            },
+            None,
+            Instant::now(),
+        );
+        tree.cursor = tree.find(&[b"a".to_vec()]).unwrap();
+        tree
+    }
+
+    /// The record `n0/n1/…/n9999`.
+    fn deep_record_ref() -> RecordRef {
+        RecordRef::from_path(
+            (0..DEEP)
+                .map(|index| format!("n{index}").into_bytes())
+                .collect(),
+            Encoding::UTF8,
+        )
+    }
+
+    /// Runs `test` on a thread with a stack far too small to recurse over the depth of the tree.
+    fn with_small_stack(test: impl FnOnce() + Send + 'static) {
+        std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    fn draw(tree: &RecordTree, palette: Palette) -> Buffer {
+        draw_in(tree, palette, 8, 4)
+    }
+
+    fn draw_in(tree: &RecordTree, palette: Palette, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                let mut context = DrawContext::new(frame, tree.id, palette);
+                tree.draw(&mut context, area, ()).unwrap();
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// The snapshot of `tree`, the labels of its rows styled as focused and changed.
+    fn expected(focused: Style, changed: Style) -> Buffer {
+        let mut buffer = Buffer::with_lines([" ▾/     ", "› ▾a    ", "    b   ", "+  c    "]);
+        buffer.set_style(Rect::new(0, 1, 4, 1), focused);
+        buffer.set_style(Rect::new(0, 3, 4, 1), changed);
+        buffer
+    }
+
+    #[test]
+    fn selected_and_changed_rows_are_marked_in_the_colorblind_theme() {
+        assert_eq!(
+            draw(&tree(), Theme::Colorblind.palette(u16::MAX)),
+            expected(
+                Style::new().fg(Color::Reset).reversed(),
+                Style::new().bold().bg(Color::Rgb(0x00, 0x2F, 0x4F)),
+            )
+        );
+    }
+
+    #[test]
+    fn selected_and_changed_rows_are_marked_without_colors() {
+        assert_eq!(
+            draw(&tree(), Theme::Colorblind.palette(0)),
+            expected(
+                Style::new().fg(Color::Reset).reversed(),
+                Style::new().bold().bg(Color::Reset),
+            )
+        );
+    }
+
+    fn press(tree: &mut RecordTree, code: KeyCode) {
+        tree.handle_event(&Event::Key(KeyEvent::from(code)))
+            .unwrap();
+    }
+
+    fn preview_loaded(tree: &mut RecordTree, path: &str, preview: ContentPreview) {
+        tree.update(ComponentMessage::ContentPreviewLoaded {
+            record_ref: RecordRef::from_utf8_path(path),
+            preview,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn previews_summarize_the_content_or_the_error() {
+        assert_eq!(
+            ContentPreview::new(Ok(b"first line\n  second line")),
+            ContentPreview::Summary("first line second line".to_string())
+        );
+        assert_eq!(
+            ContentPreview::new(Ok(&[0; 4300])),
+            ContentPreview::Summary("binary, 4.2 KiB".to_string())
+        );
+        assert_eq!(
+            ContentPreview::new(Err(&OpenError::DecryptFailed)),
+            ContentPreview::Locked
+        );
+        assert_eq!(
+            ContentPreview::new(Err(&OpenError::Io(std::io::ErrorKind::Other.into()))),
+            ContentPreview::Unavailable
+        );
+    }
+
+    #[test]
+    fn previews_are_drawn_dimmed_after_the_widest_name() {
+        let mut tree = tree();
+        let palette = Theme::Colorblind.palette(0);
+        press(&mut tree, KeyCode::Char('p'));
+        // Drawn once to find the nodes to load the previews of.
+        draw_in(&tree, palette, 20, 4);
+        assert_eq!(tree.drawn.borrow().len(), 4);
+
+        // The previews still being loaded are drawn as nothing.
+        for index in tree.drawn.take() {
+            tree.previews.insert(index, None);
+        }
+
+        preview_loaded(&mut tree, "", ContentPreview::Summary("root".to_string()));
+        preview_loaded(&mut tree, "a", ContentPreview::Locked);
+        preview_loaded(&mut tree, "c", ContentPreview::Unavailable);
+
+        let buffer = draw_in(&tree, palette, 20, 4);
+        let mut expected = Buffer::with_lines([
+            " ▾/    root         ",
+            "› ▾a   locked       ",
+            "    b               ",
+            "+  c                ",
+        ]);
+        expected.set_style(Rect::new(7, 0, 4, 1), palette.muted_style());
+        expected.set_style(Rect::new(0, 1, 4, 1), palette.focus_style());
+        expected.set_style(Rect::new(7, 1, 6, 1), palette.muted_style());
+        expected.set_style(
+            Rect::new(0, 3, 4, 1),
+            Style::new().bold().bg(palette.changed),
+        );
+        assert_eq!(buffer, expected);
+
+        // Loaded previews are not loaded again.
+        assert!(tree
+            .drawn
+            .borrow()
+            .iter()
+            .all(|index| tree.previews.contains_key(index)));
+    }
+
+    #[test]
+    fn previews_leave_a_third_of_the_width_to_the_content() {
+        let mut tree = empty_tree(usize::MAX);
+        tree.show_previews = true;
+        let index = tree.open(&RecordRef::from_utf8_path("a-long-record-name"));
+        tree.previews
+            .insert(index, Some(ContentPreview::Summary("text".to_string())));
+
+        let buffer = draw_in(&tree, Theme::Colorblind.palette(0), 12, 2);
+        let row = (0..12).map(|x| buffer[(x, 1)].symbol()).collect::<String>();
+        assert_eq!(row, "›  a-…  text");
+    }
+
+    #[test]
+    fn opened_records_are_previewed_from_their_content() {
+        let mut tree = empty_tree(usize::MAX);
+        let record_ref = RecordRef::from_utf8_path("a");
+        let index = tree.open(&record_ref);
+        tree.previews.insert(index, Some(ContentPreview::Locked));
+
+        // A preview loaded before the record was opened does not replace its content.
+        preview_loaded(&mut tree, "a", ContentPreview::Unavailable);
+        assert_eq!(tree.previews[&index], Some(ContentPreview::Locked));
+    }
+
+    #[test]
+    fn hidden_previews_are_not_loaded() {
+        let mut tree = tree();
+        let palette = Theme::Colorblind.palette(0);
+        draw_in(&tree, palette, 20, 4);
+        assert!(tree.drawn.borrow().is_empty());
+
+        press(&mut tree, KeyCode::Char('p'));
+        draw_in(&tree, palette, 20, 2);
+        // Only the drawn rows are loaded, not the whole tree.
+        assert_eq!(tree.drawn.borrow().len(), 2);
+
+        // While the registry is being opened, the drawn nodes wait for the next tick.
+        tree.update(ComponentMessage::OnTick).unwrap();
+        assert_eq!(tree.drawn.borrow().len(), 2);
+        assert!(tree.previews.is_empty());
+
+        let cancellation = tree.previews_cancellation.clone();
+        tree.previews.insert(ROOT, None);
+        press(&mut tree, KeyCode::Char('p'));
+        assert!(cancellation.is_cancelled());
+        assert!(tree.previews.is_empty());
+        assert!(tree.drawn.borrow().is_empty());
+    }
+
+    #[test]
+    fn records_10_000_deep_are_revealed_up_to_the_expansion_depth() {
+        with_small_stack(|| {
+            let record_ref = deep_record_ref();
+            let mut tree = empty_tree(32);
+            tree.open(&record_ref);
+
+            assert_eq!(tree.record_count(), DEEP + 1);
+            // The root record followed by the 32 expanded records, the last of which is collapsed.
+            assert_eq!(tree.visible_nodes().len(), 33);
+            assert_eq!(tree.cursor_index(), 32);
+            assert!(tree.collapsed.contains(&tree.cursor));
+            assert_eq!(tree.record_ref(tree.cursor), record_ref.ancestor(32));
+
+            let buffer = draw_in(&tree, Theme::Colorblind.palette(0), 40, 4);
+            let last_row = (0..40).map(|x| buffer[(x, 3)].symbol()).collect::<String>();
+            assert_eq!(last_row, format!("›{}▸n31   ", " ".repeat(32)));
+
+            // Opening the record again adds no nodes.
+            tree.open(&record_ref);
+            assert_eq!(tree.record_count(), DEEP + 1);
+        });
+    }
+
+    #[test]
+    fn records_10_000_deep_are_revealed_when_fully_expanded() {
+        with_small_stack(|| {
+            let record_ref = deep_record_ref();
+            let mut tree = empty_tree(usize::MAX);
+            tree.insert_stale(&record_ref);
+            assert!(tree.is_stale());
+            tree.open(&record_ref);
+
+            assert!(!tree.is_stale());
+            assert_eq!(tree.visible_nodes().len(), DEEP + 1);
+            assert_eq!(tree.cursor_index(), DEEP);
+            assert_eq!(tree.record_ref(tree.cursor), record_ref);
+            assert_eq!(
+                tree.get_accessibility_node().unwrap().value(),
+                Some(record_ref.display_path(LossyNameDisplay::Mark).as_str())
+            );
+
+            // The cursor is scrolled into view, its row indented no further than the width.
+            let buffer = draw_in(&tree, Theme::Colorblind.palette(0), 20, 5);
+            assert_eq!(tree.scroll.get(), DEEP - 4);
+            assert_eq!(buffer[(0, 4)].symbol(), "›");
+
+            tree.collapse_or_leave();
+            assert_eq!(tree.record_ref(tree.cursor), record_ref.parent().unwrap());
+            assert_eq!(tree.pinned_roots(), vec![record_ref.ancestor(1)]);
+        });
+    }
+}
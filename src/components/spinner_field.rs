@@ -5,10 +5,15 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::action::Action;
 use crate::component::{Component, ComponentId};
 
+/// The frames of the animation, one cell wide each.
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// An animated symbol along with the label of what is pending, advanced on ticks.
 #[derive(Debug)]
 pub struct Spinner<'a> {
     id: ComponentId,
     text: Cow<'a, str>,
+    frame: usize,
 }
 
 impl<'a> Spinner<'a> {
@@ -16,7 +21,24 @@ impl<'a> Spinner<'a> {
     where
         Self: Sized,
     {
-        Self { id, text }
+        Self { id, text, frame: 0 }
+    }
+
+    pub fn set_text(&mut self, text: impl Into<Cow<'a, str>>) {
+        self.text = text.into();
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Moves on to the next frame of the animation.
+    pub fn advance(&mut self) {
+        self.frame = (self.frame + 1) % FRAMES.len();
+    }
+
+    pub fn get_symbol(&self) -> &'static str {
+        FRAMES[self.frame]
     }
 }
 
@@ -24,4 +46,10 @@ impl<'a> Component for Spinner<'a> {
     fn get_id(&self) -> ComponentId {
         self.id
     }
+
+    fn get_accessibility_node(&self) -> color_eyre::Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::ProgressIndicator);
+        node.set_label(self.text.as_ref());
+        Ok(node)
+    }
 }
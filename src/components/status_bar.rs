@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+
+use crate::action::{Action, ComponentMessage, Severity};
+use crate::component::{Component, ComponentId};
+use crate::theme::Palette;
+
+use super::header::{HeaderElement, Overflow};
+
+/// How long a status which is not an error is shown.
+const STATUS_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct Status {
+    text: String,
+    severity: Severity,
+    /// `None` for errors, which are shown until dismissed or replaced.
+    expires_at: Option<Instant>,
+}
+
+/// The most recent status message, shown in the header next to the version with the color of its
+/// severity. Set by `ComponentMessage::ShowNotice` and `ComponentMessage::ShowError`, which
+/// background tasks send with `Action::ShowStatus`.
+#[derive(Debug)]
+pub struct StatusBar {
+    id: ComponentId,
+    status: Option<Status>,
+}
+
+impl StatusBar {
+    pub fn new(id: ComponentId) -> Self {
+        Self { id, status: None }
+    }
+
+    /// Replaces the displayed status.
+    pub fn show(&mut self, text: String, severity: Severity) {
+        self.status = Some(Status {
+            text,
+            severity,
+            expires_at: match severity {
+                Severity::Info => Some(Instant::now() + STATUS_DURATION),
+                Severity::Error => None,
+            },
+        });
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.status.as_ref().map(|status| status.text.as_str())
+    }
+
+    /// Dismisses the displayed error, as Esc does. Returns whether there was one.
+    pub fn dismiss_error(&mut self) -> bool {
+        self.status
+            .take_if(|status| status.severity == Severity::Error)
+            .is_some()
+    }
+
+    /// The element the status is laid out in the header as, with the lowest priority, so that
+    /// it takes whatever room is left.
+    pub fn header_element(&self, palette: Palette) -> Option<HeaderElement<'_>> {
+        let status = self.status.as_ref()?;
+
+        Some(
+            HeaderElement::new(status.text.as_str(), 0)
+                .with_style(match status.severity {
+                    Severity::Info => palette.info_style(),
+                    Severity::Error => palette.error_style(),
+                })
+                .with_overflow(Overflow::Truncate),
+        )
+    }
+}
+
+impl Component for StatusBar {
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            ComponentMessage::ShowNotice { message, severity } => {
+                self.show(message, severity);
+                Some(Action::Render)
+            }
+            ComponentMessage::ShowError { error } => {
+                self.show(error, Severity::Error);
+                Some(Action::Render)
+            }
+            ComponentMessage::OnTick
+                if self
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.expires_at)
+                    .is_some_and(|expires_at| expires_at <= Instant::now()) =>
+            {
+                self.status = None;
+                Some(Action::Render)
+            }
+            _ => None,
+        })
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::Status);
+
+        if let Some(text) = self.text() {
+            node.set_value(text);
+        }
+
+        Ok(node)
+    }
+
+    #[cfg(any(test, feature = "introspection"))]
+    fn introspect(&self, state: &mut crate::introspection::UiState) {
+        state.notice = self
+            .status
+            .as_ref()
+            .map(|status| crate::introspection::NoticeState {
+                message: status.text.clone(),
+                severity: format!("{:?}", status.severity),
+            });
+    }
+}
@@ -0,0 +1,237 @@
+//! The versions of the opened record, listed in the overview pane while it is focused, to load
+//! one of them into the metadata and content panes.
+//!
+//! Versions are numbered from 1 in the order they were listed in, like the version count shown in
+//! the overview. Another version of the same record is opened by its key, so that the tree and the
+//! breadcrumbs stay in place.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::Line,
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::{Action, ComponentMessage},
+    component::{Component, ComponentId, DrawContext, Drawable, HandleEventSuccess},
+    text::{truncate_to_width, TruncateMode},
+    theme::SELECTED_MARKER,
+    tui::Event,
+};
+
+use super::main_view::{MainState, OpenedRecord};
+
+#[derive(Debug)]
+pub struct VersionSelector {
+    id: ComponentId,
+    main_state: Rc<RefCell<MainState>>,
+    /// The index of the selected version, counted from the latest one.
+    cursor: usize,
+}
+
+impl VersionSelector {
+    pub(super) fn new(
+        id: ComponentId,
+        _tx: &UnboundedSender<Action>,
+        main_state: &Rc<RefCell<MainState>>,
+    ) -> Self {
+        Self {
+            id,
+            main_state: main_state.clone(),
+            cursor: 0,
+        }
+    }
+
+    /// The index of the displayed version of the opened record, counted from the latest one.
+    fn displayed_index(opened_record: &OpenedRecord) -> usize {
+        opened_record
+            .versions
+            .iter()
+            .rev()
+            .position(|version| Some(*version) == opened_record.version())
+            .unwrap_or_default()
+    }
+
+    /// Describes the displayed version of the opened record, such as "2 of 3", or "1 (latest)".
+    pub(super) fn describe(opened_record: &OpenedRecord) -> String {
+        let count = opened_record.versions.len();
+        let number = count.saturating_sub(Self::displayed_index(opened_record));
+
+        if number == count {
+            format!("{number} (latest)")
+        } else {
+            format!("{number} of {count}")
+        }
+    }
+
+    fn move_cursor(&mut self, rows: isize) {
+        let count = self
+            .main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .map_or(0, |opened_record| opened_record.versions.len());
+        self.cursor = self
+            .cursor
+            .saturating_add_signed(rows)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Loads the selected version, unless it is the displayed one.
+    fn open_selected(&self) -> Option<Action> {
+        let main_state = self.main_state.borrow();
+        let opened_record = main_state.opened_record.as_ref()?;
+
+        if self.cursor == Self::displayed_index(opened_record) {
+            return None;
+        }
+
+        // The latest version is opened as such, so that it is the same entry of the history.
+        let version = match self.cursor {
+            0 => None,
+            index => Some(*opened_record.versions.iter().rev().nth(index)?),
+        };
+
+        Some(Action::BroadcastMessage(
+            ComponentMessage::OpenRecordVersion { version },
+        ))
+    }
+}
+
+impl Component for VersionSelector {
+    /// A single version leaves nothing to select.
+    fn is_focusable(&self) -> bool {
+        self.main_state
+            .borrow()
+            .opened_record
+            .as_ref()
+            .is_some_and(|opened_record| opened_record.versions.len() > 1)
+    }
+
+    fn update(&mut self, message: ComponentMessage) -> Result<Option<Action>> {
+        Ok(match message {
+            // The main view replaced the opened record already.
+            ComponentMessage::RecordOpen {
+                read_result: Some(_),
+                ..
+            } => {
+                self.cursor = self
+                    .main_state
+                    .borrow()
+                    .opened_record
+                    .as_ref()
+                    .map_or(0, Self::displayed_index);
+                Some(Action::Render)
+            }
+            _ => None,
+        })
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Result<HandleEventSuccess> {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind,
+            ..
+        }) = event
+        else {
+            return Ok(HandleEventSuccess::unhandled());
+        };
+
+        match (code, kind) {
+            (KeyCode::Up, KeyEventKind::Press | KeyEventKind::Repeat) => self.move_cursor(-1),
+            (KeyCode::Down, KeyEventKind::Press | KeyEventKind::Repeat) => self.move_cursor(1),
+            (KeyCode::Enter, KeyEventKind::Press) => {
+                let success = HandleEventSuccess::handled();
+
+                return Ok(match self.open_selected() {
+                    Some(action) => success.with_action(action),
+                    None => success,
+                });
+            }
+            _ => return Ok(HandleEventSuccess::unhandled()),
+        }
+
+        Ok(HandleEventSuccess::handled().with_action(Action::Render))
+    }
+
+    fn get_id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn get_accessibility_node(&self) -> Result<accesskit::Node> {
+        let mut node = accesskit::Node::new(accesskit::Role::ListBox);
+        node.set_label("Record versions");
+
+        if let Some(opened_record) = self.main_state.borrow().opened_record.as_ref() {
+            node.set_value(Self::describe(opened_record));
+        }
+
+        Ok(node)
+    }
+}
+
+impl Drawable for VersionSelector {
+    type Args<'a>
+        = ()
+    where
+        Self: 'a;
+
+    /// Lists the versions newest first, scrolled so that the selected one is displayed.
+    fn draw(&self, context: &mut DrawContext, area: Rect, (): Self::Args<'_>) -> Result<()> {
+        let main_state = self.main_state.borrow();
+        let Some(opened_record) = main_state.opened_record.as_ref() else {
+            return Ok(());
+        };
+        let palette = context.palette();
+        let focused = context.focused_id() == self.id;
+        let count = opened_record.versions.len();
+        let displayed_index = Self::displayed_index(opened_record);
+        let height = area.height as usize;
+        let scroll = self.cursor.saturating_sub(height.saturating_sub(1));
+
+        for (row, index) in (scroll..count).take(height).enumerate() {
+            let selected = focused && index == self.cursor;
+            let tags = match (index == 0, index == displayed_index) {
+                (true, true) => " (latest, shown)",
+                (true, false) => " (latest)",
+                (false, true) => " (shown)",
+                (false, false) => "",
+            };
+            let label = format!(
+                "{}version {}{tags}",
+                if selected { SELECTED_MARKER } else { " " },
+                count - index,
+            );
+            let (label, _) = truncate_to_width(&label, area.width as usize, TruncateMode::End);
+            let mut style = Style::new();
+
+            if index == displayed_index {
+                style = style.bold();
+            }
+
+            if selected {
+                style = style.patch(palette.focus_style());
+            }
+
+            let area_row = Rect {
+                y: area.y.saturating_add(row as u16),
+                height: 1,
+                ..area
+            }
+            .intersection(area);
+            context
+                .frame()
+                .render_widget(Line::styled(label, style), area_row);
+        }
+
+        context.register_mouse_area(self.id, area);
+        Ok(())
+    }
+}
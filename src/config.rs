@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::args::Args;
+use crate::encoding::Encoding;
+use crate::model::LossyNameDisplay;
+use crate::theme::Theme;
+use crate::words::WordBoundaries;
+
+/// Name of the optional configuration file inside a registry directory.
+/// Only loaded with `--trust-registry-config`, as the registry may come from an untrusted source.
+const REGISTRY_CONFIG_FILE_NAME: &str = ".rrr-tui.toml";
+
+/// User configuration, merged from the built-in defaults, the files in the config directory and
+/// the overrides for the opened registry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub editing: EditingConfig,
+    #[serde(default)]
+    pub open: OpenConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub feedback: FeedbackConfig,
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+    /// Refuses the features that write record contents to temporary files, such as interactive
+    /// custom commands.
+    #[serde(default)]
+    pub never_write_plaintext: bool,
+    /// External programs the opened record can be passed to, defined as `[[custom_command]]`.
+    #[serde(default, rename = "custom_command")]
+    pub custom_commands: Vec<CustomCommand>,
+    /// Overrides of the settings above, keyed by registry directory.
+    #[serde(default)]
+    pub registry: HashMap<String, Value>,
+    /// Where the effective value of each setting comes from, keyed by the path to the setting.
+    #[serde(skip)]
+    pub provenance: BTreeMap<Vec<String>, ConfigSource>,
+    /// All merged values, including those not recognized by this version of the application.
+    #[serde(skip)]
+    pub merged: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditingConfig {
+    /// Characters that separate words in word-wise operations, in addition to the Unicode word
+    /// boundaries. For example, `"-:/"` makes each part of a record path a separate word.
+    #[serde(default)]
+    pub word_separators: String,
+}
+
+impl EditingConfig {
+    pub fn word_boundaries(&self) -> WordBoundaries {
+        WordBoundaries::new(self.word_separators.chars())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenConfig {
+    /// The encoding initially selected for record names.
+    #[serde(default)]
+    pub default_encoding: Encoding,
+    /// What happens to a record that is requested to be opened while another one is loading.
+    #[serde(default)]
+    pub busy_policy: BusyPolicy,
+    /// How many opened records are kept to go back and forward to with Alt+Left and Alt+Right.
+    #[serde(default = "OpenConfig::default_max_history_length")]
+    pub max_history_length: usize,
+    /// How many bytes of content the records kept loaded by the history may take in total, the
+    /// ones farthest from the current record are loaded again when gone back or forward to.
+    #[serde(default = "OpenConfig::default_max_history_cache_bytes")]
+    pub max_history_cache_bytes: usize,
+}
+
+impl OpenConfig {
+    fn default_max_history_length() -> usize {
+        100
+    }
+
+    fn default_max_history_cache_bytes() -> usize {
+        64 * 1024 * 1024
+    }
+}
+
+impl Default for OpenConfig {
+    fn default() -> Self {
+        Self {
+            default_encoding: Default::default(),
+            busy_policy: Default::default(),
+            max_history_length: Self::default_max_history_length(),
+            max_history_cache_bytes: Self::default_max_history_cache_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyPolicy {
+    /// Open the record once the current one is loaded, replacing any previously queued one.
+    #[default]
+    Queue,
+    /// Ignore the request.
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// How record names that cannot be displayed faithfully in their encoding are displayed,
+    /// `"mark"` or `"hex"`.
+    #[serde(default)]
+    pub lossy_names: LossyNameDisplay,
+    /// The colors of the user interface, `"default"`, `"colorblind"`, `"dark"` or `"light"`.
+    #[serde(default)]
+    pub theme: Theme,
+    /// How deep the tree is expanded to reveal the opened record. Deeper records are revealed by
+    /// their ancestor at this depth, collapsed, so that deeply nested records do not flood the
+    /// tree.
+    #[serde(default = "DisplayConfig::default_max_tree_expansion_depth")]
+    pub max_tree_expansion_depth: usize,
+    /// Whether a preview of the content of each record is shown next to its name in the tree,
+    /// which `p` toggles.
+    #[serde(default)]
+    pub tree_previews: bool,
+}
+
+impl DisplayConfig {
+    fn default_max_tree_expansion_depth() -> usize {
+        32
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            lossy_names: Default::default(),
+            theme: Default::default(),
+            max_tree_expansion_depth: Self::default_max_tree_expansion_depth(),
+            tree_previews: false,
+        }
+    }
+}
+
+/// Cues drawing attention to the terminal when something happens while the user looks away.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedbackConfig {
+    #[serde(default)]
+    pub level: FeedbackLevel,
+    #[serde(default)]
+    pub method: FeedbackMethod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackLevel {
+    #[default]
+    Off,
+    Errors,
+    ErrorsAndCompletions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackMethod {
+    /// The terminal bell, which may be audible or visual, depending on the terminal.
+    #[default]
+    Bell,
+    /// The OSC 1337 attention request, which bounces the dock icon or flashes the taskbar entry
+    /// in terminals that support it.
+    Attention,
+    /// A brief flash of the edges of the screen, drawn by the application itself.
+    Flash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardConfig {
+    /// Copying more than this many bytes asks for a confirmation first, as some terminals are
+    /// slow to take, or silently drop, large copies.
+    #[serde(default = "ClipboardConfig::default_confirmation_size")]
+    pub confirmation_size: usize,
+}
+
+impl ClipboardConfig {
+    fn default_confirmation_size() -> usize {
+        64 * 1024
+    }
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            confirmation_size: Self::default_confirmation_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomCommand {
+    pub name: String,
+    /// The command line, run by the shell.
+    pub run: String,
+    /// What is passed to the program.
+    #[serde(default)]
+    pub input: CustomCommandInput,
+    /// The command is invoked by pressing this key along with `Alt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<char>,
+    /// Whether the program is interactive and takes over the terminal while it runs. Instead of
+    /// being piped in, the input is written to a temporary file, whose path is appended to the
+    /// command line.
+    #[serde(default)]
+    pub suspend: bool,
+    /// How long the program may run before it is stopped. Does not apply to interactive programs.
+    #[serde(default = "CustomCommand::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl CustomCommand {
+    fn default_timeout_secs() -> u64 {
+        10
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomCommandInput {
+    /// The content of the opened record.
+    #[default]
+    Content,
+    /// The name of the opened record, empty for the root record.
+    Name,
+    /// The metadata of the opened record, as a JSON object.
+    Metadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    /// A `[registry."<key>"]` section of the config files.
+    RegistryOverride(String),
+    /// The config file inside the registry directory.
+    RegistryFile(PathBuf),
+    Environment(String),
+    CommandLine,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "config file {}", path.display()),
+            Self::RegistryOverride(key) => write!(f, "registry override {key:?}"),
+            Self::RegistryFile(path) => write!(f, "registry config file {}", path.display()),
+            Self::Environment(variable) => write!(f, "environment variable {variable}"),
+            Self::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Configuration values layered on top of each other, tracking which layer set each value.
+#[derive(Debug, Default)]
+struct ConfigLayers {
+    merged: Value,
+    provenance: BTreeMap<Vec<String>, ConfigSource>,
+}
+
+impl ConfigLayers {
+    fn push(&mut self, source: ConfigSource, layer: Value) {
+        Self::merge(&mut self.merged, layer, &[], &source, &mut self.provenance);
+    }
+
+    /// Sets a single value at the dotted `path`.
+    fn push_dotted(&mut self, source: ConfigSource, path: &str, value: Value) {
+        let layer = path.rsplit('.').fold(value, |value, key| {
+            Value::Object([(key.to_string(), value)].into_iter().collect())
+        });
+        self.push(source, layer);
+    }
+
+    fn merge(
+        target: &mut Value,
+        layer: Value,
+        path: &[String],
+        source: &ConfigSource,
+        provenance: &mut BTreeMap<Vec<String>, ConfigSource>,
+    ) {
+        if let Value::Object(layer) = layer {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+
+            let Value::Object(target) = target else {
+                unreachable!();
+            };
+
+            for (key, value) in layer {
+                let mut path = path.to_vec();
+                path.push(key.clone());
+                let target = target.entry(key).or_insert(Value::Null);
+                Self::merge(target, value, &path, source, provenance);
+            }
+        } else {
+            provenance.retain(|key, _| !(key.len() > path.len() && key.starts_with(path)));
+            provenance.insert(path.to_vec(), source.clone());
+            *target = layer;
+        }
+    }
+
+    fn into_config(self) -> Result<Config> {
+        let mut config: Config = serde_json::from_value(self.merged.clone())?;
+        config.provenance = self.provenance;
+        config.merged = self.merged;
+        Ok(config)
+    }
+}
+
+impl Config {
+    /// Loads the configuration effective for the registry selected by `args`.
+    pub fn new(args: &Args) -> Result<Self> {
+        let config_dir = crate::env::get_config_dir();
+        let mut layers = ConfigLayers::default();
+
+        layers.push(
+            ConfigSource::Default,
+            serde_json::to_value(Self::default())?,
+        );
+
+        for path in [
+            config_dir.join("config.json5"),
+            config_dir.join("config.toml"),
+        ] {
+            if let Some(layer) = read_config_file(&path)? {
+                layers.push(ConfigSource::File(path), layer);
+            }
+        }
+
+        if let Some((key, layer)) =
+            Self::find_registry_overrides(&layers.merged, &args.registry_directory)
+        {
+            layers.push(ConfigSource::RegistryOverride(key), layer);
+        }
+
+        if args.trust_registry_config {
+            let path = args.registry_directory.join(REGISTRY_CONFIG_FILE_NAME);
+
+            if let Some(layer) = read_config_file(&path)? {
+                layers.push(ConfigSource::RegistryFile(path), layer);
+            }
+        }
+
+        // For example, `RRR_TUI_3__EDITING__WORD_SEPARATORS=-:` sets `editing.word_separators`.
+        let environment_prefix = format!("{}__", crate::env::PROJECT_NAME.to_uppercase());
+        let mut environment_variables = std::env::vars()
+            .filter(|(variable, _)| variable.starts_with(&environment_prefix))
+            .collect::<Vec<_>>();
+        environment_variables.sort();
+
+        for (variable, value) in environment_variables {
+            let path = variable[environment_prefix.len()..]
+                .to_lowercase()
+                .replace("__", ".");
+            layers.push_dotted(
+                ConfigSource::Environment(variable),
+                &path,
+                parse_setting_value(&value),
+            );
+        }
+
+        for setting in &args.set {
+            let (path, value) = setting
+                .split_once('=')
+                .ok_or_else(|| eyre!("Expected `--set <KEY>=<VALUE>`, got `--set {setting}`."))?;
+            layers.push_dotted(ConfigSource::CommandLine, path, parse_setting_value(value));
+        }
+
+        let config = layers.into_config()?;
+        debug!(?config_dir, ?config, "Config loaded.");
+        Ok(config)
+    }
+
+    /// Renders every setting with its effective value, commented with where the value comes from.
+    /// The output is valid TOML.
+    pub fn to_effective_toml(&self) -> String {
+        let mut output = String::new();
+
+        for (path, source) in &self.provenance {
+            if path.first().is_some_and(|key| key == "registry") {
+                continue; // Already merged into the effective values.
+            }
+
+            let key = path
+                .iter()
+                .map(|key| {
+                    if !key.is_empty()
+                        && key
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        key.clone()
+                    } else {
+                        toml::Value::String(key.clone()).to_string()
+                    }
+                })
+                .join(".");
+            let value = path
+                .iter()
+                .try_fold(&self.merged, |value, key| value.get(key))
+                .and_then(|value| toml::Value::try_from(value).ok());
+
+            output.push_str(&format!("# {source}\n"));
+
+            match value {
+                _ if is_secret_setting(path) => {
+                    output.push_str(&format!("{key} = \"<redacted>\"\n"));
+                }
+                Some(value) => output.push_str(&format!("{key} = {value}\n")),
+                None => output.push_str(&format!("# {key} is unset\n")),
+            }
+        }
+
+        output
+    }
+
+    /// Finds the `[registry."<key>"]` section whose key refers to `registry_directory`.
+    fn find_registry_overrides(
+        merged: &Value,
+        registry_directory: &Path,
+    ) -> Option<(String, Value)> {
+        let canonical_registry_directory = std::fs::canonicalize(registry_directory).ok();
+
+        merged
+            .get("registry")?
+            .as_object()?
+            .iter()
+            .find(|(key, _)| {
+                Path::new(key) == registry_directory
+                    || canonical_registry_directory.is_some()
+                        && std::fs::canonicalize(key).ok() == canonical_registry_directory
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+    }
+}
+
+/// Whether the value of the setting at `path` must not be shown.
+fn is_secret_setting(path: &[String]) -> bool {
+    path.iter().any(|key| is_secret_key(key))
+}
+
+/// Whether a setting or an environment variable named `key` is likely to hold a secret.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    [
+        "secret",
+        "password",
+        "passphrase",
+        "token",
+        "credential",
+        "api_key",
+    ]
+    .iter()
+    .any(|pattern| key.contains(pattern))
+}
+
+/// Parses a setting value given as a string, treating it as a plain string unless it is valid
+/// JSON5.
+fn parse_setting_value(value: &str) -> Value {
+    json5::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+/// Reads a JSON5 or TOML config file, depending on its extension.
+/// Returns `None` if the file does not exist.
+fn read_config_file(path: &Path) -> Result<Option<Value>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    let value: Result<Value> = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json5") => json5::from_str(&contents).map_err(Into::into),
+        Some("toml") => toml::from_str(&contents).map_err(Into::into),
+        _ => Err(eyre!("Unsupported config file format.")),
+    };
+
+    value
+        .map(Some)
+        .wrap_err_with(|| format!("Failed to read the config file {}", path.display()))
+}
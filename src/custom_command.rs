@@ -0,0 +1,143 @@
+//! Running the user-defined custom commands, which pass the opened record to external programs.
+
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::config::{is_secret_key, CustomCommand};
+use crate::tempfiles::TempFile;
+
+/// The exit status of shells when the program could not be found.
+const COMMAND_NOT_FOUND_EXIT_CODE: i32 = 127;
+
+/// The output of a custom command that ran to completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCommandOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: String,
+}
+
+/// Creates a shell process running `command_line`, with the environment stripped of variables
+/// that may hold secrets.
+fn shell_command(command_line: &str, arguments: &[&std::ffi::OsStr]) -> std::process::Command {
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(command_line).arg("sh");
+        command
+    };
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.arg("/C").arg(command_line);
+        command
+    };
+
+    command
+        .args(arguments)
+        .env_clear()
+        .envs(std::env::vars_os().filter(|(variable, _)| {
+            !variable
+                .to_str()
+                .is_none_or(is_secret_key)
+        }));
+    command
+}
+
+/// Describes a failed run in a way that suggests how to fix it.
+fn describe_failure(command: &CustomCommand, status: ExitStatus, stderr: &str) -> String {
+    let stderr = stderr.trim();
+
+    if status.code() == Some(COMMAND_NOT_FOUND_EXIT_CODE) {
+        let program = command.run.split_whitespace().next().unwrap_or_default();
+        format!(
+            "Custom command {:?}: `{program}` was not found, make sure it is installed and in PATH.",
+            command.name
+        )
+    } else if stderr.is_empty() {
+        format!("Custom command {:?} failed with {status}.", command.name)
+    } else {
+        format!(
+            "Custom command {:?} failed with {status}: {stderr}",
+            command.name
+        )
+    }
+}
+
+/// Runs the command with `input` on its stdin, capturing its output.
+/// The command is killed if it does not finish in time.
+pub async fn run_captured(command: &CustomCommand, input: Vec<u8>) -> Result<CustomCommandOutput> {
+    debug!(?command, "Running a custom command.");
+    let mut child = tokio::process::Command::from(shell_command(&command.run, &[]))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .wrap_err_with(|| format!("Failed to start the custom command {:?}", command.name))?;
+    let mut stdin = child.stdin.take().unwrap();
+
+    // The input is written concurrently, so that a program producing output before reading all of
+    // its input does not block.
+    let write_input = async move {
+        // The program may exit without reading all of its input, which is not an error.
+        let _ = stdin.write_all(&input).await;
+    };
+    let timeout = Duration::from_secs(command.timeout_secs);
+    let (_, output) = tokio::time::timeout(
+        timeout,
+        futures::future::join(write_input, child.wait_with_output()),
+    )
+    .await
+    .map_err(|_| {
+        eyre!(
+            "Custom command {:?} did not finish within {} seconds and was stopped, consider \
+             raising its `timeout_secs`.",
+            command.name,
+            command.timeout_secs
+        )
+    })?;
+    let output = output?;
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    if !output.status.success() {
+        return Err(eyre!(describe_failure(command, output.status, &stderr)));
+    }
+
+    Ok(CustomCommandOutput {
+        stdout: output.stdout,
+        stderr,
+    })
+}
+
+/// Runs an interactive command in the terminal, which must not be used by the application in the
+/// meantime. The input is written to a temporary file, whose path is appended to the command line.
+pub fn run_in_terminal(command: &CustomCommand, input: &[u8]) -> Result<()> {
+    debug!(?command, "Running an interactive custom command.");
+    let input_file =
+        TempFile::create(input).wrap_err("Failed to write the input of the custom command")?;
+    let input_path = input_file.path();
+
+    #[cfg(not(windows))]
+    let command_line = format!("{} \"$1\"", command.run);
+    #[cfg(windows)]
+    let command_line = format!("{} \"{}\"", command.run, input_path.display());
+    #[cfg(not(windows))]
+    let arguments = [input_path.as_os_str()];
+    #[cfg(windows)]
+    let arguments: [&std::ffi::OsStr; 0] = [];
+
+    let status = shell_command(&command_line, &arguments).status();
+    drop(input_file);
+    let status = status
+        .wrap_err_with(|| format!("Failed to start the custom command {:?}", command.name))?;
+
+    if !status.success() {
+        return Err(eyre!(describe_failure(command, status, "")));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use itertools::Itertools;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{EncodingError, NameEncoding};
+
+/// Pairs of hexadecimal digits, optionally separated by whitespace.
+#[derive(Debug)]
+pub struct Hex;
+
+impl NameEncoding for Hex {
+    fn id(&self) -> &'static str {
+        "hex"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Hexadecimal Byte String"
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        format!("{:02x}", bytes.iter().format(""))
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        let mut bytes = Vec::new();
+        let mut high_nibble = None;
+
+        for (position, character) in text.char_indices() {
+            if character.is_whitespace() && high_nibble.is_none() {
+                continue;
+            }
+
+            let nibble = character.to_digit(16).ok_or_else(|| {
+                EncodingError::new(
+                    position,
+                    format!("`{character}` is not a hexadecimal digit"),
+                )
+            })? as u8;
+
+            match high_nibble.take() {
+                Some(high_nibble) => bytes.push((high_nibble << 4) | nibble),
+                None => high_nibble = Some(nibble),
+            }
+        }
+
+        if high_nibble.is_some() {
+            return Err(EncodingError::new(
+                text.len(),
+                "expected another hexadecimal digit",
+            ));
+        }
+
+        Ok(bytes)
+    }
+
+    /// Like the other encodings, counts grapheme clusters, so that an invalid digit which takes
+    /// more than one character is counted once.
+    fn describe_length(&self, text: &str) -> String {
+        let digits = text
+            .graphemes(true)
+            .filter(|grapheme| !grapheme.chars().all(char::is_whitespace))
+            .count();
+        format!("{} B ({digits} hex)", digits / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_pairs_of_digits() {
+        assert_eq!(Hex.decode("").unwrap(), b"");
+        assert_eq!(Hex.decode("6162").unwrap(), b"ab");
+        assert_eq!(Hex.decode("AbCd").unwrap(), [0xab, 0xcd]);
+        assert_eq!(Hex.decode(" 61\t62 \n").unwrap(), b"ab");
+    }
+
+    #[test]
+    fn encodes_pairs_of_lowercase_digits() {
+        assert_eq!(Hex.encode(&[0xab, 0x01, 0x00]), "ab0100");
+        assert!(Hex.is_lossless(&[0x00, 0xff]));
+    }
+
+    #[test]
+    fn an_odd_digit_count_fails_at_the_end() {
+        let error = Hex.decode("616").unwrap_err();
+        assert_eq!(error.position, 3);
+        assert_eq!(error.message, "expected another hexadecimal digit");
+
+        assert_eq!(Hex.decode("61 6").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn an_invalid_digit_fails_at_its_byte_offset() {
+        let error = Hex.decode("6g").unwrap_err();
+        assert_eq!(error.position, 1);
+        assert_eq!(error.message, "`g` is not a hexadecimal digit");
+
+        assert_eq!(Hex.decode("61é").unwrap_err().position, 2);
+        assert_eq!(Hex.decode("é61").unwrap_err().position, 0);
+        assert_eq!(Hex.decode("0x61").unwrap_err().position, 1);
+    }
+
+    #[test]
+    fn whitespace_within_a_byte_is_invalid() {
+        let error = Hex.decode("6 1").unwrap_err();
+        assert_eq!(error.position, 1);
+        assert_eq!(error.message, "` ` is not a hexadecimal digit");
+    }
+
+    #[test]
+    fn describes_the_length_in_bytes_and_digits() {
+        assert_eq!(Hex.describe_length(""), "0 B (0 hex)");
+        assert_eq!(Hex.describe_length("6162"), "2 B (4 hex)");
+        assert_eq!(Hex.describe_length(" 61 62\t"), "2 B (4 hex)");
+        assert_eq!(Hex.describe_length("616"), "1 B (3 hex)");
+    }
+
+    #[test]
+    fn describes_the_length_of_invalid_digits_in_graphemes() {
+        assert_eq!(
+            Hex.describe_length("61\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"),
+            "1 B (3 hex)"
+        );
+        assert_eq!(Hex.describe_length("6e\u{301}"), "1 B (2 hex)");
+        assert_eq!(Hex.describe_length("\u{1f1e8}\u{1f1ff}61"), "1 B (3 hex)");
+    }
+}
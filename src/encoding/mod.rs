@@ -0,0 +1,286 @@
+//! Encodings of record names, i.e. how the bytes of a name are typed in and displayed.
+//!
+//! To add an encoding, implement `NameEncoding` in a new submodule and register it in
+//! `ENCODINGS`.
+
+use std::fmt::{Debug, Display};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use unicode_segmentation::UnicodeSegmentation;
+
+mod hex;
+mod utf8;
+
+pub trait NameEncoding: Debug + Send + Sync {
+    /// A stable identifier, used in config files and on the command line.
+    fn id(&self) -> &'static str;
+    fn display_name(&self) -> &'static str;
+    fn encode(&self, bytes: &[u8]) -> String;
+    fn decode(&self, text: &str) -> Result<Vec<u8>, EncodingError>;
+
+    /// Whether `encode` represents the bytes faithfully, so that decoding its output produces the
+    /// same bytes.
+    fn is_lossless(&self, bytes: &[u8]) -> bool {
+        self.decode(&self.encode(bytes))
+            .is_ok_and(|decoded| decoded == bytes)
+    }
+
+    /// A compact description of the length of the text being typed in, e.g. `34 ch`.
+    /// Characters are counted as grapheme clusters.
+    fn describe_length(&self, text: &str) -> String {
+        format!("{} ch", text.graphemes(true).count())
+    }
+}
+
+/// All available encodings, in the order they are offered in.
+const ENCODINGS: &[&dyn NameEncoding] = &[&utf8::Utf8, &hex::Hex];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingError {
+    /// The byte offset within the decoded text where decoding failed.
+    pub position: usize,
+    pub message: String,
+}
+
+impl EncodingError {
+    pub fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// A handle to one of the registered encodings, compared by its ID.
+#[derive(Clone, Copy)]
+pub struct Encoding(&'static dyn NameEncoding);
+
+impl Encoding {
+    pub const UTF8: Self = Self(&utf8::Utf8);
+    pub const HEX: Self = Self(&hex::Hex);
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        ENCODINGS.iter().map(|encoding| Self(*encoding))
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::all().find(|encoding| encoding.id() == id)
+    }
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self::UTF8
+    }
+}
+
+impl Deref for Encoding {
+    type Target = dyn NameEncoding;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl PartialEq for Encoding {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Encoding {}
+
+impl Debug for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Encoding({})", self.id())
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::from_id(id).ok_or_else(|| {
+            format!(
+                "unknown encoding `{id}`, expected one of: {}",
+                Self::all().map(|encoding| encoding.id()).join(", ")
+            )
+        })
+    }
+}
+
+impl Serialize for Encoding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for Encoding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{LossyNameDisplay, RecordRef, LOSSY_NAME_MARKER};
+
+    /// Byte strings which are not valid UTF-8, each in a different way.
+    const INVALID_UTF8: &[&[u8]] = &[
+        // A byte which never occurs in UTF-8.
+        b"\xff",
+        // A continuation byte without a leading byte.
+        b"a\x80b",
+        // A sequence cut short by the end of the name.
+        b"caf\xc3",
+        // A sequence cut short by another character.
+        b"\xe2\x82z",
+        // An overlong encoding of `/`.
+        b"\xc0\xaf",
+        // An encoded UTF-16 surrogate.
+        b"\xed\xa0\x80",
+        // A code point above U+10FFFF.
+        b"\xf4\x90\x80\x80",
+        // Valid characters around an invalid byte.
+        b"\xc3\xa9\xff\xf0\x9f\x92\xa5",
+    ];
+
+    fn invalid_utf8_fixtures() -> impl Iterator<Item = Vec<u8>> {
+        INVALID_UTF8.iter().map(|fixture| fixture.to_vec()).chain([
+            // Valid characters followed by one cut off mid-character.
+            [&"é💥".as_bytes()[..4], b"\xff"].concat(),
+        ])
+    }
+
+    #[test]
+    fn invalid_utf8_fixtures_are_invalid() {
+        for fixture in invalid_utf8_fixtures() {
+            assert!(std::str::from_utf8(&fixture).is_err(), "{fixture:x?}");
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_encoded_by_every_encoding() {
+        for fixture in invalid_utf8_fixtures() {
+            for encoding in Encoding::all() {
+                // Must not panic, whether or not it is lossless.
+                let encoded = encoding.encode(&fixture);
+                assert!(!encoded.is_empty(), "{fixture:x?} in {encoding}");
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_round_trips_through_hex() {
+        for fixture in invalid_utf8_fixtures() {
+            assert_eq!(
+                Encoding::HEX.decode(&Encoding::HEX.encode(&fixture)),
+                Ok(fixture.clone())
+            );
+            assert!(Encoding::HEX.is_lossless(&fixture));
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_lossy_in_utf8() {
+        for fixture in invalid_utf8_fixtures() {
+            let encoded = Encoding::UTF8.encode(&fixture);
+
+            assert!(encoded.contains('\u{fffd}'), "{fixture:x?}");
+            assert!(!Encoding::UTF8.is_lossless(&fixture), "{fixture:x?}");
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_names_round_trip_through_record_refs() {
+        for fixture in invalid_utf8_fixtures() {
+            let record_ref =
+                RecordRef::from_utf8_path("parent").child(fixture.clone(), Encoding::UTF8);
+
+            assert!(record_ref.has_invalid_utf8_name(), "{fixture:x?}");
+            assert!(record_ref
+                .display_path(LossyNameDisplay::Mark)
+                .ends_with(LOSSY_NAME_MARKER));
+            assert_eq!(
+                record_ref.display_path(LossyNameDisplay::Hex),
+                format!("/706172656e74/{} (hex)", Encoding::HEX.encode(&fixture))
+            );
+
+            let from_key =
+                RecordRef::from_path_key(&record_ref.path_key(), Encoding::UTF8).unwrap();
+            assert_eq!(from_key.path(), record_ref.path());
+
+            let serialized = serde_json::to_string(&record_ref).unwrap();
+            let deserialized: RecordRef = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.path(), [b"parent".to_vec(), fixture]);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_content_is_warned_about() {
+        for fixture in invalid_utf8_fixtures() {
+            assert_eq!(
+                crate::text::invalid_utf8_warning(false, &fixture),
+                Some("content contains invalid UTF-8")
+            );
+        }
+        assert_eq!(
+            crate::text::invalid_utf8_warning(false, "é".as_bytes()),
+            None
+        );
+    }
+
+    #[test]
+    fn describes_the_length_in_graphemes() {
+        assert_eq!(Encoding::UTF8.describe_length(""), "0 ch");
+        assert_eq!(Encoding::UTF8.describe_length("abc"), "3 ch");
+        assert_eq!(Encoding::UTF8.describe_length("a\r\nb"), "3 ch");
+    }
+
+    #[test]
+    fn describes_the_length_of_emoji_and_combining_sequences_in_graphemes() {
+        // A family joined by zero-width joiners.
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(Encoding::UTF8.describe_length(family), "1 ch");
+        // A thumbs up with a skin tone modifier.
+        assert_eq!(
+            Encoding::UTF8.describe_length("a\u{1f44d}\u{1f3fd}b"),
+            "3 ch"
+        );
+        // Two flags, each made of two regional indicators.
+        let flags = "\u{1f1e8}\u{1f1ff}\u{1f1e9}\u{1f1ea}";
+        assert_eq!(Encoding::UTF8.describe_length(flags), "2 ch");
+        // An `e` followed by a combining acute accent, and a precomposed `é`.
+        assert_eq!(Encoding::UTF8.describe_length("e\u{301}\u{e9}"), "2 ch");
+    }
+
+    #[test]
+    fn encodings_are_found_by_their_id() {
+        for encoding in Encoding::all() {
+            assert_eq!(Encoding::from_id(encoding.id()), Some(encoding));
+            assert_eq!(encoding.id().parse::<Encoding>(), Ok(encoding));
+        }
+
+        assert!("utf-16".parse::<Encoding>().is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use super::{EncodingError, NameEncoding};
+
+#[derive(Debug)]
+pub struct Utf8;
+
+impl NameEncoding for Utf8 {
+    fn id(&self) -> &'static str {
+        "utf8"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "UTF-8"
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        Ok(text.as_bytes().to_vec())
+    }
+
+    fn is_lossless(&self, bytes: &[u8]) -> bool {
+        std::str::from_utf8(bytes).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_every_text() {
+        assert_eq!(Utf8.decode("").unwrap(), b"");
+        assert_eq!(Utf8.decode("a/é 💥").unwrap(), "a/é 💥".as_bytes());
+    }
+
+    #[test]
+    fn invalid_utf8_is_encoded_lossily() {
+        assert_eq!(Utf8.encode(b"a\xffb"), "a\u{fffd}b");
+        assert!(!Utf8.is_lossless(b"a\xffb"));
+        assert!(Utf8.is_lossless("é".as_bytes()));
+        // The replacement character itself is valid UTF-8.
+        assert!(Utf8.is_lossless("\u{fffd}".as_bytes()));
+    }
+}
@@ -0,0 +1,193 @@
+//! Statistics of the content hinting at whether it is compressed or encrypted, or structured,
+//! for triaging unknown binary records.
+
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression};
+use tokio_util::sync::CancellationToken;
+
+use crate::text::format_size;
+
+/// The size of the blocks the entropy is computed for.
+pub const BLOCK_SIZE: usize = 4 * 1024;
+
+/// Only this many bytes at the start of the content are analyzed, to bound the time it takes.
+const MAX_ANALYZED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Content with at least this many bits of entropy per byte is hardly compressible.
+const HIGH_ENTROPY: f64 = 7.5;
+
+/// Content with at most this many bits of entropy per byte is likely text or structured data.
+const LOW_ENTROPY: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentStats {
+    /// The Shannon entropy of each block of `BLOCK_SIZE` bytes, in bits per byte.
+    pub block_entropies: Vec<f64>,
+    /// The size of the analyzed content compressed with zlib, relative to its size.
+    pub compression_ratio: f64,
+    /// The number of bytes analyzed, which is less than the size of larger content.
+    pub analyzed_size: usize,
+    pub truncated: bool,
+}
+
+impl ContentStats {
+    /// The mean entropy of the blocks, in bits per byte.
+    pub fn entropy(&self) -> f64 {
+        if self.block_entropies.is_empty() {
+            return 0.0;
+        }
+
+        self.block_entropies.iter().sum::<f64>() / self.block_entropies.len() as f64
+    }
+
+    /// A one-line summary, such as "entropy 7.97 bits/byte, compresses to 100%, likely
+    /// compressed or encrypted".
+    pub fn summary(&self) -> String {
+        let entropy = self.entropy();
+        let verdict = if entropy >= HIGH_ENTROPY {
+            "likely compressed or encrypted"
+        } else if entropy <= LOW_ENTROPY {
+            "likely text or structured"
+        } else {
+            "mixed"
+        };
+        let mut summary = format!(
+            "entropy {entropy:.2} bits/byte, compresses to {:.0}%, {verdict}",
+            self.compression_ratio * 100.0
+        );
+
+        if self.truncated {
+            summary.push_str(&format!(
+                " (first {} only)",
+                format_size(self.analyzed_size)
+            ));
+        }
+
+        summary
+    }
+}
+
+/// The Shannon entropy of `data`, in bits per byte.
+fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0_usize; 256];
+
+    for byte in data {
+        counts[*byte as usize] += 1;
+    }
+
+    counts
+        .into_iter()
+        .filter(|count| *count > 0)
+        .map(|count| {
+            let probability = count as f64 / data.len() as f64;
+            // Rather than `-probability * probability.log2()`, which makes a single distinct byte
+            // have an entropy of -0, displayed with its sign.
+            probability * (1.0 / probability).log2()
+        })
+        .sum()
+}
+
+/// Analyzes the content block by block, returning `None` if cancelled in between.
+/// Blocking, so it is meant to be run with `tokio::task::spawn_blocking`.
+pub fn analyze(data: &[u8], cancellation: &CancellationToken) -> Option<ContentStats> {
+    let analyzed = &data[..std::cmp::min(data.len(), MAX_ANALYZED_SIZE)];
+    let mut block_entropies = Vec::with_capacity(analyzed.len().div_ceil(BLOCK_SIZE));
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+    for block in analyzed.chunks(BLOCK_SIZE) {
+        if cancellation.is_cancelled() {
+            return None;
+        }
+
+        block_entropies.push(shannon_entropy(block));
+        // Writing to a `Vec` does not fail.
+        encoder.write_all(block).ok()?;
+    }
+
+    let compressed_size = encoder.finish().ok()?.len();
+
+    Some(ContentStats {
+        block_entropies,
+        compression_ratio: compressed_size as f64 / std::cmp::max(1, analyzed.len()) as f64,
+        analyzed_size: analyzed.len(),
+        truncated: analyzed.len() < data.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &str = "The quick brown fox jumps over the lazy dog, while the five boxing \
+        wizards jump quickly. Records are kept in a registry, and each of them may have any \
+        number of sub-records, which are found by their names. ";
+
+    fn analyze(data: &[u8]) -> ContentStats {
+        super::analyze(data, &CancellationToken::new()).unwrap()
+    }
+
+    /// Bytes from a fixed xorshift generator, which are as good as random for the statistics.
+    fn random_bytes(length: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zeros_have_no_entropy() {
+        let stats = analyze(&[0; 4 * BLOCK_SIZE]);
+
+        assert_eq!(stats.block_entropies, [0.0; 4]);
+        assert!(stats.compression_ratio < 0.05, "{stats:?}");
+        assert!(stats.summary().starts_with("entropy 0.00 bits/byte"));
+        assert!(stats.summary().ends_with("likely text or structured"));
+    }
+
+    #[test]
+    fn english_text_has_the_entropy_of_text() {
+        let text = ENGLISH.repeat(4 * BLOCK_SIZE / ENGLISH.len());
+        let stats = analyze(text.as_bytes());
+
+        assert!((3.5..LOW_ENTROPY).contains(&stats.entropy()), "{stats:?}");
+        assert!(stats.summary().ends_with("likely text or structured"));
+    }
+
+    #[test]
+    fn random_bytes_have_the_most_entropy_and_do_not_compress() {
+        let stats = analyze(&random_bytes(4 * BLOCK_SIZE));
+
+        assert!(stats.entropy() > 7.9, "{stats:?}");
+        assert!((0.99..1.01).contains(&stats.compression_ratio), "{stats:?}");
+        assert!(stats.summary().contains("compresses to 100%"));
+        assert!(stats.summary().ends_with("likely compressed or encrypted"));
+    }
+
+    #[test]
+    fn each_block_is_measured_separately() {
+        let mut data = vec![0; BLOCK_SIZE];
+        data.extend(random_bytes(BLOCK_SIZE));
+        let stats = analyze(&data);
+
+        assert_eq!(stats.block_entropies.len(), 2);
+        assert_eq!(stats.block_entropies[0], 0.0);
+        assert!(stats.block_entropies[1] > 7.9, "{stats:?}");
+        assert!(!stats.truncated);
+        assert_eq!(stats.analyzed_size, data.len());
+    }
+
+    #[test]
+    fn cancelled_analysis_returns_nothing() {
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        assert_eq!(super::analyze(&[0; BLOCK_SIZE], &cancellation), None);
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)] // Remove this once you start using the code
 
 use std::env;
+use std::path::{Path, PathBuf};
 
+use directories::ProjectDirs;
 use lazy_static::lazy_static;
 
 
@@ -9,4 +11,212 @@ lazy_static! {
     pub static ref PKG_NAME: String = env!("CARGO_PKG_NAME").to_string();
     pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_string();
     pub static ref PROJECT_VERSION: String = env!("CARGO_PKG_VERSION").to_string();
+    pub static ref CONFIG_FOLDER: Option<PathBuf> =
+        env::var(format!("{}_CONFIG", PROJECT_NAME.to_uppercase()))
+            .ok()
+            .map(PathBuf::from);
+    pub static ref STATE_FOLDER: Option<PathBuf> =
+        env::var(format!("{}_STATE", PROJECT_NAME.to_uppercase()))
+            .ok()
+            .map(PathBuf::from);
+}
+
+fn project_directory() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", env!("CARGO_PKG_NAME"))
+}
+
+/// The directory containing user-editable configuration files.
+/// Can be overridden with the `RRR_TUI_3_CONFIG` environment variable.
+pub fn get_config_dir() -> PathBuf {
+    if cfg!(test) {
+        get_test_dir().join("config")
+    } else if let Some(config_folder) = CONFIG_FOLDER.clone() {
+        config_folder
+    } else if let Some(project_directory) = project_directory() {
+        project_directory.config_local_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".config")
+    }
+}
+
+/// The directory for application state that is not meant to be edited by the user.
+/// Can be overridden with the `RRR_TUI_3_STATE` environment variable.
+pub fn get_state_dir() -> PathBuf {
+    if cfg!(test) {
+        get_test_dir().join("state")
+    } else if let Some(state_folder) = STATE_FOLDER.clone() {
+        state_folder
+    } else if let Some(project_directory) = project_directory() {
+        project_directory
+            .state_dir()
+            .unwrap_or_else(|| project_directory.data_local_dir())
+            .to_path_buf()
+    } else {
+        PathBuf::from(".").join(".state")
+    }
+}
+
+/// Where tests keep their config and state, so that they never touch those of the user.
+fn get_test_dir() -> PathBuf {
+    env::temp_dir().join(format!("rrr-tui-test-{}", std::process::id()))
+}
+
+/// FNV-1a, because the hash must remain stable across builds.
+fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The identity of the registry at `registry_directory`, the hash of its `registry.cbor` file,
+/// which holds the parameters the registry was created with. `None` if the file is unreadable.
+pub fn get_registry_id(registry_directory: &Path) -> Option<String> {
+    let registry_config = std::fs::read(registry_directory.join("registry.cbor")).ok()?;
+    Some(format!("id-{:016x}", stable_hash(&registry_config)))
+}
+
+/// The state directory specific to the registry at `registry_directory`.
+///
+/// The state is keyed by the identity of the registry, see `get_registry_id`. The state therefore follows the
+/// registry when it is moved or synced to another path, while different registries that were
+/// once at the same path keep separate state. Registries without a readable `registry.cbor`
+/// fall back to being keyed by their path.
+pub fn get_registry_state_dir(registry_directory: &Path) -> PathBuf {
+    let registry_directory =
+        std::fs::canonicalize(registry_directory).unwrap_or_else(|_| registry_directory.into());
+    let path_hash = stable_hash(registry_directory.as_os_str().as_encoded_bytes());
+    let registries_dir = get_state_dir().join("registries");
+    let Some(registry_id) = get_registry_id(&registry_directory) else {
+        return registries_dir.join(format!("{path_hash:016x}"));
+    };
+    let state_dir = registries_dir.join(registry_id);
+
+    // Migrates the state that was keyed by the path, before registries were identified.
+    let legacy_state_dir = registries_dir.join(format!("{path_hash:016x}"));
+
+    if !state_dir.exists()
+        && legacy_state_dir.is_dir()
+        && let Err(error) = std::fs::rename(&legacy_state_dir, &state_dir)
+    {
+        tracing::warn!(?legacy_state_dir, %error, "Failed to migrate the registry state.");
+        return legacy_state_dir;
+    }
+
+    // The last known path, only as a hint for the user browsing the state directory.
+    if state_dir.is_dir() {
+        let _ = std::fs::write(
+            state_dir.join("registry-path"),
+            registry_directory.as_os_str().as_encoded_bytes(),
+        );
+    }
+
+    state_dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry directory of its own for each test, whose `registry.cbor` holds `config`.
+    fn registry_dir(test_name: &str, config: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!(
+            "rrr-tui-env-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("registry.cbor"),
+            format!("{config}-{}", std::process::id()),
+        )
+        .unwrap();
+        std::fs::canonicalize(directory).unwrap()
+    }
+
+    /// Where the state of the registry at `registry_directory` was kept before registries were
+    /// identified.
+    fn legacy_state_dir(registry_directory: &Path) -> PathBuf {
+        let path_hash = stable_hash(registry_directory.as_os_str().as_encoded_bytes());
+        get_state_dir()
+            .join("registries")
+            .join(format!("{path_hash:016x}"))
+    }
+
+    #[test]
+    fn state_follows_a_moved_registry() {
+        let registry_directory = registry_dir("moved", "moved");
+        let state_dir = get_registry_state_dir(&registry_directory);
+        std::fs::create_dir_all(&state_dir).unwrap();
+        std::fs::write(state_dir.join("session.json"), "{}").unwrap();
+
+        let moved_directory = registry_directory.with_file_name(format!(
+            "rrr-tui-env-{}-moved-elsewhere",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&moved_directory);
+        std::fs::rename(&registry_directory, &moved_directory).unwrap();
+
+        assert_eq!(get_registry_state_dir(&moved_directory), state_dir);
+        assert!(state_dir.join("session.json").is_file());
+        // The new path is adopted as the hint.
+        assert_eq!(
+            std::fs::read(state_dir.join("registry-path")).unwrap(),
+            moved_directory.as_os_str().as_encoded_bytes()
+        );
+    }
+
+    #[test]
+    fn path_keyed_state_is_migrated_and_follows_the_registry() {
+        let registry_directory = registry_dir("migrated", "migrated");
+        let legacy_state_dir = legacy_state_dir(&registry_directory);
+        std::fs::create_dir_all(&legacy_state_dir).unwrap();
+        std::fs::write(legacy_state_dir.join("session.json"), "{}").unwrap();
+
+        let state_dir = get_registry_state_dir(&registry_directory);
+
+        assert_ne!(state_dir, legacy_state_dir);
+        assert!(!legacy_state_dir.exists());
+        assert!(state_dir.join("session.json").is_file());
+
+        let moved_directory = registry_directory.with_file_name(format!(
+            "rrr-tui-env-{}-migrated-elsewhere",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&moved_directory);
+        std::fs::rename(&registry_directory, &moved_directory).unwrap();
+
+        assert_eq!(get_registry_state_dir(&moved_directory), state_dir);
+        assert!(state_dir.join("session.json").is_file());
+    }
+
+    #[test]
+    fn registries_once_at_the_same_path_keep_separate_state() {
+        let registry_directory = registry_dir("replaced", "first");
+        let first_state_dir = get_registry_state_dir(&registry_directory);
+        std::fs::create_dir_all(&first_state_dir).unwrap();
+        std::fs::write(first_state_dir.join("session.json"), "{}").unwrap();
+
+        std::fs::write(
+            registry_directory.join("registry.cbor"),
+            format!("second-{}", std::process::id()),
+        )
+        .unwrap();
+        let second_state_dir = get_registry_state_dir(&registry_directory);
+
+        assert_ne!(second_state_dir, first_state_dir);
+        assert!(!second_state_dir.join("session.json").exists());
+        assert!(first_state_dir.join("session.json").is_file());
+    }
+
+    #[test]
+    fn registry_without_a_config_is_keyed_by_its_path() {
+        let registry_directory = registry_dir("unidentified", "unidentified");
+        std::fs::remove_file(registry_directory.join("registry.cbor")).unwrap();
+
+        assert_eq!(get_registry_id(&registry_directory), None);
+        assert_eq!(
+            get_registry_state_dir(&registry_directory),
+            legacy_state_dir(&registry_directory)
+        );
+    }
 }
@@ -1,8 +1,16 @@
+use std::cell::Cell;
 use std::env;
 
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use tracing::error;
 
+thread_local! {
+    /// Whether a panic is going to be caught by `catch_panic`, in which case the panic hook only
+    /// logs it.
+    static CATCHING_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
 pub fn init() -> Result<()> {
     let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
         .panic_section(format!(
@@ -15,8 +23,17 @@ pub fn init() -> Result<()> {
         .into_hooks();
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
+        if CATCHING_PANICS.get() {
+            let msg = format!("{}", panic_hook.panic_report(panic_info));
+            error!("Caught panic: {}", strip_ansi_escapes::strip_str(msg));
+            return;
+        }
+
+        crate::tempfiles::remove_all();
+
         let span = tracing::info_span!("panic_hook");
-        if let Ok(mut t) = crate::tui::Tui::new(span) {
+        // Everything the application may have enabled is disabled, which has no effect otherwise.
+        if let Ok(mut t) = crate::tui::Tui::new(span).map(|t| t.mouse(true).paste(true)) {
             if let Err(r) = t.exit() {
                 error!("Unable to exit Terminal: {:?}", r);
             }
@@ -50,6 +67,22 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// Runs `f`, turning a panic into an error instead of exiting the application.
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R> {
+    let previous = CATCHING_PANICS.replace(true);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CATCHING_PANICS.set(previous);
+
+    result.map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_string());
+        eyre!("Panicked: {message}")
+    })
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
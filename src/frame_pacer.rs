@@ -0,0 +1,149 @@
+//! Pacing of the rendered frames, so that floods of render requests from background tasks or held
+//! keys are coalesced into at most `frame_rate` frames per second, each of the latest state.
+
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// The maximum number of queued events handled before the actions they produced are processed.
+pub const MAX_EVENTS_PER_ITERATION: usize = 64;
+
+/// The event received first along with the events that are already queued up after it, at most
+/// `MAX_EVENTS_PER_ITERATION` in all, so that input is handled before the next frame is rendered
+/// rather than one event per frame.
+pub fn take_queued<T>(first: T, queue: &mut UnboundedReceiver<T>) -> impl Iterator<Item = T> + '_ {
+    std::iter::once(first)
+        .chain(std::iter::from_fn(|| queue.try_recv().ok()))
+        .take(MAX_EVENTS_PER_ITERATION)
+}
+
+#[derive(Debug)]
+pub struct FramePacer {
+    frame_interval: Duration,
+    /// Whether anything changed since the last frame was rendered.
+    needs_render: bool,
+    last_render_at: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(frame_rate: f64) -> Self {
+        Self {
+            frame_interval: Duration::from_secs_f64(1.0 / frame_rate),
+            needs_render: true,
+            last_render_at: None,
+        }
+    }
+
+    /// Requests a frame, rendered once it is due. Any number of requests result in a single
+    /// frame.
+    pub fn request(&mut self) {
+        self.needs_render = true;
+    }
+
+    /// Whether a frame was requested since the last one, and the last one was rendered at least a
+    /// frame interval before `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.needs_render
+            && self.last_render_at.is_none_or(|last_render_at| {
+                now.duration_since(last_render_at) >= self.frame_interval
+            })
+    }
+
+    pub fn rendered(&mut self, now: Instant) {
+        self.needs_render = false;
+        self.last_render_at = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, widgets::Paragraph, Terminal};
+
+    use super::*;
+
+    /// The state drawn by the replay, after the number of pages scrolled down.
+    fn draw_page(terminal: &mut Terminal<TestBackend>, page: usize) {
+        terminal
+            .draw(|frame| frame.render_widget(Paragraph::new(format!("page {page}")), frame.area()))
+            .unwrap();
+    }
+
+    fn drawn_text(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Replays PageDown held for two seconds, repeated every millisecond, on a virtual clock. Each
+    /// step is an iteration of the loop of the app: the queued events are handled, then a frame is
+    /// rendered if it is due.
+    #[test]
+    fn held_page_down_is_handled_without_rendering_every_repeat() {
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut terminal = Terminal::new(TestBackend::new(20, 1)).unwrap();
+        let mut pacer = FramePacer::new(30.0);
+        let start = Instant::now();
+        let mut page = 0;
+        let mut requests = 0;
+        let mut step = |now: Instant, frames: &mut usize| {
+            if let Ok(first) = event_rx.try_recv() {
+                for received_at in take_queued(first, &mut event_rx) {
+                    // No event waits for a frame to be rendered before it is handled.
+                    assert_eq!(received_at, now);
+                    page += 1;
+                    requests += 1;
+                    pacer.request();
+                }
+            }
+
+            if pacer.is_due(now) {
+                draw_page(&mut terminal, page);
+                pacer.rendered(now);
+                *frames += 1;
+                // The frame is of the latest state, not of the queued intermediate ones.
+                assert_eq!(drawn_text(&terminal), format!("page {page}"));
+            }
+        };
+
+        let mut frames = 0;
+
+        for millisecond in 0..2000 {
+            let now = start + Duration::from_millis(millisecond);
+            event_tx.send(now).unwrap();
+            step(now, &mut frames);
+        }
+
+        // Rendering every repeat would draw 2000 frames.
+        assert!((55..=61).contains(&frames), "{frames} frames");
+
+        // Once the key is released, the last state is rendered, then nothing while idle.
+        let mut idle_frames = 0;
+
+        for millisecond in 2000..4000 {
+            step(start + Duration::from_millis(millisecond), &mut idle_frames);
+        }
+
+        assert_eq!(requests, 2000);
+        assert!(idle_frames <= 1, "{idle_frames} idle frames");
+        assert_eq!(drawn_text(&terminal), "page 2000");
+    }
+
+    #[test]
+    fn queued_events_are_taken_up_to_the_limit() {
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        for event in 1..100 {
+            event_tx.send(event).unwrap();
+        }
+
+        assert_eq!(
+            take_queued(0, &mut event_rx).collect::<Vec<_>>(),
+            (0..MAX_EVENTS_PER_ITERATION).collect::<Vec<_>>()
+        );
+        // The rest is handled in the next iteration, after the next frame.
+        assert_eq!(event_rx.try_recv().unwrap(), MAX_EVENTS_PER_ITERATION);
+    }
+}
@@ -0,0 +1,211 @@
+//! The records opened before, which can be gone back and forward through like the pages of a web
+//! browser.
+//!
+//! Only the entries near the current one keep the loaded record, up to a budget of bytes, so that
+//! returning to them does not resolve the record again, while the memory taken by the history
+//! stays bounded.
+
+use std::sync::Arc;
+
+use rrr::record::{HashedRecordKey, RecordReadVersionSuccess};
+
+use crate::encoding::Encoding;
+use crate::model::RecordRef;
+
+/// The number of entries on either side of the current one which keep their loaded record.
+const CACHED_ENTRIES: usize = 8;
+
+/// A record as it was loaded, to be opened again without resolving it.
+#[derive(Debug, Clone)]
+pub struct CachedRecord {
+    pub hashed_record_key: HashedRecordKey,
+    pub record: Arc<RecordReadVersionSuccess>,
+    /// The versions of the record that were listed, oldest first.
+    pub versions: Vec<u64>,
+}
+
+impl CachedRecord {
+    /// The number of bytes counted towards the budget of the history.
+    pub fn size(&self) -> usize {
+        self.record.data.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub record_ref: RecordRef,
+    /// The index of the first displayed row of the content, as it was when the record was left.
+    pub scroll: usize,
+    /// The encoding selected for record names, as it was when the record was left.
+    pub encoding: Encoding,
+    /// `None` once the entry is too far from the current one.
+    pub cached: Option<CachedRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDirection {
+    Back,
+    Forward,
+}
+
+#[derive(Debug)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    /// The index of the entry of the opened record, meaningless while `entries` is empty.
+    position: usize,
+    /// The maximum number of entries, the oldest ones are dropped beyond it.
+    capacity: usize,
+    /// The maximum total size of the loaded records.
+    cache_budget: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize, cache_budget: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            position: 0,
+            capacity: std::cmp::max(capacity, 1),
+            cache_budget,
+        }
+    }
+
+    /// The 1-based position of the current entry along with the number of entries, `None` while
+    /// the history is empty.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        (!self.entries.is_empty()).then_some((self.position + 1, self.entries.len()))
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut HistoryEntry> {
+        self.entries.get_mut(self.position)
+    }
+
+    /// The index of the entry one step in `direction` from the current one, if any.
+    pub fn neighbour(&self, direction: HistoryDirection) -> Option<usize> {
+        let index = match direction {
+            HistoryDirection::Back => self.position.checked_sub(1)?,
+            HistoryDirection::Forward => self.position + 1,
+        };
+
+        (index < self.entries.len()).then_some(index)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&HistoryEntry> {
+        self.entries.get(index)
+    }
+
+    /// Makes the entry at `index` the current one, once its record is opened again.
+    pub fn go_to(&mut self, index: usize, cached: CachedRecord) {
+        if index >= self.entries.len() {
+            return;
+        }
+
+        self.position = index;
+        self.entries[index].cached = Some(cached);
+        self.evict();
+    }
+
+    /// Adds an entry after the current one, discarding the entries that could be gone forward to.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        self.entries.truncate(self.position + 1);
+        self.entries.push(entry);
+
+        if self.entries.len() > self.capacity {
+            self.entries.drain(..self.entries.len() - self.capacity);
+        }
+
+        self.position = self.entries.len() - 1;
+        self.evict();
+    }
+
+    /// Drops the loaded records of the entries too far from the current one, or beyond the budget.
+    fn evict(&mut self) {
+        let sizes = self
+            .entries
+            .iter()
+            .map(|entry| entry.cached.as_ref().map(CachedRecord::size))
+            .collect::<Vec<_>>();
+        let kept = kept_cached_entries(&sizes, self.position, self.cache_budget);
+
+        for (entry, kept) in self.entries.iter_mut().zip(kept) {
+            if !kept {
+                entry.cached = None;
+            }
+        }
+    }
+}
+
+/// Which of the entries with the given sizes of their loaded records keep them. The nearest ones
+/// to the current entry are kept first, as long as they fit within the budget. The current entry
+/// is always kept, as its record is the opened one, which is not freed by dropping it.
+fn kept_cached_entries(sizes: &[Option<usize>], position: usize, budget: usize) -> Vec<bool> {
+    let mut kept = vec![false; sizes.len()];
+    let mut indices = (0..sizes.len()).collect::<Vec<_>>();
+    indices.sort_by_key(|&index| index.abs_diff(position));
+    let mut total_size = 0;
+
+    for index in indices {
+        let Some(size) = sizes[index] else {
+            continue;
+        };
+
+        if index == position
+            || (index.abs_diff(position) <= CACHED_ENTRIES && total_size + size <= budget)
+        {
+            kept[index] = true;
+            total_size += size;
+        }
+    }
+
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_within_the_budget_are_kept() {
+        let sizes = [Some(10), None, Some(10), Some(10), Some(10)];
+
+        assert_eq!(
+            kept_cached_entries(&sizes, 2, 40),
+            [true, false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn the_farthest_entries_beyond_the_budget_are_dropped() {
+        let sizes = [Some(10), Some(10), Some(10), Some(10), Some(10)];
+
+        assert_eq!(
+            kept_cached_entries(&sizes, 3, 30),
+            [false, false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn smaller_entries_fit_past_a_dropped_larger_one() {
+        let sizes = [Some(10), Some(100), Some(10)];
+
+        assert_eq!(kept_cached_entries(&sizes, 2, 30), [true, false, true]);
+    }
+
+    #[test]
+    fn the_current_entry_is_kept_beyond_the_budget() {
+        let sizes = [Some(10), Some(100), Some(10)];
+
+        assert_eq!(kept_cached_entries(&sizes, 1, 50), [false, true, false]);
+    }
+
+    #[test]
+    fn entries_too_far_from_the_current_one_are_dropped() {
+        let sizes = vec![Some(0); CACHED_ENTRIES * 2 + 3];
+        let kept = kept_cached_entries(&sizes, 0, usize::MAX);
+
+        assert_eq!(
+            kept.iter().filter(|&&kept| kept).count(),
+            CACHED_ENTRIES + 1
+        );
+        assert!(kept[..=CACHED_ENTRIES].iter().all(|&kept| kept));
+    }
+}
@@ -0,0 +1,475 @@
+//! Detection of other instances of the application running against the same registry.
+//!
+//! The first instance to start holds an exclusive lock on a file in the registry's state
+//! directory, writes its PID into it and, on unix, listens on a socket next to it. Later instances
+//! can ask it to open a record over that socket. The lock is released by the operating system once
+//! the instance exits, so that a crashed instance never leaves a stale lock behind.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::action::{Action, ComponentMessage};
+use crate::encoding::Encoding;
+use crate::model::RecordRef;
+
+const LOCK_FILE_NAME: &str = "instance.lock";
+const SOCKET_FILE_NAME: &str = "instance.sock";
+
+/// How long accepting connections pauses after a failure, so that a persistent one, such as
+/// running out of file descriptors, does not keep the task busy.
+#[cfg(unix)]
+const ACCEPT_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// The number of consecutive failures to accept a connection after which the socket is closed.
+#[cfg(unix)]
+const MAX_ACCEPT_FAILURES: u32 = 20;
+
+/// Bumped whenever the messages exchanged over the instance socket change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceRequest {
+    version: u32,
+    #[serde(flatten)]
+    command: InstanceCommand,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum InstanceCommand {
+    /// The path is decoded by the running instance in the encoding of the requesting one, so
+    /// that it opens the same record.
+    OpenRecord { path: String, encoding: Encoding },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceResponse {
+    version: u32,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Instance {
+    /// This process holds the instance lock and may write shared state.
+    Primary(InstanceLock),
+    /// Another live process holds the instance lock. Shared state must be treated as read-only.
+    /// The PID is `None` if that process has not written it yet.
+    Secondary {
+        pid: Option<u32>,
+        socket_path: PathBuf,
+    },
+}
+
+impl Instance {
+    pub fn acquire(registry_directory: &Path) -> Result<Self> {
+        let directory = crate::env::get_registry_state_dir(registry_directory);
+        std::fs::create_dir_all(&directory)?;
+        let lock_path = directory.join(LOCK_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                // The lock is held by a live process, whether it has written its PID yet or not.
+                let pid = std::fs::read_to_string(&lock_path)
+                    .ok()
+                    .and_then(|contents| contents.trim().parse::<u32>().ok());
+                warn!(
+                    ?pid,
+                    "Another instance is running against this registry, shared state is read-only."
+                );
+                return Ok(Self::Secondary {
+                    pid,
+                    socket_path: directory.join(SOCKET_FILE_NAME),
+                });
+            }
+            Err(TryLockError::Error(error)) => return Err(error.into()),
+        }
+
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        debug!(?lock_path, "Instance lock acquired.");
+        Ok(Self::Primary(InstanceLock {
+            directory,
+            _file: file,
+        }))
+    }
+
+    pub fn is_primary(&self) -> bool {
+        matches!(self, Self::Primary(_))
+    }
+
+    /// The warning shown on startup while another instance holds the lock, or `None` if this is
+    /// the primary instance.
+    pub fn read_only_warning(&self) -> Option<String> {
+        let Self::Secondary { pid, .. } = self else {
+            return None;
+        };
+        let instance = match pid {
+            Some(pid) => format!("Another instance (PID {pid})"),
+            None => "Another instance".to_string(),
+        };
+
+        Some(format!(
+            "{instance} is running against this registry, shared state is read-only."
+        ))
+    }
+
+    /// Starts accepting requests from other instances, if this is the primary instance.
+    pub fn listen(
+        &self,
+        action_tx: &UnboundedSender<Action>,
+        cancellation: &CancellationToken,
+    ) -> Result<()> {
+        match self {
+            Self::Primary(lock) => lock.listen(action_tx, cancellation),
+            Self::Secondary { .. } => Ok(()),
+        }
+    }
+
+    /// Asks the primary instance to open the record at `path`, whose names are in `encoding`.
+    pub async fn request_open_record(&self, path: String, encoding: Encoding) -> Result<()> {
+        let Self::Secondary { socket_path, .. } = self else {
+            bail!("This is the primary instance.");
+        };
+        let response = send_request(
+            socket_path,
+            InstanceRequest {
+                version: PROTOCOL_VERSION,
+                command: InstanceCommand::OpenRecord { path, encoding },
+            },
+        )
+        .await?;
+
+        if let Some(error) = response.error {
+            bail!("The running instance refused the request: {error}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes the socket on drop. The lock file is kept, as removing it while locked would let
+/// another instance lock a new file while a third one still waits for the removed one.
+#[derive(Debug)]
+pub struct InstanceLock {
+    directory: PathBuf,
+    /// The lock is released once the file is closed.
+    _file: File,
+}
+
+impl InstanceLock {
+    fn socket_path(&self) -> PathBuf {
+        self.directory.join(SOCKET_FILE_NAME)
+    }
+
+    #[cfg(unix)]
+    fn listen(
+        &self,
+        action_tx: &UnboundedSender<Action>,
+        cancellation: &CancellationToken,
+    ) -> Result<()> {
+        use tokio::net::UnixListener;
+        use tracing::{error, info_span, Instrument};
+
+        let socket_path = self.socket_path();
+        remove_file_if_exists(&socket_path)?;
+        let listener = UnixListener::bind(&socket_path)?;
+        let action_tx = action_tx.clone();
+        let cancellation = cancellation.child_token();
+        let connection_cancellation = cancellation.clone();
+
+        crate::tasks::spawn(
+            &cancellation,
+            async move {
+                let mut failures = 0;
+
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => {
+                            failures = 0;
+                            stream
+                        }
+                        Err(error) if failures < MAX_ACCEPT_FAILURES => {
+                            warn!(%error, "Failed to accept an instance connection.");
+                            failures += 1;
+                            tokio::time::sleep(ACCEPT_RETRY_INTERVAL).await;
+                            continue;
+                        }
+                        Err(error) => {
+                            error!(
+                                %error,
+                                "Failed to accept instance connections repeatedly, no longer accepting them."
+                            );
+                            return;
+                        }
+                    };
+                    let action_tx = action_tx.clone();
+
+                    crate::tasks::spawn(&connection_cancellation, async move {
+                        if let Err(error) = respond(stream, &action_tx).await {
+                            warn!(?error, "Failed to respond to an instance request.");
+                        }
+                    });
+                }
+            }
+            .instrument(info_span!("instance socket task")),
+        );
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn listen(
+        &self,
+        _action_tx: &UnboundedSender<Action>,
+        _cancellation: &CancellationToken,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = remove_file_if_exists(&self.socket_path());
+    }
+}
+
+/// Handles the request sent over the connection, and responds to it.
+#[cfg(unix)]
+async fn respond(
+    stream: tokio::net::UnixStream,
+    action_tx: &UnboundedSender<Action>,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let response = match lines.next_line().await {
+        Ok(Some(line)) => handle_request(&line, action_tx),
+        Ok(None) => return Ok(()),
+        Err(error) => InstanceResponse {
+            version: PROTOCOL_VERSION,
+            error: Some(error.to_string()),
+        },
+    };
+    let mut response = serde_json::to_string(&response)?;
+    response.push('\n');
+    writer.write_all(response.as_bytes()).await?;
+
+    Ok(())
+}
+
+fn handle_request(line: &str, action_tx: &UnboundedSender<Action>) -> InstanceResponse {
+    let error = match serde_json::from_str::<InstanceRequest>(line) {
+        Ok(request) if request.version != PROTOCOL_VERSION => Some(format!(
+            "Unsupported protocol version {}, expected {PROTOCOL_VERSION}.",
+            request.version
+        )),
+        Ok(InstanceRequest {
+            command: InstanceCommand::OpenRecord { path, encoding },
+            ..
+        }) => {
+            debug!(
+                ?path,
+                ?encoding,
+                "Opening a record requested by another instance."
+            );
+            RecordRef::parse_path(&path, encoding)
+                .map_err(|error| {
+                    format!("`{path}` is not a valid path in the {encoding} encoding: {error}")
+                })
+                .and_then(|record_ref| {
+                    action_tx
+                        .send(Action::BroadcastMessage(ComponentMessage::OpenRecord {
+                            record_ref,
+                        }))
+                        .map_err(|error| error.to_string())
+                })
+                .err()
+        }
+        Err(error) => Some(error.to_string()),
+    };
+
+    InstanceResponse {
+        version: PROTOCOL_VERSION,
+        error,
+    }
+}
+
+#[cfg(unix)]
+async fn send_request(socket_path: &Path, request: InstanceRequest) -> Result<InstanceResponse> {
+    use color_eyre::eyre::eyre;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let stream = UnixStream::connect(socket_path).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut request = serde_json::to_string(&request)?;
+    request.push('\n');
+    writer.write_all(request.as_bytes()).await?;
+
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| eyre!("The running instance closed the connection without responding."))?;
+
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+async fn send_request(_socket_path: &Path, _request: InstanceRequest) -> Result<InstanceResponse> {
+    bail!("Communicating with a running instance is not supported on this platform.")
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    /// A registry directory of its own for each test, whose state directory is therefore its own
+    /// too, as tests run in parallel.
+    fn registry_directory(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-instance-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(crate::env::get_registry_state_dir(&directory));
+        directory
+    }
+
+    #[test]
+    fn only_the_first_instance_is_primary() {
+        let directory = registry_directory("first_instance");
+        let primary = Instance::acquire(&directory).unwrap();
+        let secondary = Instance::acquire(&directory).unwrap();
+
+        assert!(primary.is_primary());
+        assert_eq!(primary.read_only_warning(), None);
+        assert_eq!(
+            secondary.read_only_warning(),
+            Some(format!(
+                "Another instance (PID {}) is running against this registry, shared state is \
+                 read-only.",
+                std::process::id()
+            ))
+        );
+        let Instance::Secondary { pid, socket_path } = secondary else {
+            panic!("expected a secondary instance, got {secondary:?}");
+        };
+        assert_eq!(pid, Some(std::process::id()));
+        assert_eq!(
+            socket_path,
+            crate::env::get_registry_state_dir(&directory).join(SOCKET_FILE_NAME)
+        );
+    }
+
+    #[test]
+    fn lock_is_handed_off_once_released() {
+        let directory = registry_directory("handoff");
+        let primary = Instance::acquire(&directory).unwrap();
+        let secondary = Instance::acquire(&directory).unwrap();
+        assert!(!secondary.is_primary());
+
+        drop(primary);
+
+        assert!(Instance::acquire(&directory).unwrap().is_primary());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn secondary_instance_opens_records_in_the_primary_one() {
+        let directory = registry_directory("open_record");
+        let primary = Instance::acquire(&directory).unwrap();
+        let secondary = Instance::acquire(&directory).unwrap();
+        let (action_tx, mut action_rx) = unbounded_channel();
+        let cancellation = CancellationToken::new();
+        primary.listen(&action_tx, &cancellation).unwrap();
+
+        secondary
+            .request_open_record("a/b".to_string(), Encoding::UTF8)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            action_rx.recv().await,
+            Some(Action::BroadcastMessage(ComponentMessage::OpenRecord {
+                record_ref: RecordRef::from_utf8_path("a/b"),
+            }))
+        );
+        assert!(action_rx.try_recv().is_err());
+
+        let socket_path = primary_socket_path(&primary);
+        cancellation.cancel();
+        drop(primary);
+        assert!(!socket_path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn invalid_requests_are_refused() {
+        let directory = registry_directory("refused_request");
+        let primary = Instance::acquire(&directory).unwrap();
+        let secondary = Instance::acquire(&directory).unwrap();
+        let (action_tx, _action_rx) = unbounded_channel();
+        let cancellation = CancellationToken::new();
+        primary.listen(&action_tx, &cancellation).unwrap();
+
+        let error = secondary
+            .request_open_record("6g".to_string(), Encoding::HEX)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The running instance refused the request: `6g` is not a valid path in the \
+             Hexadecimal Byte String encoding: `g` is not a hexadecimal digit at position 1"
+        );
+        cancellation.cancel();
+    }
+
+    #[test]
+    fn requests_of_other_protocol_versions_are_refused() {
+        let (action_tx, mut action_rx) = unbounded_channel();
+        let response = handle_request(
+            r#"{"version":1,"command":"open_record","path":"a","encoding":"utf8"}"#,
+            &action_tx,
+        );
+
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Unsupported protocol version 1, expected 2.")
+        );
+        assert!(action_rx.try_recv().is_err());
+        assert!(handle_request("{", &action_tx).error.is_some());
+    }
+
+    #[cfg(unix)]
+    fn primary_socket_path(instance: &Instance) -> PathBuf {
+        let Instance::Primary(lock) = instance else {
+            panic!("expected the primary instance, got {instance:?}");
+        };
+        lock.socket_path()
+    }
+}
@@ -0,0 +1,128 @@
+//! A typed snapshot of the state of the user interface, which tests and tooling can assert on
+//! without scraping the rendered screen. Only built with the `introspection` feature, so that
+//! release builds do not carry the serialization.
+//!
+//! The snapshot is taken by visiting every component of the tree, each of which records its own
+//! part of the state with `Component::introspect`.
+
+use std::ops::ControlFlow;
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::component::{self, Component, ComponentIdPath};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct UiState {
+    /// The components from the root to the focused one.
+    pub focus_path: Vec<ComponentState>,
+    /// The panels replaced by a placeholder, as they failed, with the reason.
+    pub failed_panels: Vec<String>,
+    pub opened_record: Option<OpenedRecordState>,
+    pub open_pane: Option<OpenPaneState>,
+    pub tree: Option<TreeState>,
+    pub content: Option<ContentState>,
+    /// The header shows a single notice at a time.
+    pub notice: Option<NoticeState>,
+    /// The labels of the tasks shown as pending, in the order they started in.
+    pub pending_tasks: Vec<String>,
+    pub modal: Option<ModalState>,
+}
+
+/// A component as reported to assistive technology.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentState {
+    pub role: String,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenedRecordState {
+    pub path: String,
+    pub version_count: usize,
+    pub size: usize,
+    pub metadata_rows: usize,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenPaneState {
+    pub record_name: String,
+    pub encoding: String,
+    /// Whether an operation is in progress.
+    pub busy: bool,
+    /// The progress of the operation in progress, such as "resolving 2 of 3".
+    pub progress: Option<String>,
+    /// Whether another operation waits for the one in progress.
+    pub queued: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeState {
+    /// The paths of the displayed nodes, from top to bottom.
+    pub rows: Vec<String>,
+    pub cursor: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContentState {
+    /// The title of the derived view shown in place of the content, if any.
+    pub derived_view: Option<String>,
+    pub hex_dump: bool,
+    pub scroll: usize,
+    pub following: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoticeState {
+    pub message: String,
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModalState {
+    pub title: String,
+    pub message: String,
+    pub input: Option<String>,
+    pub buttons: Vec<String>,
+}
+
+/// Takes the snapshot of the component tree under `root`, focused along `focus_path`.
+pub fn snapshot(root: &dyn Component, focus_path: &ComponentIdPath) -> Result<UiState> {
+    let mut state = UiState::default();
+    let mut component = root;
+    state.focus_path.push(component_state(component)?);
+
+    for id in focus_path.iter() {
+        let Some(child) = component
+            .get_children()
+            .into_iter()
+            .find(|child| child.get_id() == *id)
+        else {
+            break;
+        };
+
+        component = child;
+        state.focus_path.push(component_state(component)?);
+    }
+
+    let _ = component::depth_first_search::<()>(
+        root,
+        &mut |component| {
+            component.introspect(&mut state);
+            ControlFlow::Continue(())
+        },
+        &mut |_| ControlFlow::Continue(()),
+    );
+
+    Ok(state)
+}
+
+fn component_state(component: &dyn Component) -> Result<ComponentState> {
+    let node = component.get_accessibility_node()?;
+
+    Ok(ComponentState {
+        role: format!("{:?}", node.role()),
+        label: node.label().map(str::to_string),
+    })
+}
@@ -0,0 +1,509 @@
+//! Key bindings which map key chords to named commands, loaded from a TOML file such as
+//!
+//! ```toml
+//! [bindings]
+//! j = "scroll-down"
+//! k = "scroll-up"
+//! "ctrl+n" = "focus-next"
+//! ```
+//!
+//! The components handle the keys of the commands they implement directly, so most commands are
+//! carried out by translating the bound chord into the default chord of the command. Keys that are
+//! not bound are passed through unchanged.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use serde::Deserialize;
+use strum::{Display, EnumString};
+
+/// Name of the key bindings file inside the config directory.
+pub const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum KeyCommand {
+    Quit,
+    FocusNext,
+    FocusPrevious,
+    FocusPaneTree,
+    FocusPaneContent,
+    /// Swaps the focus between the last two focused panels.
+    FocusPaneAlternate,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    PageUp,
+    PageDown,
+    ScrollToTop,
+    ScrollToBottom,
+    OpenRecord,
+    OpenParent,
+    /// Opens the record visited before the opened one.
+    HistoryBack,
+    /// Opens the record visited after the opened one.
+    HistoryForward,
+    Search,
+    SearchNext,
+    SearchPrevious,
+    /// Shows or hides the previews of the content of the records in the tree.
+    TogglePreviews,
+    Cancel,
+    /// Lists the effective bindings.
+    Help,
+}
+
+impl KeyCommand {
+    pub const ALL: [Self; 24] = [
+        Self::Quit,
+        Self::FocusNext,
+        Self::FocusPrevious,
+        Self::FocusPaneTree,
+        Self::FocusPaneContent,
+        Self::FocusPaneAlternate,
+        Self::ScrollUp,
+        Self::ScrollDown,
+        Self::ScrollLeft,
+        Self::ScrollRight,
+        Self::PageUp,
+        Self::PageDown,
+        Self::ScrollToTop,
+        Self::ScrollToBottom,
+        Self::OpenRecord,
+        Self::OpenParent,
+        Self::HistoryBack,
+        Self::HistoryForward,
+        Self::Search,
+        Self::SearchNext,
+        Self::SearchPrevious,
+        Self::TogglePreviews,
+        Self::Cancel,
+        Self::Help,
+    ];
+
+    /// The chord the command is bound to by default, the one the components handle.
+    pub fn default_chord(self) -> KeyChord {
+        let (code, modifiers) = match self {
+            Self::Quit => (KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Self::FocusNext => (KeyCode::Tab, KeyModifiers::NONE),
+            Self::FocusPrevious => (KeyCode::BackTab, KeyModifiers::NONE),
+            Self::FocusPaneTree => (KeyCode::Char('t'), KeyModifiers::NONE),
+            Self::FocusPaneContent => (KeyCode::Char('c'), KeyModifiers::NONE),
+            Self::FocusPaneAlternate => (KeyCode::Char('^'), KeyModifiers::CONTROL),
+            Self::ScrollUp => (KeyCode::Up, KeyModifiers::NONE),
+            Self::ScrollDown => (KeyCode::Down, KeyModifiers::NONE),
+            Self::ScrollLeft => (KeyCode::Left, KeyModifiers::NONE),
+            Self::ScrollRight => (KeyCode::Right, KeyModifiers::NONE),
+            Self::PageUp => (KeyCode::PageUp, KeyModifiers::NONE),
+            Self::PageDown => (KeyCode::PageDown, KeyModifiers::NONE),
+            Self::ScrollToTop => (KeyCode::Home, KeyModifiers::NONE),
+            Self::ScrollToBottom => (KeyCode::End, KeyModifiers::NONE),
+            Self::OpenRecord => (KeyCode::Enter, KeyModifiers::NONE),
+            Self::OpenParent => (KeyCode::Backspace, KeyModifiers::NONE),
+            Self::HistoryBack => (KeyCode::Left, KeyModifiers::ALT),
+            Self::HistoryForward => (KeyCode::Right, KeyModifiers::ALT),
+            Self::Search => (KeyCode::Char('/'), KeyModifiers::NONE),
+            Self::SearchNext => (KeyCode::Char('n'), KeyModifiers::NONE),
+            Self::SearchPrevious => (KeyCode::Char('N'), KeyModifiers::NONE),
+            Self::TogglePreviews => (KeyCode::Char('p'), KeyModifiers::NONE),
+            Self::Cancel => (KeyCode::Esc, KeyModifiers::NONE),
+            Self::Help => (KeyCode::Char('?'), KeyModifiers::NONE),
+        };
+
+        KeyChord { code, modifiers }
+    }
+}
+
+/// A key along with the modifiers held, written like `ctrl+shift+up` or `G`.
+/// Shift is folded into the character of character keys, as terminals report them that way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self {
+            code: key.code,
+            modifiers: key.modifiers,
+        }
+        .normalized()
+    }
+
+    /// A key event of this chord, of the same kind as `key`.
+    pub fn to_event(self, key: &KeyEvent) -> KeyEvent {
+        KeyEvent {
+            code: self.code,
+            modifiers: self.modifiers,
+            ..*key
+        }
+    }
+
+    /// Folds Shift into character keys and Shift+Tab into BackTab, so that a chord matches the
+    /// event regardless of how the terminal reports it.
+    fn normalized(self) -> Self {
+        let shift = self.modifiers.contains(KeyModifiers::SHIFT);
+        let code = match self.code {
+            KeyCode::Char(character) if shift => KeyCode::Char(character.to_ascii_uppercase()),
+            KeyCode::Tab if shift => KeyCode::BackTab,
+            code => code,
+        };
+
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            Self {
+                code,
+                modifiers: self.modifiers.difference(KeyModifiers::SHIFT),
+            }
+        } else {
+            Self { code, ..self }
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        let (modifier_names, key) = match chord.strip_suffix('+') {
+            // The `+` key itself, as in `+` or `ctrl++`.
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+
+        if !modifier_names.is_empty() {
+            for name in modifier_names.split('+') {
+                modifiers |= match name.to_lowercase().as_str() {
+                    "ctrl" | "control" => KeyModifiers::CONTROL,
+                    "alt" => KeyModifiers::ALT,
+                    "shift" => KeyModifiers::SHIFT,
+                    _ => {
+                        return Err(format!(
+                            "unknown modifier `{name}`, expected `ctrl`, `alt` or `shift`"
+                        ))
+                    }
+                };
+            }
+        }
+
+        let mut characters = key.chars();
+        let code = match (characters.next(), characters.next()) {
+            (None, _) => return Err("missing key".to_string()),
+            (Some(character), None) => KeyCode::Char(character),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => match name
+                    .strip_prefix('f')
+                    .and_then(|number| number.parse().ok())
+                {
+                    Some(number @ 1..=24) => KeyCode::F(number),
+                    _ => return Err(format!("unknown key `{key}`")),
+                },
+            },
+        };
+
+        Ok(Self { code, modifiers }.normalized())
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(character) => write!(f, "{character}"),
+            KeyCode::F(number) => write!(f, "f{number}"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{}", format!("{code:?}").to_lowercase()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyMapFile {
+    /// The commands keyed by chords, with their positions for reporting errors.
+    #[serde(default)]
+    bindings: BTreeMap<String, toml::Spanned<String>>,
+}
+
+/// The effective key bindings: the default chord of every command, along with the bindings of the
+/// key bindings file, which take precedence.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(KeyChord, KeyCommand)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: KeyCommand::ALL
+                .into_iter()
+                .map(|command| (command.default_chord(), command))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Loads the key bindings file at `path`, falling back to the defaults if it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).wrap_err_with(|| {
+                format!("Failed to read the key bindings file {}", path.display())
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Parses the contents of a key bindings file. All invalid bindings are reported at once, each
+    /// with its line.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let file: KeyMapFile = toml::from_str(contents)?;
+        let mut key_map = Self::default();
+        let mut errors = Vec::new();
+
+        for (chord, command) in file.bindings {
+            let line = contents[..command.span().start].matches('\n').count() + 1;
+            let parsed_chord = match chord.parse::<KeyChord>() {
+                Ok(parsed_chord) => parsed_chord,
+                Err(error) => {
+                    errors.push((line, format!("Invalid key chord `{chord}`: {error}.")));
+                    continue;
+                }
+            };
+            let Ok(command) = command.get_ref().parse::<KeyCommand>() else {
+                errors.push((
+                    line,
+                    format!(
+                        "Unknown command `{}`, expected one of: {}.",
+                        command.get_ref(),
+                        KeyCommand::ALL.iter().join(", ")
+                    ),
+                ));
+                continue;
+            };
+
+            key_map.bind(parsed_chord, command);
+        }
+
+        if !errors.is_empty() {
+            return Err(eyre!(
+                "Invalid key bindings:\n{}",
+                errors
+                    .into_iter()
+                    .sorted()
+                    .map(|(line, error)| format!("Line {line}: {error}"))
+                    .join("\n")
+            ));
+        }
+
+        Ok(key_map)
+    }
+
+    fn bind(&mut self, chord: KeyChord, command: KeyCommand) {
+        match self
+            .bindings
+            .iter_mut()
+            .find(|(bound_chord, _)| *bound_chord == chord)
+        {
+            Some((_, bound_command)) => *bound_command = command,
+            None => self.bindings.push((chord, command)),
+        }
+    }
+
+    /// The command bound to the chord of `key`, if any.
+    pub fn translate(&self, key: &KeyEvent) -> Option<KeyCommand> {
+        let chord = KeyChord::from_event(key);
+
+        self.bindings
+            .iter()
+            .find(|(bound_chord, _)| *bound_chord == chord)
+            .map(|(_, command)| *command)
+    }
+
+    /// Lists the chords bound to each command, one command per line.
+    pub fn describe(&self) -> String {
+        KeyCommand::ALL
+            .into_iter()
+            .filter_map(|command| {
+                let chords = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, bound_command)| *bound_command == command)
+                    .map(|(chord, _)| chord)
+                    .join(", ");
+
+                (!chords.is_empty()).then(|| format!("{:<22}{chords}", command.to_string()))
+            })
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEventKind;
+
+    use super::*;
+
+    fn chord(chord: &str) -> KeyChord {
+        chord.parse().unwrap()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new_with_kind(code, modifiers, KeyEventKind::Press)
+    }
+
+    #[test]
+    fn chords_are_parsed() {
+        assert_eq!(
+            chord("Ctrl+Alt+Up"),
+            KeyChord {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT,
+            }
+        );
+        assert_eq!(chord("G"), chord("shift+g"));
+        assert_eq!(chord("G").modifiers, KeyModifiers::NONE);
+        assert_eq!(chord("shift+tab"), chord("backtab"));
+        assert_eq!(chord("+").code, KeyCode::Char('+'));
+        assert_eq!(
+            chord("ctrl++"),
+            KeyChord {
+                code: KeyCode::Char('+'),
+                modifiers: KeyModifiers::CONTROL,
+            }
+        );
+        assert_eq!(chord("space").code, KeyCode::Char(' '));
+        assert_eq!(chord("f12").code, KeyCode::F(12));
+    }
+
+    #[test]
+    fn invalid_chords_are_rejected() {
+        for (chord, error) in [
+            ("", "missing key"),
+            ("ctrl+", "missing key"),
+            (
+                "hyper+a",
+                "unknown modifier `hyper`, expected `ctrl`, `alt` or `shift`",
+            ),
+            ("f25", "unknown key `f25`"),
+            ("foo", "unknown key `foo`"),
+        ] {
+            assert_eq!(chord.parse::<KeyChord>(), Err(error.to_string()), "{chord}");
+        }
+    }
+
+    #[test]
+    fn chords_round_trip_through_their_display() {
+        let chords = KeyCommand::ALL
+            .into_iter()
+            .map(KeyCommand::default_chord)
+            .chain(
+                [
+                    "ctrl+alt+shift+up",
+                    "ctrl++",
+                    "alt+space",
+                    "f1",
+                    "shift+f24",
+                    "G",
+                    "delete",
+                    "insert",
+                    "shift+enter",
+                ]
+                .map(chord),
+            );
+
+        for chord in chords {
+            assert_eq!(chord.to_string().parse(), Ok(chord), "{chord}");
+        }
+    }
+
+    #[test]
+    fn bindings_take_precedence_over_the_defaults() {
+        let key_map = KeyMap::parse("[bindings]\nj = \"scroll-down\"\ndown = \"quit\"\n").unwrap();
+
+        assert_eq!(
+            key_map.translate(&key(KeyCode::Char('j'), KeyModifiers::NONE)),
+            Some(KeyCommand::ScrollDown)
+        );
+        assert_eq!(
+            key_map.translate(&key(KeyCode::Down, KeyModifiers::NONE)),
+            Some(KeyCommand::Quit)
+        );
+        // Shift is folded into the character however the terminal reports it.
+        assert_eq!(
+            key_map.translate(&key(KeyCode::Char('n'), KeyModifiers::SHIFT)),
+            Some(KeyCommand::SearchPrevious)
+        );
+        assert_eq!(
+            key_map.translate(&key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_bindings_are_reported_with_their_lines() {
+        let error = KeyMap::parse(
+            "[bindings]\n\
+             j = \"scroll-down\"\n\
+             \"hyper+k\" = \"scroll-up\"\n\
+             \n\
+             l = \"jump\"\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Invalid key bindings:\n\
+                 Line 3: Invalid key chord `hyper+k`: unknown modifier `hyper`, expected `ctrl`, \
+                 `alt` or `shift`.\n\
+                 Line 5: Unknown command `jump`, expected one of: {}.",
+                KeyCommand::ALL.iter().join(", ")
+            )
+        );
+    }
+
+    #[test]
+    fn malformed_file_is_an_error() {
+        assert!(KeyMap::parse("[bindings\n").is_err());
+        assert!(KeyMap::parse("[other]\n").is_err());
+    }
+}
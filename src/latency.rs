@@ -0,0 +1,152 @@
+//! Measurement of the latency between receiving an input event and the terminal writer finishing
+//! writing the next frame, see `crate::tui::WrittenFrame`.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use tracing::{debug, info};
+
+use crate::tui::WrittenFrame;
+
+/// The number of most recent samples the statistics are computed from.
+const MAX_SAMPLES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug)]
+pub struct LatencyRecorder {
+    started_at: Instant,
+    /// When the input events that have not been reflected in a frame yet were received.
+    pending: Vec<Instant>,
+    /// When the input events reflected in the flushed frames that have not been written yet were
+    /// received, by the number of the frame.
+    flushed: VecDeque<(u64, Vec<Instant>)>,
+    samples: VecDeque<Duration>,
+    /// A CSV file each sample is appended to.
+    log: Option<BufWriter<std::fs::File>>,
+}
+
+impl LatencyRecorder {
+    pub fn new(log_path: Option<&Path>) -> Result<Self> {
+        let log = if let Some(log_path) = log_path {
+            let is_new = !log_path.exists();
+            let mut log = BufWriter::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(log_path)?,
+            );
+
+            if is_new {
+                writeln!(log, "elapsed_ms,latency_us")?;
+            }
+
+            Some(log)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            started_at: Instant::now(),
+            pending: Vec::new(),
+            flushed: VecDeque::new(),
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+            log,
+        })
+    }
+
+    pub fn input_received(&mut self, received_at: Instant) {
+        self.pending.push(received_at);
+    }
+
+    /// Marks the pending input events as reflected in the frame just flushed to the terminal
+    /// writer, numbered `sequence`.
+    pub fn frame_flushed(&mut self, sequence: u64) {
+        if !self.pending.is_empty() {
+            self.flushed
+                .push_back((sequence, std::mem::take(&mut self.pending)));
+        }
+    }
+
+    /// Records the latency of the input events reflected in the written frame, or in the frames
+    /// flushed before it, which were either written along with it or dropped.
+    pub fn frame_written(&mut self, frame: WrittenFrame) -> Result<()> {
+        let mut received = Vec::new();
+
+        while let Some((sequence, _)) = self.flushed.front()
+            && *sequence <= frame.sequence
+        {
+            received.extend(self.flushed.pop_front().unwrap().1);
+        }
+
+        if received.is_empty() {
+            return Ok(());
+        }
+
+        for received_at in received {
+            let latency = frame.written_at.saturating_duration_since(received_at);
+
+            debug!(histogram.input_latency_us = latency.as_micros() as u64);
+
+            if self.samples.len() == MAX_SAMPLES {
+                self.samples.pop_front();
+            }
+
+            self.samples.push_back(latency);
+
+            if let Some(log) = self.log.as_mut() {
+                writeln!(
+                    log,
+                    "{},{}",
+                    received_at.duration_since(self.started_at).as_millis(),
+                    latency.as_micros()
+                )?;
+            }
+        }
+
+        if let Some(log) = self.log.as_mut() {
+            log.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn summary(&self) -> Option<LatencySummary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+        samples.sort_unstable();
+        let percentile = |percentile: usize| samples[(samples.len() - 1) * percentile / 100];
+
+        Some(LatencySummary {
+            count: samples.len(),
+            p50: percentile(50),
+            p95: percentile(95),
+            max: percentile(100),
+        })
+    }
+
+    pub fn log_summary(&self) {
+        if let Some(summary) = self.summary() {
+            info!(
+                count = summary.count,
+                p50 = ?summary.p50,
+                p95 = ?summary.p95,
+                max = ?summary.max,
+                "Input latency."
+            );
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Outlines of the areas widgets are drawn into, toggled with `o` in the log view (F12) or with
+//! Shift+F12.
+//! Only compiled with the `layout-debug` feature.
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Span,
+    widgets::Block,
+    Frame,
+};
+use tracing::debug;
+
+use crate::component::NamedArea;
+
+/// Outline colors, cycled by nesting depth.
+const COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+#[derive(Debug, Default)]
+pub struct LayoutDebug {
+    pub enabled: bool,
+    /// The areas of the last frame, so that they are only logged when they change.
+    last_named_areas: Vec<NamedArea>,
+}
+
+impl LayoutDebug {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.last_named_areas.clear();
+    }
+
+    /// Outlines the areas recorded during the frame and logs them if they changed.
+    pub fn draw(&mut self, frame: &mut Frame, named_areas: Vec<NamedArea>) {
+        if !self.enabled {
+            return;
+        }
+
+        for named_area in &named_areas {
+            // The number of other areas this one is nested within.
+            let depth = named_areas
+                .iter()
+                .filter(|other| {
+                    other.area != named_area.area && contains(other.area, named_area.area)
+                })
+                .count();
+            let style = Style::new().fg(COLORS[depth % COLORS.len()]);
+
+            frame.render_widget(
+                Block::bordered()
+                    .border_style(style)
+                    .title(Span::styled(named_area.name, style)),
+                named_area.area,
+            );
+        }
+
+        if named_areas != self.last_named_areas {
+            debug!(?named_areas, "Layout changed.");
+            self.last_named_areas = named_areas;
+        }
+    }
+}
+
+fn contains(outer: Rect, inner: Rect) -> bool {
+    outer.union(inner) == outer
+}
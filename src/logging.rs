@@ -1,11 +1,18 @@
 use std::env::VarError;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 use color_eyre::Result;
-use tracing::Subscriber;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
 use tracing_error::ErrorLayer;
+use tracing_subscriber::layer::Context;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter, Layer};
 
 use crate::env;
 
@@ -13,6 +20,113 @@ lazy_static::lazy_static! {
     pub static ref LOG_ENV: String = format!("{}_LOG_LEVEL", env::PROJECT_NAME.to_uppercase().clone());
 }
 
+/// The number of captured events which can wait for the log view to take them. Further events are
+/// dropped instead of blocking the tasks emitting them.
+const CAPTURED_EVENTS_CHANNEL_CAPACITY: usize = 1024;
+
+/// The events captured for the log view, until it takes them.
+static CAPTURED_EVENTS: Mutex<Option<Receiver<LogEntry>>> = Mutex::new(None);
+
+/// An event captured for the log view.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: SystemTime,
+    pub level: Level,
+    pub target: String,
+    /// The message followed by the other fields of the event, as `name=value`.
+    pub message: String,
+    /// The `operation_id` of the innermost span the event was emitted in that has one, see
+    /// `crate::tasks::OperationId`.
+    pub operation_id: Option<String>,
+}
+
+/// Takes the events captured since logging was initialized, and those captured later on.
+/// `None` if they were already taken.
+pub fn take_captured_events() -> Option<Receiver<LogEntry>> {
+    CAPTURED_EVENTS
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .take()
+}
+
+/// Captures events into a channel, which the log view drains.
+pub struct CaptureLayer {
+    sender: Sender<LogEntry>,
+}
+
+impl CaptureLayer {
+    pub fn new(sender: Sender<LogEntry>) -> Self {
+        Self { sender }
+    }
+}
+
+/// The `operation_id` field of a span, stored in its extensions.
+struct SpanOperationId(String);
+
+#[derive(Default)]
+struct OperationIdVisitor {
+    operation_id: Option<String>,
+}
+
+impl Visit for OperationIdVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "operation_id" {
+            self.operation_id = Some(format!("{value:?}"));
+        }
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl<S: Subscriber + for<'span> LookupSpan<'span>> Layer<S> for CaptureLayer {
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, context: Context<'_, S>) {
+        let mut visitor = OperationIdVisitor::default();
+        attributes.record(&mut visitor);
+
+        if let Some(operation_id) = visitor.operation_id
+            && let Some(span) = context.span(id)
+        {
+            span.extensions_mut().insert(SpanOperationId(operation_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, context: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let operation_id = context.event_scope(event).and_then(|scope| {
+            scope.into_iter().find_map(|span| {
+                span.extensions()
+                    .get::<SpanOperationId>()
+                    .map(|operation_id| operation_id.0.clone())
+            })
+        });
+
+        // Never waits, the event is dropped if the log view is behind.
+        let _ = self.sender.try_send(LogEntry {
+            timestamp: SystemTime::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message + &visitor.fields,
+            operation_id,
+        });
+    }
+}
+
 /// An RAII guard that executes the stored function on drop.
 pub struct OnDrop(Option<Box<dyn FnOnce()>>);
 
@@ -139,6 +253,16 @@ mod tracy {
     }
 }
 
+fn create_env_filter() -> Result<EnvFilter> {
+    let env_filter = EnvFilter::builder().with_default_directive(tracing::Level::INFO.into());
+    // If the `RUST_LOG` environment variable is set, use that as the default, otherwise use the
+    // value of the `LOG_ENV` environment variable. If the `LOG_ENV` environment variable contains
+    // errors, then this will return an error.
+    Ok(env_filter
+        .try_from_env()
+        .or_else(|_| env_filter.with_env_var(LOG_ENV.clone()).from_env())?)
+}
+
 pub fn create_file_layer<S>(
     log_path: String,
     _tracing_guard: &mut TracingGuard,
@@ -146,13 +270,7 @@ pub fn create_file_layer<S>(
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
-    let env_filter = EnvFilter::builder().with_default_directive(tracing::Level::INFO.into());
-    // If the `RUST_LOG` environment variable is set, use that as the default, otherwise use the
-    // value of the `LOG_ENV` environment variable. If the `LOG_ENV` environment variable contains
-    // errors, then this will return an error.
-    let env_filter = env_filter
-        .try_from_env()
-        .or_else(|_| env_filter.with_env_var(LOG_ENV.clone()).from_env())?;
+    let env_filter = create_env_filter()?;
     let log_file = std::fs::File::create(log_path)?;
     let file_subscriber = fmt::layer()
         .with_file(true)
@@ -164,6 +282,20 @@ where
     Ok(file_subscriber)
 }
 
+/// Captures the events for the log view, see `take_captured_events`. Filtered like the log file.
+pub fn create_capture_layer<S>(
+    _tracing_guard: &mut TracingGuard,
+) -> Result<impl tracing_subscriber::layer::Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let (sender, receiver) = mpsc::channel(CAPTURED_EVENTS_CHANNEL_CAPACITY);
+    *CAPTURED_EVENTS
+        .lock()
+        .unwrap_or_else(|error| error.into_inner()) = Some(receiver);
+    Ok(CaptureLayer::new(sender).with_filter(create_env_filter()?))
+}
+
 /// Enable logging if the `LOG_FILE` environment variable is specified.
 pub fn init() -> Result<TracingGuard> {
     let mut tracing_guard = TracingGuard::default();
@@ -185,7 +317,9 @@ fn with_rest<S>(subscriber: S, tracing_guard: &mut TracingGuard) -> Result<()>
 where
     S: Subscriber + Send + Sync + 'static + SubscriberInitExt + for<'span> LookupSpan<'span>,
 {
-    let subscriber = subscriber.with(ErrorLayer::default());
+    let subscriber = subscriber
+        .with(ErrorLayer::default())
+        .with(create_capture_layer(tracing_guard)?);
 
     #[cfg(feature = "opentelemetry")]
     let subscriber = subscriber
@@ -2,23 +2,59 @@
 
 use std::sync::Arc;
 
-use args::Args;
-use clap::Parser;
+use args::{Args, Command};
+use clap::CommandFactory;
 use color_eyre::Result;
 use tracing::{debug, Instrument};
 
 use crate::app::App;
+use crate::capabilities::{TerminalCapabilities, UNSUPPORTED_TERMINAL_EXIT_CODE};
+use crate::config::Config;
+use crate::instance::Instance;
+use crate::startup::Startup;
 
 mod action;
+mod action_socket;
+mod annotations;
 mod app;
 mod args;
+mod bench;
+mod capabilities;
 mod cbor;
+mod changelog;
+mod clipboard;
+mod commands;
+mod completion;
 mod component;
 mod components;
+mod config;
+mod custom_command;
+mod encoding;
+mod entropy;
 mod env;
 mod errors;
+mod frame_pacer;
+mod history;
+mod instance;
+#[cfg(any(test, feature = "introspection"))]
+mod introspection;
+mod keymap;
+mod latency;
+#[cfg(feature = "layout-debug")]
+mod layout_debug;
 mod logging;
+mod model;
+mod open_pipeline;
+mod registry_summary;
+mod report;
+mod session;
+mod startup;
+mod tasks;
+mod tempfiles;
+mod text;
+mod theme;
 mod tui;
+mod words;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -26,10 +62,100 @@ async fn main() -> Result<()> {
     let tracing_guard = crate::logging::init()?;
 
     async move {
-        let args = Arc::new(Args::parse());
-        let mut app = App::new(&args).await?;
-        app.run().await?;
-        Ok(()) as Result<()>
+        let capabilities = TerminalCapabilities::probe();
+        let args = Arc::new(Args::parse_with_capabilities(&capabilities));
+
+        match args.command.as_ref() {
+            Some(Command::Completions { shell }) => {
+                let bin_name = env!("CARGO_PKG_NAME");
+                clap_complete::generate(
+                    *shell,
+                    &mut Args::command(),
+                    bin_name,
+                    &mut std::io::stdout(),
+                );
+
+                if let Some(script) = crate::completion::dynamic_completion_script(*shell, bin_name)
+                {
+                    print!("{script}");
+                }
+
+                return Ok(());
+            }
+            Some(Command::CompleteRecord { partial }) => {
+                crate::completion::print_record_completions(
+                    args.registry_directory.clone(),
+                    partial.clone(),
+                );
+                return Ok(());
+            }
+            None => {}
+        }
+
+        let errors = args.validate();
+
+        if !errors.is_empty() {
+            for error in errors {
+                eprintln!("error: {error}");
+            }
+
+            std::process::exit(args::INVALID_ARGS_EXIT_CODE);
+        }
+
+        if args.list_commands {
+            print!("{}", crate::commands::describe_commands());
+            return Ok(());
+        }
+
+        if args.print_effective_config {
+            print!("{}", Config::new(&args)?.to_effective_toml());
+            return Ok(());
+        }
+
+        if let Some(iterations) = args.bench_startup {
+            return crate::bench::run(&args, iterations).await;
+        }
+
+        if let Some(report_path) = args.report.as_deref() {
+            let startup = Startup::run(&args, false).await?;
+            return crate::report::write_report(&args, startup, report_path).await;
+        }
+
+        let missing_capabilities = capabilities.missing_required();
+
+        if !missing_capabilities.is_empty() && !args.force_tui {
+            eprintln!(
+                "error: The terminal lacks {}, which the user interface requires.",
+                missing_capabilities.join(", ")
+            );
+            eprintln!(
+                "Use `--report -` to print a record without the user interface, or \
+                 `--force-tui` if the terminal does support these features."
+            );
+            std::process::exit(UNSUPPORTED_TERMINAL_EXIT_CODE);
+        }
+
+        debug!(?capabilities, "Terminal capabilities probed.");
+        let instance = Instance::acquire(&args.registry_directory)?;
+
+        if args.reuse
+            && !instance.is_primary()
+            && let Some((path, encoding, _)) = args.record_path()
+        {
+            instance
+                .request_open_record(path.to_string(), encoding)
+                .await?;
+            debug!("Handed the record over to the running instance.");
+            return Ok(());
+        }
+
+        let mut startup = Startup::run(&args, instance.is_primary()).await?;
+        startup.warnings.extend(capabilities.degradation_notices());
+        startup.warnings.extend(instance.read_only_warning());
+        let mut app = App::new(&args, &capabilities, instance, startup).await?;
+        let result = app.run().await;
+        crate::tempfiles::remove_all();
+        result
     }
     .instrument(tracing::info_span!("main"))
     .await?;
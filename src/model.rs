@@ -0,0 +1,619 @@
+//! Types shared by the features that refer to records.
+
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::{Encoding, EncodingError};
+
+/// Appended to names that cannot be displayed faithfully in the encoding they are displayed in.
+pub const LOSSY_NAME_MARKER: &str = "≉";
+
+/// Separates the names of a path. Within names, it is escaped as `\/`.
+const PATH_SEPARATOR: char = '/';
+
+/// Escapes `PATH_SEPARATOR` within names, and itself as `\\`.
+const PATH_ESCAPE: char = '\\';
+
+/// Marks a name of a relative path as hexadecimal, regardless of the encoding of the path.
+const HEX_NAME_PREFIX: &str = "0x";
+
+/// A name of a path with the escapes resolved.
+struct PathName {
+    name: String,
+    /// The byte offset within the path of each byte of the name, followed by the offset of the
+    /// end of the name.
+    offsets: Vec<usize>,
+}
+
+fn split_path_names(path: &str) -> Vec<PathName> {
+    let mut names = Vec::new();
+    let mut name = String::new();
+    let mut offsets = Vec::new();
+    let mut chars = path.char_indices().peekable();
+
+    while let Some((offset, char)) = chars.next() {
+        let char = match char {
+            PATH_SEPARATOR => {
+                offsets.push(offset);
+                names.push(PathName {
+                    name: std::mem::take(&mut name),
+                    offsets: std::mem::take(&mut offsets),
+                });
+                continue;
+            }
+            PATH_ESCAPE => chars
+                .next_if(|(_, next)| matches!(*next, PATH_SEPARATOR | PATH_ESCAPE))
+                .map_or(char, |(_, escaped)| escaped),
+            char => char,
+        };
+
+        offsets.extend(std::iter::repeat_n(offset, char.len_utf8()));
+        name.push(char);
+    }
+
+    offsets.push(path.len());
+    names.push(PathName { name, offsets });
+    names
+}
+
+/// Splits a `/`-separated path into its names, including the empty ones, with `\/` and `\\`
+/// within the names resolved to `/` and `\`.
+pub fn split_path(path: &str) -> Vec<String> {
+    split_path_names(path)
+        .into_iter()
+        .map(|name| name.name)
+        .collect()
+}
+
+/// A name of a path that could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPathName {
+    /// The byte range of the name within the path, including its escapes.
+    pub range: Range<usize>,
+    /// The position of the error is relative to the whole path.
+    pub error: EncodingError,
+}
+
+impl Display for InvalidPathName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.error, f)
+    }
+}
+
+/// Parses a `/`-separated path of record names relative to a record, each name in `encoding`
+/// unless prefixed with `0x`, which marks it as hexadecimal. See `split_path` for the escaping of
+/// `/` within names. Empty names are skipped, unless the whole path is empty, which names the
+/// record with the empty name, and a path of only separators names no record, which is an error.
+pub fn parse_relative_path(
+    path: &str,
+    encoding: Encoding,
+) -> Result<Vec<Vec<u8>>, InvalidPathName> {
+    let names = split_path_names(path);
+    let single = names.len() == 1;
+
+    if !single && names.iter().all(|name| name.name.is_empty()) {
+        return Err(InvalidPathName {
+            range: 0..path.len(),
+            error: EncodingError::new(0, "the path names no record"),
+        });
+    }
+
+    names
+        .into_iter()
+        .filter(|name| single || !name.name.is_empty())
+        .map(|PathName { name, offsets }| {
+            let (encoding, text, prefix_length) = match name.strip_prefix(HEX_NAME_PREFIX) {
+                Some(digits) => (Encoding::HEX, digits, HEX_NAME_PREFIX.len()),
+                None => (encoding, name.as_str(), 0),
+            };
+
+            encoding.decode(text).map_err(|error| {
+                let offset = |index: usize| {
+                    offsets
+                        .get(index)
+                        .or(offsets.last())
+                        .copied()
+                        .unwrap_or_default()
+                };
+
+                InvalidPathName {
+                    range: offset(0)..offset(offsets.len()),
+                    error: EncodingError::new(
+                        offset(prefix_length + error.position),
+                        error.message,
+                    ),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Escapes the name for `split_path`.
+pub fn escape_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+
+    for char in name.chars() {
+        if matches!(char, PATH_SEPARATOR | PATH_ESCAPE) {
+            escaped.push(PATH_ESCAPE);
+        }
+
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+/// How names that cannot be displayed faithfully in their encoding are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LossyNameDisplay {
+    /// Display the names anyway, followed by `LOSSY_NAME_MARKER`.
+    #[default]
+    Mark,
+    /// Display the whole path in hex instead.
+    Hex,
+}
+
+/// Identifies a record by the names of the records leading to it from the root record.
+///
+/// Only the names and the version are compared and hashed, so that the same record is found
+/// regardless of the encoding its name was entered in.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RecordRef {
+    /// Names of the records on the path from the root record, excluding the root record itself.
+    path: Vec<Vec<u8>>,
+    /// The encoding the names should be displayed in.
+    #[serde(default)]
+    pub encoding_hint: Encoding,
+    /// A specific version of the record, or `None` for the latest version.
+    #[serde(default)]
+    pub version: Option<u64>,
+}
+
+impl RecordRef {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parses a `/`-separated path of UTF-8 record names, relative to the root record.
+    /// See `split_path` for the escaping of `/` within names.
+    pub fn from_utf8_path(path: &str) -> Self {
+        Self {
+            path: split_path(path)
+                .into_iter()
+                .filter(|name| !name.is_empty())
+                .map(String::into_bytes)
+                .collect(),
+            encoding_hint: Encoding::UTF8,
+            version: None,
+        }
+    }
+
+    /// The reference to the latest version of the record with the names of `path`, relative to the
+    /// root record.
+    pub fn from_path(path: Vec<Vec<u8>>, encoding_hint: Encoding) -> Self {
+        Self {
+            path,
+            encoding_hint,
+            version: None,
+        }
+    }
+
+    /// Parses a `/`-separated path of record names in the given encoding, relative to the root
+    /// record. See `split_path` for the escaping of `/` within names. Error positions are
+    /// relative to the whole path.
+    pub fn parse_path(path: &str, encoding: Encoding) -> Result<Self, EncodingError> {
+        let mut names = Vec::new();
+
+        for PathName { name, offsets } in split_path_names(path) {
+            if !name.is_empty() {
+                names.push(encoding.decode(&name).map_err(|error| {
+                    let position = offsets
+                        .get(error.position)
+                        .or(offsets.last())
+                        .copied()
+                        .unwrap_or_default();
+                    EncodingError::new(position, error.message)
+                })?);
+            }
+        }
+
+        Ok(Self {
+            path: names,
+            encoding_hint: encoding,
+            version: None,
+        })
+    }
+
+    /// The reference to the latest version of the sub-record named `name`.
+    pub fn child(&self, name: impl Into<Vec<u8>>, encoding_hint: Encoding) -> Self {
+        let mut path = self.path.clone();
+        path.push(name.into());
+        Self {
+            path,
+            encoding_hint,
+            version: None,
+        }
+    }
+
+    /// The reference to the latest version of the parent record, `None` for the root record.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, path) = self.path.split_last()?;
+        Some(Self {
+            path: path.to_vec(),
+            encoding_hint: self.encoding_hint,
+            version: None,
+        })
+    }
+
+    /// The reference to the latest version of the ancestor whose path has the first `depth` names,
+    /// the root record being at depth 0.
+    pub fn ancestor(&self, depth: usize) -> Self {
+        Self {
+            path: self.path[..depth].to_vec(),
+            encoding_hint: self.encoding_hint,
+            version: None,
+        }
+    }
+
+    /// The reference to the given version of the same record, `None` for the latest version.
+    pub fn at_version(&self, version: Option<u64>) -> Self {
+        Self {
+            version,
+            ..self.clone()
+        }
+    }
+
+    pub fn path(&self) -> &[Vec<u8>] {
+        &self.path
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Whether some of the names cannot be displayed faithfully in the `encoding_hint`.
+    pub fn has_lossy_name(&self) -> bool {
+        self.path
+            .iter()
+            .any(|name| !self.encoding_hint.is_lossless(name))
+    }
+
+    /// Whether the names are to be displayed as UTF-8, but some of them are not valid UTF-8.
+    /// The original names are still used for opening the record.
+    pub fn has_invalid_utf8_name(&self) -> bool {
+        self.encoding_hint == Encoding::UTF8 && self.has_lossy_name()
+    }
+
+    /// A string uniquely identifying the path, suitable as a key in persisted maps.
+    pub fn path_key(&self) -> String {
+        self.path
+            .iter()
+            .map(|name| format!("{:02x}", name.iter().format("")))
+            .join("/")
+    }
+
+    /// The reference to the latest version of the record with the path of `path_key`, `None` if
+    /// the key is malformed.
+    pub fn from_path_key(key: &str, encoding_hint: Encoding) -> Option<Self> {
+        let path = if key.is_empty() {
+            Vec::new()
+        } else {
+            key.split('/')
+                .map(|name| Encoding::HEX.decode(name).ok())
+                .collect::<Option<_>>()?
+        };
+
+        Some(Self {
+            path,
+            encoding_hint,
+            version: None,
+        })
+    }
+
+    /// The path in the encoding of `encoding_hint`, starting with a `/`, with `/` within names
+    /// escaped like in `split_path`.
+    /// Names which cannot be displayed faithfully in that encoding are displayed according to
+    /// `lossy_names`.
+    pub fn display_path(&self, lossy_names: LossyNameDisplay) -> String {
+        if self.is_root() {
+            return "/".to_string();
+        }
+
+        // Appended to in place, as paths may be very deep.
+        let mut path = String::new();
+
+        if lossy_names == LossyNameDisplay::Hex && self.has_lossy_name() {
+            for name in &self.path {
+                path.push('/');
+                path.push_str(&Encoding::HEX.encode(name));
+            }
+
+            path.push_str(" (hex)");
+            return path;
+        }
+
+        for name in &self.path {
+            path.push('/');
+            path.push_str(&escape_name(&self.encoding_hint.encode(name)));
+
+            if !self.encoding_hint.is_lossless(name) {
+                path.push_str(LOSSY_NAME_MARKER);
+            }
+        }
+
+        path
+    }
+
+    /// The name of the record like in `display_path`, `/` for the root record.
+    pub fn display_name(&self, lossy_names: LossyNameDisplay) -> String {
+        self.display_ancestor_name(self.path.len(), lossy_names)
+    }
+
+    /// The name of the ancestor at `depth` like in `display_name`, without constructing the
+    /// ancestor. See `RecordRef::ancestor`.
+    pub fn display_ancestor_name(&self, depth: usize, lossy_names: LossyNameDisplay) -> String {
+        let Some(name) = depth.checked_sub(1).map(|index| &self.path[index]) else {
+            return "/".to_string();
+        };
+
+        if self.encoding_hint.is_lossless(name) {
+            self.encoding_hint.encode(name)
+        } else if lossy_names == LossyNameDisplay::Hex {
+            format!("{} (hex)", Encoding::HEX.encode(name))
+        } else {
+            format!("{}{LOSSY_NAME_MARKER}", self.encoding_hint.encode(name))
+        }
+    }
+}
+
+impl PartialEq for RecordRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.version == other.version
+    }
+}
+
+impl Eq for RecordRef {}
+
+impl Hash for RecordRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.version.hash(state);
+    }
+}
+
+/// Record names may be confidential, so they are not included in logs.
+impl Debug for RecordRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordRef")
+            .field("depth", &self.path.len())
+            .field("encoding_hint", &self.encoding_hint)
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(path: &str, encoding: Encoding) -> Result<Vec<Vec<u8>>, InvalidPathName> {
+        parse_relative_path(path, encoding)
+    }
+
+    #[test]
+    fn split_path_resolves_escapes() {
+        assert_eq!(split_path("a/b"), ["a", "b"]);
+        assert_eq!(split_path(r"a\/b/c"), ["a/b", "c"]);
+        assert_eq!(split_path(r"a\\/b"), [r"a\", "b"]);
+        assert_eq!(split_path(r"a\\\/b"), [r"a\/b"]);
+    }
+
+    #[test]
+    fn split_path_keeps_other_backslashes() {
+        assert_eq!(split_path(r"a\"), [r"a\"]);
+        assert_eq!(split_path(r"a\/b\"), [r"a/b\"]);
+        assert_eq!(split_path(r"a\x/b"), [r"a\x", "b"]);
+    }
+
+    #[test]
+    fn split_path_keeps_empty_names() {
+        assert_eq!(split_path(""), [""]);
+        assert_eq!(split_path("/"), ["", ""]);
+        assert_eq!(split_path("/a//b/"), ["", "a", "", "b", ""]);
+    }
+
+    #[test]
+    fn escape_name_is_resolved_by_split_path() {
+        for name in ["a/b", r"a\b", r"a\/b", r"\", "//", r"a\"] {
+            assert_eq!(split_path(&escape_name(name)), [name]);
+        }
+    }
+
+    #[test]
+    fn parse_relative_path_skips_empty_names() {
+        assert_eq!(
+            parse("/a//b/", Encoding::UTF8).unwrap(),
+            [b"a".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(
+            parse(r"a\//b", Encoding::UTF8).unwrap(),
+            [b"a/".to_vec(), b"b".to_vec()]
+        );
+    }
+
+    #[test]
+    fn parse_relative_path_of_nothing_but_an_empty_name() {
+        assert_eq!(parse("", Encoding::UTF8).unwrap(), [Vec::<u8>::new()]);
+        assert_eq!(parse("", Encoding::HEX).unwrap(), [Vec::<u8>::new()]);
+
+        for path in ["/", "//"] {
+            let error = parse(path, Encoding::UTF8).unwrap_err();
+            assert_eq!(error.range, 0..path.len());
+            assert_eq!(error.error.position, 0);
+        }
+    }
+
+    #[test]
+    fn parse_relative_path_decodes_hex_names() {
+        assert_eq!(
+            parse("0x6162/c", Encoding::UTF8).unwrap(),
+            [b"ab".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(
+            parse("6162/0x63", Encoding::HEX).unwrap(),
+            [b"ab".to_vec(), b"c".to_vec()]
+        );
+        assert_eq!(parse("0x", Encoding::UTF8).unwrap(), [Vec::<u8>::new()]);
+        // Only a leading `0x` marks the name as hexadecimal.
+        assert_eq!(parse("a0x61", Encoding::UTF8).unwrap(), [b"a0x61".to_vec()]);
+    }
+
+    #[test]
+    fn parse_relative_path_errors_are_relative_to_the_path() {
+        let error = parse("a/0x6g", Encoding::UTF8).unwrap_err();
+        assert_eq!(error.range, 2..6);
+        assert_eq!(error.error.position, 5);
+        assert_eq!(error.error.message, "`g` is not a hexadecimal digit");
+
+        let error = parse("a/0x616/b", Encoding::UTF8).unwrap_err();
+        assert_eq!(error.range, 2..7);
+        assert_eq!(error.error.position, 7);
+        assert_eq!(error.error.message, "expected another hexadecimal digit");
+
+        let error = parse("é", Encoding::HEX).unwrap_err();
+        assert_eq!(error.range, 0..2);
+        assert_eq!(error.error.position, 0);
+    }
+
+    #[test]
+    fn parse_relative_path_errors_point_at_escapes() {
+        let error = parse(r"ab/c\/d", Encoding::HEX).unwrap_err();
+        assert_eq!(error.range, 3..7);
+        assert_eq!(error.error.position, 4);
+        assert_eq!(error.error.message, "`/` is not a hexadecimal digit");
+    }
+
+    fn hash(record_ref: &RecordRef) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        record_ref.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn record_refs_are_compared_by_their_names_regardless_of_the_encoding() {
+        let utf8 = RecordRef::parse_path("ab/c", Encoding::UTF8).unwrap();
+        let hex = RecordRef::parse_path("6162/63", Encoding::HEX).unwrap();
+
+        assert_ne!(utf8.encoding_hint, hex.encoding_hint);
+        assert_eq!(utf8, hex);
+        assert_eq!(hash(&utf8), hash(&hex));
+
+        let child = RecordRef::root().child("ab", Encoding::HEX);
+        assert_eq!(child, RecordRef::from_utf8_path("/ab"));
+        assert_eq!(hash(&child), hash(&RecordRef::from_utf8_path("/ab")));
+    }
+
+    #[test]
+    fn record_refs_to_different_versions_differ() {
+        let latest = RecordRef::from_utf8_path("a");
+        let first = latest.at_version(Some(0));
+
+        assert_ne!(latest, first);
+        assert_ne!(first, latest.at_version(Some(1)));
+        assert_eq!(first.at_version(None), latest);
+    }
+
+    #[test]
+    fn record_ref_debug_omits_the_names() {
+        let record_ref = RecordRef::parse_path("secret/name", Encoding::UTF8).unwrap();
+        let debug = format!("{record_ref:?}");
+
+        for name in ["secret", "name"] {
+            assert!(!debug.contains(name), "{debug}");
+            assert!(
+                !debug.contains(&Encoding::HEX.encode(name.as_bytes())),
+                "{debug}"
+            );
+        }
+
+        assert!(!debug.contains("path"), "{debug}");
+        assert!(debug.contains("depth: 2"), "{debug}");
+    }
+
+    /// Every way of displaying the path, `Mark` first.
+    fn display_paths(record_ref: &RecordRef) -> [String; 2] {
+        [LossyNameDisplay::Mark, LossyNameDisplay::Hex].map(|lossy| record_ref.display_path(lossy))
+    }
+
+    #[test]
+    fn names_of_hex_digits_are_displayed_faithfully_in_both_encodings() {
+        let as_text = RecordRef::from_utf8_path("c0ffee/00");
+        let as_bytes = RecordRef::root()
+            .child(vec![0xc0, 0xff, 0xee], Encoding::HEX)
+            .child(vec![0x00], Encoding::HEX);
+
+        for record_ref in [&as_text, &as_bytes] {
+            assert!(!record_ref.has_lossy_name());
+            assert_eq!(display_paths(record_ref), ["/c0ffee/00", "/c0ffee/00"]);
+        }
+
+        // The same text names different records in either encoding.
+        assert_ne!(as_text, as_bytes);
+    }
+
+    #[test]
+    fn valid_utf8_names_are_displayed_faithfully() {
+        let record_ref = RecordRef::from_utf8_path("café/💥 a\u{301}");
+
+        assert!(!record_ref.has_lossy_name());
+        assert!(!record_ref.has_invalid_utf8_name());
+        assert_eq!(
+            display_paths(&record_ref),
+            ["/café/💥 a\u{301}", "/café/💥 a\u{301}"]
+        );
+        assert_eq!(
+            record_ref.display_name(LossyNameDisplay::Hex),
+            "💥 a\u{301}"
+        );
+    }
+
+    #[test]
+    fn names_neither_utf8_nor_displayed_in_hex_are_marked() {
+        let name = b"\xc0\xffcaf\xc3".to_vec();
+        let record_ref = RecordRef::from_utf8_path("parent").child(name.clone(), Encoding::UTF8);
+        let lossy = String::from_utf8_lossy(&name);
+
+        assert!(record_ref.has_lossy_name());
+        assert!(record_ref.has_invalid_utf8_name());
+        // Only the name which cannot be displayed faithfully is marked.
+        assert_eq!(
+            display_paths(&record_ref),
+            [
+                format!("/parent/{lossy}{LOSSY_NAME_MARKER}"),
+                "/706172656e74/c0ff636166c3 (hex)".to_string(),
+            ]
+        );
+        assert_eq!(
+            record_ref.display_name(LossyNameDisplay::Mark),
+            format!("{lossy}{LOSSY_NAME_MARKER}")
+        );
+        assert_eq!(
+            record_ref.display_name(LossyNameDisplay::Hex),
+            "c0ff636166c3 (hex)"
+        );
+        assert_eq!(
+            record_ref.display_ancestor_name(1, LossyNameDisplay::Hex),
+            "parent"
+        );
+
+        // Displayed in hex, the same name is faithful.
+        let in_hex = RecordRef::root().child(name, Encoding::HEX);
+        assert!(!in_hex.has_lossy_name());
+        assert_eq!(display_paths(&in_hex), ["/c0ff636166c3", "/c0ff636166c3"]);
+    }
+}
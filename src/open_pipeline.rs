@@ -0,0 +1,601 @@
+//! Opening records, from resolving their path to loading and classifying one of their versions,
+//! the latest one unless a specific one is referenced.
+
+use std::fmt::Display;
+use std::future::Future;
+
+use color_eyre::eyre::{eyre, Report};
+use rrr::record::{
+    HashedRecordKey, RecordKey, RecordName, RecordReadVersionSuccess, SuccessionNonce,
+    RECORD_NAME_ROOT,
+};
+use rrr::registry::Registry;
+use rrr::utils::fd_lock::ReadLock;
+use rrr::utils::serde::BytesOrAscii;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::model::RecordRef;
+
+/// Where the resolution of the path of a record starts.
+#[derive(Debug, Clone)]
+pub enum ResolveFrom {
+    /// The root record, resolving the whole path.
+    Root,
+    /// The parent of the record, whose succession nonce is known, so that only the last name of
+    /// the path is resolved.
+    Parent(SuccessionNonce),
+    /// The ancestor of the record whose path has the first `depth` names, whose succession nonce
+    /// is known, so that only the names following them are resolved.
+    Ancestor {
+        depth: usize,
+        succession_nonce: SuccessionNonce,
+    },
+    /// The record itself, whose key is known, so that nothing is resolved.
+    Resolved(HashedRecordKey),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenProgress {
+    /// Deriving the key of the record at `depth` of the `total` records along the path, including
+    /// the root record.
+    Resolving {
+        depth: usize,
+        total: usize,
+    },
+    ListingVersions,
+    LoadingVersion,
+}
+
+impl Display for OpenProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Resolving { depth, total } => write!(f, "resolving {depth} of {total}"),
+            Self::ListingVersions => write!(f, "listing versions"),
+            Self::LoadingVersion => write!(f, "loading"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentClassification {
+    Empty,
+    /// Valid UTF-8.
+    Text,
+    Binary,
+}
+
+impl ContentClassification {
+    pub fn classify(data: &[u8]) -> Self {
+        if data.is_empty() {
+            Self::Empty
+        } else if std::str::from_utf8(data).is_ok() {
+            Self::Text
+        } else {
+            Self::Binary
+        }
+    }
+}
+
+/// A version of a record, as loaded by `open`.
+#[derive(Debug)]
+pub struct LoadedRecord {
+    pub hashed_record_key: HashedRecordKey,
+    /// The versions of the record that were listed, oldest first, including the loaded one.
+    pub versions: Vec<u64>,
+    pub record: RecordReadVersionSuccess,
+    pub classification: ContentClassification,
+}
+
+/// The deepest record along a path that could be opened, as opened by `open_deepest`.
+#[derive(Debug)]
+pub struct DeepestRecord {
+    pub record_ref: RecordRef,
+    pub loaded_record: LoadedRecord,
+    /// The index of the name of the path whose record could not be opened, with the reason, or
+    /// `None` if the whole path was opened.
+    pub failure: Option<(usize, OpenError)>,
+}
+
+#[derive(Debug)]
+pub enum OpenError {
+    /// The record has no versions.
+    NotFound {
+        hashed_record_key: HashedRecordKey,
+    },
+    /// The referenced version of the record is not listed.
+    VersionNotFound {
+        version: u64,
+    },
+    /// The version is listed, but its content could not be loaded and decrypted.
+    DecryptFailed,
+    Io(std::io::Error),
+    Cancelled,
+    /// Any other failure of the registry.
+    Registry(Report),
+}
+
+impl OpenError {
+    /// Whether the error stops opening the path at the record by `open_deepest`, rather than
+    /// failing it, as a missing or undecryptable record is not a failure of the registry.
+    fn stops_the_path(&self) -> bool {
+        matches!(self, Self::NotFound { .. } | Self::DecryptFailed)
+    }
+
+    fn from_report(report: Report) -> Self {
+        if let Some(kind) = report
+            .chain()
+            .find_map(|error| error.downcast_ref::<std::io::Error>())
+            .map(std::io::Error::kind)
+        {
+            Self::Io(std::io::Error::new(kind, format!("{report:#}")))
+        } else {
+            Self::Registry(report)
+        }
+    }
+}
+
+impl Display for OpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound { .. } => write!(f, "The record does not exist."),
+            Self::VersionNotFound { version } => {
+                write!(f, "Version {version} of the record does not exist.")
+            }
+            Self::DecryptFailed => write!(f, "Failed to load the record version."),
+            Self::Io(error) => write!(f, "Failed to read the registry: {error}"),
+            Self::Cancelled => write!(f, "Opening the record was cancelled."),
+            Self::Registry(report) => write!(f, "{report:#}"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Registry(report) => Some(report.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Awaits a step of the pipeline, unless the pipeline is cancelled first.
+async fn step<T, E>(
+    cancellation: &CancellationToken,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, OpenError>
+where
+    Report: From<E>,
+{
+    tokio::select! {
+        biased;
+        () = cancellation.cancelled() => Err(OpenError::Cancelled),
+        result = future => result.map_err(|error| OpenError::from_report(error.into())),
+    }
+}
+
+/// Derives the key of the record at `record_ref`, reporting the progress along the way.
+async fn resolve(
+    registry: &Registry<ReadLock>,
+    record_ref: &RecordRef,
+    from: ResolveFrom,
+    progress: &mut (dyn FnMut(OpenProgress) + Send),
+    cancellation: &CancellationToken,
+) -> Result<HashedRecordKey, OpenError> {
+    Ok(match from {
+        ResolveFrom::Resolved(hashed_record_key) => hashed_record_key,
+        ResolveFrom::Parent(predecessor_nonce) => {
+            let total = record_ref.path().len() + 1;
+            progress(OpenProgress::Resolving {
+                depth: total,
+                total,
+            });
+            let record_name: RecordName = BytesOrAscii(
+                record_ref
+                    .path()
+                    .last()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .into(),
+            );
+            let record_key = RecordKey {
+                predecessor_nonce,
+                record_name,
+            };
+            step(cancellation, record_key.hash(&registry.config.hash)).await?
+        }
+        ResolveFrom::Ancestor {
+            depth,
+            succession_nonce,
+        } => {
+            let total = record_ref.path().len() + 1;
+            let Some((last_name, names)) = record_ref
+                .path()
+                .get(depth..)
+                .and_then(|names| names.split_last())
+            else {
+                return Err(OpenError::Registry(eyre!(
+                    "The record is not a descendant of the ancestor to resolve it from."
+                )));
+            };
+            let mut predecessor_nonce = succession_nonce;
+
+            for (index, name) in names.iter().enumerate() {
+                progress(OpenProgress::Resolving {
+                    depth: depth + index + 2,
+                    total,
+                });
+                let hashed_record_key = step(
+                    cancellation,
+                    RecordKey {
+                        predecessor_nonce,
+                        record_name: BytesOrAscii(name.as_slice().into()),
+                    }
+                    .hash(&registry.config.hash),
+                )
+                .await?;
+                predecessor_nonce = step(
+                    cancellation,
+                    hashed_record_key.derive_succession_nonce(&registry.config.kdf),
+                )
+                .await?;
+            }
+
+            progress(OpenProgress::Resolving {
+                depth: total,
+                total,
+            });
+            let record_key = RecordKey {
+                predecessor_nonce,
+                record_name: BytesOrAscii(last_name.as_slice().into()),
+            };
+            step(cancellation, record_key.hash(&registry.config.hash)).await?
+        }
+        ResolveFrom::Root => {
+            let total = record_ref.path().len() + 1;
+            let mut predecessor_nonce = registry
+                .config
+                .kdf
+                .get_root_record_predecessor_nonce()
+                .clone();
+            let mut record_name = RECORD_NAME_ROOT;
+
+            for (depth, next_record_name) in record_ref.path().iter().enumerate() {
+                progress(OpenProgress::Resolving {
+                    depth: depth + 1,
+                    total,
+                });
+                let hashed_record_key = step(
+                    cancellation,
+                    RecordKey {
+                        predecessor_nonce,
+                        record_name,
+                    }
+                    .hash(&registry.config.hash),
+                )
+                .await?;
+                predecessor_nonce = step(
+                    cancellation,
+                    hashed_record_key.derive_succession_nonce(&registry.config.kdf),
+                )
+                .await?;
+                record_name = BytesOrAscii(next_record_name.as_slice().into());
+            }
+
+            progress(OpenProgress::Resolving {
+                depth: total,
+                total,
+            });
+            let record_key = RecordKey {
+                predecessor_nonce,
+                record_name,
+            };
+            step(cancellation, record_key.hash(&registry.config.hash)).await?
+        }
+    })
+}
+
+/// Whether any version of the record at `record_ref` exists, without loading it.
+pub async fn exists(
+    registry: &Registry<ReadLock>,
+    record_ref: &RecordRef,
+    from: ResolveFrom,
+    cancellation: &CancellationToken,
+) -> Result<bool, OpenError> {
+    let hashed_record_key = resolve(registry, record_ref, from, &mut |_| (), cancellation).await?;
+    let versions = step(
+        cancellation,
+        registry.list_record_versions(&hashed_record_key, 4, 4),
+    )
+    .await?;
+
+    Ok(!versions.is_empty())
+}
+
+/// Opens the version of the record at `record_ref` it references, or its latest version,
+/// reporting the progress along the way.
+pub async fn open(
+    registry: &Registry<ReadLock>,
+    record_ref: &RecordRef,
+    from: ResolveFrom,
+    progress: &mut (dyn FnMut(OpenProgress) + Send),
+    cancellation: &CancellationToken,
+) -> Result<LoadedRecord, OpenError> {
+    let hashed_record_key = resolve(registry, record_ref, from, progress, cancellation).await?;
+    progress(OpenProgress::ListingVersions);
+    let versions = list_versions(registry, &hashed_record_key, cancellation).await?;
+    let Some(version) = select_version(record_ref.version, &versions)? else {
+        return Err(OpenError::NotFound { hashed_record_key });
+    };
+    let loaded_record = load_version(
+        registry,
+        &hashed_record_key,
+        versions,
+        version,
+        progress,
+        cancellation,
+    )
+    .await?;
+
+    debug!(?record_ref, version, classification = ?loaded_record.classification, "Record opened.");
+
+    Ok(loaded_record)
+}
+
+/// The `requested` version among the listed `versions`, or the latest one, or `None` if the
+/// record has no versions.
+fn select_version(requested: Option<u64>, versions: &[u64]) -> Result<Option<u64>, OpenError> {
+    match (requested, versions.last()) {
+        (_, None) => Ok(None),
+        (Some(version), Some(_)) if !versions.contains(&version) => {
+            Err(OpenError::VersionNotFound { version })
+        }
+        (Some(version), Some(_)) => Ok(Some(version)),
+        (None, Some(latest_version)) => Ok(Some(*latest_version)),
+    }
+}
+
+/// Opens the latest version of the resolved record, if it has more than `version_count`
+/// versions, so that checking for new versions loads nothing otherwise.
+pub async fn open_if_newer(
+    registry: &Registry<ReadLock>,
+    hashed_record_key: &HashedRecordKey,
+    version_count: usize,
+    progress: &mut (dyn FnMut(OpenProgress) + Send),
+    cancellation: &CancellationToken,
+) -> Result<Option<LoadedRecord>, OpenError> {
+    progress(OpenProgress::ListingVersions);
+    let versions = list_versions(registry, hashed_record_key, cancellation).await?;
+    let Some(latest_version) = versions.last().filter(|_| versions.len() > version_count) else {
+        return Ok(None);
+    };
+    let latest_version = *latest_version;
+
+    load_version(
+        registry,
+        hashed_record_key,
+        versions,
+        latest_version,
+        progress,
+        cancellation,
+    )
+    .await
+    .map(Some)
+}
+
+/// The versions of the resolved record, oldest first.
+async fn list_versions(
+    registry: &Registry<ReadLock>,
+    hashed_record_key: &HashedRecordKey,
+    cancellation: &CancellationToken,
+) -> Result<Vec<u64>, OpenError> {
+    let versions = step(
+        cancellation,
+        registry.list_record_versions(hashed_record_key, 4, 4),
+    )
+    .await?;
+
+    Ok(versions
+        .iter()
+        .map(|version| version.record_version)
+        .collect())
+}
+
+/// Loads one of the listed `versions` of the resolved record.
+async fn load_version(
+    registry: &Registry<ReadLock>,
+    hashed_record_key: &HashedRecordKey,
+    versions: Vec<u64>,
+    version: u64,
+    progress: &mut (dyn FnMut(OpenProgress) + Send),
+    cancellation: &CancellationToken,
+) -> Result<LoadedRecord, OpenError> {
+    progress(OpenProgress::LoadingVersion);
+    let record = step(
+        cancellation,
+        registry.load_record(hashed_record_key, version, 4),
+    )
+    .await?
+    .ok_or(OpenError::DecryptFailed)?;
+
+    Ok(LoadedRecord {
+        hashed_record_key: hashed_record_key.clone(),
+        versions,
+        classification: ContentClassification::classify(&record.data),
+        record,
+    })
+}
+
+/// Opens the records along the path of `record_ref` in turn, starting with the root record, and
+/// stops at the deepest one that could be opened. Only fails if the root record cannot be opened,
+/// or on errors other than a missing or undecryptable record.
+pub async fn open_deepest(
+    registry: &Registry<ReadLock>,
+    record_ref: &RecordRef,
+    progress: &mut (dyn FnMut(OpenProgress) + Send),
+    cancellation: &CancellationToken,
+) -> Result<DeepestRecord, OpenError> {
+    let mut deepest_ref = RecordRef::root();
+    deepest_ref.encoding_hint = record_ref.encoding_hint;
+    let mut deepest = open(
+        registry,
+        &deepest_ref,
+        ResolveFrom::Root,
+        progress,
+        cancellation,
+    )
+    .await?;
+
+    for (index, name) in record_ref.path().iter().enumerate() {
+        let child_ref = deepest_ref.child(name.clone(), record_ref.encoding_hint);
+        let predecessor_nonce = step(
+            cancellation,
+            deepest
+                .hashed_record_key
+                .derive_succession_nonce(&registry.config.kdf),
+        )
+        .await?;
+
+        match open(
+            registry,
+            &child_ref,
+            ResolveFrom::Parent(predecessor_nonce),
+            progress,
+            cancellation,
+        )
+        .await
+        {
+            Ok(loaded_record) => {
+                deepest_ref = child_ref;
+                deepest = loaded_record;
+            }
+            Err(error) if error.stops_the_path() => {
+                debug!(?record_ref, index, %error, "Stopped opening the path.");
+                return Ok(DeepestRecord {
+                    record_ref: deepest_ref,
+                    loaded_record: deepest,
+                    failure: Some((index, error)),
+                });
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(DeepestRecord {
+        record_ref: deepest_ref,
+        loaded_record: deepest,
+        failure: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_without_versions_is_not_found() {
+        assert!(matches!(select_version(None, &[]), Ok(None)));
+        assert!(matches!(select_version(Some(1), &[]), Ok(None)));
+    }
+
+    #[test]
+    fn unlisted_version_is_not_found() {
+        let error = select_version(Some(2), &[0, 1, 3]).unwrap_err();
+
+        assert!(matches!(error, OpenError::VersionNotFound { version: 2 }));
+        assert_eq!(error.to_string(), "Version 2 of the record does not exist.");
+        assert!(!error.stops_the_path());
+    }
+
+    #[test]
+    fn latest_version_is_selected_unless_one_is_referenced() {
+        assert_eq!(select_version(None, &[0, 1, 3]).unwrap(), Some(3));
+        assert_eq!(select_version(Some(1), &[0, 1, 3]).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn undecryptable_version_stops_the_path() {
+        let error = OpenError::DecryptFailed;
+
+        assert_eq!(error.to_string(), "Failed to load the record version.");
+        assert!(error.stops_the_path());
+    }
+
+    #[tokio::test]
+    async fn io_errors_keep_their_kind() {
+        let error = step(&CancellationToken::new(), async {
+            Err::<(), _>(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "access denied",
+            ))
+        })
+        .await
+        .unwrap_err();
+
+        let OpenError::Io(io_error) = &error else {
+            panic!("expected an I/O error, got {error:?}");
+        };
+        assert_eq!(io_error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(error
+            .to_string()
+            .starts_with("Failed to read the registry: "));
+        assert!(error.to_string().contains("access denied"));
+        assert!(!error.stops_the_path());
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_registry_errors() {
+        let error = step(&CancellationToken::new(), async {
+            Err::<(), _>(eyre!("The registry is malformed."))
+        })
+        .await
+        .unwrap_err();
+
+        assert!(matches!(error, OpenError::Registry(_)));
+        assert_eq!(error.to_string(), "The registry is malformed.");
+        assert!(std::error::Error::source(&error).is_some());
+        assert!(!error.stops_the_path());
+    }
+
+    #[tokio::test]
+    async fn cancelled_step_is_not_awaited() {
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        let error = step(&cancellation, std::future::pending::<Result<(), Report>>())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, OpenError::Cancelled));
+        assert_eq!(error.to_string(), "Opening the record was cancelled.");
+    }
+
+    #[tokio::test]
+    async fn cancellation_interrupts_a_pending_step() {
+        let cancellation = CancellationToken::new();
+        let cancel = {
+            let cancellation = cancellation.clone();
+            async move {
+                tokio::task::yield_now().await;
+                cancellation.cancel();
+            }
+        };
+
+        let (result, ()) = tokio::join!(
+            step(&cancellation, std::future::pending::<Result<(), Report>>()),
+            cancel
+        );
+
+        assert!(matches!(result, Err(OpenError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn completed_step_is_not_cancelled() {
+        let value = step(&CancellationToken::new(), async { Ok::<_, Report>(7) })
+            .await
+            .unwrap();
+
+        assert_eq!(value, 7);
+    }
+}
@@ -0,0 +1,238 @@
+//! A summary of the registry saved whenever the application exits cleanly, from which the
+//! interface is drawn on the next start while the registry is still being opened.
+//! Only references to records are stored, never their content.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::model::RecordRef;
+
+/// Name of the summary file inside the registry state directory.
+const SUMMARY_FILE_NAME: &str = "summary.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistrySummary {
+    /// The identity of the registry the summary was saved for, see `crate::env::get_registry_id`.
+    pub registry_id: Option<String>,
+    /// The number of records that were in the tree. The registry cannot list its records, so
+    /// this is only a lower bound.
+    pub record_count: usize,
+    /// When the registry was last opened, in seconds since the Unix epoch.
+    pub opened_at: u64,
+    /// The records directly under the root record that were in the tree, from which the skeleton
+    /// of the tree is drawn along with the path of `last_record`.
+    pub pinned_roots: Vec<RecordRef>,
+    /// The record that was opened when the application exited.
+    pub last_record: Option<RecordRef>,
+}
+
+/// The differences between the summary drawn on startup and the state of the registry once
+/// opened.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SummaryChanges {
+    /// Records directly under the root record that were not in the summary, oldest first.
+    pub added_roots: Vec<RecordRef>,
+    /// Whether the record opened first is not the one that was opened last time.
+    pub last_record_changed: bool,
+    /// The number of records in the summary and in the tree, if they differ.
+    pub record_count: Option<(usize, usize)>,
+}
+
+impl SummaryChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl RegistrySummary {
+    pub fn new(
+        registry_id: Option<String>,
+        record_count: usize,
+        pinned_roots: Vec<RecordRef>,
+        last_record: Option<RecordRef>,
+    ) -> Self {
+        Self {
+            registry_id,
+            record_count,
+            opened_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            pinned_roots,
+            last_record,
+        }
+    }
+
+    fn path(registry_directory: &Path) -> PathBuf {
+        crate::env::get_registry_state_dir(registry_directory).join(SUMMARY_FILE_NAME)
+    }
+
+    /// Loads the summary saved for the registry at `registry_directory`. `None` if there is none,
+    /// or if it was saved for another registry, such as one that was replaced at the same path.
+    pub fn load(registry_directory: &Path) -> Result<Option<Self>> {
+        Self::load_from(
+            &Self::path(registry_directory),
+            crate::env::get_registry_id(registry_directory).as_deref(),
+        )
+    }
+
+    fn load_from(path: &Path, registry_id: Option<&str>) -> Result<Option<Self>> {
+        let summary: Self = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).wrap_err_with(|| {
+                format!(
+                    "Failed to read the registry summary file {}",
+                    path.display()
+                )
+            })?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        if summary.registry_id.as_deref() != registry_id {
+            debug!(
+                saved_for = ?summary.registry_id,
+                ?registry_id,
+                "The registry summary was saved for another registry, ignoring it."
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(summary))
+    }
+
+    pub fn save(&self, registry_directory: &Path) -> Result<()> {
+        self.save_to(&Self::path(registry_directory))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// What differs in this summary, of the opened registry, from the `stale` one drawn before.
+    pub fn changes_since(&self, stale: &Self) -> SummaryChanges {
+        SummaryChanges {
+            added_roots: self
+                .pinned_roots
+                .iter()
+                .filter(|record_ref| !stale.pinned_roots.contains(record_ref))
+                .cloned()
+                .collect(),
+            last_record_changed: self.last_record != stale.last_record,
+            record_count: (self.record_count != stale.record_count)
+                .then_some((stale.record_count, self.record_count)),
+        }
+    }
+
+    /// How long ago the registry was last opened, e.g. `3 hours ago`.
+    pub fn age(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let minutes_ago = now.saturating_sub(self.opened_at) / 60;
+
+        match minutes_ago {
+            0 => "just now".to_string(),
+            1 => "1 minute ago".to_string(),
+            minutes if minutes < 120 => format!("{minutes} minutes ago"),
+            minutes if minutes < 48 * 60 => format!("{} hours ago", minutes / 60),
+            minutes => format!("{} days ago", minutes / (24 * 60)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for the summary file of each test, as tests run in parallel.
+    fn summary_path(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-registry-summary-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory.join(SUMMARY_FILE_NAME)
+    }
+
+    fn summary(registry_id: &str, roots: &[&str], last_record: &str) -> RegistrySummary {
+        RegistrySummary::new(
+            Some(registry_id.to_string()),
+            roots.len() + 1,
+            roots
+                .iter()
+                .map(|root| RecordRef::from_utf8_path(root))
+                .collect(),
+            Some(RecordRef::from_utf8_path(last_record)),
+        )
+    }
+
+    #[test]
+    fn summary_of_the_same_registry_is_loaded() {
+        let path = summary_path("same_registry");
+        let saved = summary("id-0000000000000001", &["a", "b"], "a/c");
+        saved.save_to(&path).unwrap();
+
+        assert_eq!(
+            RegistrySummary::load_from(&path, Some("id-0000000000000001")).unwrap(),
+            Some(saved)
+        );
+    }
+
+    #[test]
+    fn summary_of_another_registry_is_ignored() {
+        let path = summary_path("other_registry");
+        summary("id-0000000000000001", &["a"], "a")
+            .save_to(&path)
+            .unwrap();
+
+        assert_eq!(
+            RegistrySummary::load_from(&path, Some("id-0000000000000002")).unwrap(),
+            None
+        );
+        // Registries without a readable `registry.cbor` have no identity to match either.
+        assert_eq!(RegistrySummary::load_from(&path, None).unwrap(), None);
+    }
+
+    #[test]
+    fn missing_summary_is_not_an_error() {
+        let path = summary_path("missing");
+
+        assert_eq!(
+            RegistrySummary::load_from(&path, Some("id-0000000000000001")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn unchanged_registry_has_no_changes() {
+        let stale = summary("id-0000000000000001", &["a", "b"], "a/c");
+        let current = summary("id-0000000000000001", &["a", "b"], "a/c");
+
+        assert!(current.changes_since(&stale).is_empty());
+    }
+
+    #[test]
+    fn reconciliation_reports_what_changed() {
+        let stale = summary("id-0000000000000001", &["a", "b"], "a/c");
+        let current = summary("id-0000000000000001", &["a", "b", "d"], "d");
+
+        assert_eq!(
+            current.changes_since(&stale),
+            SummaryChanges {
+                added_roots: vec![RecordRef::from_utf8_path("d")],
+                last_record_changed: true,
+                record_count: Some((3, 4)),
+            }
+        );
+    }
+}
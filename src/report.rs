@@ -0,0 +1,417 @@
+//! Plain text reports about a record, meant to be printed or attached to audits.
+//! Independent of the user interface, so that the same report is produced from the command line.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+use tokio_util::sync::CancellationToken;
+
+use crate::args::Args;
+use crate::cbor::{cbor_value_to_json, record_metadata_key_to_string};
+use crate::config::is_secret_key;
+use crate::model::{LossyNameDisplay, RecordRef};
+use crate::open_pipeline::{OpenError, ResolveFrom};
+use crate::startup::Startup;
+use crate::text::{format_size, invalid_utf8_warning};
+
+/// Number of bytes on each line of a hex preview.
+const HEX_BYTES_PER_LINE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Plain UTF-8 text.
+    #[default]
+    Text,
+    Markdown,
+}
+
+/// How much of the content is included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ReportContent {
+    #[default]
+    None,
+    /// The first lines, as text.
+    Lines,
+    /// The first bytes, as a hex dump.
+    Hex,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    pub content: ReportContent,
+    /// The number of lines or bytes included with `ReportContent::Lines` and `ReportContent::Hex`.
+    pub limit: usize,
+    pub lossy_names: LossyNameDisplay,
+}
+
+/// Everything a report is made of.
+#[derive(Debug, Clone)]
+pub struct ReportRecord<'a> {
+    pub record_ref: &'a RecordRef,
+    pub alias: Option<&'a str>,
+    /// Metadata entries, as textual keys and JSON values.
+    pub metadata: Vec<(String, serde_json::Value)>,
+    pub version_count: usize,
+    pub data: &'a [u8],
+}
+
+/// Writes the report, redacting the values of metadata entries whose keys suggest secrets.
+pub fn render(record: &ReportRecord, options: &ReportOptions) -> String {
+    let mut report = Report {
+        format: options.format,
+        output: String::new(),
+    };
+
+    let path = record.record_ref.display_path(options.lossy_names);
+    report.heading(&format!("Record {path}"));
+    let mut overview = vec![
+        ("Path", path),
+        ("Alias", record.alias.unwrap_or("none").to_string()),
+    ];
+    if let Some(warning) =
+        invalid_utf8_warning(record.record_ref.has_invalid_utf8_name(), record.data)
+    {
+        overview.push(("Warning", warning.to_string()));
+    }
+    report.table(&overview);
+
+    report.heading("Metadata");
+    let metadata = record
+        .metadata
+        .iter()
+        .map(|(key, value)| {
+            let value = if is_secret_key(key) {
+                "<redacted>".to_string()
+            } else {
+                value.to_string()
+            };
+            (key.as_str(), value)
+        })
+        .collect::<Vec<_>>();
+    if metadata.is_empty() {
+        report.paragraph("No metadata.");
+    } else {
+        report.table(&metadata);
+    }
+
+    report.heading("Checksums");
+    report.table(&[
+        (
+            "Size",
+            format!(
+                "{} ({} bytes)",
+                format_size(record.data.len()),
+                record.data.len()
+            ),
+        ),
+        (
+            "SHA-256",
+            format!("{:02x}", Sha256::digest(record.data).iter().format("")),
+        ),
+    ]);
+
+    report.heading("Versions");
+    report.paragraph(&format!(
+        "{} version(s), this report describes the latest one.",
+        record.version_count
+    ));
+
+    let text = String::from_utf8_lossy(record.data);
+    let (title, content) = match options.content {
+        ReportContent::None => return report.output,
+        ReportContent::Lines => (
+            format!("Content (first {} lines)", options.limit),
+            text.lines().take(options.limit).join("\n"),
+        ),
+        ReportContent::Hex => (
+            format!("Content (first {} bytes)", options.limit),
+            record.data[..std::cmp::min(options.limit, record.data.len())]
+                .chunks(HEX_BYTES_PER_LINE)
+                .enumerate()
+                .map(|(index, chunk)| {
+                    format!(
+                        "{:08x}  {:02x}",
+                        index * HEX_BYTES_PER_LINE,
+                        chunk.iter().format(" ")
+                    )
+                })
+                .join("\n"),
+        ),
+        ReportContent::Full => ("Content".to_string(), text.into_owned()),
+    };
+
+    report.heading(&title);
+    report.preformatted(&content);
+    report.output
+}
+
+/// Writes the report requested by `--report` to its destination.
+pub async fn write_report(args: &Args, startup: Startup, path: &Path) -> Result<()> {
+    let record_ref = args.record_ref()?.unwrap_or_else(RecordRef::root);
+    let registry = crate::startup::open_registry(args).await?;
+    let loaded_record = match crate::open_pipeline::open(
+        &registry,
+        &record_ref,
+        ResolveFrom::Root,
+        &mut |_| {},
+        &CancellationToken::new(),
+    )
+    .await
+    {
+        Ok(loaded_record) => loaded_record,
+        Err(OpenError::NotFound { .. }) => {
+            return Err(color_eyre::eyre::eyre!(
+                "The record {} does not exist.",
+                record_ref.display_path(startup.config.display.lossy_names)
+            ));
+        }
+        Err(error) => return Err(error.into()),
+    };
+    let metadata = loaded_record
+        .record
+        .metadata
+        .iter_with_semantic_keys()
+        .map(|(key, value)| {
+            (
+                record_metadata_key_to_string(&key),
+                cbor_value_to_json(value),
+            )
+        })
+        .collect();
+    let report = render(
+        &ReportRecord {
+            record_ref: &record_ref,
+            alias: startup.annotations.get_alias(&record_ref),
+            metadata,
+            version_count: loaded_record.versions.len(),
+            data: &loaded_record.record.data,
+        },
+        &ReportOptions {
+            format: args.report_format,
+            content: args.report_content,
+            limit: args.report_limit,
+            lossy_names: startup.config.display.lossy_names,
+        },
+    );
+
+    if path == Path::new("-") {
+        print!("{report}");
+    } else {
+        std::fs::write(path, report)
+            .wrap_err_with(|| format!("Failed to write the report to {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+struct Report {
+    format: ReportFormat,
+    output: String,
+}
+
+impl Report {
+    fn heading(&mut self, heading: &str) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        match self.format {
+            ReportFormat::Text => {
+                let underline = if self.output.is_empty() { "=" } else { "-" };
+                writeln!(self.output, "{heading}").unwrap();
+                writeln!(self.output, "{}", underline.repeat(heading.chars().count())).unwrap();
+            }
+            ReportFormat::Markdown => {
+                let level = if self.output.is_empty() { "#" } else { "##" };
+                writeln!(self.output, "{level} {}", escape_markdown(heading)).unwrap();
+            }
+        }
+
+        self.output.push('\n');
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        match self.format {
+            ReportFormat::Text => writeln!(self.output, "{text}").unwrap(),
+            ReportFormat::Markdown => writeln!(self.output, "{}", escape_markdown(text)).unwrap(),
+        }
+    }
+
+    fn table<K: AsRef<str>>(&mut self, rows: &[(K, String)]) {
+        match self.format {
+            ReportFormat::Text => {
+                let key_width = rows
+                    .iter()
+                    .map(|(key, _)| key.as_ref().chars().count())
+                    .max()
+                    .unwrap_or(0);
+
+                for (key, value) in rows {
+                    let key = key.as_ref();
+                    let padding = " ".repeat(key_width - key.chars().count());
+                    writeln!(self.output, "{key}{padding}  {value}").unwrap();
+                }
+            }
+            ReportFormat::Markdown => {
+                writeln!(self.output, "| Key | Value |").unwrap();
+                writeln!(self.output, "| --- | --- |").unwrap();
+
+                for (key, value) in rows {
+                    writeln!(
+                        self.output,
+                        "| {} | {} |",
+                        escape_markdown_cell(key.as_ref()),
+                        escape_markdown_cell(value)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    fn preformatted(&mut self, text: &str) {
+        match self.format {
+            ReportFormat::Text => {
+                for line in text.lines() {
+                    writeln!(self.output, "    {line}").unwrap();
+                }
+            }
+            ReportFormat::Markdown => {
+                // The fence must be longer than any run of backticks in the text.
+                let longest_run = text
+                    .split(|character| character != '`')
+                    .map(str::len)
+                    .max()
+                    .unwrap_or(0);
+                let fence = "`".repeat(std::cmp::max(3, longest_run + 1));
+                writeln!(self.output, "{fence}\n{text}\n{fence}").unwrap();
+            }
+        }
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.chars().fold(
+        String::with_capacity(text.len()),
+        |mut escaped, character| {
+            if "\\`*_[]<>#|".contains(character) {
+                escaped.push('\\');
+            }
+            escaped.push(character);
+            escaped
+        },
+    )
+}
+
+/// Escapes the text so that it stays within a single table cell.
+fn escape_markdown_cell(text: &str) -> String {
+    escape_markdown(text).replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compares the report with the golden file at `testdata/report/{name}`, which is rewritten
+    /// instead when `UPDATE_GOLDEN_FILES` is set, so that intended changes are reviewed as diffs.
+    fn assert_golden(name: &str, report: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/report")
+            .join(name);
+
+        if std::env::var_os("UPDATE_GOLDEN_FILES").is_some() {
+            std::fs::write(&path, report).unwrap();
+        }
+
+        let golden = std::fs::read_to_string(&path)
+            .unwrap_or_else(|error| panic!("Failed to read {}: {error}", path.display()));
+        assert!(
+            report == golden,
+            "The report differs from {}:\n{report}",
+            path.display()
+        );
+    }
+
+    fn render_fixture(format: ReportFormat, content: ReportContent, data: &[u8]) -> String {
+        let record_ref = RecordRef::from_utf8_path("notes/2024 *draft*");
+
+        render(
+            &ReportRecord {
+                record_ref: &record_ref,
+                alias: Some("drafts"),
+                metadata: vec![
+                    ("created".to_string(), serde_json::json!("2024-01-01")),
+                    ("api_token".to_string(), serde_json::json!("hunter2")),
+                    (
+                        "summary".to_string(),
+                        serde_json::json!("a | b\nsecond line"),
+                    ),
+                ],
+                version_count: 3,
+                data,
+            },
+            &ReportOptions {
+                format,
+                content,
+                limit: 3,
+                lossy_names: LossyNameDisplay::Mark,
+            },
+        )
+    }
+
+    const TEXT_DATA: &[u8] = b"first line\nsecond `line`\n```\nfourth line\n";
+
+    #[test]
+    fn text_report_matches_the_golden_file() {
+        assert_golden(
+            "lines.txt",
+            &render_fixture(ReportFormat::Text, ReportContent::Lines, TEXT_DATA),
+        );
+    }
+
+    #[test]
+    fn markdown_report_matches_the_golden_file() {
+        assert_golden(
+            "lines.md",
+            &render_fixture(ReportFormat::Markdown, ReportContent::Lines, TEXT_DATA),
+        );
+    }
+
+    #[test]
+    fn hex_reports_of_binary_content_match_the_golden_files() {
+        let data = (0..40u8).map(|byte| byte.wrapping_mul(37)).collect::<Vec<u8>>();
+        let mut options = ReportOptions {
+            format: ReportFormat::Text,
+            content: ReportContent::Hex,
+            limit: 32,
+            lossy_names: LossyNameDisplay::Mark,
+        };
+        let record_ref = RecordRef::from_utf8_path("binary");
+        let record = ReportRecord {
+            record_ref: &record_ref,
+            alias: None,
+            metadata: Vec::new(),
+            version_count: 1,
+            data: &data,
+        };
+
+        assert_golden("hex.txt", &render(&record, &options));
+        options.format = ReportFormat::Markdown;
+        assert_golden("hex.md", &render(&record, &options));
+    }
+
+    #[test]
+    fn secrets_are_redacted_in_both_formats() {
+        for format in [ReportFormat::Text, ReportFormat::Markdown] {
+            let report = render_fixture(format, ReportContent::None, TEXT_DATA);
+
+            assert!(!report.contains("hunter2"), "{report}");
+            assert!(report.contains("redacted"), "{report}");
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Snapshots of the session, offered for restoring after the application exited uncleanly.
+//! Only references to records are stored, never their content.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::model::{LossyNameDisplay, RecordRef};
+
+/// Name of the snapshots file inside the registry state directory.
+const SNAPSHOTS_FILE_NAME: &str = "sessions.json";
+
+/// Name of the file inside the registry state directory which exists while the application runs.
+/// If it exists on startup, the previous session did not exit cleanly.
+const MARKER_FILE_NAME: &str = "session.running";
+
+/// The number of most recent snapshots kept.
+const MAX_SNAPSHOTS: usize = 3;
+
+/// How often a snapshot is taken, in addition to every time a record is opened.
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Seconds since the Unix epoch.
+    pub taken_at: u64,
+    pub record_ref: Option<RecordRef>,
+    /// The index of the first displayed row of the content.
+    pub scroll: usize,
+}
+
+impl SessionSnapshot {
+    pub fn new(record_ref: Option<RecordRef>, scroll: usize) -> Self {
+        Self {
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            record_ref,
+            scroll,
+        }
+    }
+
+    /// A one-line description of when the snapshot was taken and what it contains.
+    pub fn summary(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let minutes_ago = now.saturating_sub(self.taken_at) / 60;
+        let age = match minutes_ago {
+            0 => "just now".to_string(),
+            1 => "1 minute ago".to_string(),
+            minutes if minutes < 120 => format!("{minutes} minutes ago"),
+            minutes => format!("{} hours ago", minutes / 60),
+        };
+
+        match self.record_ref.as_ref() {
+            Some(record_ref) => format!(
+                "{age}: {} at row {}",
+                record_ref.display_path(LossyNameDisplay::Mark),
+                self.scroll + 1
+            ),
+            None => format!("{age}: no record opened"),
+        }
+    }
+}
+
+/// The most recent snapshots of the sessions on a registry, newest last.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionStore {
+    #[serde(default)]
+    snapshots: Vec<SessionSnapshot>,
+    /// Where the snapshots are saved to, if anywhere.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SessionStore {
+    pub fn load(registry_directory: &Path) -> Result<Self> {
+        let path = crate::env::get_registry_state_dir(registry_directory).join(SNAPSHOTS_FILE_NAME);
+        let mut store: Self = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).wrap_err_with(|| {
+                format!(
+                    "Failed to read the session snapshots file {}",
+                    path.display()
+                )
+            })?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(error) => return Err(error.into()),
+        };
+
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    pub fn snapshots(&self) -> &[SessionSnapshot] {
+        &self.snapshots
+    }
+
+    /// Adds the snapshot, dropping the oldest ones beyond `MAX_SNAPSHOTS`, and saves the store.
+    pub fn push(&mut self, snapshot: SessionSnapshot) {
+        if self.snapshots.last().is_some_and(|last| {
+            last.record_ref == snapshot.record_ref && last.scroll == snapshot.scroll
+        }) {
+            self.snapshots.pop();
+        }
+
+        self.snapshots.push(snapshot);
+
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots
+                .drain(..(self.snapshots.len() - MAX_SNAPSHOTS));
+        }
+
+        if let Err(error) = self.save() {
+            error!(?error, "Failed to save the session snapshots.");
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Exists while the application runs, to detect unclean exits.
+#[derive(Debug)]
+pub struct SessionMarker {
+    path: PathBuf,
+}
+
+impl SessionMarker {
+    /// Creates the marker, returning it along with whether the previous session exited uncleanly.
+    pub fn acquire(registry_directory: &Path) -> Result<(Self, bool)> {
+        let path = crate::env::get_registry_state_dir(registry_directory).join(MARKER_FILE_NAME);
+        let unclean_exit = path.exists();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, std::process::id().to_string())?;
+        Ok((Self { path }, unclean_exit))
+    }
+
+    /// Removes the marker, which must only happen when the application exits cleanly.
+    pub fn release(self) {
+        if let Err(error) = std::fs::remove_file(&self.path) {
+            error!(?error, "Failed to remove the session marker.");
+        }
+    }
+}
+
+/// Asks on the terminal which of the snapshots to restore, if any.
+/// Nothing is restored if stdin is not a terminal.
+pub fn prompt_restore(snapshots: &[SessionSnapshot]) -> Option<SessionSnapshot> {
+    if snapshots.is_empty() || !std::io::stdin().is_terminal() {
+        return None;
+    }
+
+    prompt_restore_from(
+        snapshots,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+/// Like `prompt_restore`, asking on `stderr` and reading the answer from `stdin`.
+fn prompt_restore_from(
+    snapshots: &[SessionSnapshot],
+    stdin: &mut impl BufRead,
+    stderr: &mut impl Write,
+) -> Option<SessionSnapshot> {
+    let _ = writeln!(stderr, "The previous session did not exit cleanly.");
+
+    for (index, snapshot) in snapshots.iter().rev().enumerate() {
+        let label = if index == 0 {
+            "Restore the latest snapshot"
+        } else {
+            "Restore an older snapshot"
+        };
+        let _ = writeln!(stderr, "  [{}] {label}, {}", index + 1, snapshot.summary());
+    }
+
+    let _ = write!(stderr, "  [Enter] Start fresh\n> ");
+    let _ = stderr.flush();
+
+    let mut answer = String::new();
+    stdin.read_line(&mut answer).ok()?;
+    let index = answer.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    let snapshot = snapshots.iter().rev().nth(index).cloned();
+
+    debug!(?snapshot, "Session snapshot chosen.");
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry directory of its own for each test, whose state directory is therefore its own
+    /// too, as tests run in parallel.
+    fn registry_directory(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-session-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(crate::env::get_registry_state_dir(&directory));
+        directory
+    }
+
+    fn snapshot(path: &str, scroll: usize) -> SessionSnapshot {
+        SessionSnapshot::new(Some(RecordRef::from_utf8_path(path)), scroll)
+    }
+
+    fn prompt(snapshots: &[SessionSnapshot], answer: &str) -> (Option<SessionSnapshot>, String) {
+        let mut output = Vec::new();
+        let snapshot = prompt_restore_from(snapshots, &mut answer.as_bytes(), &mut output);
+        (snapshot, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn released_marker_is_a_clean_exit() {
+        let directory = registry_directory("clean_exit");
+
+        let (marker, unclean_exit) = SessionMarker::acquire(&directory).unwrap();
+        assert!(!unclean_exit);
+        marker.release();
+
+        let (marker, unclean_exit) = SessionMarker::acquire(&directory).unwrap();
+        assert!(!unclean_exit);
+        marker.release();
+    }
+
+    #[test]
+    fn marker_left_behind_is_an_unclean_exit() {
+        let directory = registry_directory("unclean_exit");
+
+        // As when the process is killed, nothing removes the marker.
+        let (marker, unclean_exit) = SessionMarker::acquire(&directory).unwrap();
+        assert!(!unclean_exit);
+        drop(marker);
+
+        let (marker, unclean_exit) = SessionMarker::acquire(&directory).unwrap();
+        assert!(unclean_exit);
+        marker.release();
+
+        let (marker, unclean_exit) = SessionMarker::acquire(&directory).unwrap();
+        assert!(!unclean_exit);
+        marker.release();
+    }
+
+    #[test]
+    fn snapshots_are_saved_and_the_oldest_dropped() {
+        let directory = registry_directory("saved_snapshots");
+        let mut store = SessionStore::load(&directory).unwrap();
+        assert_eq!(store.snapshots(), []);
+
+        for (index, path) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            store.push(snapshot(path, index));
+        }
+
+        let loaded = SessionStore::load(&directory).unwrap();
+        assert_eq!(loaded.snapshots(), store.snapshots());
+        assert_eq!(
+            loaded
+                .snapshots()
+                .iter()
+                .map(|snapshot| (snapshot.record_ref.clone(), snapshot.scroll))
+                .collect::<Vec<_>>(),
+            [("b", 1), ("c", 2), ("d", 3)]
+                .map(|(path, scroll)| (Some(RecordRef::from_utf8_path(path)), scroll))
+        );
+    }
+
+    #[test]
+    fn unchanged_snapshot_replaces_the_last_one() {
+        let directory = registry_directory("unchanged_snapshot");
+        let mut store = SessionStore::load(&directory).unwrap();
+
+        store.push(snapshot("a", 0));
+        store.push(snapshot("b", 4));
+        store.push(snapshot("b", 4));
+
+        assert_eq!(store.snapshots().len(), 2);
+        assert_eq!(store.snapshots()[1].record_ref, snapshot("b", 4).record_ref);
+    }
+
+    #[test]
+    fn malformed_snapshots_fail_to_load() {
+        let directory = registry_directory("malformed_snapshots");
+        let state_dir = crate::env::get_registry_state_dir(&directory);
+        std::fs::create_dir_all(&state_dir).unwrap();
+        std::fs::write(state_dir.join(SNAPSHOTS_FILE_NAME), "{").unwrap();
+
+        let error = SessionStore::load(&directory).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Failed to read the session snapshots file"));
+    }
+
+    #[test]
+    fn snapshots_are_offered_newest_first() {
+        let snapshots = [snapshot("a", 0), snapshot("b", 9)];
+        let (_, output) = prompt(&snapshots, "\n");
+
+        assert_eq!(
+            output,
+            "The previous session did not exit cleanly.\n\
+             \x20 [1] Restore the latest snapshot, just now: /b at row 10\n\
+             \x20 [2] Restore an older snapshot, just now: /a at row 1\n\
+             \x20 [Enter] Start fresh\n> "
+        );
+    }
+
+    #[test]
+    fn older_snapshot_is_restored() {
+        let snapshots = [snapshot("a", 0), snapshot("b", 1), snapshot("c", 2)];
+
+        assert_eq!(prompt(&snapshots, "1\n").0, Some(snapshots[2].clone()));
+        assert_eq!(prompt(&snapshots, " 2 \n").0, Some(snapshots[1].clone()));
+        assert_eq!(prompt(&snapshots, "3").0, Some(snapshots[0].clone()));
+    }
+
+    #[test]
+    fn other_answers_start_fresh() {
+        let snapshots = [snapshot("a", 0)];
+
+        for answer in ["\n", "", "0\n", "2\n", "yes\n"] {
+            assert_eq!(prompt(&snapshots, answer).0, None, "{answer:?}");
+        }
+    }
+}
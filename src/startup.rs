@@ -0,0 +1,523 @@
+//! The ordered phases the application goes through before the interface is shown.
+
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
+
+use color_eyre::eyre::{Result, WrapErr};
+use rrr::registry::Registry;
+use rrr::utils::fd_lock::ReadLock;
+use tracing::{debug, warn};
+
+use crate::annotations::Annotations;
+use crate::args::Args;
+use crate::config::Config;
+use crate::keymap::KeyMap;
+use crate::registry_summary::RegistrySummary;
+use crate::session::{prompt_restore, SessionMarker, SessionSnapshot, SessionStore};
+use crate::theme::{ERROR_MARKER, SUCCESS_MARKER};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPhase {
+    /// Falls back to the default configuration and key bindings on failure.
+    LoadConfig,
+    /// Falls back to empty annotations on failure, setting the unreadable file aside.
+    LoadAnnotations,
+    /// Offers to restore a snapshot of the previous session if it did not exit cleanly.
+    /// Snapshots are not taken on failure.
+    RecoverSession,
+    /// Runs in the background once the interface is shown, as a pending task in the header.
+    /// The failure is shown in the interface, in place of the registry.
+    OpenRegistry,
+}
+
+impl Display for StartupPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LoadConfig => write!(f, "Loading the configuration"),
+            Self::LoadAnnotations => write!(f, "Loading the annotations"),
+            Self::RecoverSession => write!(f, "Recovering the session"),
+            Self::OpenRegistry => write!(f, "Opening the registry"),
+        }
+    }
+}
+
+/// A single line on stderr showing the current startup phase, cleared once the startup is over.
+/// Nothing is shown if stderr is not a terminal.
+#[derive(Debug)]
+struct BootStatus {
+    enabled: bool,
+}
+
+impl BootStatus {
+    fn new() -> Self {
+        Self {
+            enabled: std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn show(&self, phase: StartupPhase) {
+        debug!(?phase, "Startup phase entered.");
+
+        if self.enabled {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K{phase}…");
+            let _ = stderr.flush();
+        }
+    }
+
+    fn clear(&self) {
+        if self.enabled {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for BootStatus {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Runs the parsers of the config files and the persisted state, describing which fail and why.
+pub fn diagnose_startup_files(args: &Args) -> String {
+    let results = [
+        ("Configuration", Config::new(args).map(|_| ())),
+        (
+            "Key bindings",
+            KeyMap::load(&args.keymap_path()).map(|_| ()),
+        ),
+        (
+            "Annotations",
+            Annotations::load(&args.registry_directory).map(|_| ()),
+        ),
+        (
+            "Session snapshots",
+            SessionStore::load(&args.registry_directory).map(|_| ()),
+        ),
+    ];
+
+    results
+        .into_iter()
+        .map(|(name, result)| match result {
+            Ok(()) => format!("{SUCCESS_MARKER} {name}: OK\n"),
+            Err(error) => format!("{ERROR_MARKER} {name}: FAILED\n    {error:#}\n"),
+        })
+        .collect()
+}
+
+/// Opens the registry at `--registry-directory`, naming it on failure.
+pub async fn open_registry(args: &Args) -> Result<Registry<ReadLock>> {
+    tracing::trace!(dir=?args.registry_directory);
+    Registry::open(args.registry_directory.clone())
+        .await
+        .wrap_err_with(|| {
+            format!(
+                "Failed to open the registry at {}",
+                args.registry_directory.display()
+            )
+        })
+}
+
+/// Everything loaded before the interface is constructed.
+#[derive(Debug)]
+pub struct Startup {
+    pub config: Arc<Config>,
+    pub keymap: KeyMap,
+    pub annotations: Annotations,
+    /// `None` if the main view is to open the registry in the background, so that a failure to
+    /// open it is shown in the interface.
+    pub registry: Option<Arc<Registry<ReadLock>>>,
+    /// Drawn while the registry is being opened, if it was summarized when last closed.
+    pub registry_summary: Option<RegistrySummary>,
+    pub session_store: SessionStore,
+    /// Present if the session is tracked, to be released on a clean exit.
+    pub session_marker: Option<SessionMarker>,
+    /// The snapshot the user chose to restore, if any.
+    pub restored_session: Option<SessionSnapshot>,
+    /// The version that ran before, if the current one is newer, to show what is new in it.
+    pub upgraded_from: Option<String>,
+    /// The result of `diagnose_startup_files`, in safe mode.
+    pub startup_file_diagnosis: Option<String>,
+    /// Recoverable failures, to be shown once the interface is up.
+    pub warnings: Vec<String>,
+}
+
+impl Startup {
+    /// Runs the startup phases in order. Recoverable failures are logged and collected in
+    /// `warnings`, fatal ones are returned.
+    /// The session is only tracked with `track_session`, so that only one instance does so.
+    /// In safe mode, nothing is loaded from the config files or the persisted state.
+    pub async fn run(args: &Args, track_session: bool) -> Result<Self> {
+        let status = BootStatus::new();
+        let mut warnings = Vec::new();
+
+        status.show(StartupPhase::LoadConfig);
+        let config = if args.safe_mode {
+            Config::default()
+        } else {
+            Config::new(args).unwrap_or_else(|error| {
+                warn!(?error, "Failed to load the config, using the defaults.");
+                warnings.push(format!(
+                    "Invalid configuration, using the defaults: {error:#}"
+                ));
+                Config::default()
+            })
+        };
+        let keymap = if args.safe_mode {
+            KeyMap::default()
+        } else {
+            KeyMap::load(&args.keymap_path()).unwrap_or_else(|error| {
+                warn!(
+                    ?error,
+                    "Failed to load the key bindings, using the defaults."
+                );
+                warnings.push(format!(
+                    "Invalid key bindings, using the defaults: {error:#}"
+                ));
+                KeyMap::default()
+            })
+        };
+
+        status.show(StartupPhase::LoadAnnotations);
+        let annotations = if args.safe_mode {
+            // Without a path, the annotations are not saved either.
+            Annotations::default()
+        } else {
+            Annotations::load(&args.registry_directory).unwrap_or_else(|error| {
+                warn!(?error, "Failed to load the annotations, resetting them.");
+                warnings.push(format!("Annotations reset: {error:#}"));
+                Annotations::reset(&args.registry_directory).unwrap_or_else(|error| {
+                    warn!(
+                        ?error,
+                        "Failed to reset the annotations, they will not be saved."
+                    );
+                    Annotations::default()
+                })
+            })
+        };
+
+        // Only saves drawing the interface before the registry opens, so a failure is not shown.
+        let registry_summary = if args.safe_mode {
+            None
+        } else {
+            RegistrySummary::load(&args.registry_directory).unwrap_or_else(|error| {
+                warn!(?error, "Failed to load the registry summary.");
+                None
+            })
+        };
+
+        let mut session_store = SessionStore::default();
+        let mut session_marker = None;
+        let mut restored_session = None;
+        let mut upgraded_from = None;
+
+        if track_session && !args.safe_mode {
+            upgraded_from = crate::changelog::record_run().unwrap_or_else(|error| {
+                warn!(?error, "Failed to record the version of this run.");
+                None
+            });
+
+            status.show(StartupPhase::RecoverSession);
+            session_store = SessionStore::load(&args.registry_directory).unwrap_or_else(|error| {
+                warn!(?error, "Failed to load the session snapshots.");
+                SessionStore::default()
+            });
+
+            match SessionMarker::acquire(&args.registry_directory) {
+                Ok((marker, unclean_exit)) => {
+                    session_marker = Some(marker);
+
+                    // An explicitly requested record takes precedence over the previous session.
+                    if unclean_exit && args.record_path().is_none() {
+                        status.clear();
+                        restored_session = prompt_restore(session_store.snapshots());
+                    }
+                }
+                Err(error) => {
+                    warn!(?error, "Failed to create the session marker.");
+                    warnings.push(format!(
+                        "Crashes will not be detected, failed to create the session marker: \
+                         {error:#}"
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            config: Arc::new(config),
+            keymap,
+            annotations,
+            registry: None,
+            registry_summary,
+            session_store,
+            session_marker,
+            restored_session,
+            upgraded_from,
+            startup_file_diagnosis: args.safe_mode.then(|| diagnose_startup_files(args)),
+            warnings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+
+    /// An empty registry directory of its own for each test, along with its state directory, as
+    /// tests run in parallel.
+    fn registry_directory(test_name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-startup-{}-{test_name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let _ = std::fs::remove_dir_all(crate::env::get_registry_state_dir(&directory));
+        directory
+    }
+
+    /// Writes a file into the state directory of the registry.
+    fn write_state_file(registry_directory: &Path, file_name: &str, contents: &str) {
+        let state_dir = crate::env::get_registry_state_dir(registry_directory);
+        std::fs::create_dir_all(&state_dir).unwrap();
+        std::fs::write(state_dir.join(file_name), contents).unwrap();
+    }
+
+    fn args(registry_directory: &Path, arguments: &[&str]) -> Args {
+        // `-h` is taken by `--force-max-height`, which the debug assertions of clap reject along
+        // with the generated `--help`.
+        let matches = Args::command().disable_help_flag(true).get_matches_from(
+            ["rrr-tui-3", "--registry-directory"]
+                .into_iter()
+                .chain([registry_directory.to_str().unwrap()])
+                .chain(arguments.iter().copied()),
+        );
+        Args::from_arg_matches(&matches).unwrap()
+    }
+
+    #[tokio::test]
+    async fn startup_without_files_has_no_warnings() {
+        let directory = registry_directory("without_files");
+        let startup = Startup::run(&args(&directory, &[]), false).await.unwrap();
+
+        assert_eq!(startup.warnings, Vec::<String>::new());
+        assert!(startup.registry.is_none());
+        assert!(startup.session_marker.is_none());
+    }
+
+    #[tokio::test]
+    async fn config_failure_falls_back_to_the_defaults() {
+        let directory = registry_directory("config_failure");
+        std::fs::write(directory.join(".rrr-tui.toml"), "editing = [").unwrap();
+        let startup = Startup::run(&args(&directory, &["--trust-registry-config"]), false)
+            .await
+            .unwrap();
+
+        assert_eq!(startup.warnings.len(), 1);
+        assert!(startup.warnings[0].starts_with("Invalid configuration, using the defaults: "));
+        assert_eq!(
+            startup.config.to_effective_toml(),
+            Config::default().to_effective_toml()
+        );
+    }
+
+    #[tokio::test]
+    async fn key_bindings_failure_falls_back_to_the_defaults() {
+        let directory = registry_directory("key_bindings_failure");
+        let keymap_path = directory.join("keymap.toml");
+        std::fs::write(
+            &keymap_path,
+            "[bindings]\n\"ctrl-x\" = \"no-such-command\"\n",
+        )
+        .unwrap();
+        let startup = Startup::run(
+            &args(&directory, &["--keymap", keymap_path.to_str().unwrap()]),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(startup.warnings.len(), 1);
+        assert!(startup.warnings[0].starts_with("Invalid key bindings, using the defaults: "));
+        assert_eq!(startup.keymap.describe(), KeyMap::default().describe());
+    }
+
+    #[tokio::test]
+    async fn annotations_failure_sets_the_file_aside() {
+        let directory = registry_directory("annotations_failure");
+        write_state_file(&directory, "annotations.json", "{");
+        let startup = Startup::run(&args(&directory, &[]), false).await.unwrap();
+        let state_dir = crate::env::get_registry_state_dir(&directory);
+
+        assert_eq!(startup.warnings.len(), 1);
+        assert!(startup.warnings[0].starts_with("Annotations reset: "));
+        assert_eq!(startup.annotations.aliases().count(), 0);
+        assert!(!state_dir.join("annotations.json").exists());
+        assert_eq!(
+            std::fs::read_to_string(state_dir.join("annotations.json.corrupt")).unwrap(),
+            "{"
+        );
+    }
+
+    #[tokio::test]
+    async fn session_snapshots_failure_starts_without_snapshots() {
+        let directory = registry_directory("session_snapshots_failure");
+        write_state_file(&directory, "sessions.json", "[");
+        let mut startup = Startup::run(&args(&directory, &[]), true).await.unwrap();
+
+        // Unreadable snapshots are only logged, as there is nothing to restore either way.
+        assert_eq!(startup.warnings, Vec::<String>::new());
+        assert!(startup.session_store.snapshots().is_empty());
+        assert!(startup.restored_session.is_none());
+        startup.session_marker.take().unwrap().release();
+    }
+
+    #[tokio::test]
+    async fn session_marker_failure_is_a_warning() {
+        let directory = registry_directory("session_marker_failure");
+        // A directory in place of the marker file cannot be written to.
+        std::fs::create_dir_all(
+            crate::env::get_registry_state_dir(&directory).join("session.running"),
+        )
+        .unwrap();
+        let startup = Startup::run(&args(&directory, &[]), true).await.unwrap();
+
+        assert_eq!(startup.warnings.len(), 1);
+        assert!(startup.warnings[0].starts_with("Crashes will not be detected"));
+        assert!(startup.session_marker.is_none());
+    }
+
+    #[tokio::test]
+    async fn registry_failure_names_the_directory() {
+        let directory = registry_directory("registry_failure").join("missing");
+        let error = open_registry(&args(&directory, &[])).await.unwrap_err();
+
+        assert!(format!("{error:#}").starts_with(&format!(
+            "Failed to open the registry at {}",
+            directory.display()
+        )));
+    }
+
+    #[tokio::test]
+    async fn schema_rejections_are_reported_alike_by_every_entry_point() {
+        let directory = registry_directory("schema_rejection");
+        std::fs::write(
+            directory.join(".rrr-tui.toml"),
+            "[editing]\nword_separators = 5\n",
+        )
+        .unwrap();
+
+        for arguments in [
+            &["--trust-registry-config"][..],
+            &["--set", "editing.word_separators=5"],
+        ] {
+            let args = args(&directory, arguments);
+            // What `--print-effective-config` fails with.
+            let error = format!("{:#}", Config::new(&args).unwrap_err());
+            let startup = Startup::run(&args, false).await.unwrap();
+
+            assert_eq!(
+                error, "invalid type: integer `5`, expected a string",
+                "{arguments:?}"
+            );
+            assert_eq!(
+                startup.warnings,
+                [format!(
+                    "Invalid configuration, using the defaults: {error}"
+                )],
+                "{arguments:?}"
+            );
+            assert!(
+                diagnose_startup_files(&args)
+                    .contains(&format!("Configuration: FAILED\n    {error}\n")),
+                "{arguments:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn safe_mode_reaches_the_main_view_despite_corrupted_files() {
+        use ratatui::{backend::TestBackend, Terminal};
+        use tokio_util::sync::CancellationToken;
+
+        use crate::component::{ComponentId, DefaultDrawable, DrawContext};
+        use crate::components::main_view::MainView;
+
+        let directory = registry_directory("safe_mode");
+        std::fs::write(directory.join(".rrr-tui.toml"), "editing = [").unwrap();
+        let keymap_path = directory.join("keymap.toml");
+        std::fs::write(&keymap_path, "[bindings").unwrap();
+        write_state_file(&directory, "annotations.json", "{");
+        write_state_file(&directory, "sessions.json", "{");
+        let arguments = [
+            "--trust-registry-config",
+            "--keymap",
+            keymap_path.to_str().unwrap(),
+        ];
+        // The configuration alone fails `--print-effective-config` and `--bench-startup`.
+        assert!(Config::new(&args(&directory, &arguments)).is_err());
+
+        let args = Arc::new(args(
+            &directory,
+            &[&arguments[..], &["--safe-mode"]].concat(),
+        ));
+        let startup = Startup::run(&args, true).await.unwrap();
+
+        assert_eq!(startup.warnings, Vec::<String>::new());
+        assert_eq!(
+            startup.config.to_effective_toml(),
+            Config::default().to_effective_toml()
+        );
+        assert!(startup.session_marker.is_none());
+        let diagnosis = startup.startup_file_diagnosis.clone().unwrap();
+        for name in [
+            "Configuration",
+            "Key bindings",
+            "Annotations",
+            "Session snapshots",
+        ] {
+            assert!(
+                diagnosis.contains(&format!("{ERROR_MARKER} {name}: FAILED\n")),
+                "{diagnosis}"
+            );
+        }
+
+        let (action_tx, _action_rx) = tokio::sync::mpsc::unbounded_channel();
+        let cancellation = CancellationToken::new();
+        let main_view = MainView::new(
+            ComponentId::root(),
+            &action_tx,
+            &cancellation,
+            &args,
+            startup,
+        )
+        .await
+        .unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                let palette = Config::default().display.theme.palette(u16::MAX);
+                let mut context = DrawContext::new(frame, ComponentId::root(), palette);
+                main_view.default_draw(&mut context, area).unwrap();
+            })
+            .unwrap();
+        cancellation.cancel();
+
+        let buffer = terminal.backend().buffer();
+        let header = (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol())
+            .collect::<String>();
+        assert!(
+            header.contains("[SAFE MODE] F3: diagnose startup files"),
+            "{header}"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Background tasks, each of which is spawned with a `CancellationToken` it is cancelled by.
+//!
+//! The tokens form a hierarchy rooted in the token of the application, which is cancelled on
+//! quit. Components derive child tokens for their own tasks, and individual operations derive
+//! child tokens from those, so that cancelling an operation leaves the other tasks running.
+//! `tokio::spawn` is disallowed by `clippy.toml` in favor of `spawn`.
+//!
+//! The tasks the user waits for, spawned with `spawn_tracked`, are operations identified by an
+//! `OperationId`, which is both logged and included in the errors they report, so that a reported
+//! error can be found in the log.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info_span, Instrument};
+
+use crate::action::{Action, ComponentMessage};
+
+/// Identifies a task which is shown as pending while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    fn new() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        Self(COUNTER.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A short identifier of an operation, displayed as 6 hex digits. Random rather than sequential, so
+/// that the operations of different runs logged to the same file are told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OperationId(u32);
+
+impl OperationId {
+    fn new() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let hash = std::collections::hash_map::RandomState::new()
+            .hash_one(COUNTER.fetch_add(1, Ordering::Relaxed));
+        Self((hash & 0xff_ffff) as u32)
+    }
+
+    /// The message of an error reported by the operation, tagged with its ID.
+    pub fn tag_error(self, error: impl Display) -> String {
+        format!("{error} (error id {self})")
+    }
+}
+
+impl Display for OperationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:06x}", self.0)
+    }
+}
+
+/// Spawns the task, which is dropped at its next await point once `cancellation` is cancelled.
+/// The task resolves to `None` if it was cancelled.
+#[allow(clippy::disallowed_methods)]
+pub fn spawn<F>(cancellation: &CancellationToken, task: F) -> JoinHandle<Option<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let cancellation = cancellation.clone();
+    tokio::spawn(async move { cancellation.run_until_cancelled(task).await })
+}
+
+/// Spawns the task like `spawn`, announcing it with `ComponentMessage::TaskStarted` and, once it
+/// finishes or is cancelled, with `ComponentMessage::TaskFinished`, so that it is shown as
+/// pending in the meantime.
+/// The task is created with a new `OperationId`, and instrumented with a span carrying it as the
+/// `operation_id` field, within `span`.
+#[allow(clippy::disallowed_methods)]
+pub fn spawn_tracked<F>(
+    cancellation: &CancellationToken,
+    action_tx: &UnboundedSender<Action>,
+    label: impl Into<String>,
+    span: tracing::Span,
+    task: impl FnOnce(OperationId) -> F,
+) -> JoinHandle<Option<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let operation_id = OperationId::new();
+    let span = info_span!(parent: &span, "operation", %operation_id);
+    let task = task(operation_id).instrument(span);
+    let id = TaskId::new();
+    let _ = action_tx.send(Action::BroadcastMessage(ComponentMessage::TaskStarted {
+        id,
+        label: label.into(),
+    }));
+    let cancellation = cancellation.clone();
+    let action_tx = action_tx.clone();
+    tokio::spawn(async move {
+        let output = cancellation.run_until_cancelled(task).await;
+        let _ = action_tx.send(Action::BroadcastMessage(ComponentMessage::TaskFinished {
+            id,
+        }));
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+    use super::*;
+    use crate::logging::CaptureLayer;
+
+    #[tokio::test]
+    async fn operation_id_is_in_the_events_and_the_error() {
+        let (entries_tx, mut entries_rx) = mpsc::channel(16);
+        // The tasks of the single-threaded test runtime run on this thread.
+        let _subscriber = tracing_subscriber::registry()
+            .with(CaptureLayer::new(entries_tx))
+            .set_default();
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let result = spawn_tracked(
+            &CancellationToken::new(),
+            &action_tx,
+            "Failing",
+            info_span!("failing operation"),
+            |operation_id| async move {
+                tracing::warn!("Failing on purpose.");
+                Err::<(), _>(operation_id.tag_error("Forced failure"))
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let entry = entries_rx.try_recv().unwrap();
+        let operation_id = entry.operation_id.unwrap();
+        assert_eq!(entry.message, "Failing on purpose.");
+        assert_eq!(operation_id.len(), 6);
+        assert!(operation_id.chars().all(|char| char.is_ascii_hexdigit()));
+        assert_eq!(
+            result.unwrap_err(),
+            format!("Forced failure (error id {operation_id})")
+        );
+
+        assert!(matches!(
+            action_rx.try_recv().unwrap(),
+            Action::BroadcastMessage(ComponentMessage::TaskStarted { label, .. }) if label == "Failing"
+        ));
+        assert!(matches!(
+            action_rx.try_recv().unwrap(),
+            Action::BroadcastMessage(ComponentMessage::TaskFinished { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn events_outside_of_operations_have_no_operation_id() {
+        let (entries_tx, mut entries_rx) = mpsc::channel(16);
+        let _subscriber = tracing_subscriber::registry()
+            .with(CaptureLayer::new(entries_tx))
+            .set_default();
+
+        info_span!("not an operation").in_scope(|| tracing::info!("Outside."));
+
+        assert_eq!(entries_rx.try_recv().unwrap().operation_id, None);
+    }
+
+    /// A fetch which completes once `complete_tx` is sent to, and reports whether it was dropped
+    /// before completing, as cancelled tasks are.
+    fn fetch() -> (
+        impl Future<Output = u64> + Send + 'static,
+        tokio::sync::oneshot::Sender<u64>,
+        tokio::sync::oneshot::Receiver<()>,
+    ) {
+        struct DropGuard(Option<tokio::sync::oneshot::Sender<()>>);
+
+        impl Drop for DropGuard {
+            fn drop(&mut self) {
+                if let Some(dropped_tx) = self.0.take() {
+                    let _ = dropped_tx.send(());
+                }
+            }
+        }
+
+        let (complete_tx, complete_rx) = tokio::sync::oneshot::channel();
+        let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel();
+        let future = async move {
+            let mut guard = DropGuard(Some(dropped_tx));
+            let value = complete_rx.await.unwrap();
+            guard.0 = None;
+            value
+        };
+
+        (future, complete_tx, dropped_rx)
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_component_cancels_its_fetch_but_not_a_siblings() {
+        let application = CancellationToken::new();
+        let closed = application.child_token();
+        let sibling = application.child_token();
+        let (closed_fetch, _closed_complete_tx, closed_dropped_rx) = fetch();
+        let (sibling_fetch, sibling_complete_tx, mut sibling_dropped_rx) = fetch();
+        let closed_task = spawn(&closed.child_token(), closed_fetch);
+        let sibling_task = spawn(&sibling.child_token(), sibling_fetch);
+        tokio::task::yield_now().await;
+
+        closed.cancel();
+        assert_eq!(closed_task.await.unwrap(), None);
+        closed_dropped_rx.await.unwrap();
+
+        assert!(!sibling.is_cancelled());
+        assert!(sibling_dropped_rx.try_recv().is_err());
+        sibling_complete_tx.send(7).unwrap();
+        assert_eq!(sibling_task.await.unwrap(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_application_cancels_every_task() {
+        let application = CancellationToken::new();
+        let fetches = [fetch(), fetch()];
+        let mut tasks = Vec::new();
+        let mut dropped = Vec::new();
+        // Kept, so that the fetches do not fail rather than being cancelled.
+        let mut complete = Vec::new();
+
+        for (future, complete_tx, dropped_rx) in fetches {
+            tasks.push(spawn(&application.child_token().child_token(), future));
+            complete.push(complete_tx);
+            dropped.push(dropped_rx);
+        }
+        tokio::task::yield_now().await;
+
+        application.cancel();
+        for (task, dropped_rx) in tasks.into_iter().zip(dropped) {
+            assert_eq!(task.await.unwrap(), None);
+            dropped_rx.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_tracked_task_is_reported_as_finished() {
+        let cancellation = CancellationToken::new();
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let (future, _complete_tx, dropped_rx) = fetch();
+        let task = spawn_tracked(
+            &cancellation,
+            &action_tx,
+            "Fetching",
+            info_span!("fetch"),
+            |_| future,
+        );
+        tokio::task::yield_now().await;
+
+        cancellation.cancel();
+        assert_eq!(task.await.unwrap(), None);
+        dropped_rx.await.unwrap();
+
+        let Action::BroadcastMessage(ComponentMessage::TaskStarted { id: started, .. }) =
+            action_rx.try_recv().unwrap()
+        else {
+            panic!("The task was not announced.");
+        };
+        assert!(matches!(
+            action_rx.try_recv().unwrap(),
+            Action::BroadcastMessage(ComponentMessage::TaskFinished { id }) if id == started
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+//! Temporary files holding record contents for external programs, which are only readable by the
+//! user and removed as soon as they are no longer needed.
+//!
+//! The files are created in a private directory of this run, inside the runtime directory of the
+//! user if there is one, as it is not persisted across reboots. Files that are still alive when
+//! the application exits or panics are removed by `remove_all`. No files are created at all
+//! while `never_write_plaintext` is set.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use lazy_static::lazy_static;
+use tracing::warn;
+
+lazy_static! {
+    static ref DIRECTORY: PathBuf = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|directory| directory.is_absolute())
+        .unwrap_or_else(std::env::temp_dir)
+        .join(format!(
+            "{}-{}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id()
+        ));
+    /// The paths of the files which have not been removed yet.
+    static ref ALIVE: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+}
+
+/// Whether creating temporary files is refused, see `Config::never_write_plaintext`.
+static NEVER_WRITE_PLAINTEXT: AtomicBool = AtomicBool::new(false);
+
+/// Refuses to create temporary files from now on if `never_write_plaintext`, or allows it again.
+pub fn set_never_write_plaintext(never_write_plaintext: bool) {
+    NEVER_WRITE_PLAINTEXT.store(never_write_plaintext, Ordering::Relaxed);
+}
+
+/// A temporary file, removed on drop.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Creates a file with the contents, readable and writable only by the user.
+    pub fn create(contents: &[u8]) -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        if NEVER_WRITE_PLAINTEXT.load(Ordering::Relaxed) {
+            return Err(eyre!(
+                "Refused to write a temporary file, which `never_write_plaintext` forbids"
+            ));
+        }
+
+        create_private_directory(&DIRECTORY)
+            .wrap_err("Failed to create the directory of temporary files")?;
+
+        let path = DIRECTORY.join(COUNTER.fetch_add(1, Ordering::Relaxed).to_string());
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        // Registered before it is written to, so that a partially written file is removed too.
+        alive().insert(path.clone());
+        let file = Self { path };
+        options
+            .open(&file.path)
+            .and_then(|mut handle| handle.write_all(contents))
+            .wrap_err("Failed to write a temporary file")?;
+        Ok(file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        remove(&self.path);
+        alive().remove(&self.path);
+    }
+}
+
+/// Locks the registry of alive files, even if it is poisoned, so that the files are still
+/// removed after a panic.
+fn alive() -> MutexGuard<'static, BTreeSet<PathBuf>> {
+    ALIVE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn create_private_directory(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    match builder.create(path) {
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        result => result,
+    }
+}
+
+fn remove(path: &Path) {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            warn!(?path, %error, "Failed to remove a temporary file.");
+        }
+        _ => (),
+    }
+}
+
+/// Removes the files that are still alive along with their directory, on exit and on panic.
+pub fn remove_all() {
+    for path in std::mem::take(&mut *alive()) {
+        remove(&path);
+    }
+
+    let _ = std::fs::remove_dir(&*DIRECTORY);
+}
+
+/// Lists the files that are currently alive, for auditing.
+pub fn describe_alive() -> String {
+    let paths = alive();
+
+    if paths.is_empty() {
+        return "No temporary files are alive.\n".to_string();
+    }
+
+    let mut description = String::new();
+
+    for path in paths.iter() {
+        writeln!(description, "{}", path.display()).unwrap();
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    lazy_static! {
+        /// Held by each test, as they share the directory and the registry of alive files, which
+        /// `remove_all` empties.
+        static ref LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    fn lock() -> MutexGuard<'static, ()> {
+        LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[test]
+    fn files_are_private_to_the_user() {
+        let _lock = lock();
+        let file = TempFile::create(b"secret").unwrap();
+
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"secret");
+        assert_eq!(file.path().parent(), Some(DIRECTORY.as_path()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(file.path()), 0o600);
+            assert_eq!(mode(&DIRECTORY), 0o700);
+        }
+    }
+
+    #[test]
+    fn files_are_removed_on_drop() {
+        let _lock = lock();
+        let file = TempFile::create(b"secret").unwrap();
+        let path = file.path().to_path_buf();
+        assert!(describe_alive().contains(&path.display().to_string()));
+
+        drop(file);
+
+        assert!(!path.exists());
+        assert!(!describe_alive().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn files_that_are_never_dropped_are_removed_on_exit() {
+        let _lock = lock();
+        // As when the application panics or is interrupted while a file is alive.
+        let file = TempFile::create(b"secret").unwrap();
+        let path = file.path().to_path_buf();
+        std::mem::forget(file);
+
+        remove_all();
+
+        assert!(!path.exists());
+        assert!(!DIRECTORY.exists());
+        assert_eq!(describe_alive(), "No temporary files are alive.\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn input_file_is_removed_after_the_command_crashes() {
+        let _lock = lock();
+        let directory = std::env::temp_dir().join(format!(
+            "rrr-tui-tempfiles-{}-command_crash",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let copy_path = directory.join("copy");
+        let path_path = directory.join("path");
+        let command = crate::config::CustomCommand {
+            name: "crashing".to_string(),
+            run: format!(
+                "cp \"$1\" '{}'; printf %s \"$1\" > '{}'; kill -9 $$; :",
+                copy_path.display(),
+                path_path.display()
+            ),
+            input: Default::default(),
+            key: None,
+            suspend: true,
+            timeout_secs: 10,
+        };
+
+        let error = crate::custom_command::run_in_terminal(&command, b"secret").unwrap_err();
+
+        assert!(format!("{error:#}").contains("crashing"), "{error:#}");
+        // The command did read the file, which is gone once it crashed.
+        assert_eq!(std::fs::read(&copy_path).unwrap(), b"secret");
+        let input_path = PathBuf::from(std::fs::read_to_string(&path_path).unwrap());
+        assert_eq!(input_path.parent(), Some(DIRECTORY.as_path()));
+        assert!(!input_path.exists());
+        assert!(!describe_alive().contains(&input_path.display().to_string()));
+    }
+
+    #[test]
+    fn files_are_refused_while_plaintext_must_not_be_written() {
+        let _lock = lock();
+        let alive_before = describe_alive();
+
+        set_never_write_plaintext(true);
+        let result = TempFile::create(b"secret");
+        set_never_write_plaintext(false);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Refused to write a temporary file, which `never_write_plaintext` forbids"
+        );
+        assert_eq!(describe_alive(), alive_before);
+        assert!(TempFile::create(b"secret").is_ok());
+    }
+}
@@ -0,0 +1,580 @@
+//! Helpers for fitting text into a limited number of terminal cells.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Inserted in place of the removed part of a truncated text.
+pub const ELLIPSIS: &str = "…";
+
+/// Which part of a text is removed when it does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncateMode {
+    #[default]
+    End,
+    Middle,
+    Start,
+}
+
+/// Shortens `text` to at most `width` terminal cells, replacing the removed part with a single
+/// [`ELLIPSIS`]. Grapheme clusters are never split.
+/// Returns the resulting text along with its width.
+pub fn truncate_to_width(text: &str, width: usize, mode: TruncateMode) -> (String, usize) {
+    let graphemes = text
+        .graphemes(true)
+        .map(|grapheme| (grapheme, grapheme.width()))
+        .collect::<Vec<_>>();
+    let text_width = graphemes.iter().map(|(_, width)| width).sum::<usize>();
+
+    if text_width <= width {
+        return (text.to_string(), text_width);
+    }
+
+    if width == 0 {
+        return (String::new(), 0);
+    }
+
+    let budget = width - ELLIPSIS.width();
+    // Returns the number of graphemes that fit into `budget`, along with their width.
+    let fit = |graphemes: &mut dyn Iterator<Item = &(&str, usize)>, budget: usize| {
+        let mut count = 0;
+        let mut used = 0;
+
+        for (_, width) in graphemes {
+            if used + width > budget {
+                break;
+            }
+
+            count += 1;
+            used += width;
+        }
+
+        (count, used)
+    };
+    let (prefix_budget, suffix_budget) = match mode {
+        TruncateMode::End => (budget, 0),
+        TruncateMode::Middle => (budget.div_ceil(2), budget / 2),
+        TruncateMode::Start => (0, budget),
+    };
+    let (prefix_count, prefix_width) = fit(&mut graphemes.iter(), prefix_budget);
+    let (suffix_count, suffix_width) = fit(
+        &mut graphemes[prefix_count..].iter().rev(),
+        suffix_budget + prefix_budget - prefix_width,
+    );
+    let truncated = graphemes[..prefix_count]
+        .iter()
+        .map(|(grapheme, _)| *grapheme)
+        .chain([ELLIPSIS])
+        .chain(
+            graphemes[(graphemes.len() - suffix_count)..]
+                .iter()
+                .map(|(grapheme, _)| *grapheme),
+        )
+        .collect::<String>();
+
+    (truncated, prefix_width + ELLIPSIS.width() + suffix_width)
+}
+
+/// Soft-wraps `text` into rows of at most `width` terminal cells, breaking lines at grapheme
+/// cluster boundaries. Returns the byte range of each row, excluding line breaks.
+/// A single grapheme wider than `width` occupies a row of its own.
+pub fn wrap_to_width(text: &str, width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+
+    for line in text.split_inclusive('\n') {
+        let content = line.strip_suffix('\n').unwrap_or(line);
+        let content = content.strip_suffix('\r').unwrap_or(content);
+        let mut row_start = line_start;
+        let mut row_width = 0;
+
+        for (offset, grapheme) in content.grapheme_indices(true) {
+            let grapheme_width = grapheme.width();
+
+            if row_width > 0 && row_width + grapheme_width > width {
+                rows.push(row_start..(line_start + offset));
+                row_start = line_start + offset;
+                row_width = 0;
+            }
+
+            row_width += grapheme_width;
+        }
+
+        rows.push(row_start..(line_start + content.len()));
+        line_start += line.len();
+    }
+
+    rows
+}
+
+/// Wraps a single line of `text` into rows of at most `width` terminal cells, breaking them at
+/// spaces, which are excluded from the returned byte ranges. Words wider than `width` are broken
+/// as by `wrap_to_width`.
+pub fn wrap_words_to_width(text: &str, width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut row: Option<Range<usize>> = None;
+    let mut start = 0;
+
+    for word in text.split(' ') {
+        let word_start = start;
+        let word_end = start + word.len();
+        start = word_end + 1;
+
+        if word.is_empty() {
+            continue;
+        }
+
+        match row.take() {
+            Some(current) if text[current.start..word_end].width() <= width => {
+                row = Some(current.start..word_end);
+                continue;
+            }
+            Some(current) => rows.push(current),
+            None => {}
+        }
+
+        let mut pieces = wrap_to_width(word, width).into_iter();
+        let last = pieces.next_back();
+        rows.extend(pieces.map(|piece| word_start + piece.start..word_start + piece.end));
+        row = last.map(|piece| word_start + piece.start..word_start + piece.end);
+    }
+
+    rows.extend(row);
+    rows
+}
+
+/// The indices of the rows of `wrap_to_width` that the byte range of the text spans, such as the
+/// rows a match is highlighted on.
+pub fn rows_of_range(rows: &[Range<usize>], range: &Range<usize>) -> Range<usize> {
+    rows.partition_point(|row| row.end <= range.start)
+        ..rows.partition_point(|row| row.start < range.end)
+}
+
+/// Which parts of a record are not valid UTF-8, and are therefore displayed with replacement
+/// characters, or `None` if everything is valid.
+pub fn invalid_utf8_warning(name_invalid: bool, content: &[u8]) -> Option<&'static str> {
+    match (name_invalid, std::str::from_utf8(content).is_err()) {
+        (true, true) => Some("name and content contain invalid UTF-8"),
+        (true, false) => Some("name contains invalid UTF-8"),
+        (false, true) => Some("content contains invalid UTF-8"),
+        (false, false) => None,
+    }
+}
+
+/// Formats a number of bytes with a binary unit, e.g. `4.2 KiB`.
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Summarizes record content in a single line of at most `width` cells: its first characters if
+/// it is printable text, or its size otherwise.
+pub fn preview_content(data: &[u8], width: usize) -> String {
+    let summary = match std::str::from_utf8(data) {
+        Ok("") => "empty".to_string(),
+        Ok(text)
+            if !text
+                .chars()
+                .any(|character| character.is_control() && !character.is_whitespace()) =>
+        {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+        _ => format!("binary, {}", format_size(data.len())),
+    };
+
+    truncate_to_width(&summary, width, TruncateMode::End).0
+}
+
+/// The number of bytes per row of a hex dump that fits in `width` cells. Rounded down to a
+/// multiple of 8 bytes when there is room for that many, so that the columns are easy to follow.
+pub fn hex_dump_bytes_per_row(width: usize, gutter: bool) -> usize {
+    // An 8-digit offset, the bytes in hex separated by spaces and the ASCII gutter, with two
+    // spaces between the columns.
+    let bytes_per_row = if gutter {
+        width.saturating_sub(11) / 4
+    } else {
+        width.saturating_sub(9) / 3
+    };
+    let bytes_per_row = std::cmp::max(1, bytes_per_row);
+
+    if bytes_per_row >= 8 {
+        bytes_per_row - bytes_per_row % 8
+    } else {
+        bytes_per_row
+    }
+}
+
+/// Formats `data` as a hex dump with `bytes_per_row` bytes per line: an offset column, the bytes
+/// in hex and, if `gutter`, an ASCII gutter, in which non-printable bytes are shown as `.`.
+pub fn hex_dump(data: &[u8], bytes_per_row: usize, gutter: bool) -> String {
+    let mut dump = String::new();
+
+    for (index, row) in data.chunks(bytes_per_row).enumerate() {
+        if index > 0 {
+            dump.push('\n');
+        }
+
+        dump.push_str(&format!("{:08x} ", index * bytes_per_row));
+
+        for byte in row {
+            dump.push_str(&format!(" {byte:02x}"));
+        }
+
+        if !gutter {
+            continue;
+        }
+
+        // Aligns the gutter of the last row with the rows above.
+        dump.push_str(&"   ".repeat(bytes_per_row - row.len()));
+        dump.push_str("  ");
+        dump.extend(row.iter().map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            }
+        }));
+    }
+
+    dump
+}
+
+/// The byte ranges within a row of a hex dump displaying the bytes at `bytes` of that row: in the
+/// hex column and, if `gutter`, in the ASCII gutter. See `hex_dump`.
+pub fn hex_dump_columns(
+    bytes: Range<usize>,
+    bytes_per_row: usize,
+    gutter: bool,
+) -> (Range<usize>, Option<Range<usize>>) {
+    // Each byte is preceded by a space, after the offset and its space.
+    let hex = (10 + 3 * bytes.start)..(9 + 3 * bytes.end);
+    let gutter_start = 9 + 3 * bytes_per_row + 2;
+    let gutter = gutter.then(|| (gutter_start + bytes.start)..(gutter_start + bytes.end));
+
+    (hex, gutter)
+}
+
+/// The byte ranges of the occurrences of `query` within `text`, ignoring case, in order and
+/// without overlaps.
+pub fn find_ignoring_case(text: &str, query: &str) -> Vec<Range<usize>> {
+    let query = query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<String>();
+
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // The offsets of each character within the lowercase text and within `text`, as lowercase
+    // characters may be encoded with a different number of bytes.
+    let mut offsets = Vec::new();
+    let mut lowercase = String::with_capacity(text.len());
+
+    for (offset, character) in text.char_indices() {
+        offsets.push((lowercase.len(), offset));
+        lowercase.extend(character.to_lowercase());
+    }
+
+    offsets.push((lowercase.len(), text.len()));
+
+    lowercase
+        .match_indices(&query)
+        .map(|(start, found)| {
+            let end = start + found.len();
+            // A match starting or ending within the lowercase form of a character covers it whole.
+            let start_index = offsets.partition_point(|(lowercase, _)| *lowercase <= start) - 1;
+            let end_index = offsets.partition_point(|(lowercase, _)| *lowercase < end);
+            offsets[start_index].1..offsets[end_index].1
+        })
+        .collect()
+}
+
+/// Parses a pattern of bytes written in hex, such as `de ad be ef`, ignoring whitespace.
+/// Returns `None` unless every byte is written with two hex digits.
+pub fn parse_hex_pattern(pattern: &str) -> Option<Vec<u8>> {
+    let digits = pattern
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .map(|character| character.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+/// The byte ranges of the occurrences of `pattern` within `data`, in order and without overlaps.
+pub fn find_bytes(data: &[u8], pattern: &[u8]) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+
+    if pattern.is_empty() {
+        return matches;
+    }
+
+    let mut start = 0;
+
+    while let Some(position) = data[start..]
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+    {
+        let found = start + position;
+        matches.push(found..(found + pattern.len()));
+        start = found + pattern.len();
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [TruncateMode; 3] = [TruncateMode::End, TruncateMode::Middle, TruncateMode::Start];
+
+    /// Texts of wide characters, and of grapheme clusters made of several characters.
+    const TEXTS: [&str; 7] = [
+        "record name",
+        "日本語のレコード名",
+        // A family joined by zero-width joiners, repeated.
+        "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}x",
+        // `e` followed by a combining acute accent, repeated.
+        "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}",
+        // Flags, each made of two regional indicators.
+        "\u{1f1e8}\u{1f1ff}\u{1f1e9}\u{1f1ea}\u{1f1eb}\u{1f1f7}",
+        "a\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}b日e\u{301}c",
+        "\r\n\r\n",
+    ];
+
+    fn is_grapheme_boundary(text: &str, offset: usize) -> bool {
+        offset == text.len()
+            || text
+                .grapheme_indices(true)
+                .any(|(boundary, _)| boundary == offset)
+    }
+
+    #[test]
+    fn truncate_to_width_fits_into_the_width() {
+        for (text, mode) in TEXTS
+            .into_iter()
+            .flat_map(|text| MODES.map(|mode| (text, mode)))
+        {
+            for width in 0..=(text.width() + 1) {
+                let (truncated, truncated_width) = truncate_to_width(text, width, mode);
+
+                assert!(truncated_width <= width, "{text:?} {width} {mode:?}");
+                assert_eq!(
+                    truncated.width(),
+                    truncated_width,
+                    "{text:?} {width} {mode:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_to_width_never_splits_graphemes() {
+        for (text, mode) in TEXTS
+            .into_iter()
+            .flat_map(|text| MODES.map(|mode| (text, mode)))
+        {
+            for width in 1..text.width() {
+                let (truncated, _) = truncate_to_width(text, width, mode);
+                let (prefix, suffix) = truncated
+                    .split_once(ELLIPSIS)
+                    .unwrap_or_else(|| panic!("{text:?} {width} {mode:?}"));
+
+                assert!(text.starts_with(prefix), "{text:?} {width} {mode:?}");
+                assert!(text.ends_with(suffix), "{text:?} {width} {mode:?}");
+                assert!(is_grapheme_boundary(text, prefix.len()));
+                assert!(is_grapheme_boundary(text, text.len() - suffix.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_to_width_keeps_text_that_fits() {
+        for (text, mode) in TEXTS
+            .into_iter()
+            .flat_map(|text| MODES.map(|mode| (text, mode)))
+        {
+            assert_eq!(
+                truncate_to_width(text, text.width(), mode),
+                (text.to_string(), text.width())
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_to_width_removes_the_part_of_the_mode() {
+        assert_eq!(
+            truncate_to_width("abcdef", 4, TruncateMode::End),
+            ("abc…".to_string(), 4)
+        );
+        assert_eq!(
+            truncate_to_width("abcdef", 4, TruncateMode::Middle),
+            ("ab…f".to_string(), 4)
+        );
+        assert_eq!(
+            truncate_to_width("abcdef", 4, TruncateMode::Start),
+            ("…def".to_string(), 4)
+        );
+        assert_eq!(
+            truncate_to_width("abcdef", 1, TruncateMode::Middle),
+            ("…".to_string(), 1)
+        );
+        assert_eq!(
+            truncate_to_width("abcdef", 0, TruncateMode::End),
+            (String::new(), 0)
+        );
+    }
+
+    /// The text of each row.
+    fn wrap(text: &str, width: usize) -> Vec<&str> {
+        wrap_to_width(text, width)
+            .into_iter()
+            .map(|row| &text[row])
+            .collect()
+    }
+
+    #[test]
+    fn wrap_to_width_fits_rows_into_narrow_widths() {
+        for text in TEXTS {
+            for width in 1..=4 {
+                let rows = wrap_to_width(text, width);
+
+                for row in &rows {
+                    let row_text = &text[row.clone()];
+                    assert!(is_grapheme_boundary(text, row.start), "{text:?} {width}");
+                    assert!(is_grapheme_boundary(text, row.end), "{text:?} {width}");
+                    // Only a single grapheme may be wider than the row.
+                    assert!(
+                        row_text.width() <= width || row_text.graphemes(true).count() == 1,
+                        "{text:?} {width} {row_text:?}"
+                    );
+                }
+
+                let wrapped = rows
+                    .iter()
+                    .map(|row| &text[row.clone()])
+                    .collect::<String>();
+                assert_eq!(wrapped, text.replace(['\r', '\n'], ""), "{text:?} {width}");
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_to_width_keeps_wide_characters_whole() {
+        assert_eq!(wrap("日本語", 1), ["日", "本", "語"]);
+        assert_eq!(wrap("日本語", 3), ["日", "本", "語"]);
+        assert_eq!(wrap("日本語", 4), ["日本", "語"]);
+        assert_eq!(wrap("a日b", 2), ["a", "日", "b"]);
+        assert_eq!(wrap("ab日", 3), ["ab", "日"]);
+    }
+
+    #[test]
+    fn wrap_to_width_keeps_grapheme_clusters_whole() {
+        assert_eq!(
+            wrap("e\u{301}e\u{301}e\u{301}", 2),
+            ["e\u{301}e\u{301}", "e\u{301}"]
+        );
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(wrap(&format!("a{family}b"), 2), ["a", family, "b"]);
+        assert_eq!(
+            wrap("\u{1f1e8}\u{1f1ff}\u{1f1e9}\u{1f1ea}", 3),
+            ["\u{1f1e8}\u{1f1ff}", "\u{1f1e9}\u{1f1ea}"]
+        );
+    }
+
+    #[test]
+    fn wrap_to_width_ranges_exclude_line_breaks() {
+        let text = "ab\r\n\nc日";
+
+        assert_eq!(wrap_to_width(text, 2), [0..2, 4..4, 5..6, 6..9]);
+        assert_eq!(wrap(text, 10), ["ab", "", "c日"]);
+    }
+
+    /// The text of each row, wrapped at spaces.
+    fn wrap_words(text: &str, width: usize) -> Vec<&str> {
+        wrap_words_to_width(text, width)
+            .into_iter()
+            .map(|row| &text[row])
+            .collect()
+    }
+
+    #[test]
+    fn wrap_words_to_width_breaks_rows_at_spaces() {
+        assert_eq!(wrap_words("need at least 24×9", 80), ["need at least 24×9"]);
+        assert_eq!(
+            wrap_words("need at least 24×9", 9),
+            ["need at", "least", "24×9"]
+        );
+        assert_eq!(
+            wrap_words("need at least 24×9", 7),
+            ["need at", "least", "24×9"]
+        );
+        assert_eq!(wrap_words("  need  at ", 4), ["need", "at"]);
+        // Words wider than the row are broken within.
+        assert_eq!(wrap_words("a 日本語 b", 4), ["a", "日本", "語 b"]);
+        assert_eq!(wrap_words("", 4), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn ranges_spanning_a_wrap_point_are_on_both_rows() {
+        let text = "aé日本x";
+        let rows = wrap_to_width(text, 3);
+        assert_eq!(wrap(text, 3), ["aé", "日", "本x"]);
+
+        // `é日`, from the middle of the first row to the end of the second.
+        let found = text.find('é').unwrap()..text.find('本').unwrap();
+        assert_eq!(rows_of_range(&rows, &found), 0..2);
+        // `日本x`, starting at the wrap point.
+        let found = text.find('日').unwrap()..text.len();
+        assert_eq!(rows_of_range(&rows, &found), 1..3);
+        // `x`, within the last row.
+        let found = text.find('x').unwrap()..text.len();
+        assert_eq!(rows_of_range(&rows, &found), 2..3);
+    }
+
+    #[test]
+    fn ranges_spanning_a_line_break_are_on_both_lines() {
+        let text = "ab\ncd";
+        let rows = wrap_to_width(text, 10);
+
+        assert_eq!(rows_of_range(&rows, &(1..4)), 0..2);
+        assert_eq!(rows_of_range(&rows, &(3..5)), 1..2);
+    }
+
+    #[test]
+    fn truncate_to_width_leaves_a_cell_empty_rather_than_split_a_wide_character() {
+        assert_eq!(
+            truncate_to_width("日本語", 4, TruncateMode::End),
+            ("日…".to_string(), 3)
+        );
+        assert_eq!(
+            truncate_to_width("日本語", 4, TruncateMode::Start),
+            ("…語".to_string(), 3)
+        );
+    }
+}
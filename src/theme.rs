@@ -0,0 +1,393 @@
+//! Colors of semantic states, and the markers that convey the states without relying on color.
+//!
+//! Every state that is signaled by a color is also signaled by a glyph or a text attribute, so
+//! that it can be told apart in any theme and by users who cannot distinguish the colors.
+
+use std::borrow::Cow;
+
+use clap::ValueEnum;
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+
+/// Marks errors, such as an invalid input.
+pub const ERROR_MARKER: &str = "✗";
+/// Marks successfully completed operations.
+pub const SUCCESS_MARKER: &str = "✓";
+/// Marks the row under the cursor in lists and trees.
+pub const SELECTED_MARKER: &str = "›";
+/// Marks entries that were added or changed between two versions.
+pub const ADDED_MARKER: &str = "+";
+/// Marks entries that were removed between two versions.
+pub const REMOVED_MARKER: &str = "-";
+
+/// The standard colors of 16-color terminals, with their usual values, which colors are reduced
+/// to when the terminal supports no more.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0x00, 0x00, 0x00)),
+    (Color::Red, (0xCD, 0x00, 0x00)),
+    (Color::Green, (0x00, 0xCD, 0x00)),
+    (Color::Yellow, (0xCD, 0xCD, 0x00)),
+    (Color::Blue, (0x00, 0x00, 0xEE)),
+    (Color::Magenta, (0xCD, 0x00, 0xCD)),
+    (Color::Cyan, (0x00, 0xCD, 0xCD)),
+    (Color::Gray, (0xE5, 0xE5, 0xE5)),
+    (Color::DarkGray, (0x7F, 0x7F, 0x7F)),
+    (Color::LightRed, (0xFF, 0x00, 0x00)),
+    (Color::LightGreen, (0x00, 0xFF, 0x00)),
+    (Color::LightYellow, (0xFF, 0xFF, 0x00)),
+    (Color::LightBlue, (0x5C, 0x5C, 0xFF)),
+    (Color::LightMagenta, (0xFF, 0x00, 0xFF)),
+    (Color::LightCyan, (0x00, 0xFF, 0xFF)),
+    (Color::White, (0xFF, 0xFF, 0xFF)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// The colors of the terminal, with few highlights.
+    #[default]
+    Default,
+    /// Colors from the Okabe–Ito palette, which remain distinguishable with the common forms of
+    /// color blindness.
+    Colorblind,
+    /// Colors for terminals with a dark background.
+    Dark,
+    /// Colors for terminals with a light background.
+    Light,
+}
+
+impl Theme {
+    /// The palette of the theme, with its colors reduced to the `color_count` colors the terminal
+    /// supports.
+    pub fn palette(self, color_count: u16) -> Palette {
+        let default = Palette {
+            accent: Color::Blue,
+            error: Color::Red,
+            warning: Color::Yellow,
+            info: Color::Yellow,
+            current_match: Color::Yellow,
+            search_match: Color::Reset,
+            changed: Color::Rgb(0x1F, 0x3F, 0x1F),
+            title: Color::Reset,
+            focus: Color::Reset,
+            selected: Color::Reset,
+            input: Color::Reset,
+            cursor: Color::Reset,
+            scrollbar: Color::Reset,
+            breadcrumbs: Color::Reset,
+            separator: Color::Reset,
+            muted: Color::Reset,
+        };
+        let palette = match self {
+            Self::Default => default,
+            Self::Colorblind => Palette {
+                accent: Color::Rgb(0x00, 0x72, 0xB2),
+                error: Color::Rgb(0xD5, 0x5E, 0x00),
+                warning: Color::Rgb(0xF0, 0xE4, 0x42),
+                info: Color::Rgb(0x56, 0xB4, 0xE9),
+                current_match: Color::Rgb(0xE6, 0x9F, 0x00),
+                changed: Color::Rgb(0x00, 0x2F, 0x4F),
+                ..default
+            },
+            Self::Dark => Palette {
+                accent: Color::Rgb(0x26, 0x4F, 0x78),
+                error: Color::Rgb(0xF4, 0x47, 0x47),
+                warning: Color::Rgb(0xCC, 0xA7, 0x00),
+                info: Color::Rgb(0x4F, 0xC1, 0xFF),
+                current_match: Color::Rgb(0xF2, 0xCC, 0x60),
+                search_match: Color::Rgb(0x8A, 0x7A, 0x3A),
+                changed: Color::Rgb(0x1F, 0x3F, 0x1F),
+                title: Color::Rgb(0x9C, 0xDC, 0xFE),
+                focus: Color::Rgb(0x56, 0x9C, 0xD6),
+                selected: Color::Rgb(0x4E, 0xC9, 0xB0),
+                input: Color::Rgb(0xD4, 0xD4, 0xD4),
+                cursor: Color::Rgb(0xAE, 0xAF, 0xAD),
+                scrollbar: Color::Rgb(0x79, 0x79, 0x79),
+                breadcrumbs: Color::Rgb(0xCE, 0x91, 0x78),
+                separator: Color::Rgb(0x44, 0x44, 0x44),
+                muted: Color::Rgb(0x80, 0x80, 0x80),
+            },
+            Self::Light => Palette {
+                accent: Color::Rgb(0x00, 0x5F, 0xB8),
+                error: Color::Rgb(0xCD, 0x31, 0x31),
+                warning: Color::Rgb(0x94, 0x6A, 0x00),
+                info: Color::Rgb(0x00, 0x6A, 0xB1),
+                current_match: Color::Rgb(0xF5, 0xB0, 0x00),
+                search_match: Color::Rgb(0xE8, 0xD8, 0x8A),
+                changed: Color::Rgb(0xD6, 0xF0, 0xD6),
+                title: Color::Rgb(0x00, 0x37, 0x80),
+                focus: Color::Rgb(0x00, 0x5F, 0xB8),
+                selected: Color::Rgb(0x10, 0x7C, 0x10),
+                input: Color::Rgb(0x1E, 0x1E, 0x1E),
+                cursor: Color::Rgb(0x33, 0x33, 0x33),
+                scrollbar: Color::Rgb(0x8A, 0x8A, 0x8A),
+                breadcrumbs: Color::Rgb(0xA3, 0x15, 0x15),
+                separator: Color::Rgb(0xC8, 0xC8, 0xC8),
+                muted: Color::Rgb(0x6E, 0x6E, 0x6E),
+            },
+        };
+
+        palette.map(|color| reduce_color(color, color_count))
+    }
+}
+
+/// The closest color among the `color_count` colors the terminal supports. Without colors, only
+/// the default colors of the terminal remain.
+fn reduce_color(color: Color, color_count: u16) -> Color {
+    let Color::Rgb(red, green, blue) = color else {
+        return color;
+    };
+
+    match color_count {
+        0..8 => Color::Reset,
+        8..256 => {
+            let distance = |(other_red, other_green, other_blue): (u8, u8, u8)| {
+                [(red, other_red), (green, other_green), (blue, other_blue)]
+                    .into_iter()
+                    .map(|(a, b)| (a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            };
+
+            ANSI_COLORS
+                .into_iter()
+                .min_by_key(|(_, value)| distance(*value))
+                .map(|(color, _)| color)
+                .unwrap_or(Color::Reset)
+        }
+        // The 6×6×6 color cube of 256-color terminals, with the levels 0, 95, 135, 175, 215, 255.
+        256 => {
+            let level = |value: u8| match value {
+                0..48 => 0,
+                48..115 => 1,
+                _ => (value - 35) / 40,
+            };
+
+            Color::Indexed(16 + 36 * level(red) + 6 * level(green) + level(blue))
+        }
+        _ => color,
+    }
+}
+
+/// The colors of semantic states and of the elements of the interface in a theme. The colors of
+/// the `Default` theme which are `Color::Reset` leave the colors of the terminal unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// The background of selected text.
+    pub accent: Color,
+    pub error: Color,
+    pub warning: Color,
+    /// Notices that are neither errors nor warnings.
+    pub info: Color,
+    /// The background of the current search match.
+    pub current_match: Color,
+    /// The other search matches, which are reversed.
+    pub search_match: Color,
+    /// The background of entries that changed between two versions.
+    pub changed: Color,
+    /// The titles of panes and dialogs.
+    pub title: Color,
+    /// The focused element, which is reversed.
+    pub focus: Color,
+    /// The checked item of radio arrays and checkboxes.
+    pub selected: Color,
+    /// The text of inputs.
+    pub input: Color,
+    /// The text cursor, which is reversed.
+    pub cursor: Color,
+    pub scrollbar: Color,
+    /// The segments of the breadcrumb trail.
+    pub breadcrumbs: Color,
+    /// The lines separating the panes, and the borders of dialogs.
+    pub separator: Color,
+    /// Hints and placeholders, which are dimmed.
+    pub muted: Color,
+}
+
+impl Palette {
+    fn map(self, map: impl Fn(Color) -> Color) -> Self {
+        Self {
+            accent: map(self.accent),
+            error: map(self.error),
+            warning: map(self.warning),
+            info: map(self.info),
+            current_match: map(self.current_match),
+            search_match: map(self.search_match),
+            changed: map(self.changed),
+            title: map(self.title),
+            focus: map(self.focus),
+            selected: map(self.selected),
+            input: map(self.input),
+            cursor: map(self.cursor),
+            scrollbar: map(self.scrollbar),
+            breadcrumbs: map(self.breadcrumbs),
+            separator: map(self.separator),
+            muted: map(self.muted),
+        }
+    }
+
+    pub fn error_style(&self) -> Style {
+        Style::new().fg(self.error).bold()
+    }
+
+    pub fn warning_style(&self) -> Style {
+        Style::new().fg(self.warning)
+    }
+
+    pub fn info_style(&self) -> Style {
+        Style::new().fg(self.info)
+    }
+
+    /// Selected text.
+    pub fn selection_style(&self) -> Style {
+        Style::new().white().bg(self.accent)
+    }
+
+    /// A match of the search, emphasized if it is the current one.
+    pub fn match_style(&self, current: bool) -> Style {
+        if current {
+            Style::new().black().bg(self.current_match).bold()
+        } else {
+            Style::new().fg(self.search_match).reversed()
+        }
+    }
+
+    pub fn title_style(&self) -> Style {
+        Style::new().fg(self.title)
+    }
+
+    /// The focused element, reversed so that it stands out in any theme.
+    pub fn focus_style(&self) -> Style {
+        Style::new().fg(self.focus).reversed()
+    }
+
+    pub fn selected_style(&self) -> Style {
+        Style::new().fg(self.selected)
+    }
+
+    pub fn input_style(&self) -> Style {
+        Style::new().fg(self.input)
+    }
+
+    pub fn cursor_style(&self) -> Style {
+        Style::new().fg(self.cursor).reversed()
+    }
+
+    pub fn scrollbar_style(&self) -> Style {
+        Style::new().fg(self.scrollbar)
+    }
+
+    pub fn breadcrumbs_style(&self) -> Style {
+        Style::new().fg(self.breadcrumbs)
+    }
+
+    pub fn separator_style(&self) -> Style {
+        Style::new().fg(self.separator)
+    }
+
+    pub fn muted_style(&self) -> Style {
+        Style::new().fg(self.muted).dim()
+    }
+
+    /// The text prefixed with the error marker.
+    pub fn error<'a>(&self, text: impl Into<Cow<'a, str>>) -> Line<'a> {
+        marked(ERROR_MARKER, text.into(), self.error_style())
+    }
+}
+
+fn marked<'a>(marker: &'static str, text: Cow<'a, str>, style: Style) -> Line<'a> {
+    Line::from_iter([Span::raw(marker), Span::raw(" "), Span::raw(text)]).style(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    use super::*;
+
+    /// The semantic colors which must be told apart.
+    fn semantic_colors(palette: Palette) -> [Color; 5] {
+        [
+            palette.accent,
+            palette.error,
+            palette.warning,
+            palette.info,
+            palette.current_match,
+        ]
+    }
+
+    fn render(line: Line, width: u16) -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, width, 1));
+        line.render(buffer.area, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn colorblind_palette_is_reduced_deterministically() {
+        assert_eq!(
+            semantic_colors(Theme::Colorblind.palette(u16::MAX)),
+            [
+                Color::Rgb(0x00, 0x72, 0xB2),
+                Color::Rgb(0xD5, 0x5E, 0x00),
+                Color::Rgb(0xF0, 0xE4, 0x42),
+                Color::Rgb(0x56, 0xB4, 0xE9),
+                Color::Rgb(0xE6, 0x9F, 0x00),
+            ]
+        );
+        assert_eq!(
+            semantic_colors(Theme::Colorblind.palette(256)),
+            [25, 166, 221, 74, 178].map(Color::Indexed)
+        );
+        assert_eq!(
+            semantic_colors(Theme::Colorblind.palette(16)),
+            [
+                Color::Cyan,
+                Color::Red,
+                Color::LightYellow,
+                Color::LightBlue,
+                Color::Yellow,
+            ]
+        );
+        assert_eq!(
+            Theme::Colorblind.palette(0),
+            Theme::Default.palette(u16::MAX).map(|_| Color::Reset)
+        );
+    }
+
+    #[test]
+    fn colorblind_semantic_colors_stay_distinct() {
+        for color_count in [u16::MAX, 256, 16] {
+            let colors = semantic_colors(Theme::Colorblind.palette(color_count));
+
+            for (index, color) in colors.iter().enumerate() {
+                assert!(
+                    !colors[index + 1..].contains(color),
+                    "{color:?} of {color_count} colors"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn errors_are_marked_in_the_colorblind_theme() {
+        let palette = Theme::Colorblind.palette(u16::MAX);
+        let mut expected = Buffer::with_lines(["✗ Invalid name  "]);
+        expected.set_style(
+            expected.area,
+            Style::new().fg(Color::Rgb(0xD5, 0x5E, 0x00)).bold(),
+        );
+
+        assert_eq!(render(palette.error("Invalid name"), 16), expected);
+    }
+
+    #[test]
+    fn errors_are_marked_without_colors() {
+        let palette = Theme::Colorblind.palette(0);
+        let mut expected = Buffer::with_lines(["✗ Invalid name  "]);
+        expected.set_style(expected.area, Style::new().fg(Color::Reset).bold());
+
+        assert_eq!(render(palette.error("Invalid name"), 16), expected);
+    }
+}
@@ -1,17 +1,22 @@
 #![allow(dead_code)] // Remove this once you start using the code
 
 use std::{
-    io::{stdout, Stdout},
+    io::{stdout, Write},
     ops::{Deref, DerefMut},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc as std_mpsc, Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use color_eyre::Result;
 use crossterm::{
     cursor,
     event::{
-        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent,
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, Event as CrosstermEvent, EventStream, KeyEvent,
+        KeyEventKind, MouseEvent,
     },
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -24,7 +29,7 @@ use tokio::{
     time::{interval, MissedTickBehavior},
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{error, Instrument};
+use tracing::{debug, error, trace, Instrument};
 
 /// Backend-generated events.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -43,13 +48,200 @@ pub enum Event {
     Resize(u16, u16),
 }
 
+/// The number of flushed frames that may wait for the terminal writer thread. Frames flushed while
+/// the queue is full are dropped, so that a slow terminal connection shows the latest state as
+/// soon as it catches up, instead of replaying every stale frame.
+const MAX_QUEUED_FRAMES: usize = 2;
+
+#[derive(Debug)]
+enum TerminalWriterMessage {
+    Write {
+        bytes: Vec<u8>,
+        /// The number of the frame, see `TerminalWriterState::flushed`.
+        sequence: u64,
+    },
+    /// Acknowledged once everything sent before this message has been written.
+    Sync(std_mpsc::Sender<()>),
+}
+
+/// Shared between the terminal writer and the thread it writes on.
+#[derive(Debug, Default)]
+struct TerminalWriterState {
+    /// The number of flushed frames that have not been written yet, including the ones being
+    /// written, which makes at most `2 * MAX_QUEUED_FRAMES + 1`.
+    queued: AtomicUsize,
+    /// Whether a frame was dropped since the screen was last redrawn in full.
+    dropped: AtomicBool,
+    /// The number of frames flushed so far, including the dropped ones, by which each flushed
+    /// frame is numbered.
+    flushed: AtomicU64,
+    /// How long every write is delayed by, in milliseconds, to simulate a slow terminal.
+    write_delay_ms: AtomicU64,
+}
+
+/// A frame the writer thread finished writing to the terminal, along with the frames flushed
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrittenFrame {
+    pub sequence: u64,
+    pub written_at: Instant,
+}
+
+/// Buffers everything written to the terminal and writes it to the standard output on a dedicated
+/// thread when flushed, so that a slow terminal connection never blocks the event loop.
+/// Only the writes are moved off the event loop: the frames are still drawn on it, see
+/// `crate::app::App::render`.
+#[derive(Debug)]
+pub struct TerminalWriter {
+    buffer: Vec<u8>,
+    tx: std_mpsc::SyncSender<TerminalWriterMessage>,
+    state: Arc<TerminalWriterState>,
+}
+
+impl TerminalWriter {
+    fn new(written_tx: std_mpsc::Sender<WrittenFrame>) -> Result<Self> {
+        Self::with_output(stdout(), Some(written_tx))
+    }
+
+    /// Every written frame is reported to `written_tx`, if any.
+    fn with_output(
+        output: impl Write + Send + 'static,
+        written_tx: Option<std_mpsc::Sender<WrittenFrame>>,
+    ) -> Result<Self> {
+        let (tx, rx) = std_mpsc::sync_channel(MAX_QUEUED_FRAMES);
+        let state = Arc::new(TerminalWriterState::default());
+        let thread_state = state.clone();
+        std::thread::Builder::new()
+            .name("terminal writer".to_string())
+            .spawn(move || Self::run(rx, output, &thread_state, written_tx))?;
+        Ok(Self {
+            buffer: Vec::new(),
+            tx,
+            state,
+        })
+    }
+
+    fn run(
+        rx: std_mpsc::Receiver<TerminalWriterMessage>,
+        mut output: impl Write,
+        state: &TerminalWriterState,
+        written_tx: Option<std_mpsc::Sender<WrittenFrame>>,
+    ) {
+        let mut pending = Vec::new();
+        let mut frames = 0;
+        let mut last_sequence = 0;
+        let mut synced = Vec::new();
+
+        while let Ok(message) = rx.recv() {
+            // Frames drawn while the previous write was in progress are written all at once.
+            for message in std::iter::once(message)
+                .chain(rx.try_iter())
+                .take(MAX_QUEUED_FRAMES + 1)
+            {
+                match message {
+                    TerminalWriterMessage::Write { bytes, sequence } => {
+                        pending.extend(bytes);
+                        frames += 1;
+                        last_sequence = sequence;
+                    }
+                    TerminalWriterMessage::Sync(done_tx) => synced.push(done_tx),
+                }
+            }
+
+            trace!(bytes = pending.len(), frames, "Writing to the terminal.");
+
+            let write_delay_ms = state.write_delay_ms.load(Ordering::Relaxed);
+
+            if write_delay_ms > 0 {
+                std::thread::sleep(Duration::from_millis(write_delay_ms));
+            }
+
+            if let Err(error) = output.write_all(&pending).and_then(|()| output.flush()) {
+                error!(%error, "Failed to write to the terminal.");
+            }
+
+            // Only the last of the frames matters, as the earlier ones were written along with it.
+            if frames > 0
+                && let Some(written_tx) = written_tx.as_ref()
+            {
+                let _ = written_tx.send(WrittenFrame {
+                    sequence: last_sequence,
+                    written_at: Instant::now(),
+                });
+            }
+
+            pending.clear();
+            state.queued.fetch_sub(frames, Ordering::AcqRel);
+            frames = 0;
+
+            for done_tx in synced.drain(..) {
+                let _ = done_tx.send(());
+            }
+        }
+    }
+
+    /// Blocks until everything flushed so far has been written to the terminal.
+    fn sync(tx: &std_mpsc::SyncSender<TerminalWriterMessage>) {
+        let (done_tx, done_rx) = std_mpsc::channel();
+
+        if tx.send(TerminalWriterMessage::Sync(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+impl Write for TerminalWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // Counted before sending, so that the writer thread never finds more frames than queued.
+        self.state.queued.fetch_add(1, Ordering::AcqRel);
+
+        let message = TerminalWriterMessage::Write {
+            bytes: std::mem::take(&mut self.buffer),
+            sequence: self.state.flushed.fetch_add(1, Ordering::AcqRel) + 1,
+        };
+
+        match self.tx.try_send(message) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                self.state.queued.fetch_sub(1, Ordering::AcqRel);
+
+                match error {
+                    std_mpsc::TrySendError::Full(_) => {
+                        debug!("The terminal writer is backed up, dropping a frame.");
+                        self.state.dropped.store(true, Ordering::Release);
+                        Ok(())
+                    }
+                    std_mpsc::TrySendError::Disconnected(_) => {
+                        Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Tui {
-    pub terminal: ratatui::Terminal<Backend<Stdout>>,
-    pub task: Option<JoinHandle<()>>,
+    pub terminal: ratatui::Terminal<Backend<TerminalWriter>>,
+    /// Used to wait for the terminal writer before writing to the standard output directly.
+    terminal_writer_tx: std_mpsc::SyncSender<TerminalWriterMessage>,
+    terminal_writer_state: Arc<TerminalWriterState>,
+    /// Reports when the writer thread finished writing the frames.
+    written_frames_rx: std_mpsc::Receiver<WrittenFrame>,
+    pub task: Option<JoinHandle<Option<()>>>,
     pub cancellation_token: CancellationToken,
-    pub event_rx: UnboundedReceiver<Event>,
-    pub event_tx: UnboundedSender<Event>,
+    /// Events along with the time they were received at.
+    pub event_rx: UnboundedReceiver<(Event, Instant)>,
+    pub event_tx: UnboundedSender<(Event, Instant)>,
     pub frame_rate: f64,
     pub tick_rate: f64,
     pub mouse: bool,
@@ -61,8 +253,13 @@ pub struct Tui {
 impl Tui {
     pub fn new(parent_span: tracing::Span) -> Result<Self> {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (written_frames_tx, written_frames_rx) = std_mpsc::channel();
+        let terminal_writer = TerminalWriter::new(written_frames_tx)?;
         Ok(Self {
-            terminal: ratatui::Terminal::new(Backend::new(stdout()))?,
+            terminal_writer_tx: terminal_writer.tx.clone(),
+            terminal_writer_state: terminal_writer.state.clone(),
+            written_frames_rx,
+            terminal: ratatui::Terminal::new(Backend::new(terminal_writer))?,
             task: None,
             cancellation_token: CancellationToken::new(),
             event_rx,
@@ -95,6 +292,45 @@ impl Tui {
         self
     }
 
+    /// Delays every write to the terminal, to measure the input latency over a slow connection.
+    pub fn write_delay(self, write_delay: Duration) -> Self {
+        self.terminal_writer_state
+            .write_delay_ms
+            .store(write_delay.as_millis() as u64, Ordering::Relaxed);
+        self
+    }
+
+    /// The number of the last frame flushed to the terminal writer, see `take_written_frames`.
+    pub fn last_flushed_frame(&self) -> u64 {
+        self.terminal_writer_state.flushed.load(Ordering::Acquire)
+    }
+
+    /// The frames the writer thread finished writing since this was last called, oldest first.
+    /// Frames are dropped while the writer is backed up, so not every number is reported.
+    pub fn take_written_frames(&self) -> impl Iterator<Item = WrittenFrame> + '_ {
+        self.written_frames_rx.try_iter()
+    }
+
+    /// Whether the next frame can be drawn. Once the terminal writer has dropped a frame, the
+    /// screen no longer matches the last drawn frame, which every following frame is only a diff
+    /// of. No frames are drawn until the writer catches up, and then the screen is redrawn in full.
+    pub fn begin_frame(&mut self) -> Result<bool> {
+        let state = &self.terminal_writer_state;
+
+        if !state.dropped.load(Ordering::Acquire) {
+            return Ok(true);
+        }
+
+        if state.queued.load(Ordering::Acquire) > 0 {
+            return Ok(false);
+        }
+
+        debug!("The terminal writer caught up, redrawing the screen.");
+        state.dropped.store(false, Ordering::Release);
+        self.terminal.clear()?;
+        Ok(true)
+    }
+
     pub fn start(&mut self) {
         self.cancel(); // Cancel any existing task
         self.cancellation_token = CancellationToken::new();
@@ -104,7 +340,8 @@ impl Tui {
             self.tick_rate,
             self.frame_rate,
         );
-        self.task = Some(tokio::spawn(
+        self.task = Some(crate::tasks::spawn(
+            &self.cancellation_token,
             async {
                 event_loop.await;
             }
@@ -113,7 +350,7 @@ impl Tui {
     }
 
     async fn event_loop(
-        event_tx: UnboundedSender<Event>,
+        event_tx: UnboundedSender<(Event, Instant)>,
         cancellation_token: CancellationToken,
         tick_rate: f64,
         frame_rate: f64,
@@ -126,7 +363,7 @@ impl Tui {
 
         // if this fails, then it's likely a bug in the calling code
         event_tx
-            .send(Event::Init)
+            .send((Event::Init, Instant::now()))
             .expect("failed to send init event");
         loop {
             let event = tokio::select! {
@@ -149,7 +386,7 @@ impl Tui {
                     None => break, // the event stream has stopped and will not produce any more events
                 },
             };
-            if event_tx.send(event).is_err() {
+            if event_tx.send((event, Instant::now())).is_err() {
                 // the receiver has been dropped, so there's no point in continuing the loop
                 break;
             }
@@ -178,7 +415,12 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+        crossterm::execute!(
+            stdout(),
+            EnterAlternateScreen,
+            cursor::Hide,
+            EnableFocusChange
+        )?;
         if self.mouse {
             crossterm::execute!(stdout(), EnableMouseCapture)?;
         }
@@ -193,13 +435,19 @@ impl Tui {
         self.stop()?;
         if crossterm::terminal::is_raw_mode_enabled()? {
             self.flush()?;
+            TerminalWriter::sync(&self.terminal_writer_tx);
             if self.paste {
                 crossterm::execute!(stdout(), DisableBracketedPaste)?;
             }
             if self.mouse {
                 crossterm::execute!(stdout(), DisableMouseCapture)?;
             }
-            crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
+            crossterm::execute!(
+                stdout(),
+                DisableFocusChange,
+                LeaveAlternateScreen,
+                cursor::Show
+            )?;
             crossterm::terminal::disable_raw_mode()?;
         }
         Ok(())
@@ -221,13 +469,13 @@ impl Tui {
         Ok(())
     }
 
-    pub async fn next_event(&mut self) -> Option<Event> {
+    pub async fn next_event(&mut self) -> Option<(Event, Instant)> {
         self.event_rx.recv().await
     }
 }
 
 impl Deref for Tui {
-    type Target = ratatui::Terminal<Backend<Stdout>>;
+    type Target = ratatui::Terminal<Backend<TerminalWriter>>;
 
     fn deref(&self) -> &Self::Target {
         &self.terminal
@@ -245,3 +493,126 @@ impl Drop for Tui {
         self.exit().unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// How long writing a single byte takes, simulating a slow terminal connection.
+    const BYTE_WRITE_DURATION: Duration = Duration::from_micros(200);
+    const FRAME_SIZE: usize = 10;
+
+    /// Records when each frame, identified by its bytes, was written.
+    #[derive(Clone, Default)]
+    struct SlowOutput {
+        written: Arc<Mutex<Vec<(u8, Instant)>>>,
+    }
+
+    impl Write for SlowOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            std::thread::sleep(BYTE_WRITE_DURATION * buf.len() as u32);
+            let written_at = Instant::now();
+            let mut written = self.written.lock().unwrap();
+
+            for frame in buf.chunks(FRAME_SIZE) {
+                written.push((frame[0], written_at));
+            }
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Flushes the given number of frames, one every millisecond, and returns when each of them
+    /// was flushed, once they have been written.
+    fn flush_frames(writer: &mut TerminalWriter, count: u8) -> Vec<Instant> {
+        let flushed_at = (0..count)
+            .map(|index| {
+                writer.write_all(&[index; FRAME_SIZE]).unwrap();
+                writer.flush().unwrap();
+                assert!(writer.state.queued.load(Ordering::Acquire) <= 2 * MAX_QUEUED_FRAMES + 1);
+                let flushed_at = Instant::now();
+                std::thread::sleep(Duration::from_millis(1));
+                flushed_at
+            })
+            .collect();
+
+        TerminalWriter::sync(&writer.tx);
+        flushed_at
+    }
+
+    #[test]
+    fn frames_are_kept_while_the_writer_keeps_up() {
+        let output = SlowOutput::default();
+        let mut writer = TerminalWriter::with_output(output.clone(), None).unwrap();
+
+        for count in 1..=5 {
+            flush_frames(&mut writer, 1);
+            assert_eq!(output.written.lock().unwrap().len(), count);
+        }
+
+        assert!(!writer.state.dropped.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn frames_are_dropped_while_the_writer_is_backed_up() {
+        let output = SlowOutput::default();
+        let mut writer = TerminalWriter::with_output(output.clone(), None).unwrap();
+        let frame_count = 200;
+        let flushed_at = flush_frames(&mut writer, frame_count);
+        let written = output.written.lock().unwrap();
+
+        assert!(writer.state.dropped.load(Ordering::Acquire));
+        assert_eq!(writer.state.queued.load(Ordering::Acquire), 0);
+        assert!(written.len() < frame_count as usize);
+        assert!(written.windows(2).all(|frames| frames[0].0 < frames[1].0));
+
+        // Writing every frame would take 2 ms each, so the last ones would be written hundreds of
+        // milliseconds after they were flushed, if none were dropped.
+        let max_latency = written
+            .iter()
+            .map(|&(index, written_at)| written_at - flushed_at[index as usize])
+            .max()
+            .unwrap();
+        assert!(max_latency < Duration::from_millis(100), "{max_latency:?}");
+    }
+
+    #[test]
+    fn the_write_delay_delays_every_write() {
+        let output = SlowOutput::default();
+        let mut writer = TerminalWriter::with_output(output.clone(), None).unwrap();
+        writer.state.write_delay_ms.store(50, Ordering::Relaxed);
+        let flushed_at = flush_frames(&mut writer, 1);
+        let written = output.written.lock().unwrap();
+
+        assert!(written[0].1 - flushed_at[0] >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn written_frames_are_reported_once_written() {
+        let output = SlowOutput::default();
+        let (written_tx, written_rx) = std_mpsc::channel();
+        let mut writer = TerminalWriter::with_output(output.clone(), Some(written_tx)).unwrap();
+        flush_frames(&mut writer, 3);
+        let written = output.written.lock().unwrap();
+        let reported = written_rx.try_iter().collect::<Vec<_>>();
+
+        assert_eq!(reported.last().map(|frame| frame.sequence), Some(3));
+        assert!(reported
+            .windows(2)
+            .all(|frames| frames[0].sequence < frames[1].sequence));
+        // Frames are numbered from 1, and the bytes of each frame are its index.
+        for frame in &reported {
+            let (_, output_written_at) = written
+                .iter()
+                .find(|(index, _)| u64::from(*index) + 1 == frame.sequence)
+                .unwrap();
+            assert!(frame.written_at >= *output_written_at);
+        }
+    }
+}
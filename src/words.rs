@@ -0,0 +1,160 @@
+//! The definition of a "word", shared by all word-wise operations.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits text into words at the Unicode word boundaries (UAX #29) and at a configurable set of
+/// additional separator characters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WordBoundaries {
+    extra_separators: Vec<char>,
+}
+
+impl WordBoundaries {
+    pub fn new(extra_separators: impl IntoIterator<Item = char>) -> Self {
+        Self {
+            extra_separators: extra_separators.into_iter().collect(),
+        }
+    }
+
+    /// Returns the byte ranges of all words in `text`, in order.
+    /// Whitespace and punctuation between words is not part of any word.
+    pub fn word_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut push_range = |range: Range<usize>| {
+            if text[range.clone()].chars().any(char::is_alphanumeric) {
+                ranges.push(range);
+            }
+        };
+
+        for (offset, segment) in text.split_word_bound_indices() {
+            let mut start = offset;
+
+            for (index, character) in segment.char_indices() {
+                if self.extra_separators.contains(&character) {
+                    push_range(start..(offset + index));
+                    start = offset + index + character.len_utf8();
+                }
+            }
+
+            push_range(start..(offset + segment.len()));
+        }
+
+        ranges
+    }
+
+    /// Returns the start of the word before `position`, or the start of the word containing it.
+    pub fn previous_word_start(&self, text: &str, position: usize) -> usize {
+        self.word_ranges(text)
+            .into_iter()
+            .rev()
+            .find(|range| range.start < position)
+            .map(|range| range.start)
+            .unwrap_or(0)
+    }
+
+    /// Returns the end of the word after `position`, or the end of the word containing it.
+    pub fn next_word_end(&self, text: &str, position: usize) -> usize {
+        self.word_ranges(text)
+            .into_iter()
+            .find(|range| range.end > position)
+            .map(|range| range.end)
+            .unwrap_or(text.len())
+    }
+
+    /// Returns the range of the word containing `position`, if any.
+    pub fn word_at(&self, text: &str, position: usize) -> Option<Range<usize>> {
+        self.word_ranges(text)
+            .into_iter()
+            .find(|range| range.contains(&position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The words of `text`, as split by `boundaries`.
+    fn words<'a>(boundaries: &WordBoundaries, text: &'a str) -> Vec<&'a str> {
+        boundaries
+            .word_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn hyphens_separate_words() {
+        let boundaries = WordBoundaries::default();
+
+        assert_eq!(
+            words(&boundaries, "well-known name"),
+            ["well", "known", "name"]
+        );
+        assert_eq!(words(&boundaries, "-a--b-"), ["a", "b"]);
+    }
+
+    #[test]
+    fn underscores_join_words_unless_they_are_separators() {
+        assert_eq!(
+            words(&WordBoundaries::default(), "snake_case name"),
+            ["snake_case", "name"]
+        );
+        assert_eq!(
+            words(&WordBoundaries::new(['_']), "snake_case name"),
+            ["snake", "case", "name"]
+        );
+        assert_eq!(words(&WordBoundaries::new(['_']), "__a__"), ["a"]);
+    }
+
+    #[test]
+    fn every_cjk_ideograph_is_a_word() {
+        let boundaries = WordBoundaries::default();
+
+        assert_eq!(words(&boundaries, "日本語"), ["日", "本", "語"]);
+        assert_eq!(boundaries.word_ranges("日本 語"), [0..3, 3..6, 7..10]);
+        assert_eq!(words(&WordBoundaries::new(['本']), "日本語"), ["日", "語"]);
+    }
+
+    #[test]
+    fn hex_strings_are_words() {
+        let boundaries = WordBoundaries::default();
+
+        assert_eq!(words(&boundaries, "0xdeadbeef"), ["0xdeadbeef"]);
+        assert_eq!(words(&boundaries, "de ad be ef"), ["de", "ad", "be", "ef"]);
+        // Colons join letters, but not digits.
+        assert_eq!(words(&boundaries, "de:ad 00:1a"), ["de:ad", "00", "1a"]);
+        assert_eq!(
+            words(&WordBoundaries::new([':']), "de:ad 00:1a"),
+            ["de", "ad", "00", "1a"]
+        );
+    }
+
+    #[test]
+    fn extra_separators_split_record_paths() {
+        let text = "/a/b-c:d";
+
+        assert_eq!(words(&WordBoundaries::default(), text), ["a", "b", "c:d"]);
+        assert_eq!(
+            words(&WordBoundaries::new("-:/".chars()), text),
+            ["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn words_are_found_around_positions() {
+        let boundaries = WordBoundaries::new(['/']);
+        let text = "ab/cd ef";
+
+        assert_eq!(boundaries.previous_word_start(text, 8), 6);
+        assert_eq!(boundaries.previous_word_start(text, 6), 3);
+        assert_eq!(boundaries.previous_word_start(text, 4), 3);
+        assert_eq!(boundaries.previous_word_start(text, 0), 0);
+        assert_eq!(boundaries.next_word_end(text, 0), 2);
+        assert_eq!(boundaries.next_word_end(text, 2), 5);
+        assert_eq!(boundaries.next_word_end(text, 8), 8);
+        assert_eq!(boundaries.word_at(text, 4), Some(3..5));
+        assert_eq!(boundaries.word_at(text, 2), None);
+    }
+}